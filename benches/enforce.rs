@@ -0,0 +1,125 @@
+use arc_swap::ArcSwap;
+use casbin::{CachedApi, CachedEnforcer, CoreApi};
+use casbin_grpc::server::abac::resolve_abac;
+use casbin_grpc::server::registry::{DecisionCacheOptions, SharedEnforcer};
+use futures::lock::Mutex;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+static TASKS: usize = 8;
+static CALLS: usize = 20_000;
+
+// REQUESTS mix decisions granted directly, through a role, and denied.
+static REQUESTS: &[(&str, &str, &str)] = &[
+    ("alice", "data1", "read"),
+    ("alice", "data2", "write"),
+    ("bob", "data2", "write"),
+    ("bob", "data1", "read"),
+];
+
+async fn new_enforcer() -> CachedEnforcer {
+    let mut e = CachedEnforcer::new("examples/rbac_model.conf", "examples/rbac_policy.csv")
+        .await
+        .unwrap();
+    e.set_capacity(10_000);
+    e
+}
+
+// run makes TASKS tasks each decide CALLS requests with enforce, getting how long they took.
+async fn run<F, Fut>(enforce: F) -> Duration
+where
+    F: Fn(usize) -> Fut + Clone + Send + 'static,
+    Fut: std::future::Future<Output = bool> + Send,
+{
+    let start = Instant::now();
+    let tasks: Vec<_> = (0..TASKS)
+        .map(|task| {
+            let enforce = enforce.clone();
+            tokio::spawn(async move {
+                for i in 0..CALLS {
+                    enforce(task + i).await;
+                    // A call to the service yields between decisions.
+                    tokio::task::yield_now().await;
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+    start.elapsed()
+}
+
+fn report(name: &str, elapsed: Duration) {
+    let calls = (TASKS * CALLS) as f64;
+    println!(
+        "{:<10} {:>10.0} decisions/s ({:?} for {} decisions over {} tasks)",
+        name,
+        calls / elapsed.as_secs_f64(),
+        elapsed,
+        TASKS * CALLS,
+        TASKS
+    );
+}
+
+// The enforce benchmark compares concurrent Enforce calls serialized through a mutex, as they
+// were, with the same calls sharing the enforcer through SharedEnforcer. It also measures
+// what reading the enforcer costs through the RwLock SharedEnforcer holds it in and through
+// an ArcSwap of it, both deciding without a cache.
+#[tokio::main(flavor = "multi_thread")]
+async fn main() {
+    let mutex = Arc::new(Mutex::new(new_enforcer().await));
+    let elapsed = run(move |i| {
+        let mutex = mutex.clone();
+        async move {
+            let rvals = REQUESTS[i % REQUESTS.len()];
+            mutex.lock().await.enforce_mut(rvals).unwrap()
+        }
+    })
+    .await;
+    report("mutex", elapsed);
+
+    let cache = DecisionCacheOptions {
+        max_entries: 10_000,
+        ..Default::default()
+    };
+    let shared = Arc::new(SharedEnforcer::with_cache(
+        new_enforcer().await,
+        cache,
+        Default::default(),
+        Default::default(),
+    ));
+    let elapsed = run(move |i| {
+        let shared = shared.clone();
+        async move {
+            let (sub, obj, act) = REQUESTS[i % REQUESTS.len()];
+            let rvals = resolve_abac(vec![sub.to_owned(), obj.to_owned(), act.to_owned()]).unwrap();
+            shared.read().await.enforce_cached(rvals).unwrap()
+        }
+    })
+    .await;
+    report("shared", elapsed);
+
+    let lock = Arc::new(RwLock::new(new_enforcer().await));
+    let elapsed = run(move |i| {
+        let lock = lock.clone();
+        async move {
+            let rvals = REQUESTS[i % REQUESTS.len()];
+            lock.read().await.enforce(rvals).unwrap()
+        }
+    })
+    .await;
+    report("rwlock", elapsed);
+
+    let swap = Arc::new(ArcSwap::from_pointee(new_enforcer().await));
+    let elapsed = run(move |i| {
+        let swap = swap.clone();
+        async move {
+            let rvals = REQUESTS[i % REQUESTS.len()];
+            swap.load().enforce(rvals).unwrap()
+        }
+    })
+    .await;
+    report("arc-swap", elapsed);
+}
@@ -1,4 +1,15 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("api/protos/casbin.proto")?;
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
+    let mut config =
+        tonic_build::configure().file_descriptor_set_path(out_dir.join("casbin_descriptor.bin"));
+    // The REST gateway transcodes the messages of the Casbin service from and to JSON.
+    if std::env::var_os("CARGO_FEATURE_REST").is_some() {
+        config = config.type_attribute(
+            ".proto",
+            "#[derive(serde::Serialize, serde::Deserialize)] #[serde(default, rename_all = \"camelCase\")]",
+        );
+    }
+    config.compile(&["api/protos/casbin.proto"], &["api/protos"])?;
+    tonic_build::compile_protos("api/protos/raft.proto")?;
     Ok(())
 }
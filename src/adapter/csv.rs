@@ -0,0 +1,503 @@
+use crate::adapter::matches_filter;
+use casbin::{Adapter, Filter, Model};
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+// CsvStore is where the policy text of a CsvAdapter lives.
+#[tonic::async_trait]
+pub trait CsvStore: Send + Sync {
+    // name identifies the store in error messages.
+    fn name(&self) -> String;
+
+    // read gets the policy text, or None when there is none yet.
+    async fn read(&self) -> casbin::Result<Option<String>>;
+
+    // load reads the policy text that the rules in memory are loaded from. Stores detecting
+    // concurrent changes check later reads and writes against what load saw.
+    async fn load(&self) -> casbin::Result<Option<String>> {
+        self.read().await
+    }
+
+    // write replaces the policy text.
+    async fn write(&self, text: String) -> casbin::Result<()>;
+}
+
+// FileStore keeps the policy in a local file.
+pub struct FileStore {
+    path: PathBuf,
+}
+
+#[tonic::async_trait]
+impl CsvStore for FileStore {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    async fn read(&self) -> casbin::Result<Option<String>> {
+        match tokio::fs::read_to_string(&self.path).await {
+            Ok(text) => Ok(Some(text)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // write replaces the file through a temporary file, so readers never see a partially
+    // written policy.
+    async fn write(&self, text: String) -> casbin::Result<()> {
+        if self.path.as_os_str().is_empty() {
+            return Err(Error::other("policy file path is empty").into());
+        }
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        tokio::fs::write(&tmp, text).await?;
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+// CsvAdapter stores rules in the standard casbin policy.csv format, one `ptype, field, ...`
+// rule per line. Fields holding a comma, a quote or surrounding spaces are written in double
+// quotes, with quotes doubled, and read back the same way. Rules are added by appending to the
+// policy and removed by rewriting it, keeping blank lines and `#` comments.
+pub struct CsvAdapter<S = FileStore> {
+    store: S,
+    is_filtered: bool,
+}
+
+impl CsvAdapter {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self::with_store(FileStore { path: path.into() })
+    }
+}
+
+impl<S: CsvStore> CsvAdapter<S> {
+    pub fn with_store(store: S) -> Self {
+        CsvAdapter {
+            store,
+            is_filtered: false,
+        }
+    }
+
+    async fn read_lines(&self, load: bool) -> casbin::Result<Vec<(String, Option<Vec<String>>)>> {
+        let text = if load {
+            self.store.load().await?
+        } else {
+            self.store.read().await?
+        };
+        let text = text.ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("{} does not exist", self.store.name()),
+            )
+        })?;
+        let mut lines = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let tokens = parse_line(line).map_err(|msg| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{}:{}: {}", self.store.name(), i + 1, msg),
+                )
+            })?;
+            lines.push((line.to_owned(), tokens));
+        }
+        Ok(lines)
+    }
+
+    async fn append_rules(&self, ptype: &str, rules: &[Vec<String>]) -> casbin::Result<()> {
+        let mut text = self.store.read().await?.unwrap_or_default();
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        for rule in rules {
+            text.push_str(&format_line(ptype, rule));
+            text.push('\n');
+        }
+        self.store.write(text).await
+    }
+
+    // remove_where drops the rule lines for which is_match holds, and reports how many were
+    // dropped.
+    async fn remove_where<F>(&self, mut is_match: F) -> casbin::Result<usize>
+    where
+        F: FnMut(&str, &[String]) -> bool,
+    {
+        let mut removed = 0;
+        let mut text = String::new();
+        for (line, tokens) in self.read_lines(false).await? {
+            if let Some(tokens) = tokens {
+                if is_match(&tokens[0], &tokens[1..]) {
+                    removed += 1;
+                    continue;
+                }
+            }
+            text.push_str(&line);
+            text.push('\n');
+        }
+        if removed > 0 {
+            self.store.write(text).await?;
+        }
+        Ok(removed)
+    }
+}
+
+// parse_line splits a policy line into its ptype and fields, or gives None for a blank line
+// or a comment.
+fn parse_line(line: &str) -> Result<Option<Vec<String>>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let mut fields = vec![];
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| *c == ' ' || *c == '\t') {
+            chars.next();
+        }
+        let mut field = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err("unterminated quoted field".to_owned()),
+                }
+            }
+            while chars.peek().is_some_and(|c| *c == ' ' || *c == '\t') {
+                chars.next();
+            }
+            match chars.peek() {
+                None | Some(',') => {}
+                Some(_) => return Err("unexpected text after quoted field".to_owned()),
+            }
+        } else {
+            while let Some(c) = chars.peek() {
+                if *c == ',' {
+                    break;
+                }
+                field.push(*c);
+                chars.next();
+            }
+            field = field.trim_end().to_owned();
+        }
+        fields.push(field);
+        if chars.next().is_none() {
+            break;
+        }
+    }
+    Ok(Some(fields))
+}
+
+fn format_field(field: &str) -> String {
+    if field.is_empty()
+        || field.contains([',', '"', '\n', '\r'])
+        || field.starts_with(char::is_whitespace)
+        || field.ends_with(char::is_whitespace)
+    {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+pub fn format_line(ptype: &str, rule: &[String]) -> String {
+    let mut fields = vec![ptype.to_owned()];
+    fields.extend(rule.iter().map(|field| format_field(field)));
+    fields.join(", ")
+}
+
+#[tonic::async_trait]
+impl<S: CsvStore> Adapter for CsvAdapter<S> {
+    async fn load_policy(&self, m: &mut dyn Model) -> casbin::Result<()> {
+        for (_, tokens) in self.read_lines(true).await? {
+            if let Some(tokens) = tokens {
+                let sec = &tokens[0][..1];
+                m.add_policy(sec, &tokens[0], tokens[1..].to_vec());
+            }
+        }
+        Ok(())
+    }
+
+    async fn load_filtered_policy<'a>(
+        &mut self,
+        m: &mut dyn Model,
+        f: Filter<'a>,
+    ) -> casbin::Result<()> {
+        let mut is_filtered = false;
+        for (_, tokens) in self.read_lines(true).await? {
+            if let Some(tokens) = tokens {
+                let sec = &tokens[0][..1];
+                let filter = if sec == "g" { &f.g } else { &f.p };
+                if matches_filter(&tokens[1..], filter) {
+                    m.add_policy(sec, &tokens[0], tokens[1..].to_vec());
+                } else {
+                    is_filtered = true;
+                }
+            }
+        }
+        self.is_filtered = is_filtered;
+        Ok(())
+    }
+
+    async fn save_policy(&mut self, m: &mut dyn Model) -> casbin::Result<()> {
+        let mut text = String::new();
+        for sec in ["p", "g"] {
+            if let Some(assertions) = m.get_model().get(sec) {
+                let mut ptypes: Vec<&String> = assertions.keys().collect();
+                ptypes.sort();
+                for ptype in ptypes {
+                    for rule in assertions[ptype].get_policy() {
+                        text.push_str(&format_line(ptype, rule));
+                        text.push('\n');
+                    }
+                }
+            }
+        }
+        self.store.write(text).await
+    }
+
+    async fn clear_policy(&mut self) -> casbin::Result<()> {
+        self.store.write(String::new()).await
+    }
+
+    fn is_filtered(&self) -> bool {
+        self.is_filtered
+    }
+
+    async fn add_policy(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        self.append_rules(ptype, &[rule]).await?;
+        Ok(true)
+    }
+
+    async fn add_policies(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        self.append_rules(ptype, &rules).await?;
+        Ok(true)
+    }
+
+    async fn remove_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        self.remove_policies(sec, ptype, vec![rule]).await
+    }
+
+    // remove_policies removes nothing and returns false unless every rule is in the file.
+    async fn remove_policies(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        let lines = self.read_lines(false).await?;
+        let all_present = rules.iter().all(|rule| {
+            lines.iter().any(|(_, tokens)| {
+                tokens
+                    .as_ref()
+                    .is_some_and(|t| t[0] == ptype && t[1..] == rule[..])
+            })
+        });
+        if !all_present {
+            return Ok(false);
+        }
+        self.remove_where(|p, fields| p == ptype && rules.iter().any(|rule| fields == &rule[..]))
+            .await?;
+        Ok(true)
+    }
+
+    async fn remove_filtered_policy(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        field_index: usize,
+        field_values: Vec<String>,
+    ) -> casbin::Result<bool> {
+        let removed = self
+            .remove_where(|p, fields| {
+                p == ptype
+                    && field_values.iter().enumerate().all(|(i, value)| {
+                        value.is_empty() || fields.get(field_index + i).is_some_and(|f| f == value)
+                    })
+            })
+            .await?;
+        Ok(removed > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_line, parse_line, CsvAdapter, CsvStore};
+    use casbin::{Adapter, CoreApi, DefaultModel, Enforcer, Filter, MgmtApi};
+    use std::sync::{Arc, Mutex};
+
+    // MemStore keeps the policy text in memory, shared with the test.
+    #[derive(Clone, Default)]
+    struct MemStore(Arc<Mutex<Option<String>>>);
+
+    #[tonic::async_trait]
+    impl CsvStore for MemStore {
+        fn name(&self) -> String {
+            String::from("memory")
+        }
+
+        async fn read(&self) -> casbin::Result<Option<String>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        async fn write(&self, text: String) -> casbin::Result<()> {
+            *self.0.lock().unwrap() = Some(text);
+            Ok(())
+        }
+    }
+
+    impl MemStore {
+        fn with(text: &str) -> Self {
+            MemStore(Arc::new(Mutex::new(Some(text.to_owned()))))
+        }
+
+        fn text(&self) -> String {
+            self.0.lock().unwrap().clone().unwrap_or_default()
+        }
+    }
+
+    fn rule(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|f| f.to_string()).collect()
+    }
+
+    async fn enforcer(store: &MemStore) -> Enforcer {
+        let m = DefaultModel::from_file("examples/rbac_model.conf")
+            .await
+            .unwrap();
+        Enforcer::new(m, CsvAdapter::with_store(store.clone()))
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line("  "), Ok(None));
+        assert_eq!(parse_line("# p, alice, data1, read"), Ok(None));
+        assert_eq!(
+            parse_line("p, alice ,data1,  read"),
+            Ok(Some(rule(&["p", "alice", "data1", "read"])))
+        );
+        assert_eq!(
+            parse_line(r#"p, "a, b", " c ", "say ""hi""", """#),
+            Ok(Some(rule(&["p", "a, b", " c ", r#"say "hi""#, ""])))
+        );
+        assert!(parse_line(r#"p, "open"#).is_err());
+        assert!(parse_line(r#"p, "a" b"#).is_err());
+    }
+
+    #[test]
+    fn test_format_line_round_trips() {
+        let fields = rule(&["plain", "a, b", " c ", r#"say "hi""#, "", "line\nbreak"]);
+        let line = format_line("p", &fields);
+        assert_eq!(
+            line,
+            r#"p, plain, "a, b", " c ", "say ""hi""", "", "line
+break""#
+        );
+        let single = format_line("p", &fields[..5]);
+        let mut parsed = parse_line(&single).unwrap().unwrap();
+        assert_eq!(parsed.remove(0), "p");
+        assert_eq!(parsed, &fields[..5]);
+    }
+
+    #[tokio::test]
+    async fn test_writes_through() {
+        let store = MemStore::with("# admins\np, alice, data1, read\n\ng, bob, admin\n");
+        let mut e = enforcer(&store).await;
+        assert!(e.enforce(("alice", "data1", "read")).unwrap());
+
+        e.add_policy(rule(&["bob", "a, b", "write"])).await.unwrap();
+        assert_eq!(
+            store.text(),
+            "# admins\np, alice, data1, read\n\ng, bob, admin\np, bob, \"a, b\", write\n"
+        );
+
+        // Removals keep the comments and blank lines.
+        e.remove_policy(rule(&["alice", "data1", "read"]))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.text(),
+            "# admins\n\ng, bob, admin\np, bob, \"a, b\", write\n"
+        );
+        e.remove_filtered_policy(0, rule(&["bob"])).await.unwrap();
+        assert_eq!(store.text(), "# admins\n\ng, bob, admin\n");
+
+        e.save_policy().await.unwrap();
+        assert_eq!(store.text(), "g, bob, admin\n");
+    }
+
+    #[tokio::test]
+    async fn test_remove_policies_removes_all_or_nothing() {
+        let store = MemStore::with("p, alice, data1, read\np, bob, data2, write\n");
+        let mut adapter = CsvAdapter::with_store(store.clone());
+        let removed = adapter
+            .remove_policies(
+                "p",
+                "p",
+                vec![
+                    rule(&["alice", "data1", "read"]),
+                    rule(&["carol", "data3", "read"]),
+                ],
+            )
+            .await
+            .unwrap();
+        assert!(!removed);
+        assert_eq!(
+            store.text(),
+            "p, alice, data1, read\np, bob, data2, write\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_filtered_policy() {
+        let store = MemStore::with("p, alice, data1, read\np, bob, data2, write\ng, bob, admin\n");
+        let mut e = enforcer(&store).await;
+        let filter = Filter {
+            p: vec!["", "data2"],
+            g: vec!["carol"],
+        };
+        e.load_filtered_policy(filter).await.unwrap();
+        assert_eq!(e.get_policy(), vec![rule(&["bob", "data2", "write"])]);
+        assert!(e.get_grouping_policy().is_empty());
+        assert!(e.is_filtered());
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_invalid_policy() {
+        let mut adapter = CsvAdapter::with_store(MemStore::default());
+        let mut m = DefaultModel::from_file("examples/rbac_model.conf")
+            .await
+            .unwrap();
+        let err = adapter.load_policy(&mut m).await.unwrap_err();
+        assert!(err.to_string().contains("memory does not exist"), "{}", err);
+
+        adapter = CsvAdapter::with_store(MemStore::with("p, alice\np, \"bob\n"));
+        let err = adapter.load_policy(&mut m).await.unwrap_err();
+        assert!(
+            err.to_string().contains("memory:2: unterminated"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use crate::adapter::adapter_error;
+use crate::adapter::csv::format_line;
+use casbin::{Adapter, Filter, Model};
+use etcd_client::{
+    Client, Compare, CompareOp, ConnectOptions, DeleteOptions, GetOptions, KvClient, Txn, TxnOp,
+};
+
+// MAX_TXN_OPS is the number of operations etcd accepts in a transaction unless started with a
+// larger --max-txn-ops.
+static MAX_TXN_OPS: usize = 128;
+
+// EtcdAdapter stores one key per rule under a key prefix. The key is the prefix followed by
+// the rule as a policy.csv line, and the value the rule as a JSON array of its ptype and
+// fields.
+pub struct EtcdAdapter {
+    kv: KvClient,
+    prefix: String,
+    is_filtered: bool,
+}
+
+// connect connects to the endpoints of a connection string such as
+// `etcd://host1:2379,host2:2379/casbin/`, returning the client and the path of the string.
+// `?user=&password=` authenticate the connection.
+pub async fn connect(connection: &str) -> casbin::Result<(Client, String)> {
+    let rest = connection
+        .strip_prefix("etcd://")
+        .ok_or_else(|| adapter_error("connection string must start with etcd://"))?;
+    let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let (hosts, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let endpoints: Vec<String> = hosts
+        .split(',')
+        .filter(|host| !host.is_empty())
+        .map(|host| format!("http://{}", host))
+        .collect();
+    if endpoints.is_empty() {
+        return Err(adapter_error("connection string names no etcd endpoint"));
+    }
+
+    let (mut user, mut password) = (String::new(), String::new());
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        match pair.split_once('=').unwrap_or((pair, "")) {
+            ("user", value) => user = value.to_owned(),
+            ("password", value) => password = value.to_owned(),
+            (name, _) => return Err(adapter_error(format!("unknown parameter `{}`", name))),
+        }
+    }
+    let options = if user.is_empty() {
+        None
+    } else {
+        Some(ConnectOptions::new().with_user(user, password))
+    };
+
+    let client = Client::connect(endpoints, options)
+        .await
+        .map_err(adapter_error)?;
+    Ok((client, path.to_owned()))
+}
+
+impl EtcdAdapter {
+    // new connects to the endpoints of a connection string such as
+    // `etcd://host1:2379,host2:2379/casbin/` with the key prefix as its path, `casbin/` when it
+    // has none.
+    pub async fn new(connection: &str) -> casbin::Result<Self> {
+        let (client, prefix) = connect(connection).await?;
+        Ok(EtcdAdapter {
+            kv: client.kv_client(),
+            prefix: if prefix.is_empty() {
+                String::from("casbin/")
+            } else {
+                prefix
+            },
+            is_filtered: false,
+        })
+    }
+
+    fn rule_key(&self, ptype: &str, rule: &[String]) -> String {
+        format!("{}{}", self.prefix, format_line(ptype, rule))
+    }
+
+    // load_rules reads the rules under the prefix, or under the prefix of one policy type.
+    async fn load_rules(&self, ptype: &str) -> casbin::Result<Vec<(String, Vec<String>)>> {
+        let mut kv = self.kv.clone();
+        let key = format!("{}{}", self.prefix, ptype);
+        let res = kv
+            .get(key, Some(GetOptions::new().with_prefix()))
+            .await
+            .map_err(adapter_error)?;
+        let mut rules = vec![];
+        for kv in res.kvs() {
+            let mut tokens: Vec<String> =
+                serde_json::from_slice(kv.value()).map_err(adapter_error)?;
+            if tokens.is_empty() || tokens[0].is_empty() {
+                continue;
+            }
+            let rule = tokens.split_off(1);
+            rules.push((tokens.remove(0), rule));
+        }
+        Ok(rules)
+    }
+
+    // put_rules writes rules in transactions of at most MAX_TXN_OPS puts.
+    async fn put_rules(&mut self, rules: &[(String, Vec<String>)]) -> casbin::Result<()> {
+        let mut ops = vec![];
+        for (ptype, rule) in rules {
+            let mut value = vec![ptype.to_owned()];
+            value.extend(rule.iter().cloned());
+            let value = serde_json::to_string(&value).map_err(adapter_error)?;
+            ops.push(TxnOp::put(self.rule_key(ptype, rule), value, None));
+        }
+        self.run_ops(ops).await
+    }
+
+    async fn run_ops(&mut self, mut ops: Vec<TxnOp>) -> casbin::Result<()> {
+        while !ops.is_empty() {
+            let rest = ops.split_off(ops.len().min(MAX_TXN_OPS));
+            self.kv
+                .txn(Txn::new().and_then(ops))
+                .await
+                .map_err(adapter_error)?;
+            ops = rest;
+        }
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl Adapter for EtcdAdapter {
+    async fn load_policy(&self, m: &mut dyn Model) -> casbin::Result<()> {
+        for (ptype, rule) in self.load_rules("").await? {
+            m.add_policy(&ptype[..1], &ptype, rule);
+        }
+        Ok(())
+    }
+
+    // load_filtered_policy only reads the keys of the filtered sections, as the key prefix
+    // cannot select on the fields.
+    async fn load_filtered_policy<'a>(
+        &mut self,
+        m: &mut dyn Model,
+        f: Filter<'a>,
+    ) -> casbin::Result<()> {
+        let mut is_filtered = false;
+        for (sec, filter) in [("p", &f.p), ("g", &f.g)] {
+            for (ptype, rule) in self.load_rules(sec).await? {
+                if crate::adapter::matches_filter(&rule, filter) {
+                    m.add_policy(sec, &ptype, rule);
+                } else {
+                    is_filtered = true;
+                }
+            }
+        }
+        self.is_filtered = is_filtered;
+        Ok(())
+    }
+
+    // save_policy replaces the rules under the prefix. etcd limits the size of a transaction,
+    // so a large policy is written in several and readers may briefly see part of it.
+    async fn save_policy(&mut self, m: &mut dyn Model) -> casbin::Result<()> {
+        let mut rules = vec![];
+        for sec in ["p", "g"] {
+            if let Some(assertions) = m.get_model().get(sec) {
+                for (ptype, ast) in assertions {
+                    for rule in ast.get_policy() {
+                        rules.push((ptype.to_owned(), rule.to_vec()));
+                    }
+                }
+            }
+        }
+        self.clear_policy().await?;
+        self.put_rules(&rules).await
+    }
+
+    async fn clear_policy(&mut self) -> casbin::Result<()> {
+        self.kv
+            .delete(
+                self.prefix.as_str(),
+                Some(DeleteOptions::new().with_prefix()),
+            )
+            .await
+            .map_err(adapter_error)?;
+        Ok(())
+    }
+
+    fn is_filtered(&self) -> bool {
+        self.is_filtered
+    }
+
+    async fn add_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        self.add_policies(sec, ptype, vec![rule]).await
+    }
+
+    async fn add_policies(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        let rules: Vec<(String, Vec<String>)> = rules
+            .into_iter()
+            .map(|rule| (ptype.to_owned(), rule))
+            .collect();
+        self.put_rules(&rules).await?;
+        Ok(true)
+    }
+
+    async fn remove_policy(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        let res = self
+            .kv
+            .delete(self.rule_key(ptype, &rule), None)
+            .await
+            .map_err(adapter_error)?;
+        Ok(res.deleted() > 0)
+    }
+
+    // remove_policies removes nothing and returns false unless every rule is stored. The
+    // check and the removal happen in one transaction when it fits in MAX_TXN_OPS.
+    async fn remove_policies(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        let keys: Vec<String> = rules
+            .iter()
+            .map(|rule| self.rule_key(ptype, rule))
+            .collect();
+        let deletes: Vec<TxnOp> = keys
+            .iter()
+            .map(|key| TxnOp::delete(key.as_str(), None))
+            .collect();
+        if keys.len() <= MAX_TXN_OPS {
+            let compares: Vec<Compare> = keys
+                .iter()
+                .map(|key| Compare::version(key.as_str(), CompareOp::Greater, 0))
+                .collect();
+            let res = self
+                .kv
+                .txn(Txn::new().when(compares).and_then(deletes))
+                .await
+                .map_err(adapter_error)?;
+            return Ok(res.succeeded());
+        }
+        for key in keys.iter() {
+            let res = self
+                .kv
+                .get(key.as_str(), Some(GetOptions::new().with_count_only()))
+                .await
+                .map_err(adapter_error)?;
+            if res.count() == 0 {
+                return Ok(false);
+            }
+        }
+        self.run_ops(deletes).await?;
+        Ok(true)
+    }
+
+    async fn remove_filtered_policy(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        field_index: usize,
+        field_values: Vec<String>,
+    ) -> casbin::Result<bool> {
+        let deletes: Vec<TxnOp> = self
+            .load_rules(ptype)
+            .await?
+            .into_iter()
+            .filter(|(t, rule)| {
+                t == ptype
+                    && field_values.iter().enumerate().all(|(i, value)| {
+                        value.is_empty() || rule.get(field_index + i) == Some(value)
+                    })
+            })
+            .map(|(t, rule)| TxnOp::delete(self.rule_key(&t, &rule), None))
+            .collect();
+        if deletes.is_empty() {
+            return Ok(false);
+        }
+        self.run_ops(deletes).await?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,255 @@
+use self::retry::{RetryAdapter, BACKOFF};
+use casbin::error::AdapterError;
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+// Backends outside this crate implement casbin's Adapter trait and are registered as a driver
+// with CasbinGRPCBuilder::adapter_driver.
+pub use casbin::{Adapter, Filter, Model};
+
+pub mod csv;
+#[cfg(feature = "etcd")]
+pub mod etcd;
+#[cfg(feature = "mongodb")]
+pub mod mongo;
+#[cfg(feature = "mysql")]
+pub mod mysql;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "redis")]
+pub mod redis;
+pub mod retry;
+#[cfg(feature = "s3")]
+pub mod s3;
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+pub mod sql;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+// open_adapter creates the adapter for a driver name and its connection string, the path of
+// the policy file for the `file` driver.
+pub async fn open_adapter(driver: &str, connection: &str) -> casbin::Result<Box<dyn Adapter>> {
+    Ok(match driver {
+        "file" => Box::new(csv::CsvAdapter::new(connection)),
+        #[cfg(feature = "postgres")]
+        "postgres" => Box::new(postgres::connect(connection).await?),
+        #[cfg(feature = "mysql")]
+        "mysql" => Box::new(mysql::connect(connection).await?),
+        #[cfg(feature = "sqlite")]
+        "sqlite" => Box::new(sqlite::connect(connection).await?),
+        #[cfg(feature = "mongodb")]
+        "mongodb" => Box::new(mongo::MongoAdapter::new(connection).await?),
+        #[cfg(feature = "redis")]
+        "redis" => Box::new(self::redis::RedisAdapter::new(connection).await?),
+        #[cfg(feature = "s3")]
+        "s3" => Box::new(s3::connect(connection)?),
+        #[cfg(feature = "etcd")]
+        "etcd" => Box::new(etcd::EtcdAdapter::new(connection).await?),
+        _ => {
+            return Err(adapter_error(format!(
+                "unsupported driver `{}`, supported drivers are: {}",
+                driver,
+                DRIVERS.join(", ")
+            )))
+        }
+    })
+}
+
+// DRIVERS lists the built-in drivers this build of the server supports.
+pub static DRIVERS: &[&str] = &[
+    "file",
+    #[cfg(feature = "postgres")]
+    "postgres",
+    #[cfg(feature = "mysql")]
+    "mysql",
+    #[cfg(feature = "sqlite")]
+    "sqlite",
+    #[cfg(feature = "mongodb")]
+    "mongodb",
+    #[cfg(feature = "redis")]
+    "redis",
+    #[cfg(feature = "s3")]
+    "s3",
+    #[cfg(feature = "etcd")]
+    "etcd",
+];
+
+// DriverFn opens an adapter from a connection string.
+pub type DriverFn =
+    Arc<dyn Fn(String) -> BoxFuture<'static, casbin::Result<Box<dyn Adapter>>> + Send + Sync>;
+
+// Drivers holds the adapter drivers registered by an embedding application, which take
+// precedence over the built-in drivers of the same name.
+#[derive(Clone, Default)]
+pub struct Drivers {
+    custom: HashMap<String, DriverFn>,
+}
+
+impl Drivers {
+    pub fn register<F, Fut, A>(&mut self, name: &str, open: F)
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = casbin::Result<A>> + Send + 'static,
+        A: Adapter + 'static,
+    {
+        let open: DriverFn = Arc::new(move |connection| {
+            let fut = open(connection);
+            Box::pin(async move { Ok(Box::new(fut.await?) as Box<dyn Adapter>) })
+        });
+        self.custom.insert(name.to_owned(), open);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.custom.contains_key(name) || DRIVERS.contains(&name)
+    }
+
+    // names lists the built-in and registered drivers.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = DRIVERS.iter().map(|name| name.to_string()).collect();
+        let mut custom: Vec<String> = self
+            .custom
+            .keys()
+            .filter(|name| !DRIVERS.contains(&name.as_str()))
+            .cloned()
+            .collect();
+        custom.sort();
+        names.extend(custom);
+        names
+    }
+
+    // connect makes a single attempt at opening the adapter of a driver.
+    pub async fn connect(
+        &self,
+        driver: &str,
+        connection: &str,
+    ) -> casbin::Result<Box<dyn Adapter>> {
+        match self.custom.get(driver) {
+            Some(open) => open(connection.to_owned()).await,
+            None if DRIVERS.contains(&driver) => open_adapter(driver, connection).await,
+            None => Err(adapter_error(format!(
+                "unsupported driver `{}`, supported drivers are: {}",
+                driver,
+                self.names().join(", ")
+            ))),
+        }
+    }
+
+    // open connects to the store of a driver, retrying while it is unreachable, and returns
+    // an adapter retrying the calls that fail on it.
+    pub async fn open(&self, driver: &str, connection: &str) -> casbin::Result<Box<dyn Adapter>> {
+        if !self.contains(driver) {
+            return self.connect(driver, connection).await;
+        }
+        let what = format!("opening the {} adapter", driver);
+        let adapter = BACKOFF
+            .retry(&what, || self.connect(driver, connection))
+            .await?;
+        Ok(Box::new(RetryAdapter::new(adapter, BACKOFF)))
+    }
+}
+
+// The SQL adapters share the casbin_rule table of the Go sql-adapter: a p_type column
+// followed by the columns v0 to v5 holding the rule fields, empty when unused.
+pub static RULE_FIELDS: usize = 6;
+
+// matches_filter tells whether a rule passes a load filter, whose empty values match anything.
+pub fn matches_filter(rule: &[String], filter: &[&str]) -> bool {
+    filter
+        .iter()
+        .enumerate()
+        .all(|(i, value)| value.is_empty() || rule.get(i).is_some_and(|field| field == value))
+}
+
+// rule_columns pads a rule out to the v0 to v5 columns, rejecting rules with more fields.
+pub fn rule_columns(rule: &[String]) -> casbin::Result<Vec<String>> {
+    if rule.len() > RULE_FIELDS {
+        return Err(adapter_error(format!(
+            "rule has {} fields, at most {} can be stored",
+            rule.len(),
+            RULE_FIELDS
+        )));
+    }
+    let mut columns = rule.to_vec();
+    columns.resize(RULE_FIELDS, String::new());
+    Ok(columns)
+}
+
+// rule_from_columns drops the unused trailing columns of a stored rule.
+pub fn rule_from_columns(mut columns: Vec<String>) -> Vec<String> {
+    while columns.last().is_some_and(|c| c.is_empty()) {
+        columns.pop();
+    }
+    columns
+}
+
+pub fn adapter_error<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> casbin::Error {
+    AdapterError(err.into()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::csv::CsvAdapter;
+    use super::{matches_filter, rule_columns, rule_from_columns, Drivers, RULE_FIELDS};
+    use casbin::MemoryAdapter;
+
+    fn rule(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn test_rule_columns() {
+        let columns = rule_columns(&rule(&["alice", "data1", "read"])).unwrap();
+        assert_eq!(columns, rule(&["alice", "data1", "read", "", "", ""]));
+        assert_eq!(
+            rule_from_columns(columns),
+            rule(&["alice", "data1", "read"])
+        );
+        // Empty fields inside the rule are kept.
+        let columns = rule_columns(&rule(&["alice", "", "read"])).unwrap();
+        assert_eq!(rule_from_columns(columns), rule(&["alice", "", "read"]));
+
+        assert!(rule_columns(&vec![String::from("x"); RULE_FIELDS]).is_ok());
+        assert!(rule_columns(&vec![String::from("x"); RULE_FIELDS + 1]).is_err());
+    }
+
+    #[test]
+    fn test_matches_filter() {
+        let r = rule(&["alice", "data1", "read"]);
+        assert!(matches_filter(&r, &[]));
+        assert!(matches_filter(&r, &["alice"]));
+        assert!(matches_filter(&r, &["", "data1"]));
+        assert!(!matches_filter(&r, &["bob"]));
+        assert!(!matches_filter(&r, &["alice", "data1", "read", "extra"]));
+        assert!(matches_filter(&r, &["alice", "data1", "read", ""]));
+    }
+
+    #[tokio::test]
+    async fn test_drivers() {
+        let mut drivers = Drivers::default();
+        assert!(drivers.contains("file"));
+        assert!(!drivers.contains("memory"));
+
+        drivers.register("memory", |connection: String| async move {
+            if connection.is_empty() {
+                return Err(super::adapter_error("no connection"));
+            }
+            Ok(MemoryAdapter::default())
+        });
+        // A registered driver may replace a built-in one.
+        drivers.register("file", |path: String| async move {
+            Ok(CsvAdapter::new(format!("{}.override", path)))
+        });
+        assert!(drivers.contains("memory"));
+        let names = drivers.names();
+        assert_eq!(names.first().map(String::as_str), Some("file"));
+        assert_eq!(names.last().map(String::as_str), Some("memory"));
+        assert_eq!(names.iter().filter(|name| *name == "file").count(), 1);
+
+        assert!(drivers.connect("memory", "anything").await.is_ok());
+        assert!(drivers.connect("memory", "").await.is_err());
+        let err = drivers.connect("nope", "").await.err().unwrap();
+        assert!(err.to_string().contains("memory"), "{}", err);
+    }
+}
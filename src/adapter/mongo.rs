@@ -0,0 +1,265 @@
+use crate::adapter::{adapter_error, rule_columns, rule_from_columns, RULE_FIELDS};
+use casbin::{Adapter, Filter, Model};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::ClientOptions;
+use mongodb::{Client, Collection, IndexModel};
+use serde::{Deserialize, Serialize};
+
+// Rule is the document a rule is stored in, with the fields of the Go mongodb-adapter.
+#[derive(Serialize, Deserialize)]
+struct Rule {
+    ptype: String,
+    #[serde(default)]
+    v0: String,
+    #[serde(default)]
+    v1: String,
+    #[serde(default)]
+    v2: String,
+    #[serde(default)]
+    v3: String,
+    #[serde(default)]
+    v4: String,
+    #[serde(default)]
+    v5: String,
+}
+
+impl Rule {
+    fn new(ptype: &str, rule: &[String]) -> casbin::Result<Self> {
+        let mut columns = rule_columns(rule)?.into_iter();
+        let mut next = || columns.next().unwrap_or_default();
+        Ok(Rule {
+            ptype: ptype.to_owned(),
+            v0: next(),
+            v1: next(),
+            v2: next(),
+            v3: next(),
+            v4: next(),
+            v5: next(),
+        })
+    }
+
+    fn into_rule(self) -> Vec<String> {
+        rule_from_columns(vec![self.v0, self.v1, self.v2, self.v3, self.v4, self.v5])
+    }
+}
+
+// rule_query matches the documents holding a rule of a policy type exactly.
+fn rule_query(ptype: &str, rule: &[String]) -> casbin::Result<Document> {
+    let mut query = doc! { "ptype": ptype };
+    for (i, value) in rule_columns(rule)?.into_iter().enumerate() {
+        query.insert(format!("v{}", i), value);
+    }
+    Ok(query)
+}
+
+// filter_query matches the documents of a section whose fields hold the non-empty values of a
+// load filter.
+fn filter_query(sec: &str, filter: &[&str]) -> Document {
+    let mut query = doc! { "ptype": { "$regex": format!("^{}", sec) } };
+    for (i, value) in filter.iter().enumerate().take(RULE_FIELDS) {
+        if !value.is_empty() {
+            query.insert(format!("v{}", i), *value);
+        }
+    }
+    query
+}
+
+// MongoAdapter stores one document per rule in the casbin_rule collection of a MongoDB
+// database.
+pub struct MongoAdapter {
+    collection: Collection<Rule>,
+    is_filtered: bool,
+}
+
+impl MongoAdapter {
+    // new connects to the database named by a connection string such as
+    // `mongodb://host:27017/dbname`, the `casbin` database when the string names none, and
+    // indexes the ptype and first rule fields of the collection.
+    pub async fn new(url: &str) -> casbin::Result<Self> {
+        let options = ClientOptions::parse(url).await.map_err(adapter_error)?;
+        let database = options
+            .default_database
+            .clone()
+            .unwrap_or_else(|| String::from("casbin"));
+        let client = Client::with_options(options).map_err(adapter_error)?;
+        let collection = client.database(&database).collection::<Rule>("casbin_rule");
+        let indexes = ["ptype", "v0", "v1", "v2"]
+            .iter()
+            .map(|field| IndexModel::builder().keys(doc! { *field: 1 }).build());
+        collection
+            .create_indexes(indexes, None)
+            .await
+            .map_err(adapter_error)?;
+        Ok(MongoAdapter {
+            collection,
+            is_filtered: false,
+        })
+    }
+
+    async fn load_rules(&self, m: &mut dyn Model, query: Document) -> casbin::Result<()> {
+        let mut cursor = self
+            .collection
+            .find(query, None)
+            .await
+            .map_err(adapter_error)?;
+        while let Some(rule) = cursor.try_next().await.map_err(adapter_error)? {
+            if rule.ptype.is_empty() {
+                continue;
+            }
+            let ptype = rule.ptype.clone();
+            m.add_policy(&ptype[..1], &ptype, rule.into_rule());
+        }
+        Ok(())
+    }
+
+    async fn insert_rules(&self, ptype: &str, rules: &[Vec<String>]) -> casbin::Result<()> {
+        let docs = rules
+            .iter()
+            .map(|rule| Rule::new(ptype, rule))
+            .collect::<casbin::Result<Vec<Rule>>>()?;
+        if !docs.is_empty() {
+            self.collection
+                .insert_many(docs, None)
+                .await
+                .map_err(adapter_error)?;
+        }
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl Adapter for MongoAdapter {
+    async fn load_policy(&self, m: &mut dyn Model) -> casbin::Result<()> {
+        self.load_rules(m, doc! {}).await
+    }
+
+    // load_filtered_policy has the database apply the filter, so only matching rules are read.
+    async fn load_filtered_policy<'a>(
+        &mut self,
+        m: &mut dyn Model,
+        f: Filter<'a>,
+    ) -> casbin::Result<()> {
+        let query = doc! { "$or": [filter_query("p", &f.p), filter_query("g", &f.g)] };
+        self.load_rules(m, query).await?;
+        self.is_filtered = f.p.iter().chain(f.g.iter()).any(|value| !value.is_empty());
+        Ok(())
+    }
+
+    async fn save_policy(&mut self, m: &mut dyn Model) -> casbin::Result<()> {
+        self.clear_policy().await?;
+        for sec in ["p", "g"] {
+            if let Some(assertions) = m.get_model().get(sec) {
+                for (ptype, ast) in assertions {
+                    let rules: Vec<Vec<String>> = ast.get_policy().iter().cloned().collect();
+                    self.insert_rules(ptype, &rules).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn clear_policy(&mut self) -> casbin::Result<()> {
+        self.collection
+            .delete_many(doc! {}, None)
+            .await
+            .map_err(adapter_error)?;
+        Ok(())
+    }
+
+    fn is_filtered(&self) -> bool {
+        self.is_filtered
+    }
+
+    async fn add_policy(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        self.collection
+            .insert_one(Rule::new(ptype, &rule)?, None)
+            .await
+            .map_err(adapter_error)?;
+        Ok(true)
+    }
+
+    async fn add_policies(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        self.insert_rules(ptype, &rules).await?;
+        Ok(true)
+    }
+
+    async fn remove_policy(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        let res = self
+            .collection
+            .delete_many(rule_query(ptype, &rule)?, None)
+            .await
+            .map_err(adapter_error)?;
+        Ok(res.deleted_count > 0)
+    }
+
+    // remove_policies removes nothing and returns false unless every rule is stored.
+    async fn remove_policies(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        let queries = rules
+            .iter()
+            .map(|rule| rule_query(ptype, rule))
+            .collect::<casbin::Result<Vec<Document>>>()?;
+        for query in queries.iter() {
+            let found = self
+                .collection
+                .find_one(query.clone(), None)
+                .await
+                .map_err(adapter_error)?;
+            if found.is_none() {
+                return Ok(false);
+            }
+        }
+        for query in queries {
+            self.collection
+                .delete_many(query, None)
+                .await
+                .map_err(adapter_error)?;
+        }
+        Ok(true)
+    }
+
+    async fn remove_filtered_policy(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        field_index: usize,
+        field_values: Vec<String>,
+    ) -> casbin::Result<bool> {
+        let mut query = doc! { "ptype": ptype };
+        for (i, value) in field_values.into_iter().enumerate() {
+            if value.is_empty() {
+                continue;
+            }
+            if field_index + i >= RULE_FIELDS {
+                return Ok(false);
+            }
+            query.insert(format!("v{}", field_index + i), value);
+        }
+        let res = self
+            .collection
+            .delete_many(query, None)
+            .await
+            .map_err(adapter_error)?;
+        Ok(res.deleted_count > 0)
+    }
+}
@@ -0,0 +1,252 @@
+use crate::adapter::{adapter_error, matches_filter, rule_columns, rule_from_columns};
+use casbin::{Adapter, Filter, Model};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+
+// KEY is the list holding the rules, as in the Go redis-adapter.
+static KEY: &str = "casbin_rules";
+
+// Rule is the JSON a rule is stored as, with the fields of the Go redis-adapter.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Rule {
+    #[serde(rename = "PType")]
+    ptype: String,
+    #[serde(default)]
+    v0: String,
+    #[serde(default)]
+    v1: String,
+    #[serde(default)]
+    v2: String,
+    #[serde(default)]
+    v3: String,
+    #[serde(default)]
+    v4: String,
+    #[serde(default)]
+    v5: String,
+}
+
+impl Rule {
+    fn encode(ptype: &str, rule: &[String]) -> casbin::Result<String> {
+        let mut columns = rule_columns(rule)?.into_iter();
+        let mut next = || columns.next().unwrap_or_default();
+        let rule = Rule {
+            ptype: ptype.to_owned(),
+            v0: next(),
+            v1: next(),
+            v2: next(),
+            v3: next(),
+            v4: next(),
+            v5: next(),
+        };
+        serde_json::to_string(&rule).map_err(adapter_error)
+    }
+
+    fn into_rule(self) -> Vec<String> {
+        rule_from_columns(vec![self.v0, self.v1, self.v2, self.v3, self.v4, self.v5])
+    }
+}
+
+// RedisAdapter stores the rules as JSON in a Redis list, so replicas pointed at the same
+// server share them.
+pub struct RedisAdapter {
+    conn: ConnectionManager,
+    is_filtered: bool,
+}
+
+impl RedisAdapter {
+    // new connects to the server named by a connection string such as
+    // `redis://:password@host:6379/0`, reconnecting whenever the connection drops.
+    pub async fn new(url: &str) -> casbin::Result<Self> {
+        let client = redis::Client::open(url).map_err(adapter_error)?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .map_err(adapter_error)?;
+        Ok(RedisAdapter {
+            conn,
+            is_filtered: false,
+        })
+    }
+
+    // load_rules reads the stored rules as they are, along with the decoded policy type and
+    // fields.
+    async fn load_rules(&self) -> casbin::Result<Vec<(String, String, Vec<String>)>> {
+        let mut conn = self.conn.clone();
+        let values: Vec<String> = conn.lrange(KEY, 0, -1).await.map_err(adapter_error)?;
+        let mut rules = vec![];
+        for value in values {
+            let rule: Rule = serde_json::from_str(&value).map_err(adapter_error)?;
+            if !rule.ptype.is_empty() {
+                let ptype = rule.ptype.clone();
+                rules.push((value, ptype, rule.into_rule()));
+            }
+        }
+        Ok(rules)
+    }
+
+    // remove_values removes every copy of the stored values in one round trip.
+    async fn remove_values(&mut self, values: &[String]) -> casbin::Result<()> {
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for value in values {
+            pipe.lrem(KEY, 0, value).ignore();
+        }
+        pipe.query_async(&mut self.conn)
+            .await
+            .map_err(adapter_error)
+    }
+
+    fn add_loaded(m: &mut dyn Model, rules: Vec<(String, String, Vec<String>)>) {
+        for (_, ptype, rule) in rules {
+            m.add_policy(&ptype[..1], &ptype, rule);
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Adapter for RedisAdapter {
+    async fn load_policy(&self, m: &mut dyn Model) -> casbin::Result<()> {
+        Self::add_loaded(m, self.load_rules().await?);
+        Ok(())
+    }
+
+    async fn load_filtered_policy<'a>(
+        &mut self,
+        m: &mut dyn Model,
+        f: Filter<'a>,
+    ) -> casbin::Result<()> {
+        let rules = self.load_rules().await?;
+        let total = rules.len();
+        let rules: Vec<_> = rules
+            .into_iter()
+            .filter(|(_, ptype, rule)| {
+                matches_filter(rule, if ptype.starts_with('g') { &f.g } else { &f.p })
+            })
+            .collect();
+        self.is_filtered = rules.len() < total;
+        Self::add_loaded(m, rules);
+        Ok(())
+    }
+
+    // save_policy replaces the list in a single atomic pipeline rather than a round trip per
+    // rule.
+    async fn save_policy(&mut self, m: &mut dyn Model) -> casbin::Result<()> {
+        let mut values = vec![];
+        for sec in ["p", "g"] {
+            if let Some(assertions) = m.get_model().get(sec) {
+                for (ptype, ast) in assertions {
+                    for rule in ast.get_policy() {
+                        values.push(Rule::encode(ptype, rule)?);
+                    }
+                }
+            }
+        }
+        let mut pipe = redis::pipe();
+        pipe.atomic().del(KEY).ignore();
+        if !values.is_empty() {
+            pipe.rpush(KEY, values).ignore();
+        }
+        pipe.query_async(&mut self.conn)
+            .await
+            .map_err(adapter_error)
+    }
+
+    async fn clear_policy(&mut self) -> casbin::Result<()> {
+        self.conn.del(KEY).await.map_err(adapter_error)
+    }
+
+    fn is_filtered(&self) -> bool {
+        self.is_filtered
+    }
+
+    async fn add_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        self.add_policies(sec, ptype, vec![rule]).await
+    }
+
+    async fn add_policies(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        let values = rules
+            .iter()
+            .map(|rule| Rule::encode(ptype, rule))
+            .collect::<casbin::Result<Vec<String>>>()?;
+        if !values.is_empty() {
+            let _: () = self.conn.rpush(KEY, values).await.map_err(adapter_error)?;
+        }
+        Ok(true)
+    }
+
+    // remove_policy removes the rule with a single LREM when it is stored as encoded here, and
+    // only looks through the list for rules written by other tools otherwise.
+    async fn remove_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        let value = Rule::encode(ptype, &rule)?;
+        let removed: usize = self.conn.lrem(KEY, 0, value).await.map_err(adapter_error)?;
+        if removed > 0 {
+            return Ok(true);
+        }
+        self.remove_policies(sec, ptype, vec![rule]).await
+    }
+
+    // remove_policies removes nothing and returns false unless every rule is stored.
+    async fn remove_policies(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        let stored = self.load_rules().await?;
+        let mut values = vec![];
+        for rule in rules.iter() {
+            let columns = rule_columns(rule)?;
+            match stored
+                .iter()
+                .find(|(_, t, r)| t == ptype && rule_columns(r).is_ok_and(|c| c == columns))
+            {
+                Some((value, _, _)) => values.push(value.clone()),
+                None => return Ok(false),
+            }
+        }
+        self.remove_values(&values).await?;
+        Ok(true)
+    }
+
+    async fn remove_filtered_policy(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        field_index: usize,
+        field_values: Vec<String>,
+    ) -> casbin::Result<bool> {
+        let values: Vec<String> = self
+            .load_rules()
+            .await?
+            .into_iter()
+            .filter(|(_, t, rule)| {
+                t == ptype
+                    && field_values.iter().enumerate().all(|(i, value)| {
+                        value.is_empty() || rule.get(field_index + i) == Some(value)
+                    })
+            })
+            .map(|(value, _, _)| value)
+            .collect();
+        if values.is_empty() {
+            return Ok(false);
+        }
+        self.remove_values(&values).await?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,350 @@
+use casbin::error::AdapterError;
+use casbin::{Adapter, Filter, Model};
+use std::future::Future;
+use std::time::Duration;
+
+// Backoff is how many times and after how long an operation on a store that failed is retried,
+// doubling the delay after every attempt.
+#[derive(Clone, Copy)]
+pub struct Backoff {
+    pub retries: u32,
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+// BACKOFF rides out a database restarting or a connection being dropped, retrying for about a
+// second and a half.
+pub static BACKOFF: Backoff = Backoff {
+    retries: 3,
+    initial: Duration::from_millis(200),
+    max: Duration::from_secs(2),
+};
+
+impl Backoff {
+    fn should_retry(&self, attempt: u32, err: &casbin::Error) -> bool {
+        attempt < self.retries && is_transient(err)
+    }
+
+    async fn pause(&self, attempt: u32, what: &str, err: &casbin::Error) {
+        let delay = self
+            .initial
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.max);
+        tracing::warn!(?delay, error = %err, "{} failed, retrying", what);
+        tokio::time::sleep(delay).await;
+    }
+
+    pub async fn retry<T, F, Fut>(&self, what: &str, mut op: F) -> casbin::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = casbin::Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let res = op().await;
+            match &res {
+                Err(err) if self.should_retry(attempt, err) => {
+                    self.pause(attempt, what, err).await;
+                    attempt += 1;
+                }
+                _ => return res,
+            }
+        }
+    }
+}
+
+// is_transient tells whether an adapter failed because its store could not be reached or did
+// not answer in time, which a retry can get past, rather than with an error it would fail with
+// again such as a rule too long to store.
+fn is_transient(err: &casbin::Error) -> bool {
+    let err = match err {
+        casbin::Error::AdapterError(AdapterError(err)) => err,
+        _ => return false,
+    };
+    if err.downcast_ref::<std::io::Error>().is_some() {
+        return true;
+    }
+    if let Some(err) = err.downcast_ref::<reqwest::Error>() {
+        return err.is_connect() || err.is_timeout();
+    }
+    #[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+    if let Some(err) = err.downcast_ref::<sqlx::Error>() {
+        // SQLSTATE class 08 is a connection exception and 57P0x the server shutting down.
+        return match err {
+            sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed => true,
+            sqlx::Error::Database(err) => err
+                .code()
+                .is_some_and(|code| code.starts_with("08") || code.starts_with("57P0")),
+            _ => false,
+        };
+    }
+    #[cfg(feature = "mongodb")]
+    if let Some(err) = err.downcast_ref::<mongodb::error::Error>() {
+        use mongodb::error::ErrorKind;
+        return matches!(
+            *err.kind,
+            ErrorKind::Io(_)
+                | ErrorKind::ConnectionPoolCleared { .. }
+                | ErrorKind::ServerSelection { .. }
+        );
+    }
+    #[cfg(feature = "redis")]
+    if let Some(err) = err.downcast_ref::<redis::RedisError>() {
+        return err.is_io_error()
+            || err.is_connection_refusal()
+            || err.is_connection_dropped()
+            || err.is_timeout();
+    }
+    #[cfg(feature = "etcd")]
+    if let Some(err) = err.downcast_ref::<etcd_client::Error>() {
+        return match err {
+            etcd_client::Error::IoError(_) | etcd_client::Error::TransportError(_) => true,
+            etcd_client::Error::GRpcStatus(status) => matches!(
+                status.code(),
+                tonic::Code::Unavailable | tonic::Code::DeadlineExceeded
+            ),
+            _ => false,
+        };
+    }
+    false
+}
+
+// retry! runs an adapter call again while it fails with a transient error, as Backoff::retry does
+// for calls not borrowing the adapter.
+macro_rules! retry {
+    ($self:ident, $what:expr, $op:expr) => {{
+        let mut attempt = 0;
+        loop {
+            let res = $op.await;
+            match &res {
+                Err(err) if $self.backoff.should_retry(attempt, err) => {
+                    $self.backoff.pause(attempt, $what, err).await;
+                    attempt += 1;
+                }
+                _ => break res,
+            }
+        }
+    }};
+}
+
+// RetryAdapter retries the calls of an adapter failing on an unreachable store with a
+// backoff, so a request arriving while the database restarts waits for it instead of failing.
+// The pools and clients of the adapters reconnect on their own, the retries give them the time
+// to.
+//
+// A write whose reply was lost is written again, which at worst stores a rule twice.
+pub struct RetryAdapter {
+    inner: Box<dyn Adapter>,
+    backoff: Backoff,
+}
+
+impl RetryAdapter {
+    pub fn new(inner: Box<dyn Adapter>, backoff: Backoff) -> Self {
+        RetryAdapter { inner, backoff }
+    }
+}
+
+#[tonic::async_trait]
+impl Adapter for RetryAdapter {
+    async fn load_policy(&self, m: &mut dyn Model) -> casbin::Result<()> {
+        retry!(self, "loading the policy", self.inner.load_policy(m))
+    }
+
+    async fn load_filtered_policy<'a>(
+        &mut self,
+        m: &mut dyn Model,
+        f: Filter<'a>,
+    ) -> casbin::Result<()> {
+        retry!(
+            self,
+            "loading the filtered policy",
+            self.inner.load_filtered_policy(m, f.clone())
+        )
+    }
+
+    async fn save_policy(&mut self, m: &mut dyn Model) -> casbin::Result<()> {
+        retry!(self, "saving the policy", self.inner.save_policy(m))
+    }
+
+    async fn clear_policy(&mut self) -> casbin::Result<()> {
+        retry!(self, "clearing the policy", self.inner.clear_policy())
+    }
+
+    fn is_filtered(&self) -> bool {
+        self.inner.is_filtered()
+    }
+
+    async fn add_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        retry!(
+            self,
+            "adding a rule",
+            self.inner.add_policy(sec, ptype, rule.clone())
+        )
+    }
+
+    async fn add_policies(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        retry!(
+            self,
+            "adding rules",
+            self.inner.add_policies(sec, ptype, rules.clone())
+        )
+    }
+
+    async fn remove_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        retry!(
+            self,
+            "removing a rule",
+            self.inner.remove_policy(sec, ptype, rule.clone())
+        )
+    }
+
+    async fn remove_policies(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        retry!(
+            self,
+            "removing rules",
+            self.inner.remove_policies(sec, ptype, rules.clone())
+        )
+    }
+
+    async fn remove_filtered_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        field_index: usize,
+        field_values: Vec<String>,
+    ) -> casbin::Result<bool> {
+        retry!(
+            self,
+            "removing filtered rules",
+            self.inner
+                .remove_filtered_policy(sec, ptype, field_index, field_values.clone())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backoff, RetryAdapter};
+    use crate::adapter::adapter_error;
+    use crate::adapter::csv::{CsvAdapter, CsvStore};
+    use casbin::{CoreApi, DefaultModel};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    static FAST: Backoff = Backoff {
+        retries: 3,
+        initial: Duration::from_millis(1),
+        max: Duration::from_millis(2),
+    };
+
+    fn unreachable() -> casbin::Error {
+        adapter_error(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+    }
+
+    // attempt is an operation failing with err the first failures times it is made, counting
+    // the attempts in attempts.
+    async fn attempt(
+        attempts: &AtomicU32,
+        failures: u32,
+        err: fn() -> casbin::Error,
+    ) -> casbin::Result<u32> {
+        let n = attempts.fetch_add(1, Ordering::Relaxed);
+        if n < failures {
+            Err(err())
+        } else {
+            Ok(n)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors() {
+        let attempts = AtomicU32::new(0);
+        let res = FAST
+            .retry("connecting", || attempt(&attempts, 2, unreachable))
+            .await;
+        assert_eq!(res.unwrap(), 2);
+
+        // Retries stop after FAST.retries.
+        let attempts = AtomicU32::new(0);
+        let res = FAST
+            .retry("connecting", || attempt(&attempts, 10, unreachable))
+            .await;
+        assert!(res.is_err());
+        assert_eq!(attempts.load(Ordering::Relaxed), FAST.retries + 1);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_lasting_errors() {
+        let attempts = AtomicU32::new(0);
+        let res = FAST
+            .retry("saving", || {
+                attempt(&attempts, 10, || {
+                    adapter_error("rule has 7 fields, at most 6 can be stored")
+                })
+            })
+            .await;
+        assert!(res.is_err());
+        assert_eq!(attempts.load(Ordering::Relaxed), 1);
+    }
+
+    // FlakyStore fails its first reads as if its store were unreachable.
+    struct FlakyStore {
+        failures: u32,
+        reads: Arc<AtomicU32>,
+    }
+
+    #[tonic::async_trait]
+    impl CsvStore for FlakyStore {
+        fn name(&self) -> String {
+            String::from("flaky")
+        }
+
+        async fn read(&self) -> casbin::Result<Option<String>> {
+            if self.reads.fetch_add(1, Ordering::Relaxed) < self.failures {
+                return Err(unreachable());
+            }
+            Ok(Some(String::from("p, alice, data1, read\n")))
+        }
+
+        async fn write(&self, _: String) -> casbin::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_adapter() {
+        let reads = Arc::new(AtomicU32::new(0));
+        let store = FlakyStore {
+            failures: 2,
+            reads: reads.clone(),
+        };
+        let adapter = RetryAdapter::new(Box::new(CsvAdapter::with_store(store)), FAST);
+        let m = DefaultModel::from_file("examples/rbac_model.conf")
+            .await
+            .unwrap();
+        let e = casbin::Enforcer::new(m, adapter).await.unwrap();
+        assert!(e.enforce(("alice", "data1", "read")).unwrap());
+        assert_eq!(reads.load(Ordering::Relaxed), 3);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::adapter::adapter_error;
+use crate::adapter::csv::{CsvAdapter, CsvStore};
+use hmac::{Hmac, Mac};
+use reqwest::{Method, RequestBuilder, StatusCode};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+static REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Version is what the policy in memory was loaded from or last written as, which later reads
+// and writes require the object to still be.
+#[derive(Clone, PartialEq)]
+enum Version {
+    Unknown,
+    Absent,
+    ETag(String),
+}
+
+// S3Store keeps the policy in an object of S3 or of an S3-compatible store, signing requests
+// with the credentials in AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and AWS_SESSION_TOKEN.
+// Writes are conditional on the ETag the policy was loaded with, so a policy changed by another
+// server in the meantime is reported instead of overwritten until it is reloaded.
+pub struct S3Store {
+    client: reqwest::Client,
+    url: reqwest::Url,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+    version: Mutex<Version>,
+}
+
+impl S3Store {
+    // new parses a connection string such as `s3://bucket/path/policy.csv`, read from
+    // AWS_REGION or us-east-1. `?region=` picks another region and `?endpoint=` an
+    // S3-compatible server such as `http://localhost:9000`, addressed path-style.
+    pub fn new(connection: &str) -> casbin::Result<Self> {
+        let rest = connection
+            .strip_prefix("s3://")
+            .ok_or_else(|| adapter_error("connection string must start with s3://"))?;
+        let (location, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (bucket, key) = location
+            .split_once('/')
+            .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+            .ok_or_else(|| adapter_error("connection string must be s3://bucket/key"))?;
+
+        let mut region = std::env::var("AWS_REGION").unwrap_or_default();
+        let mut endpoint = String::new();
+        for (name, value) in reqwest::Url::parse(&format!("s3://x/?{}", query))
+            .map_err(adapter_error)?
+            .query_pairs()
+        {
+            match &*name {
+                "region" => region = value.into_owned(),
+                "endpoint" => endpoint = value.trim_end_matches('/').to_owned(),
+                _ => return Err(adapter_error(format!("unknown parameter `{}`", name))),
+            }
+        }
+        if region.is_empty() {
+            region = String::from("us-east-1");
+        }
+        let url = if endpoint.is_empty() {
+            format!(
+                "https://{}.s3.{}.amazonaws.com/{}",
+                bucket,
+                region,
+                uri_encode(key)
+            )
+        } else {
+            format!("{}/{}/{}", endpoint, bucket, uri_encode(key))
+        };
+
+        let access_key = std::env::var("AWS_ACCESS_KEY_ID").unwrap_or_default();
+        let secret_key = std::env::var("AWS_SECRET_ACCESS_KEY").unwrap_or_default();
+        if access_key.is_empty() || secret_key.is_empty() {
+            return Err(adapter_error(
+                "AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY must be set",
+            ));
+        }
+        Ok(S3Store {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .map_err(adapter_error)?,
+            url: reqwest::Url::parse(&url).map_err(adapter_error)?,
+            region,
+            access_key,
+            secret_key,
+            session_token: std::env::var("AWS_SESSION_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty()),
+            version: Mutex::new(Version::Unknown),
+        })
+    }
+
+    // request builds a request signed with AWS Signature Version 4.
+    fn request(&self, method: Method, body: &[u8]) -> RequestBuilder {
+        let (date, time) = utc_now();
+        let amz_date = format!("{}T{}Z", date, time);
+        let payload_hash = hex(&Sha256::digest(body));
+        let host = match self.url.port() {
+            Some(port) => format!("{}:{}", self.url.host_str().unwrap_or_default(), port),
+            None => self.url.host_str().unwrap_or_default().to_owned(),
+        };
+
+        let mut headers = vec![
+            ("host", host),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let signed_headers: Vec<&str> = headers.iter().map(|(name, _)| *name).collect();
+        let signed_headers = signed_headers.join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method,
+            self.url.path(),
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut key = format!("AWS4{}", self.secret_key).into_bytes();
+        for part in [date.as_str(), self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac(&key, part.as_bytes());
+        }
+        let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+
+        let mut request = self.client.request(method, self.url.clone());
+        for (name, value) in headers.into_iter().skip(1) {
+            request = request.header(name, value);
+        }
+        request.header(
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                self.access_key, scope, signed_headers, signature
+            ),
+        )
+    }
+
+    fn set_version(&self, version: Version) {
+        *self.version.lock().unwrap() = version;
+    }
+
+    fn conflict(&self) -> casbin::Error {
+        adapter_error(format!(
+            "{} was modified by someone else since the policy was loaded, reload it",
+            self.url
+        ))
+    }
+
+    async fn get(&self) -> casbin::Result<(Option<String>, Version)> {
+        let response = self
+            .request(Method::GET, b"")
+            .send()
+            .await
+            .map_err(adapter_error)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok((None, Version::Absent));
+        }
+        if !response.status().is_success() {
+            return Err(adapter_error(format!(
+                "reading {} failed with {}",
+                self.url,
+                response.status()
+            )));
+        }
+        let version = etag_version(response.headers());
+        let text = response.text().await.map_err(adapter_error)?;
+        Ok((Some(text), version))
+    }
+}
+
+fn etag_version(headers: &reqwest::header::HeaderMap) -> Version {
+    headers
+        .get("etag")
+        .and_then(|etag| etag.to_str().ok())
+        .map_or(Version::Unknown, |etag| Version::ETag(etag.to_owned()))
+}
+
+#[tonic::async_trait]
+impl CsvStore for S3Store {
+    fn name(&self) -> String {
+        self.url.to_string()
+    }
+
+    async fn read(&self) -> casbin::Result<Option<String>> {
+        let (text, version) = self.get().await?;
+        let mut loaded = self.version.lock().unwrap();
+        match &*loaded {
+            Version::Unknown => *loaded = version,
+            seen if *seen != version => return Err(self.conflict()),
+            _ => {}
+        }
+        Ok(text)
+    }
+
+    async fn load(&self) -> casbin::Result<Option<String>> {
+        let (text, version) = self.get().await?;
+        self.set_version(version);
+        Ok(text)
+    }
+
+    // write replaces the object only if it is still the version the policy was loaded from,
+    // or was last written as.
+    async fn write(&self, text: String) -> casbin::Result<()> {
+        let version = self.version.lock().unwrap().clone();
+        let mut request = self
+            .request(Method::PUT, text.as_bytes())
+            .header("content-type", "text/csv");
+        request = match version {
+            Version::ETag(etag) => request.header("if-match", etag),
+            Version::Absent => request.header("if-none-match", "*"),
+            Version::Unknown => request,
+        };
+        let response = request.body(text).send().await.map_err(adapter_error)?;
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED | StatusCode::CONFLICT => Err(self.conflict()),
+            status if !status.is_success() => Err(adapter_error(format!(
+                "writing {} failed with {}",
+                self.url, status
+            ))),
+            _ => {
+                self.set_version(etag_version(response.headers()));
+                Ok(())
+            }
+        }
+    }
+}
+
+// connect opens the policy CSV kept in S3 under an s3:// connection string.
+pub fn connect(connection: &str) -> casbin::Result<CsvAdapter<S3Store>> {
+    Ok(CsvAdapter::with_store(S3Store::new(connection)?))
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// uri_encode percent-encodes an object key as SigV4 expects, keeping the `/` separators.
+fn uri_encode(key: &str) -> String {
+    key.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// utc_now gives the current UTC date as YYYYMMDD and time as HHMMSS.
+fn utc_now() -> (String, String) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let (days, rem) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    // Civil date from days since the epoch, after Howard Hinnant's civil_from_days.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (
+        format!("{:04}{:02}{:02}", year, month, day),
+        format!("{:02}{:02}{:02}", rem / 3600, rem % 3600 / 60, rem % 60),
+    )
+}
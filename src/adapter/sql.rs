@@ -0,0 +1,326 @@
+use crate::adapter::{adapter_error, rule_columns, rule_from_columns, RULE_FIELDS};
+use casbin::{Adapter, Filter, Model};
+use sqlx::any::{AnyArguments, AnyPool, AnyPoolOptions};
+use sqlx::query::Query;
+use sqlx::{Any, Row, Transaction};
+use std::time::Duration;
+
+static ACQUIRE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Dialect holds what differs between the databases sharing the casbin_rule table.
+pub struct Dialect {
+    // URL schemes of the connection strings the database accepts.
+    pub schemes: &'static [&'static str],
+    // Statements creating the table and its index when they do not exist yet.
+    pub schema: &'static [&'static str],
+    // placeholder gives the bind parameter at a 1-based position.
+    pub placeholder: fn(usize) -> String,
+}
+
+// SqlAdapter stores rules in the casbin_rule table of an SQL database, laid out like the one of
+// the Go sql-adapter: a p_type column followed by the rule fields in v0 to v5.
+pub struct SqlAdapter {
+    pool: AnyPool,
+    dialect: &'static Dialect,
+    is_filtered: bool,
+}
+
+impl SqlAdapter {
+    // connect opens a pool that checks connections before handing them out, so the ones
+    // broken by a database restart are replaced, and gives up waiting for one after
+    // ACQUIRE_TIMEOUT for the call to be retried.
+    pub async fn connect(url: &str, dialect: &'static Dialect) -> casbin::Result<Self> {
+        let options = AnyPoolOptions::new()
+            .test_before_acquire(true)
+            .acquire_timeout(ACQUIRE_TIMEOUT);
+        Self::connect_with(options, url, dialect).await
+    }
+
+    // connect_with connects through a pool configured for the needs of the database.
+    pub async fn connect_with(
+        options: AnyPoolOptions,
+        url: &str,
+        dialect: &'static Dialect,
+    ) -> casbin::Result<Self> {
+        let scheme = url.split(':').next().unwrap_or_default();
+        if !dialect.schemes.contains(&scheme) {
+            return Err(adapter_error(format!(
+                "connection string must start with {}://",
+                dialect.schemes[0]
+            )));
+        }
+        let pool = options.connect(url).await.map_err(adapter_error)?;
+        for statement in dialect.schema {
+            sqlx::query(statement)
+                .execute(&pool)
+                .await
+                .map_err(adapter_error)?;
+        }
+        Ok(SqlAdapter {
+            pool,
+            dialect,
+            is_filtered: false,
+        })
+    }
+
+    // rule_sql builds a statement matching p_type and the given rule columns against bind
+    // parameters in that order.
+    fn rule_sql(&self, prefix: &str, columns: &[String], sep: &str) -> String {
+        let placeholder = self.dialect.placeholder;
+        let conditions: Vec<String> = std::iter::once("p_type".to_owned())
+            .chain(columns.iter().cloned())
+            .enumerate()
+            .map(|(i, column)| format!("{} = {}", column, placeholder(i + 1)))
+            .collect();
+        format!("{}{}", prefix, conditions.join(sep))
+    }
+
+    fn insert_sql(&self) -> String {
+        let placeholders: Vec<String> = (1..=RULE_FIELDS + 1)
+            .map(|i| (self.dialect.placeholder)(i))
+            .collect();
+        format!(
+            "INSERT INTO casbin_rule (p_type, v0, v1, v2, v3, v4, v5) VALUES ({})",
+            placeholders.join(", ")
+        )
+    }
+
+    // filter_sql builds the condition selecting the rules of each section whose fields equal
+    // the non-empty values of its load filter, with the values to bind to it. It is None when
+    // no rule can match.
+    fn filter_sql(&self, filters: &[(&str, &[&str])]) -> Option<(String, Vec<String>)> {
+        let placeholder = self.dialect.placeholder;
+        let mut clauses = vec![];
+        let mut values = vec![];
+        for (sec, filter) in filters {
+            if filter
+                .iter()
+                .skip(RULE_FIELDS)
+                .any(|value| !value.is_empty())
+            {
+                continue;
+            }
+            values.push(format!("{}%", sec));
+            let mut conditions = vec![format!("p_type LIKE {}", placeholder(values.len()))];
+            for (i, value) in filter.iter().enumerate() {
+                if !value.is_empty() {
+                    values.push(value.to_string());
+                    conditions.push(format!("v{} = {}", i, placeholder(values.len())));
+                }
+            }
+            clauses.push(format!("({})", conditions.join(" AND ")));
+        }
+        if clauses.is_empty() {
+            return None;
+        }
+        Some((clauses.join(" OR "), values))
+    }
+
+    async fn load_rules(
+        &self,
+        condition: Option<(String, Vec<String>)>,
+    ) -> casbin::Result<Vec<(String, Vec<String>)>> {
+        let mut sql = String::from("SELECT p_type, v0, v1, v2, v3, v4, v5 FROM casbin_rule");
+        let mut values = vec![];
+        if let Some((condition, binds)) = condition {
+            sql = format!("{} WHERE {}", sql, condition);
+            values = binds;
+        }
+        let mut query = sqlx::query(&sql);
+        for value in values {
+            query = query.bind(value);
+        }
+        let rows = query.fetch_all(&self.pool).await.map_err(adapter_error)?;
+        rows.iter()
+            .map(|row| {
+                let ptype: String = row.try_get(0).map_err(adapter_error)?;
+                let columns = (1..=RULE_FIELDS)
+                    .map(|i| row.try_get(i))
+                    .collect::<Result<Vec<String>, _>>()
+                    .map_err(adapter_error)?;
+                Ok((ptype, rule_from_columns(columns)))
+            })
+            .collect()
+    }
+
+    // bind_rule binds the policy type and the padded columns of a rule to a statement.
+    fn bind_rule<'q>(
+        sql: &'q str,
+        ptype: &'q str,
+        rule: &[String],
+    ) -> casbin::Result<Query<'q, Any, AnyArguments<'q>>> {
+        let mut query = sqlx::query(sql).bind(ptype);
+        for column in rule_columns(rule)? {
+            query = query.bind(column);
+        }
+        Ok(query)
+    }
+
+    fn delete_sql(&self) -> String {
+        let columns: Vec<String> = (0..RULE_FIELDS).map(|i| format!("v{}", i)).collect();
+        self.rule_sql("DELETE FROM casbin_rule WHERE ", &columns, " AND ")
+    }
+
+    async fn insert_rules(
+        &self,
+        tx: &mut Transaction<'_, Any>,
+        ptype: &str,
+        rules: &[Vec<String>],
+    ) -> casbin::Result<()> {
+        let sql = self.insert_sql();
+        for rule in rules {
+            Self::bind_rule(&sql, ptype, rule)?
+                .execute(&mut *tx)
+                .await
+                .map_err(adapter_error)?;
+        }
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl Adapter for SqlAdapter {
+    async fn load_policy(&self, m: &mut dyn Model) -> casbin::Result<()> {
+        for (ptype, rule) in self.load_rules(None).await? {
+            m.add_policy(&ptype[..1], &ptype, rule);
+        }
+        Ok(())
+    }
+
+    // load_filtered_policy has the database apply the filter, so only matching rules are read.
+    async fn load_filtered_policy<'a>(
+        &mut self,
+        m: &mut dyn Model,
+        f: Filter<'a>,
+    ) -> casbin::Result<()> {
+        if let Some(condition) = self.filter_sql(&[("p", &f.p), ("g", &f.g)]) {
+            for (ptype, rule) in self.load_rules(Some(condition)).await? {
+                m.add_policy(&ptype[..1], &ptype, rule);
+            }
+        }
+        self.is_filtered = f.p.iter().chain(f.g.iter()).any(|value| !value.is_empty());
+        Ok(())
+    }
+
+    async fn save_policy(&mut self, m: &mut dyn Model) -> casbin::Result<()> {
+        let mut tx = self.pool.begin().await.map_err(adapter_error)?;
+        sqlx::query("DELETE FROM casbin_rule")
+            .execute(&mut tx)
+            .await
+            .map_err(adapter_error)?;
+        for sec in ["p", "g"] {
+            if let Some(assertions) = m.get_model().get(sec) {
+                for (ptype, ast) in assertions {
+                    let rules: Vec<Vec<String>> = ast.get_policy().iter().cloned().collect();
+                    self.insert_rules(&mut tx, ptype, &rules).await?;
+                }
+            }
+        }
+        tx.commit().await.map_err(adapter_error)
+    }
+
+    async fn clear_policy(&mut self) -> casbin::Result<()> {
+        sqlx::query("DELETE FROM casbin_rule")
+            .execute(&self.pool)
+            .await
+            .map_err(adapter_error)?;
+        Ok(())
+    }
+
+    fn is_filtered(&self) -> bool {
+        self.is_filtered
+    }
+
+    // add_policy runs a single INSERT, outside of a transaction.
+    async fn add_policy(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        let sql = self.insert_sql();
+        Self::bind_rule(&sql, ptype, &rule)?
+            .execute(&self.pool)
+            .await
+            .map_err(adapter_error)?;
+        Ok(true)
+    }
+
+    async fn add_policies(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        let mut tx = self.pool.begin().await.map_err(adapter_error)?;
+        self.insert_rules(&mut tx, ptype, &rules).await?;
+        tx.commit().await.map_err(adapter_error)?;
+        Ok(true)
+    }
+
+    // remove_policy runs a single DELETE, outside of a transaction.
+    async fn remove_policy(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        let sql = self.delete_sql();
+        let res = Self::bind_rule(&sql, ptype, &rule)?
+            .execute(&self.pool)
+            .await
+            .map_err(adapter_error)?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    // remove_policies removes nothing and returns false unless every rule is stored.
+    async fn remove_policies(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        let sql = self.delete_sql();
+        let mut tx = self.pool.begin().await.map_err(adapter_error)?;
+        for rule in rules.iter() {
+            let res = Self::bind_rule(&sql, ptype, rule)?
+                .execute(&mut tx)
+                .await
+                .map_err(adapter_error)?;
+            if res.rows_affected() == 0 {
+                tx.rollback().await.map_err(adapter_error)?;
+                return Ok(false);
+            }
+        }
+        tx.commit().await.map_err(adapter_error)?;
+        Ok(true)
+    }
+
+    async fn remove_filtered_policy(
+        &mut self,
+        _sec: &str,
+        ptype: &str,
+        field_index: usize,
+        field_values: Vec<String>,
+    ) -> casbin::Result<bool> {
+        let mut columns = vec![];
+        let mut values = vec![];
+        for (i, value) in field_values.into_iter().enumerate() {
+            if value.is_empty() {
+                continue;
+            }
+            if field_index + i >= RULE_FIELDS {
+                return Ok(false);
+            }
+            columns.push(format!("v{}", field_index + i));
+            values.push(value);
+        }
+        let sql = self.rule_sql("DELETE FROM casbin_rule WHERE ", &columns, " AND ");
+        let mut query = sqlx::query(&sql).bind(ptype);
+        for value in values {
+            query = query.bind(value);
+        }
+        let res = query.execute(&self.pool).await.map_err(adapter_error)?;
+        Ok(res.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,41 @@
+use crate::adapter::sql::{Dialect, SqlAdapter};
+use sqlx::any::AnyPoolOptions;
+
+pub static SQLITE: Dialect = Dialect {
+    schemes: &["sqlite"],
+    schema: &[
+        "CREATE TABLE IF NOT EXISTS casbin_rule (
+            p_type VARCHAR(32) DEFAULT '' NOT NULL,
+            v0 VARCHAR(255) DEFAULT '' NOT NULL,
+            v1 VARCHAR(255) DEFAULT '' NOT NULL,
+            v2 VARCHAR(255) DEFAULT '' NOT NULL,
+            v3 VARCHAR(255) DEFAULT '' NOT NULL,
+            v4 VARCHAR(255) DEFAULT '' NOT NULL,
+            v5 VARCHAR(255) DEFAULT '' NOT NULL
+        )",
+        "CREATE INDEX IF NOT EXISTS idx_casbin_rule ON casbin_rule (p_type, v0, v1)",
+    ],
+    placeholder: |_| String::from("?"),
+};
+
+// connect opens the casbin_rule table of the SQLite database file named by a connection string
+// such as `sqlite://policy.db`, creating the file when it does not exist, or of an in-memory
+// database for `sqlite::memory:`.
+// An in-memory database lives only as long as a connection to it, so it gets a single
+// connection that the pool never closes.
+pub async fn connect(url: &str) -> casbin::Result<SqlAdapter> {
+    let (database, params) = url.split_once('?').unwrap_or((url, ""));
+    let in_memory = database.ends_with(":memory:") || params.contains("mode=memory");
+    if in_memory {
+        let options = AnyPoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None);
+        return SqlAdapter::connect_with(options, url, &SQLITE).await;
+    }
+    if params.contains("mode=") {
+        return SqlAdapter::connect(url, &SQLITE).await;
+    }
+    let sep = if params.is_empty() { '?' } else { '&' };
+    SqlAdapter::connect(&format!("{}{}mode=rwc", url, sep), &SQLITE).await
+}
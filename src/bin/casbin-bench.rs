@@ -0,0 +1,368 @@
+use casbin_grpc::casbin_proto::casbin_client::CasbinClient;
+use casbin_grpc::casbin_proto::casbin_server::CasbinServer;
+use casbin_grpc::casbin_proto::{
+    batch_enforce_request, policies_request, BatchEnforceRequest, DecisionCacheOptions,
+    EnforceRequest, NewEnforcerRequest, PoliciesRequest,
+};
+use casbin_grpc::CasbinGRPC;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, Instant};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::{Channel, Server};
+
+// casbin-bench fills an enforcer with a synthetic RBAC policy and runs concurrent workloads of
+// Enforce and BatchEnforce calls against it, reporting their throughput and latency. It serves
+// the Casbin service itself on a port of its own unless given the --target of a server.
+
+static USAGE: &str = "usage: casbin-bench [options]
+  --target URL        server to load, such as http://[::1]:50051; one is served in-process if empty
+  --users N           users the requests are made for (1000)
+  --roles N           roles the users are given (100)
+  --resources N       resources the rules and requests are about (1000)
+  --policies N        rules granting a role an action on a resource (10000)
+  --depth N           levels of roles inheriting from each other (1)
+  --workload W        enforce, batch or both (both)
+  --calls N           Enforce calls to make, and decisions to ask BatchEnforce for (20000)
+  --batch-size N      requests per BatchEnforce call (100)
+  --concurrency N     calls in flight at a time (32)
+  --no-cache          decide every request anew instead of caching 10000 decisions
+  --seed N            seed of the policy and the requests (1)";
+
+// CACHED_DECISIONS is how many decisions the enforcer benchmarked caches, unless --no-cache.
+static CACHED_DECISIONS: i64 = 10_000;
+
+static MODEL: &str = "[request_definition]
+r = sub, obj, act
+
+[policy_definition]
+p = sub, obj, act
+
+[role_definition]
+g = _, _
+
+[policy_effect]
+e = some(where (p.eft == allow))
+
+[matchers]
+m = g(r.sub, p.sub) && r.obj == p.obj && r.act == p.act
+";
+
+static ACTIONS: &[&str] = &["read", "write", "delete"];
+
+// RULES_PER_CALL bounds the rules added per AddPolicies call while loading the policy.
+static RULES_PER_CALL: usize = 1000;
+
+struct Options {
+    target: String,
+    users: usize,
+    roles: usize,
+    resources: usize,
+    policies: usize,
+    depth: usize,
+    workload: String,
+    calls: usize,
+    batch_size: usize,
+    concurrency: usize,
+    no_cache: bool,
+    seed: u64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            target: String::new(),
+            users: 1000,
+            roles: 100,
+            resources: 1000,
+            policies: 10_000,
+            depth: 1,
+            workload: "both".to_owned(),
+            calls: 20_000,
+            batch_size: 100,
+            concurrency: 32,
+            no_cache: false,
+            seed: 1,
+        }
+    }
+}
+
+impl Options {
+    fn parse() -> Result<Self, String> {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--no-cache" {
+                options.no_cache = true;
+                continue;
+            }
+            if arg == "-h" || arg == "--help" {
+                return Err(String::new());
+            }
+            if !arg.starts_with("--") || !USAGE.contains(&format!("  {} ", arg)) {
+                return Err(format!("unknown option {}", arg));
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} needs a value", arg))?;
+            let number = || {
+                value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| format!("{} needs a positive number, not {}", arg, value))
+            };
+            match arg.as_str() {
+                "--target" => options.target = value.clone(),
+                "--users" => options.users = number()?,
+                "--roles" => options.roles = number()?,
+                "--resources" => options.resources = number()?,
+                "--policies" => options.policies = number()?,
+                "--depth" => options.depth = number()?,
+                "--calls" => options.calls = number()?,
+                "--batch-size" => options.batch_size = number()?,
+                "--concurrency" => options.concurrency = number()?,
+                "--seed" => options.seed = number()? as u64,
+                "--workload" => match value.as_str() {
+                    "enforce" | "batch" | "both" => options.workload = value.clone(),
+                    _ => return Err(format!("unknown workload {}", value)),
+                },
+                _ => return Err(format!("unknown option {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+
+    // request makes the params of a request for a random user, resource and action.
+    fn request(&self, rng: &mut StdRng) -> Vec<String> {
+        vec![
+            format!("user{}", rng.gen_range(0..self.users)),
+            format!("data{}", rng.gen_range(0..self.resources)),
+            ACTIONS[rng.gen_range(0..ACTIONS.len())].to_owned(),
+        ]
+    }
+}
+
+// serve serves the Casbin service on a free port of the loopback interface, getting its URL.
+async fn serve() -> Result<String, Box<dyn std::error::Error>> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(
+        Server::builder()
+            .add_service(CasbinServer::new(CasbinGRPC::new_server()))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    Ok(format!("http://{}", addr))
+}
+
+// load creates an enforcer without an adapter and fills it with the synthetic policy, getting
+// its handle.
+async fn load(
+    client: &mut CasbinClient<Channel>,
+    options: &Options,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let handle = client
+        .new_enforcer(NewEnforcerRequest {
+            model_text: MODEL.to_owned(),
+            adapter_handle: -1,
+            decision_cache: Some(DecisionCacheOptions {
+                disabled: options.no_cache,
+                max_entries: CACHED_DECISIONS,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .await?
+        .into_inner()
+        .handler;
+
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let policies: Vec<Vec<String>> = (0..options.policies)
+        .map(|i| {
+            vec![
+                format!("role{}", i % options.roles),
+                format!("data{}", rng.gen_range(0..options.resources)),
+                ACTIONS[rng.gen_range(0..ACTIONS.len())].to_owned(),
+            ]
+        })
+        .collect();
+    // Every user is given a role, and the roles of each level inherit those of the level below.
+    let mut groupings: Vec<Vec<String>> = (0..options.users)
+        .map(|i| vec![format!("user{}", i), format!("role{}", i % options.roles)])
+        .collect();
+    for i in 0..options.roles {
+        if i % options.depth != 0 {
+            groupings.push(vec![format!("role{}", i), format!("role{}", i - 1)]);
+        }
+    }
+    for (p_type, rules) in [("p", policies), ("g", groupings)] {
+        for chunk in rules.chunks(RULES_PER_CALL) {
+            let rules = chunk
+                .iter()
+                .map(|params| policies_request::D {
+                    params: params.clone(),
+                })
+                .collect();
+            let request = PoliciesRequest {
+                enforcer_handler: handle,
+                p_type: p_type.to_owned(),
+                rules,
+            };
+            if p_type == "p" {
+                client.add_named_policies(request).await?;
+            } else {
+                client.add_named_grouping_policies(request).await?;
+            }
+        }
+    }
+    Ok(handle)
+}
+
+// Report is what a workload measured: the latency of every call, and how many decisions they
+// made over how long.
+struct Report {
+    latencies: Vec<Duration>,
+    decisions: usize,
+    elapsed: Duration,
+    failed: usize,
+}
+
+impl Report {
+    fn print(&mut self, name: &str) {
+        self.latencies.sort_unstable();
+        let percentile = |p: f64| {
+            let i = ((self.latencies.len() as f64 * p).ceil() as usize).max(1) - 1;
+            self.latencies.get(i).copied().unwrap_or_default()
+        };
+        let secs = self.elapsed.as_secs_f64();
+        println!(
+            "{:<8} {:>7} calls {:>9.0} calls/s {:>10.0} decisions/s  p50 {:>9.3?}  p99 {:>9.3?}  max {:>9.3?}{}",
+            name,
+            self.latencies.len(),
+            self.latencies.len() as f64 / secs,
+            self.decisions as f64 / secs,
+            percentile(0.5),
+            percentile(0.99),
+            self.latencies.last().copied().unwrap_or_default(),
+            match self.failed {
+                0 => String::new(),
+                n => format!("  {} failed", n),
+            }
+        );
+    }
+}
+
+// run makes calls calls of batch_size requests each, BatchEnforce ones unless batch_size is 0,
+// with up to concurrency of them in flight.
+async fn run(
+    client: &CasbinClient<Channel>,
+    handle: i32,
+    options: &Options,
+    calls: usize,
+    batch_size: usize,
+) -> Report {
+    let start = Instant::now();
+    let tasks: Vec<_> = (0..options.concurrency)
+        .map(|task| {
+            let mut client = client.clone();
+            let mut rng = StdRng::seed_from_u64(options.seed + 1 + task as u64);
+            let requests: Vec<Vec<Vec<String>>> = (task..calls)
+                .step_by(options.concurrency)
+                .map(|_| {
+                    (0..batch_size.max(1))
+                        .map(|_| options.request(&mut rng))
+                        .collect()
+                })
+                .collect();
+            tokio::spawn(async move {
+                let (mut latencies, mut failed) = (Vec::with_capacity(requests.len()), 0);
+                for mut call in requests {
+                    let start = Instant::now();
+                    let res = if batch_size == 0 {
+                        client
+                            .enforce(EnforceRequest {
+                                enforcer_handler: handle,
+                                params: call.pop().unwrap_or_default(),
+                            })
+                            .await
+                            .map(|_| ())
+                    } else {
+                        client
+                            .batch_enforce(BatchEnforceRequest {
+                                enforcer_handler: handle,
+                                requests: call
+                                    .into_iter()
+                                    .map(|params| batch_enforce_request::D { params })
+                                    .collect(),
+                            })
+                            .await
+                            .map(|_| ())
+                    };
+                    latencies.push(start.elapsed());
+                    if res.is_err() {
+                        failed += 1;
+                    }
+                }
+                (latencies, failed)
+            })
+        })
+        .collect();
+    let mut report = Report {
+        latencies: Vec::with_capacity(calls),
+        decisions: calls * batch_size.max(1),
+        elapsed: Duration::ZERO,
+        failed: 0,
+    };
+    for task in tasks {
+        if let Ok((latencies, failed)) = task.await {
+            report.latencies.extend(latencies);
+            report.failed += failed;
+        }
+    }
+    report.elapsed = start.elapsed();
+    report.decisions -= report.failed * batch_size.max(1);
+    report
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = match Options::parse() {
+        Ok(options) => options,
+        Err(err) => {
+            if !err.is_empty() {
+                eprintln!("{}", err);
+            }
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+    let target = match options.target.as_str() {
+        "" => serve().await?,
+        target => target.to_owned(),
+    };
+    let mut client = casbin_grpc::client::connect(&target).await?;
+
+    let start = Instant::now();
+    let handle = load(&mut client, &options).await?;
+    println!(
+        "Loaded {} rules for {} users in {} roles over {} levels in {:?}",
+        options.policies,
+        options.users,
+        options.roles,
+        options.depth,
+        start.elapsed()
+    );
+
+    if options.workload != "batch" {
+        run(&client, handle, &options, options.calls, 0)
+            .await
+            .print("Enforce");
+    }
+    if options.workload != "enforce" {
+        let calls = (options.calls / options.batch_size).max(1);
+        run(&client, handle, &options, calls, options.batch_size)
+            .await
+            .print("Batch");
+    }
+    Ok(())
+}
@@ -0,0 +1,1900 @@
+use crate::casbin_proto::{self, casbin_client::CasbinClient as RawClient};
+use crate::datastructure::lru::LruCache;
+use rand::Rng;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tonic::codec::CompressionEncoding;
+use tonic::codegen::InterceptedService;
+use tonic::metadata::{AsciiMetadataValue, MetadataKey};
+use tonic::service::Interceptor;
+use tonic::transport::{Channel, Endpoint, Error};
+use tonic::{Code, Request, Response, Status};
+use tonic_health::proto::health_check_response::ServingStatus;
+use tonic_health::proto::health_client::HealthClient;
+use tonic_health::proto::HealthCheckRequest;
+use tower::discover::Change;
+
+pub mod blocking;
+
+// connect makes a client of the Casbin service at target: the http:// URL of a server, or
+// `unix:/path/of/socket`, also written `unix:///path/of/socket`, for a server on a unix socket
+// of the same host.
+pub async fn connect(target: &str) -> Result<RawClient<Channel>, Error> {
+    let endpoint = Endpoint::from_shared(endpoint_uri(target))?;
+    Ok(RawClient::new(channel(endpoint, target).await?))
+}
+
+// connect_compressed makes a client like connect that compresses its requests with encoding
+// and asks for replies compressed with it, for a server with that compression, as the others
+// reject compressed requests.
+pub async fn connect_compressed(
+    target: &str,
+    encoding: CompressionEncoding,
+) -> Result<RawClient<Channel>, Error> {
+    let client = connect(target).await?;
+    Ok(client.send_compressed(encoding).accept_compressed(encoding))
+}
+
+// endpoint_uri is the URI of the endpoint of target. The URI of a unix socket only names the
+// authority of the requests, they all go to the socket.
+fn endpoint_uri(target: &str) -> String {
+    if target.starts_with("unix:") {
+        String::from("http://localhost")
+    } else {
+        target.to_owned()
+    }
+}
+
+async fn channel(endpoint: Endpoint, target: &str) -> Result<Channel, Error> {
+    match target.strip_prefix("unix:") {
+        #[cfg(unix)]
+        Some(path) => {
+            let path = path.strip_prefix("//").unwrap_or(path).to_owned();
+            endpoint
+                .connect_with_connector(tower::service_fn(move |_| {
+                    tokio::net::UnixStream::connect(path.clone())
+                }))
+                .await
+        }
+        _ => endpoint.connect().await,
+    }
+}
+
+// Credentials are the headers a CasbinClient authenticates its calls with, and names their
+// tenant with.
+#[derive(Clone, Default)]
+pub struct Credentials {
+    headers: Vec<(MetadataKey<tonic::metadata::Ascii>, AsciiMetadataValue)>,
+}
+
+impl Interceptor for Credentials {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        for (key, value) in &self.headers {
+            request.metadata_mut().insert(key.clone(), value.clone());
+        }
+        Ok(request)
+    }
+}
+
+// RetryPolicy retries the calls of a CasbinClient that only read, such as Enforce and the
+// Get RPCs, failing with UNAVAILABLE, as servers do while they restart. Calls changing the
+// policy are never retried, as one may have been applied before the connection broke.
+//
+// An attempt waits a random time up to its backoff, starting at initial_backoff and growing by
+// multiplier up to max_backoff. Retries spend from a budget shared by the clones of a client,
+// which holds up to budget_reserve retries and earns budget_ratio of a retry per call, so
+// retries stay a fraction of the calls while a server is down.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    pub budget_ratio: f64,
+    pub budget_reserve: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+            multiplier: 2.0,
+            budget_ratio: 0.1,
+            budget_reserve: 10.0,
+        }
+    }
+}
+
+// Retries are a retry policy with the budget it has left.
+struct Retries {
+    policy: RetryPolicy,
+    budget: Mutex<f64>,
+}
+
+impl Retries {
+    fn new(policy: RetryPolicy) -> Self {
+        let budget = Mutex::new(policy.budget_reserve);
+        Retries { policy, budget }
+    }
+
+    fn earn(&self) {
+        let mut budget = self.budget.lock().unwrap();
+        *budget = (*budget + self.policy.budget_ratio).min(self.policy.budget_reserve);
+    }
+
+    fn spend(&self) -> bool {
+        let mut budget = self.budget.lock().unwrap();
+        if *budget < 1.0 {
+            return false;
+        }
+        *budget -= 1.0;
+        true
+    }
+
+    // backoff is how long to wait before the retry following attempt, counted from 1.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let policy = &self.policy;
+        let backoff = policy.initial_backoff.as_secs_f64()
+            * policy.multiplier.powi(attempt.saturating_sub(1) as i32);
+        let backoff = backoff.min(policy.max_backoff.as_secs_f64());
+        Duration::from_secs_f64(rand::thread_rng().gen_range(0.0..=backoff))
+    }
+}
+
+// WATCH_RETRY is how long a DecisionCache waits before watching an enforcer again, after its
+// watch failed or ended.
+static WATCH_RETRY: Duration = Duration::from_secs(1);
+
+// DecisionCache keeps the Enforce decisions of a CasbinClient, and of its clones, for up to
+// ttl, evicting the least recently used ones past capacity. The enforcers it caches decisions
+// of are watched through WatchPolicyUpdates, and their decisions dropped on any change of
+// their policy. While a watch is down, the decisions of its enforcer are not cached.
+struct DecisionCache {
+    state: Mutex<CacheState>,
+    // Watches end once the cache is dropped, along with the sender.
+    _closed: watch::Sender<()>,
+    closed: watch::Receiver<()>,
+}
+
+struct CacheState {
+    decisions: LruCache<(i32, Vec<String>), bool>,
+    watches: HashMap<i32, Watch>,
+}
+
+// Watch is the state of the watch of an enforcer. Its generation counts the changes of the
+// policy, so decisions made before one are not cached after it.
+#[derive(Default)]
+struct Watch {
+    live: bool,
+    generation: u64,
+}
+
+// Lookup is a decision found in a DecisionCache, or the generation to cache the decision with,
+// if the watch of its enforcer is live.
+enum Lookup {
+    Hit(bool),
+    Miss(Option<u64>),
+}
+
+impl CacheState {
+    // invalidate drops the decisions of the enforcer behind handle.
+    fn invalidate(&mut self, handle: i32) {
+        let watch = self.watches.entry(handle).or_default();
+        watch.generation += 1;
+        self.decisions.retain(|(h, _), _| *h != handle);
+    }
+}
+
+impl DecisionCache {
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        let (_closed, closed) = watch::channel(());
+        DecisionCache {
+            state: Mutex::new(CacheState {
+                decisions: LruCache::new(capacity).with_ttl(ttl),
+                watches: HashMap::new(),
+            }),
+            _closed,
+            closed,
+        }
+    }
+
+    // lookup finds the decision of params by the enforcer behind handle, starting to watch the
+    // enforcer with client if it is not yet.
+    fn lookup(
+        self: &Arc<Self>,
+        client: &CasbinClient,
+        handle: i32,
+        params: Vec<String>,
+    ) -> (Lookup, Vec<String>) {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let watch = match state.watches.get(&handle) {
+            Some(watch) => watch,
+            None => {
+                state.watches.insert(handle, Watch::default());
+                tokio::spawn(watch_policy(Arc::downgrade(self), client.raw(), handle));
+                return (Lookup::Miss(None), params);
+            }
+        };
+        if !watch.live {
+            return (Lookup::Miss(None), params);
+        }
+        let generation = watch.generation;
+        let key = (handle, params);
+        match state.decisions.get(&key) {
+            Some(allowed) => (Lookup::Hit(*allowed), Vec::new()),
+            None => (Lookup::Miss(Some(generation)), key.1),
+        }
+    }
+
+    // insert caches the decision of params by the enforcer behind handle, unless its policy
+    // changed since generation.
+    fn insert(&self, handle: i32, params: Vec<String>, allowed: bool, generation: u64) {
+        let mut state = self.state.lock().unwrap();
+        match state.watches.get(&handle) {
+            Some(watch) if watch.live && watch.generation == generation => {}
+            _ => return,
+        }
+        state.decisions.insert((handle, params), allowed);
+    }
+
+    // set_live marks the watch of the enforcer behind handle live or down, dropping its
+    // decisions as changes may have been missed.
+    fn set_live(&self, handle: i32, live: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.invalidate(handle);
+        state.watches.entry(handle).or_default().live = live;
+    }
+}
+
+// watch_policy watches the policy of the enforcer behind handle for as long as cache is kept,
+// dropping the decisions of the enforcer on every change.
+async fn watch_policy(
+    cache: Weak<DecisionCache>,
+    mut client: RawClient<InterceptedService<Channel, Credentials>>,
+    handle: i32,
+) {
+    let mut closed = match cache.upgrade() {
+        Some(cache) => cache.closed.clone(),
+        None => return,
+    };
+    loop {
+        let request = casbin_proto::WatchPolicyUpdatesRequest {
+            enforcer_handler: handle,
+        };
+        let watched = tokio::select! {
+            res = client.watch_policy_updates(request) => res,
+            _ = closed.changed() => return,
+        };
+        if let (Ok(updates), Some(live)) = (watched, cache.upgrade()) {
+            live.set_live(handle, true);
+            drop(live);
+            let mut updates = updates.into_inner();
+            loop {
+                let update = tokio::select! {
+                    update = updates.message() => update,
+                    _ = closed.changed() => return,
+                };
+                match (update, cache.upgrade()) {
+                    (Ok(Some(_)), Some(cache)) => cache.state.lock().unwrap().invalidate(handle),
+                    _ => break,
+                }
+            }
+        }
+        match cache.upgrade() {
+            Some(cache) => cache.set_live(handle, false),
+            None => return,
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(WATCH_RETRY) => {}
+            _ = closed.changed() => return,
+        }
+    }
+}
+
+// HEALTH_INTERVAL is how often a client of several endpoints checks their health, by default.
+static HEALTH_INTERVAL: Duration = Duration::from_secs(5);
+
+// Balance is how a client of several endpoints, or of a DNS name, spreads its calls over the
+// endpoints found healthy by the gRPC health service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Balance {
+    // Spread sends each call to the less loaded of two healthy endpoints picked at random.
+    Spread,
+    // PickFirst sends every call to the first healthy endpoint, in the order they were given,
+    // failing over to the next one while it is not.
+    PickFirst,
+}
+
+// dns_name is the host:port of a `dns:///host:port` or `dns:host:port` target, which stands
+// for every address the name resolves to.
+fn dns_name(target: &str) -> Option<&str> {
+    let name = target.strip_prefix("dns:")?;
+    Some(name.strip_prefix("///").unwrap_or(name))
+}
+
+// Pool keeps the endpoints of the targets of a balanced channel, and which of them take calls.
+struct Pool {
+    targets: Vec<String>,
+    balance: Balance,
+    endpoint: Box<dyn Fn(String) -> Result<Endpoint, Error> + Send>,
+    // resolved are the endpoint URIs of each target, kept when it fails to resolve again.
+    resolved: HashMap<String, Vec<String>>,
+    health: HashMap<String, HealthClient<Channel>>,
+    members: Vec<String>,
+    changes: mpsc::Sender<Change<String, Endpoint>>,
+}
+
+impl Pool {
+    async fn resolve(&mut self) -> Vec<String> {
+        let mut uris = Vec::new();
+        for target in &self.targets {
+            let resolved = match dns_name(target) {
+                Some(name) => match tokio::net::lookup_host(name).await {
+                    Ok(addrs) => addrs.map(|addr| format!("http://{}", addr)).collect(),
+                    Err(_) => self.resolved.get(target).cloned().unwrap_or_default(),
+                },
+                None => vec![target.clone()],
+            };
+            for uri in &resolved {
+                if !uris.contains(uri) {
+                    uris.push(uri.clone());
+                }
+            }
+            self.resolved.insert(target.clone(), resolved);
+        }
+        uris
+    }
+
+    // refresh resolves the targets again, checks the health of their endpoints and updates
+    // which of them take calls, getting how many are healthy.
+    async fn refresh(&mut self) -> usize {
+        let uris = self.resolve().await;
+        self.health.retain(|uri, _| uris.contains(uri));
+        for uri in &uris {
+            if !self.health.contains_key(uri) {
+                if let Ok(endpoint) = (self.endpoint)(uri.clone()) {
+                    let client = HealthClient::new(endpoint.connect_lazy());
+                    self.health.insert(uri.clone(), client);
+                }
+            }
+        }
+        let checks = uris.iter().map(|uri| {
+            let client = self.health.get(uri).cloned();
+            async move {
+                let mut client = client?;
+                let request = HealthCheckRequest {
+                    service: String::new(),
+                };
+                match client.check(request).await {
+                    Ok(reply) if reply.get_ref().status == ServingStatus::Serving as i32 => {
+                        Some(uri.clone())
+                    }
+                    // Servers without the health service are taken as healthy once reached.
+                    Err(status) if status.code() == Code::Unimplemented => Some(uri.clone()),
+                    _ => None,
+                }
+            }
+        });
+        let healthy: Vec<String> = futures::future::join_all(checks)
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+        let members = match self.balance {
+            Balance::Spread => healthy.clone(),
+            Balance::PickFirst => healthy.iter().take(1).cloned().collect(),
+        };
+        for uri in &self.members {
+            if !members.contains(uri) {
+                let _ = self.changes.send(Change::Remove(uri.clone())).await;
+            }
+        }
+        for uri in &members {
+            if !self.members.contains(uri) {
+                if let Ok(endpoint) = (self.endpoint)(uri.clone()) {
+                    let _ = self
+                        .changes
+                        .send(Change::Insert(uri.clone(), endpoint))
+                        .await;
+                }
+            }
+        }
+        self.members = members;
+        healthy.len()
+    }
+}
+
+// balanced_channel makes a channel balancing the calls over the endpoints of targets, made by
+// endpoint, once one of them is healthy. Their health is then checked every interval for as
+// long as the channel is kept.
+async fn balanced_channel(
+    targets: Vec<String>,
+    balance: Balance,
+    interval: Duration,
+    endpoint: Box<dyn Fn(String) -> Result<Endpoint, Error> + Send>,
+) -> Result<Channel, Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(target) = targets.iter().find(|target| target.starts_with("unix:")) {
+        return Err(format!("cannot balance calls over the unix socket {}", target).into());
+    }
+    let (channel, changes) = Channel::balance_channel(targets.len().max(16));
+    let mut pool = Pool {
+        targets,
+        balance,
+        endpoint,
+        resolved: HashMap::new(),
+        health: HashMap::new(),
+        members: Vec::new(),
+        changes,
+    };
+    if pool.refresh().await == 0 {
+        return Err(format!("no healthy endpoint among {}", pool.targets.join(", ")).into());
+    }
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                // The balancer is dropped along with the last clone of the channel.
+                _ = pool.changes.closed() => return,
+            }
+            pool.refresh().await;
+        }
+    });
+    Ok(channel)
+}
+
+// ClientBuilder sets up the connection of a CasbinClient:
+//
+//     let client = CasbinClient::builder("http://127.0.0.1:50051")
+//         .api_key("secret")
+//         .timeout(Duration::from_secs(5))
+//         .connect()
+//         .await?;
+//     client.enforce(&["alice", "data1", "read"]).await?;
+//
+// A client of several replicas is given each of them with endpoint, or a DNS name resolving to
+// them, such as `dns:///casbin.internal:50051`.
+pub struct ClientBuilder {
+    targets: Vec<String>,
+    balance: Balance,
+    health_interval: Duration,
+    handle: i32,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    compression: Option<CompressionEncoding>,
+    api_key: Option<String>,
+    bearer_token: Option<String>,
+    tenant: Option<String>,
+    retry: Option<RetryPolicy>,
+    cache: Option<(usize, Duration)>,
+}
+
+impl ClientBuilder {
+    // endpoint adds target to the endpoints the client balances its calls over.
+    pub fn endpoint(mut self, target: &str) -> Self {
+        self.targets.push(target.to_owned());
+        self
+    }
+
+    // balance sets how the calls are balanced over several endpoints, Spread by default.
+    pub fn balance(mut self, balance: Balance) -> Self {
+        self.balance = balance;
+        self
+    }
+
+    // health_interval sets how often the health of several endpoints is checked, to only send
+    // calls to the healthy ones.
+    pub fn health_interval(mut self, interval: Duration) -> Self {
+        self.health_interval = interval;
+        self
+    }
+
+    // enforcer makes the client call the enforcer behind handle, instead of the one of the
+    // local config of the server.
+    pub fn enforcer(mut self, handle: i32) -> Self {
+        self.handle = handle;
+        self
+    }
+
+    // timeout sets the deadline of every call.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    // connect_timeout bounds how long connecting to the server may take.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    // compression compresses the requests with encoding and asks for replies compressed with
+    // it, for a server with that compression.
+    pub fn compression(mut self, encoding: CompressionEncoding) -> Self {
+        self.compression = Some(encoding);
+        self
+    }
+
+    // api_key authenticates the calls with an API key of the server.
+    pub fn api_key(mut self, key: &str) -> Self {
+        self.api_key = Some(key.to_owned());
+        self
+    }
+
+    // bearer_token authenticates the calls with a JWT.
+    pub fn bearer_token(mut self, token: &str) -> Self {
+        self.bearer_token = Some(token.to_owned());
+        self
+    }
+
+    // tenant makes the calls for a tenant of a server serving tenants, which only reach the
+    // enforcer of that tenant. The server takes it from an admin API key of no tenant alone,
+    // the other credentials naming their tenant themselves.
+    pub fn tenant(mut self, tenant: &str) -> Self {
+        self.tenant = Some(tenant.to_owned());
+        self
+    }
+
+    // retry retries the calls that only read with policy, instead of failing them at once.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    // cache_decisions keeps up to capacity Enforce decisions for up to ttl, or until evicted if
+    // it is zero, answering the same requests again without calling the server until the
+    // policy of their enforcer changes. The client then needs to be allowed
+    // WatchPolicyUpdates, decisions are not cached without.
+    pub fn cache_decisions(mut self, capacity: usize, ttl: Duration) -> Self {
+        self.cache = Some((capacity, ttl));
+        self
+    }
+
+    pub async fn connect(self) -> Result<CasbinClient, Box<dyn std::error::Error + Send + Sync>> {
+        let mut credentials = Credentials::default();
+        if let Some(key) = &self.api_key {
+            credentials
+                .headers
+                .push((MetadataKey::from_static("x-api-key"), key.parse()?));
+        }
+        if let Some(token) = &self.bearer_token {
+            credentials.headers.push((
+                MetadataKey::from_static("authorization"),
+                format!("Bearer {}", token).parse()?,
+            ));
+        }
+        if let Some(tenant) = &self.tenant {
+            credentials
+                .headers
+                .push((MetadataKey::from_static("x-tenant-id"), tenant.parse()?));
+        }
+        let (timeout, connect_timeout) = (self.timeout, self.connect_timeout);
+        let endpoint = move |uri: String| {
+            let mut endpoint = Endpoint::from_shared(uri)?;
+            if let Some(timeout) = timeout {
+                endpoint = endpoint.timeout(timeout);
+            }
+            if let Some(timeout) = connect_timeout {
+                endpoint = endpoint.connect_timeout(timeout);
+            }
+            Ok(endpoint)
+        };
+        let channel = match &self.targets[..] {
+            [target] if dns_name(target).is_none() => {
+                channel(endpoint(endpoint_uri(target))?, target).await?
+            }
+            _ => {
+                balanced_channel(
+                    self.targets,
+                    self.balance,
+                    self.health_interval,
+                    Box::new(endpoint),
+                )
+                .await?
+            }
+        };
+        let mut inner = RawClient::with_interceptor(channel, credentials);
+        if let Some(encoding) = self.compression {
+            inner = inner.send_compressed(encoding).accept_compressed(encoding);
+        }
+        Ok(CasbinClient {
+            inner,
+            handle: self.handle,
+            retries: self.retry.map(|policy| Arc::new(Retries::new(policy))),
+            cache: self
+                .cache
+                .filter(|(capacity, _)| *capacity > 0)
+                .map(|(capacity, ttl)| Arc::new(DecisionCache::new(capacity, ttl))),
+        })
+    }
+}
+
+// Explanation is a decision of EnforceEx, with the policy rules that explain it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    pub allowed: bool,
+    pub rules: Vec<Vec<String>>,
+}
+
+// EnforcerInfo describes an enforcer of the server, as ListEnforcers does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnforcerInfo {
+    pub handle: i32,
+    pub model_hash: String,
+    pub rule_count: i64,
+    pub last_used_ms: i64,
+    pub pinned: bool,
+    pub auto_save: bool,
+    pub role_cache: RoleCacheStats,
+}
+
+// RoleCacheStats count the lookups of the role links an enforcer caches, as ListEnforcers does.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RoleCacheStats {
+    pub hits: i64,
+    pub misses: i64,
+    pub entries: i64,
+    pub generation: i64,
+}
+
+// ConfigReload tells what a ReloadConfig call did: the settings of the local config of the
+// server it applied, and those only applied once the server restarts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConfigReload {
+    pub reloaded: Vec<String>,
+    pub restart_required: Vec<String>,
+}
+
+// CasbinClient calls the Casbin service on one of its enforcers, the one of the local config of
+// the server unless the builder or for_enforcer picks another, taking and returning plain
+// strings rather than the messages of the service. Clones share the connection.
+#[derive(Clone)]
+pub struct CasbinClient {
+    inner: RawClient<InterceptedService<Channel, Credentials>>,
+    handle: i32,
+    retries: Option<Arc<Retries>>,
+    cache: Option<Arc<DecisionCache>>,
+}
+
+fn strings<S: AsRef<str>>(values: &[S]) -> Vec<String> {
+    values.iter().map(|v| v.as_ref().to_owned()).collect()
+}
+
+fn rules<R: AsRef<[S]>, S: AsRef<str>>(rules: &[R]) -> Vec<casbin_proto::policies_request::D> {
+    rules
+        .iter()
+        .map(|rule| casbin_proto::policies_request::D {
+            params: strings(rule.as_ref()),
+        })
+        .collect()
+}
+
+fn rows(reply: casbin_proto::Array2DReply) -> Vec<Vec<String>> {
+    reply.d2.into_iter().map(|d| d.d1).collect()
+}
+
+impl CasbinClient {
+    pub fn builder(target: &str) -> ClientBuilder {
+        ClientBuilder {
+            targets: vec![target.to_owned()],
+            balance: Balance::Spread,
+            health_interval: HEALTH_INTERVAL,
+            handle: 0,
+            timeout: None,
+            connect_timeout: None,
+            compression: None,
+            api_key: None,
+            bearer_token: None,
+            tenant: None,
+            retry: None,
+            cache: None,
+        }
+    }
+
+    // connect makes a client of the server at target with the default options.
+    pub async fn connect(target: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::builder(target).connect().await
+    }
+
+    // handle is the handle of the enforcer the client calls.
+    pub fn handle(&self) -> i32 {
+        self.handle
+    }
+
+    // for_enforcer makes a client of the enforcer behind handle sharing this connection.
+    pub fn for_enforcer(&self, handle: i32) -> Self {
+        CasbinClient {
+            inner: self.inner.clone(),
+            handle,
+            retries: self.retries.clone(),
+            cache: self.cache.clone(),
+        }
+    }
+
+    // idempotent makes a call that only reads, retrying it as the retry policy of the client
+    // allows.
+    async fn idempotent<Req, Reply, F, Fut>(
+        &self,
+        request: Req,
+        call: F,
+    ) -> Result<Response<Reply>, Status>
+    where
+        Req: Clone,
+        F: Fn(RawClient<InterceptedService<Channel, Credentials>>, Req) -> Fut,
+        Fut: Future<Output = Result<Response<Reply>, Status>>,
+    {
+        let retries = match &self.retries {
+            Some(retries) => retries,
+            None => return call(self.raw(), request).await,
+        };
+        retries.earn();
+        let mut attempt = 1;
+        loop {
+            match call(self.raw(), request.clone()).await {
+                Err(status)
+                    if status.code() == Code::Unavailable
+                        && attempt < retries.policy.max_attempts
+                        && retries.spend() =>
+                {
+                    tokio::time::sleep(retries.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    // raw is the client of the generated stubs, for the RPCs this client does not wrap.
+    pub fn raw(&self) -> RawClient<InterceptedService<Channel, Credentials>> {
+        self.inner.clone()
+    }
+
+    // new_enforcer creates an enforcer of model_text over the adapter behind adapter_handle, or
+    // keeping its policy in memory alone when -1, and makes a client of it.
+    pub async fn new_enforcer(
+        &self,
+        model_text: &str,
+        adapter_handle: i32,
+    ) -> Result<Self, Status> {
+        let reply = self
+            .raw()
+            .new_enforcer(casbin_proto::NewEnforcerRequest {
+                model_text: model_text.to_owned(),
+                adapter_handle,
+                model_path: String::new(),
+                model_headers: HashMap::new(),
+                decision_cache: None,
+            })
+            .await?
+            .into_inner();
+        Ok(self.for_enforcer(reply.handler))
+    }
+
+    // new_adapter opens an adapter of driver, such as file or postgres, at connection, getting
+    // its handle for new_enforcer.
+    pub async fn new_adapter(&self, driver: &str, connection: &str) -> Result<i32, Status> {
+        let reply = self
+            .raw()
+            .new_adapter(casbin_proto::NewAdapterRequest {
+                adapter_name: String::new(),
+                driver_name: driver.to_owned(),
+                connect_string: connection.to_owned(),
+                db_specified: false,
+            })
+            .await?
+            .into_inner();
+        Ok(reply.handler)
+    }
+
+    // delete_enforcer drops the enforcer of the client from the server.
+    pub async fn delete_enforcer(self) -> Result<(), Status> {
+        self.raw()
+            .delete_enforcer(casbin_proto::EmptyRequest {
+                handler: self.handle,
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_enforcers(&self) -> Result<Vec<EnforcerInfo>, Status> {
+        let reply = self
+            .idempotent(
+                casbin_proto::ListEnforcersRequest {},
+                |mut c, r| async move { c.list_enforcers(r).await },
+            )
+            .await?
+            .into_inner();
+        Ok(reply
+            .enforcers
+            .into_iter()
+            .map(|e| EnforcerInfo {
+                handle: e.handler,
+                model_hash: e.model_hash,
+                rule_count: e.rule_count,
+                last_used_ms: e.last_used,
+                pinned: e.pinned,
+                auto_save: e.auto_save,
+                role_cache: e
+                    .role_cache
+                    .map(|c| RoleCacheStats {
+                        hits: c.hits,
+                        misses: c.misses,
+                        entries: c.entries,
+                        generation: c.generation,
+                    })
+                    .unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    // reload_config makes the server reload its local config without restarting.
+    pub async fn reload_config(&self) -> Result<ConfigReload, Status> {
+        let reply = self
+            .idempotent(
+                casbin_proto::ReloadConfigRequest {},
+                |mut c, r| async move { c.reload_config(r).await },
+            )
+            .await?
+            .into_inner();
+        Ok(ConfigReload {
+            reloaded: reply.reloaded,
+            restart_required: reply.restart_required,
+        })
+    }
+
+    pub async fn enforce<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        let params = strings(params);
+        let (params, generation) = match &self.cache {
+            Some(cache) => match cache.lookup(self, self.handle, params) {
+                (Lookup::Hit(allowed), _) => return Ok(allowed),
+                (Lookup::Miss(generation), params) => (params, generation),
+            },
+            None => (params, None),
+        };
+        let reply = self
+            .idempotent(
+                casbin_proto::EnforceRequest {
+                    enforcer_handler: self.handle,
+                    params: params.clone(),
+                },
+                |mut c, r| async move { c.enforce(r).await },
+            )
+            .await?
+            .into_inner();
+        if let (Some(cache), Some(generation)) = (&self.cache, generation) {
+            cache.insert(self.handle, params, reply.res, generation);
+        }
+        Ok(reply.res)
+    }
+
+    pub async fn batch_enforce<R: AsRef<[S]>, S: AsRef<str>>(
+        &self,
+        requests: &[R],
+    ) -> Result<Vec<bool>, Status> {
+        let requests = requests
+            .iter()
+            .map(|params| casbin_proto::batch_enforce_request::D {
+                params: strings(params.as_ref()),
+            })
+            .collect();
+        let reply = self
+            .idempotent(
+                casbin_proto::BatchEnforceRequest {
+                    enforcer_handler: self.handle,
+                    requests,
+                },
+                |mut c, r| async move { c.batch_enforce(r).await },
+            )
+            .await?
+            .into_inner();
+        Ok(reply.res)
+    }
+
+    pub async fn enforce_ex<S: AsRef<str>>(&self, params: &[S]) -> Result<Explanation, Status> {
+        let reply = self
+            .idempotent(
+                casbin_proto::EnforceRequest {
+                    enforcer_handler: self.handle,
+                    params: strings(params),
+                },
+                |mut c, r| async move { c.enforce_ex(r).await },
+            )
+            .await?
+            .into_inner();
+        Ok(Explanation {
+            allowed: reply.res,
+            rules: reply.explain.into_iter().map(|d| d.d1).collect(),
+        })
+    }
+
+    pub async fn enforce_with_matcher<S: AsRef<str>>(
+        &self,
+        matcher: &str,
+        params: &[S],
+    ) -> Result<bool, Status> {
+        let reply = self
+            .idempotent(
+                casbin_proto::EnforceWithMatcherRequest {
+                    enforcer_handler: self.handle,
+                    matcher: matcher.to_owned(),
+                    params: strings(params),
+                },
+                |mut c, r| async move { c.enforce_with_matcher(r).await },
+            )
+            .await?
+            .into_inner();
+        Ok(reply.res)
+    }
+
+    pub async fn load_policy(&self) -> Result<(), Status> {
+        self.raw()
+            .load_policy(casbin_proto::EmptyRequest {
+                handler: self.handle,
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn save_policy(&self) -> Result<(), Status> {
+        self.raw()
+            .save_policy(casbin_proto::EmptyRequest {
+                handler: self.handle,
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_model(&self) -> Result<String, Status> {
+        let reply = self
+            .idempotent(
+                casbin_proto::EmptyRequest {
+                    handler: self.handle,
+                },
+                |mut c, r| async move { c.get_model(r).await },
+            )
+            .await?
+            .into_inner();
+        Ok(reply.model_text)
+    }
+
+    // add_custom_function defines the function name for the matchers of the enforcer, with the
+    // value of the expression body, in which params name the arguments.
+    pub async fn add_custom_function<S: AsRef<str>>(
+        &self,
+        name: &str,
+        params: &[S],
+        body: &str,
+    ) -> Result<(), Status> {
+        self.raw()
+            .add_custom_function(casbin_proto::CustomFunctionRequest {
+                enforcer_handler: self.handle,
+                name: name.to_owned(),
+                params: strings(params),
+                body: body.to_owned(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    fn policy<S: AsRef<str>>(&self, p_type: &str, params: &[S]) -> casbin_proto::PolicyRequest {
+        casbin_proto::PolicyRequest {
+            enforcer_handler: self.handle,
+            p_type: p_type.to_owned(),
+            params: strings(params),
+        }
+    }
+
+    fn policies<R: AsRef<[S]>, S: AsRef<str>>(
+        &self,
+        p_type: &str,
+        values: &[R],
+    ) -> casbin_proto::PoliciesRequest {
+        casbin_proto::PoliciesRequest {
+            enforcer_handler: self.handle,
+            p_type: p_type.to_owned(),
+            rules: rules(values),
+        }
+    }
+
+    fn filter<S: AsRef<str>>(
+        &self,
+        p_type: &str,
+        field_index: i32,
+        field_values: &[S],
+    ) -> casbin_proto::FilteredPolicyRequest {
+        casbin_proto::FilteredPolicyRequest {
+            enforcer_handler: self.handle,
+            p_type: p_type.to_owned(),
+            field_index,
+            field_values: strings(field_values),
+        }
+    }
+
+    pub async fn get_policy(&self) -> Result<Vec<Vec<String>>, Status> {
+        let reply = self
+            .idempotent(
+                casbin_proto::EmptyRequest {
+                    handler: self.handle,
+                },
+                |mut c, r| async move { c.get_policy(r).await },
+            )
+            .await?
+            .into_inner();
+        Ok(rows(reply))
+    }
+
+    pub async fn get_filtered_policy<S: AsRef<str>>(
+        &self,
+        field_index: i32,
+        field_values: &[S],
+    ) -> Result<Vec<Vec<String>>, Status> {
+        let request = self.filter("p", field_index, field_values);
+        let reply = self
+            .idempotent(
+                request,
+                |mut c, r| async move { c.get_filtered_policy(r).await },
+            )
+            .await?
+            .into_inner();
+        Ok(rows(reply))
+    }
+
+    pub async fn has_policy<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        let reply = self
+            .idempotent(self.policy("p", params), |mut c, r| async move {
+                c.has_policy(r).await
+            })
+            .await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn add_policy<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        self.add_named_policy("p", params).await
+    }
+
+    pub async fn add_named_policy<S: AsRef<str>>(
+        &self,
+        p_type: &str,
+        params: &[S],
+    ) -> Result<bool, Status> {
+        let reply = self
+            .raw()
+            .add_named_policy(self.policy(p_type, params))
+            .await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn add_policies<R: AsRef<[S]>, S: AsRef<str>>(
+        &self,
+        rules: &[R],
+    ) -> Result<bool, Status> {
+        let reply = self.raw().add_policies(self.policies("p", rules)).await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn remove_policy<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        self.remove_named_policy("p", params).await
+    }
+
+    pub async fn remove_named_policy<S: AsRef<str>>(
+        &self,
+        p_type: &str,
+        params: &[S],
+    ) -> Result<bool, Status> {
+        let reply = self
+            .raw()
+            .remove_named_policy(self.policy(p_type, params))
+            .await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn remove_policies<R: AsRef<[S]>, S: AsRef<str>>(
+        &self,
+        rules: &[R],
+    ) -> Result<bool, Status> {
+        let reply = self
+            .raw()
+            .remove_policies(self.policies("p", rules))
+            .await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn remove_filtered_policy<S: AsRef<str>>(
+        &self,
+        field_index: i32,
+        field_values: &[S],
+    ) -> Result<bool, Status> {
+        let request = self.filter("p", field_index, field_values);
+        let reply = self.raw().remove_filtered_policy(request).await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn update_policy<S: AsRef<str>>(
+        &self,
+        old_rule: &[S],
+        new_rule: &[S],
+    ) -> Result<bool, Status> {
+        let reply = self
+            .raw()
+            .update_policy(casbin_proto::UpdatePolicyRequest {
+                enforcer_handler: self.handle,
+                p_type: String::from("p"),
+                old_rule: strings(old_rule),
+                new_rule: strings(new_rule),
+            })
+            .await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn get_grouping_policy(&self) -> Result<Vec<Vec<String>>, Status> {
+        let reply = self
+            .idempotent(
+                casbin_proto::EmptyRequest {
+                    handler: self.handle,
+                },
+                |mut c, r| async move { c.get_grouping_policy(r).await },
+            )
+            .await?
+            .into_inner();
+        Ok(rows(reply))
+    }
+
+    pub async fn has_grouping_policy<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        let reply = self
+            .idempotent(self.policy("g", params), |mut c, r| async move {
+                c.has_grouping_policy(r).await
+            })
+            .await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn add_grouping_policy<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        let reply = self
+            .raw()
+            .add_grouping_policy(self.policy("g", params))
+            .await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn remove_grouping_policy<S: AsRef<str>>(
+        &self,
+        params: &[S],
+    ) -> Result<bool, Status> {
+        let reply = self
+            .raw()
+            .remove_grouping_policy(self.policy("g", params))
+            .await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn get_all_subjects(&self) -> Result<Vec<String>, Status> {
+        let request = casbin_proto::EmptyRequest {
+            handler: self.handle,
+        };
+        Ok(self
+            .idempotent(
+                request,
+                |mut c, r| async move { c.get_all_subjects(r).await },
+            )
+            .await?
+            .into_inner()
+            .array)
+    }
+
+    pub async fn get_all_objects(&self) -> Result<Vec<String>, Status> {
+        let request = casbin_proto::EmptyRequest {
+            handler: self.handle,
+        };
+        Ok(self
+            .idempotent(
+                request,
+                |mut c, r| async move { c.get_all_objects(r).await },
+            )
+            .await?
+            .into_inner()
+            .array)
+    }
+
+    pub async fn get_all_actions(&self) -> Result<Vec<String>, Status> {
+        let request = casbin_proto::EmptyRequest {
+            handler: self.handle,
+        };
+        Ok(self
+            .idempotent(
+                request,
+                |mut c, r| async move { c.get_all_actions(r).await },
+            )
+            .await?
+            .into_inner()
+            .array)
+    }
+
+    pub async fn get_all_roles(&self) -> Result<Vec<String>, Status> {
+        let request = casbin_proto::EmptyRequest {
+            handler: self.handle,
+        };
+        Ok(self
+            .idempotent(request, |mut c, r| async move { c.get_all_roles(r).await })
+            .await?
+            .into_inner()
+            .array)
+    }
+
+    fn user_role(&self, user: &str, role: &str) -> casbin_proto::UserRoleRequest {
+        casbin_proto::UserRoleRequest {
+            enforcer_handler: self.handle,
+            user: user.to_owned(),
+            role: role.to_owned(),
+            domain: String::new(),
+            g_type: String::new(),
+            p_type: String::new(),
+        }
+    }
+
+    fn permission<S: AsRef<str>>(
+        &self,
+        user: &str,
+        permissions: &[S],
+    ) -> casbin_proto::PermissionRequest {
+        casbin_proto::PermissionRequest {
+            enforcer_handler: self.handle,
+            user: user.to_owned(),
+            permissions: strings(permissions),
+            domain: String::new(),
+            p_type: String::new(),
+            g_type: String::new(),
+        }
+    }
+
+    pub async fn get_roles_for_user(&self, user: &str) -> Result<Vec<String>, Status> {
+        let reply = self
+            .idempotent(self.user_role(user, ""), |mut c, r| async move {
+                c.get_roles_for_user(r).await
+            })
+            .await?;
+        Ok(reply.into_inner().array)
+    }
+
+    pub async fn get_implicit_roles_for_user(&self, user: &str) -> Result<Vec<String>, Status> {
+        let reply = self
+            .idempotent(self.user_role(user, ""), |mut c, r| async move {
+                c.get_implicit_roles_for_user(r).await
+            })
+            .await?;
+        Ok(reply.into_inner().array)
+    }
+
+    pub async fn get_users_for_role(&self, role: &str) -> Result<Vec<String>, Status> {
+        let reply = self
+            .idempotent(self.user_role("", role), |mut c, r| async move {
+                c.get_users_for_role(r).await
+            })
+            .await?;
+        Ok(reply.into_inner().array)
+    }
+
+    pub async fn has_role_for_user(&self, user: &str, role: &str) -> Result<bool, Status> {
+        let reply = self
+            .idempotent(self.user_role(user, role), |mut c, r| async move {
+                c.has_role_for_user(r).await
+            })
+            .await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn add_role_for_user(&self, user: &str, role: &str) -> Result<bool, Status> {
+        let reply = self
+            .raw()
+            .add_role_for_user(self.user_role(user, role))
+            .await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn delete_role_for_user(&self, user: &str, role: &str) -> Result<bool, Status> {
+        let reply = self
+            .raw()
+            .delete_role_for_user(self.user_role(user, role))
+            .await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn get_permissions_for_user(&self, user: &str) -> Result<Vec<Vec<String>>, Status> {
+        let request = self.permission::<&str>(user, &[]);
+        let reply = self
+            .idempotent(request, |mut c, r| async move {
+                c.get_permissions_for_user(r).await
+            })
+            .await?;
+        Ok(rows(reply.into_inner()))
+    }
+
+    pub async fn get_implicit_permissions_for_user(
+        &self,
+        user: &str,
+    ) -> Result<Vec<Vec<String>>, Status> {
+        let request = self.permission::<&str>(user, &[]);
+        let reply = self
+            .idempotent(request, |mut c, r| async move {
+                c.get_implicit_permissions_for_user(r).await
+            })
+            .await?;
+        Ok(rows(reply.into_inner()))
+    }
+
+    pub async fn add_permission_for_user<S: AsRef<str>>(
+        &self,
+        user: &str,
+        permission: &[S],
+    ) -> Result<bool, Status> {
+        let request = self.permission(user, permission);
+        let reply = self.raw().add_permission_for_user(request).await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn delete_permission_for_user<S: AsRef<str>>(
+        &self,
+        user: &str,
+        permission: &[S],
+    ) -> Result<bool, Status> {
+        let request = self.permission(user, permission);
+        let reply = self.raw().delete_permission_for_user(request).await?;
+        Ok(reply.into_inner().res)
+    }
+
+    pub async fn has_permission_for_user<S: AsRef<str>>(
+        &self,
+        user: &str,
+        permission: &[S],
+    ) -> Result<bool, Status> {
+        let request = self.permission(user, permission);
+        let reply = self
+            .idempotent(request, |mut c, r| async move {
+                c.has_permission_for_user(r).await
+            })
+            .await?;
+        Ok(reply.into_inner().res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dns_name, endpoint_uri, Balance, CasbinClient, DecisionCache};
+    use super::{Retries, RetryPolicy};
+    use crate::casbin_proto::casbin_server::{Casbin, CasbinServer};
+    use crate::casbin_proto::PolicyRequest;
+    use crate::server::enforcer;
+    use crate::CasbinGRPC;
+    use casbin::{DefaultModel, MemoryAdapter};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::service::Interceptor;
+    use tonic::transport::server::Router;
+    use tonic::transport::Server;
+    use tonic::{Code, Request, Status};
+    use tonic_health::server::HealthReporter;
+    use tonic_health::ServingStatus;
+
+    // casbin makes a server holding an enforcer of the RBAC model with rules, as handle 0.
+    pub async fn casbin(rules: &[[&str; 3]]) -> CasbinGRPC {
+        let casbin = CasbinGRPC::new_server();
+        let model_text = std::fs::read_to_string("examples/rbac_model.conf").unwrap();
+        let m = DefaultModel::from_str(&model_text).await.unwrap();
+        let e = enforcer::build_enforcer(m, MemoryAdapter::default())
+            .await
+            .unwrap();
+        let handle = casbin.add_enforcer(e, model_text).await;
+        for rule in rules {
+            casbin
+                .add_policy(Request::new(PolicyRequest {
+                    enforcer_handler: handle,
+                    p_type: "p".to_owned(),
+                    params: rule.iter().map(|v| v.to_string()).collect(),
+                }))
+                .await
+                .unwrap();
+        }
+        casbin
+    }
+
+    // listen serves router on a port of its own, getting its address.
+    pub async fn listen(router: Router) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(router.serve_with_incoming(TcpListenerStream::new(listener)));
+        addr
+    }
+
+    // authenticated only lets through the calls with the API key or the bearer token of the
+    // tests, and with no tenant.
+    #[allow(clippy::result_large_err)]
+    fn authenticated(request: Request<()>) -> Result<Request<()>, Status> {
+        let metadata = request.metadata();
+        let key = metadata.get("x-api-key").is_some_and(|key| key == "secret");
+        let token = metadata
+            .get("authorization")
+            .is_some_and(|token| token == "Bearer token");
+        if !(key || token) || metadata.contains_key("x-tenant-id") {
+            return Err(Status::unauthenticated("no credentials"));
+        }
+        Ok(request)
+    }
+
+    #[test]
+    fn test_endpoint_uri() {
+        assert_eq!(
+            endpoint_uri("http://10.0.0.1:50051"),
+            "http://10.0.0.1:50051"
+        );
+        assert_eq!(endpoint_uri("unix:/run/casbin.sock"), "http://localhost");
+        assert_eq!(endpoint_uri("unix:///run/casbin.sock"), "http://localhost");
+    }
+
+    #[tokio::test]
+    async fn test_credentials() {
+        let service = CasbinServer::with_interceptor(casbin(&[]).await, authenticated);
+        let addr = listen(Server::builder().add_service(service)).await;
+
+        let anonymous = CasbinClient::connect(&addr).await.unwrap();
+        let err = anonymous.get_policy().await.unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        let with_key = CasbinClient::builder(&addr).api_key("secret");
+        assert!(with_key.connect().await.unwrap().get_policy().await.is_ok());
+        let with_token = CasbinClient::builder(&addr).bearer_token("token");
+        assert!(with_token
+            .connect()
+            .await
+            .unwrap()
+            .get_policy()
+            .await
+            .is_ok());
+        let with_tenant = CasbinClient::builder(&addr)
+            .api_key("secret")
+            .tenant("acme")
+            .connect()
+            .await
+            .unwrap();
+        let err = with_tenant.get_policy().await.unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_client() {
+        let casbin = casbin(&[["alice", "data1", "read"]]).await;
+        let addr = listen(Server::builder().add_service(CasbinServer::new(casbin))).await;
+        let client = CasbinClient::connect(&addr).await.unwrap();
+        assert_eq!(client.handle(), 0);
+
+        assert!(client.enforce(&["alice", "data1", "read"]).await.unwrap());
+        assert!(!client.enforce(&["bob", "data1", "read"]).await.unwrap());
+        assert!(client.add_policy(&["bob", "data2", "write"]).await.unwrap());
+        assert!(!client.add_policy(&["bob", "data2", "write"]).await.unwrap());
+        assert!(client.has_policy(&["bob", "data2", "write"]).await.unwrap());
+        assert_eq!(
+            client.get_filtered_policy(0, &["bob"]).await.unwrap(),
+            vec![vec!["bob", "data2", "write"]]
+        );
+        assert_eq!(
+            client
+                .batch_enforce(&[["bob", "data2", "write"], ["bob", "data1", "read"]])
+                .await
+                .unwrap(),
+            vec![true, false]
+        );
+
+        assert!(client.add_role_for_user("bob", "admin").await.unwrap());
+        assert!(client
+            .add_policy(&["admin", "data1", "read"])
+            .await
+            .unwrap());
+        assert_eq!(
+            client.get_roles_for_user("bob").await.unwrap(),
+            vec!["admin"]
+        );
+        assert!(client.enforce(&["bob", "data1", "read"]).await.unwrap());
+        assert!(client
+            .remove_policy(&["admin", "data1", "read"])
+            .await
+            .unwrap());
+        assert!(!client.enforce(&["bob", "data1", "read"]).await.unwrap());
+        assert_eq!(
+            client.get_policy().await.unwrap(),
+            vec![
+                vec!["alice", "data1", "read"],
+                vec!["bob", "data2", "write"]
+            ]
+        );
+
+        // An enforcer of its own keeps a policy of its own.
+        let model_text = client.get_model().await.unwrap();
+        let other = client.new_enforcer(&model_text, -1).await.unwrap();
+        assert_ne!(other.handle(), client.handle());
+        assert!(other.get_policy().await.unwrap().is_empty());
+        assert!(other.add_policy(&["carol", "data3", "read"]).await.unwrap());
+        assert!(!client.enforce(&["carol", "data3", "read"]).await.unwrap());
+        let handles: Vec<i32> = client
+            .list_enforcers()
+            .await
+            .unwrap()
+            .iter()
+            .map(|info| info.handle)
+            .collect();
+        assert!(handles.contains(&other.handle()));
+
+        let stale = client.for_enforcer(other.handle());
+        other.delete_enforcer().await.unwrap();
+        let err = stale.get_policy().await.unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+    }
+
+    // Flaky fails the calls with UNAVAILABLE while it has failures left, counting every call.
+    #[derive(Clone, Default)]
+    pub struct Flaky {
+        pub failures: Arc<AtomicUsize>,
+        pub calls: Arc<AtomicUsize>,
+    }
+
+    impl Interceptor for Flaky {
+        fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let failing = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1));
+            match failing {
+                Ok(_) => Err(Status::unavailable("restarting")),
+                Err(_) => Ok(request),
+            }
+        }
+    }
+
+    fn quick_retries() -> RetryPolicy {
+        RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_retry_budget() {
+        let retries = Retries::new(RetryPolicy {
+            budget_ratio: 0.5,
+            budget_reserve: 2.0,
+            ..RetryPolicy::default()
+        });
+        assert!(retries.spend());
+        assert!(retries.spend());
+        assert!(!retries.spend());
+        retries.earn();
+        assert!(!retries.spend());
+        retries.earn();
+        assert!(retries.spend());
+        for _ in 0..10 {
+            retries.earn();
+        }
+        assert_eq!(*retries.budget.lock().unwrap(), 2.0);
+
+        for attempt in 1..10 {
+            assert!(retries.backoff(attempt) <= Duration::from_secs(2));
+        }
+        assert!(retries.backoff(1) <= Duration::from_millis(50));
+        assert!(retries.backoff(2) <= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_retries() {
+        let flaky = Flaky::default();
+        let service = CasbinServer::with_interceptor(
+            casbin(&[["alice", "data1", "read"]]).await,
+            flaky.clone(),
+        );
+        let addr = listen(Server::builder().add_service(service)).await;
+        let client = CasbinClient::builder(&addr)
+            .retry(quick_retries())
+            .connect()
+            .await
+            .unwrap();
+
+        flaky.failures.store(2, Ordering::SeqCst);
+        assert!(client.enforce(&["alice", "data1", "read"]).await.unwrap());
+        assert_eq!(flaky.calls.swap(0, Ordering::SeqCst), 3);
+
+        // The attempts stop at max_attempts.
+        flaky.failures.store(5, Ordering::SeqCst);
+        let err = client.get_policy().await.unwrap_err();
+        assert_eq!(err.code(), Code::Unavailable);
+        assert_eq!(flaky.calls.swap(0, Ordering::SeqCst), 3);
+
+        // Changes of the policy are not retried.
+        flaky.failures.store(1, Ordering::SeqCst);
+        let err = client
+            .add_policy(&["bob", "data1", "read"])
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unavailable);
+        assert_eq!(flaky.calls.swap(0, Ordering::SeqCst), 1);
+        assert!(!client.has_policy(&["bob", "data1", "read"]).await.unwrap());
+
+        // Without a retry policy the first failure is returned.
+        let client = CasbinClient::connect(&addr).await.unwrap();
+        flaky.calls.store(0, Ordering::SeqCst);
+        flaky.failures.store(1, Ordering::SeqCst);
+        let err = client
+            .enforce(&["alice", "data1", "read"])
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unavailable);
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_runs_out() {
+        let flaky = Flaky::default();
+        let service = CasbinServer::with_interceptor(casbin(&[]).await, flaky.clone());
+        let addr = listen(Server::builder().add_service(service)).await;
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            budget_ratio: 0.0,
+            budget_reserve: 3.0,
+            ..quick_retries()
+        };
+        let client = CasbinClient::builder(&addr)
+            .retry(policy)
+            .connect()
+            .await
+            .unwrap();
+
+        // Clones share the budget of their three retries.
+        flaky.failures.store(usize::MAX, Ordering::SeqCst);
+        assert!(client.get_policy().await.is_err());
+        assert!(client.clone().get_policy().await.is_err());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn test_decision_cache_generations() {
+        let cache = DecisionCache::new(2, Duration::ZERO);
+        let decision = |handle| (handle, vec!["alice".to_owned()]);
+        let cached = |handle| {
+            let state = cache.state.lock().unwrap();
+            state.decisions.peek(&decision(handle)).copied()
+        };
+
+        // Decisions are not cached while the watch of their enforcer is down.
+        cache.insert(0, decision(0).1, true, 0);
+        assert_eq!(cached(0), None);
+
+        cache.set_live(0, true);
+        let generation = cache.state.lock().unwrap().watches[&0].generation;
+        cache.insert(0, decision(0).1, true, generation);
+        assert_eq!(cached(0), Some(true));
+
+        // A decision made before a change of the policy is not cached after it.
+        cache.state.lock().unwrap().invalidate(0);
+        assert_eq!(cached(0), None);
+        cache.insert(0, decision(0).1, true, generation);
+        assert_eq!(cached(0), None);
+
+        // Each enforcer has generations of its own.
+        cache.set_live(1, true);
+        let other = cache.state.lock().unwrap().watches[&1].generation;
+        cache.insert(1, decision(1).1, false, other);
+        cache.insert(0, decision(0).1, true, generation + 1);
+        assert_eq!(cached(0), Some(true));
+        cache.set_live(0, false);
+        assert_eq!((cached(0), cached(1)), (None, Some(false)));
+    }
+
+    // watched waits for the cache of client to watch the enforcer behind handle.
+    async fn watched(client: &CasbinClient, handle: i32) {
+        let cache = client.cache.clone().unwrap();
+        for _ in 0..100 {
+            let live = cache
+                .state
+                .lock()
+                .unwrap()
+                .watches
+                .get(&handle)
+                .map(|w| w.live);
+            if live == Some(true) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("enforcer {} not watched", handle);
+    }
+
+    #[tokio::test]
+    async fn test_cached_decisions() {
+        let flaky = Flaky::default();
+        let service = CasbinServer::with_interceptor(
+            casbin(&[["alice", "data1", "read"]]).await,
+            flaky.clone(),
+        );
+        let addr = listen(Server::builder().add_service(service)).await;
+        let client = CasbinClient::builder(&addr)
+            .cache_decisions(16, Duration::ZERO)
+            .connect()
+            .await
+            .unwrap();
+        let alice = ["alice", "data1", "read"];
+
+        // The first decision starts the watch of the enforcer, and is cached once it is live.
+        assert!(client.enforce(&alice).await.unwrap());
+        watched(&client, 0).await;
+        let calls = flaky.calls.load(Ordering::SeqCst);
+        assert!(client.enforce(&alice).await.unwrap());
+        assert!(client.clone().enforce(&alice).await.unwrap());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), calls + 1);
+
+        // Changes of the policy, by any client, drop the cached decisions.
+        let other = CasbinClient::connect(&addr).await.unwrap();
+        assert!(other.remove_policy(&alice).await.unwrap());
+        for _ in 0..100 {
+            if client
+                .cache
+                .as_ref()
+                .unwrap()
+                .state
+                .lock()
+                .unwrap()
+                .decisions
+                .is_empty()
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!client.enforce(&alice).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_cache_disabled_without_capacity() {
+        let casbin = casbin(&[]).await;
+        let addr = listen(Server::builder().add_service(CasbinServer::new(casbin))).await;
+        let client = CasbinClient::builder(&addr)
+            .cache_decisions(0, Duration::from_secs(60))
+            .connect()
+            .await
+            .unwrap();
+        assert!(client.cache.is_none());
+    }
+
+    #[test]
+    fn test_dns_name() {
+        assert_eq!(dns_name("dns:///casbin:50051"), Some("casbin:50051"));
+        assert_eq!(dns_name("dns:casbin:50051"), Some("casbin:50051"));
+        assert_eq!(dns_name("http://casbin:50051"), None);
+    }
+
+    // replica serves an enforcer holding rules along with the health service, reported serving,
+    // counting the calls to the enforcer with flaky.
+    async fn replica(rules: &[[&str; 3]], flaky: Flaky) -> (String, HealthReporter) {
+        let (mut reporter, health) = tonic_health::server::health_reporter();
+        reporter
+            .set_service_status("", ServingStatus::Serving)
+            .await;
+        let service = CasbinServer::with_interceptor(casbin(rules).await, flaky);
+        let router = Server::builder().add_service(health).add_service(service);
+        (listen(router).await, reporter)
+    }
+
+    // decided waits for client to decide params as expected, within a second.
+    async fn decided(client: &CasbinClient, params: &[&str], expected: bool) {
+        for _ in 0..50 {
+            if client.enforce(params).await.unwrap() == expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("{:?} never decided {}", params, expected);
+    }
+
+    #[tokio::test]
+    async fn test_pick_first() {
+        let alice = ["alice", "data1", "read"];
+        let (first, mut reporter) = replica(&[alice], Flaky::default()).await;
+        let (second, _second) = replica(&[], Flaky::default()).await;
+        let client = CasbinClient::builder(&first)
+            .endpoint(&second)
+            .balance(Balance::PickFirst)
+            .health_interval(Duration::from_millis(20))
+            .connect()
+            .await
+            .unwrap();
+        for _ in 0..5 {
+            assert!(client.enforce(&alice).await.unwrap());
+        }
+
+        // The calls fail over to the second endpoint while the first is not serving.
+        reporter
+            .set_service_status("", ServingStatus::NotServing)
+            .await;
+        decided(&client, &alice, false).await;
+        reporter
+            .set_service_status("", ServingStatus::Serving)
+            .await;
+        decided(&client, &alice, true).await;
+    }
+
+    #[tokio::test]
+    async fn test_spread() {
+        let (first_calls, second_calls) = (Flaky::default(), Flaky::default());
+        let (first, mut reporter) = replica(&[], first_calls.clone()).await;
+        let (second, _second) = replica(&[], second_calls.clone()).await;
+        let client = CasbinClient::builder(&first)
+            .endpoint(&second)
+            .health_interval(Duration::from_millis(20))
+            .connect()
+            .await
+            .unwrap();
+        for _ in 0..50 {
+            client.get_policy().await.unwrap();
+        }
+        assert!(first_calls.calls.load(Ordering::SeqCst) > 0);
+        assert!(second_calls.calls.load(Ordering::SeqCst) > 0);
+
+        reporter
+            .set_service_status("", ServingStatus::NotServing)
+            .await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let calls = first_calls.calls.load(Ordering::SeqCst);
+        for _ in 0..20 {
+            client.get_policy().await.unwrap();
+        }
+        assert_eq!(first_calls.calls.load(Ordering::SeqCst), calls);
+    }
+
+    #[tokio::test]
+    async fn test_dns_target() {
+        // Servers without the health service are taken as healthy, and the addresses of the
+        // name that no server listens on are left out.
+        let casbin = casbin(&[["alice", "data1", "read"]]).await;
+        let addr = listen(Server::builder().add_service(CasbinServer::new(casbin))).await;
+        let port = addr.rsplit(':').next().unwrap();
+        let client = CasbinClient::connect(&format!("dns:///localhost:{}", port))
+            .await
+            .unwrap();
+        for _ in 0..5 {
+            assert!(client.enforce(&["alice", "data1", "read"]).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_healthy_endpoint() {
+        let (first, mut first_reporter) = replica(&[], Flaky::default()).await;
+        let (second, mut second_reporter) = replica(&[], Flaky::default()).await;
+        for reporter in [&mut first_reporter, &mut second_reporter] {
+            reporter
+                .set_service_status("", ServingStatus::NotServing)
+                .await;
+        }
+        let err = CasbinClient::builder(&first)
+            .endpoint(&second)
+            .connect()
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().starts_with("no healthy endpoint among"),
+            "{}",
+            err
+        );
+
+        let err = CasbinClient::builder(&first)
+            .endpoint("unix:/run/casbin.sock")
+            .connect()
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("unix socket"), "{}", err);
+    }
+}
@@ -0,0 +1,441 @@
+use super::{Balance, EnforcerInfo, Explanation, RetryPolicy};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tonic::codec::CompressionEncoding;
+use tonic::Status;
+
+// The blocking CasbinClient is a client::CasbinClient for code that is not async, like the
+// blocking client of reqwest. It runs the calls on a runtime of its own, shared by its clones,
+// which also keeps up the background tasks of the client, such as the health checks of several
+// endpoints. Its methods block on those of client::CasbinClient of the same name, so they must
+// not be called from async code, which would panic.
+//
+//     let client = blocking::CasbinClient::builder("http://127.0.0.1:50051")
+//         .api_key("secret")
+//         .connect()?;
+//     client.enforce(&["alice", "data1", "read"])?;
+#[derive(Clone)]
+pub struct CasbinClient {
+    inner: super::CasbinClient,
+    runtime: Arc<Runtime>,
+}
+
+// ClientBuilder sets up the connection of a blocking CasbinClient, like client::ClientBuilder.
+pub struct ClientBuilder {
+    inner: super::ClientBuilder,
+}
+
+impl ClientBuilder {
+    pub fn endpoint(self, target: &str) -> Self {
+        ClientBuilder {
+            inner: self.inner.endpoint(target),
+        }
+    }
+
+    pub fn balance(self, balance: Balance) -> Self {
+        ClientBuilder {
+            inner: self.inner.balance(balance),
+        }
+    }
+
+    pub fn health_interval(self, interval: Duration) -> Self {
+        ClientBuilder {
+            inner: self.inner.health_interval(interval),
+        }
+    }
+
+    pub fn enforcer(self, handle: i32) -> Self {
+        ClientBuilder {
+            inner: self.inner.enforcer(handle),
+        }
+    }
+
+    pub fn timeout(self, timeout: Duration) -> Self {
+        ClientBuilder {
+            inner: self.inner.timeout(timeout),
+        }
+    }
+
+    pub fn connect_timeout(self, timeout: Duration) -> Self {
+        ClientBuilder {
+            inner: self.inner.connect_timeout(timeout),
+        }
+    }
+
+    pub fn compression(self, encoding: CompressionEncoding) -> Self {
+        ClientBuilder {
+            inner: self.inner.compression(encoding),
+        }
+    }
+
+    pub fn api_key(self, key: &str) -> Self {
+        ClientBuilder {
+            inner: self.inner.api_key(key),
+        }
+    }
+
+    pub fn bearer_token(self, token: &str) -> Self {
+        ClientBuilder {
+            inner: self.inner.bearer_token(token),
+        }
+    }
+
+    pub fn retry(self, policy: RetryPolicy) -> Self {
+        ClientBuilder {
+            inner: self.inner.retry(policy),
+        }
+    }
+
+    pub fn cache_decisions(self, capacity: usize, ttl: Duration) -> Self {
+        ClientBuilder {
+            inner: self.inner.cache_decisions(capacity, ttl),
+        }
+    }
+
+    // connect starts the runtime of the client, then connects it as client::ClientBuilder does.
+    pub fn connect(self) -> Result<CasbinClient, Box<dyn std::error::Error + Send + Sync>> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("casbin-client")
+            .enable_all()
+            .build()?;
+        let inner = runtime.block_on(self.inner.connect())?;
+        Ok(CasbinClient {
+            inner,
+            runtime: Arc::new(runtime),
+        })
+    }
+}
+
+// The calls fail with the status of the service, as those of the async client do.
+#[allow(clippy::result_large_err)]
+impl CasbinClient {
+    pub fn builder(target: &str) -> ClientBuilder {
+        ClientBuilder {
+            inner: super::CasbinClient::builder(target),
+        }
+    }
+
+    // connect makes a client of the server at target with the default options.
+    pub fn connect(target: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::builder(target).connect()
+    }
+
+    pub fn handle(&self) -> i32 {
+        self.inner.handle()
+    }
+
+    // for_enforcer makes a client of the enforcer behind handle sharing the connection and the
+    // runtime of this one.
+    pub fn for_enforcer(&self, handle: i32) -> Self {
+        CasbinClient {
+            inner: self.inner.for_enforcer(handle),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn new_enforcer(&self, model_text: &str, adapter_handle: i32) -> Result<Self, Status> {
+        let inner = self
+            .runtime
+            .block_on(self.inner.new_enforcer(model_text, adapter_handle))?;
+        Ok(CasbinClient {
+            inner,
+            runtime: self.runtime.clone(),
+        })
+    }
+
+    pub fn delete_enforcer(self) -> Result<(), Status> {
+        self.runtime.block_on(self.inner.delete_enforcer())
+    }
+
+    pub fn new_adapter(&self, driver: &str, connection: &str) -> Result<i32, Status> {
+        self.runtime
+            .block_on(self.inner.new_adapter(driver, connection))
+    }
+
+    pub fn list_enforcers(&self) -> Result<Vec<EnforcerInfo>, Status> {
+        self.runtime.block_on(self.inner.list_enforcers())
+    }
+
+    pub fn enforce<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        self.runtime.block_on(self.inner.enforce(params))
+    }
+
+    pub fn batch_enforce<R: AsRef<[S]>, S: AsRef<str>>(
+        &self,
+        requests: &[R],
+    ) -> Result<Vec<bool>, Status> {
+        self.runtime.block_on(self.inner.batch_enforce(requests))
+    }
+
+    pub fn enforce_ex<S: AsRef<str>>(&self, params: &[S]) -> Result<Explanation, Status> {
+        self.runtime.block_on(self.inner.enforce_ex(params))
+    }
+
+    pub fn enforce_with_matcher<S: AsRef<str>>(
+        &self,
+        matcher: &str,
+        params: &[S],
+    ) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.enforce_with_matcher(matcher, params))
+    }
+
+    pub fn load_policy(&self) -> Result<(), Status> {
+        self.runtime.block_on(self.inner.load_policy())
+    }
+
+    pub fn save_policy(&self) -> Result<(), Status> {
+        self.runtime.block_on(self.inner.save_policy())
+    }
+
+    pub fn get_model(&self) -> Result<String, Status> {
+        self.runtime.block_on(self.inner.get_model())
+    }
+
+    pub fn get_policy(&self) -> Result<Vec<Vec<String>>, Status> {
+        self.runtime.block_on(self.inner.get_policy())
+    }
+
+    pub fn get_filtered_policy<S: AsRef<str>>(
+        &self,
+        field_index: i32,
+        field_values: &[S],
+    ) -> Result<Vec<Vec<String>>, Status> {
+        self.runtime
+            .block_on(self.inner.get_filtered_policy(field_index, field_values))
+    }
+
+    pub fn has_policy<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        self.runtime.block_on(self.inner.has_policy(params))
+    }
+
+    pub fn add_policy<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        self.runtime.block_on(self.inner.add_policy(params))
+    }
+
+    pub fn add_named_policy<S: AsRef<str>>(
+        &self,
+        p_type: &str,
+        params: &[S],
+    ) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.add_named_policy(p_type, params))
+    }
+
+    pub fn add_policies<R: AsRef<[S]>, S: AsRef<str>>(&self, rules: &[R]) -> Result<bool, Status> {
+        self.runtime.block_on(self.inner.add_policies(rules))
+    }
+
+    pub fn remove_policy<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        self.runtime.block_on(self.inner.remove_policy(params))
+    }
+
+    pub fn remove_named_policy<S: AsRef<str>>(
+        &self,
+        p_type: &str,
+        params: &[S],
+    ) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.remove_named_policy(p_type, params))
+    }
+
+    pub fn remove_policies<R: AsRef<[S]>, S: AsRef<str>>(
+        &self,
+        rules: &[R],
+    ) -> Result<bool, Status> {
+        self.runtime.block_on(self.inner.remove_policies(rules))
+    }
+
+    pub fn remove_filtered_policy<S: AsRef<str>>(
+        &self,
+        field_index: i32,
+        field_values: &[S],
+    ) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.remove_filtered_policy(field_index, field_values))
+    }
+
+    pub fn update_policy<S: AsRef<str>>(
+        &self,
+        old_rule: &[S],
+        new_rule: &[S],
+    ) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.update_policy(old_rule, new_rule))
+    }
+
+    pub fn get_grouping_policy(&self) -> Result<Vec<Vec<String>>, Status> {
+        self.runtime.block_on(self.inner.get_grouping_policy())
+    }
+
+    pub fn has_grouping_policy<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.has_grouping_policy(params))
+    }
+
+    pub fn add_grouping_policy<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.add_grouping_policy(params))
+    }
+
+    pub fn remove_grouping_policy<S: AsRef<str>>(&self, params: &[S]) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.remove_grouping_policy(params))
+    }
+
+    pub fn get_all_subjects(&self) -> Result<Vec<String>, Status> {
+        self.runtime.block_on(self.inner.get_all_subjects())
+    }
+
+    pub fn get_all_objects(&self) -> Result<Vec<String>, Status> {
+        self.runtime.block_on(self.inner.get_all_objects())
+    }
+
+    pub fn get_all_actions(&self) -> Result<Vec<String>, Status> {
+        self.runtime.block_on(self.inner.get_all_actions())
+    }
+
+    pub fn get_all_roles(&self) -> Result<Vec<String>, Status> {
+        self.runtime.block_on(self.inner.get_all_roles())
+    }
+
+    pub fn get_roles_for_user(&self, user: &str) -> Result<Vec<String>, Status> {
+        self.runtime.block_on(self.inner.get_roles_for_user(user))
+    }
+
+    pub fn get_implicit_roles_for_user(&self, user: &str) -> Result<Vec<String>, Status> {
+        self.runtime
+            .block_on(self.inner.get_implicit_roles_for_user(user))
+    }
+
+    pub fn get_users_for_role(&self, role: &str) -> Result<Vec<String>, Status> {
+        self.runtime.block_on(self.inner.get_users_for_role(role))
+    }
+
+    pub fn has_role_for_user(&self, user: &str, role: &str) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.has_role_for_user(user, role))
+    }
+
+    pub fn add_role_for_user(&self, user: &str, role: &str) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.add_role_for_user(user, role))
+    }
+
+    pub fn delete_role_for_user(&self, user: &str, role: &str) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.delete_role_for_user(user, role))
+    }
+
+    pub fn get_permissions_for_user(&self, user: &str) -> Result<Vec<Vec<String>>, Status> {
+        self.runtime
+            .block_on(self.inner.get_permissions_for_user(user))
+    }
+
+    pub fn get_implicit_permissions_for_user(
+        &self,
+        user: &str,
+    ) -> Result<Vec<Vec<String>>, Status> {
+        self.runtime
+            .block_on(self.inner.get_implicit_permissions_for_user(user))
+    }
+
+    pub fn add_permission_for_user<S: AsRef<str>>(
+        &self,
+        user: &str,
+        permission: &[S],
+    ) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.add_permission_for_user(user, permission))
+    }
+
+    pub fn delete_permission_for_user<S: AsRef<str>>(
+        &self,
+        user: &str,
+        permission: &[S],
+    ) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.delete_permission_for_user(user, permission))
+    }
+
+    pub fn has_permission_for_user<S: AsRef<str>>(
+        &self,
+        user: &str,
+        permission: &[S],
+    ) -> Result<bool, Status> {
+        self.runtime
+            .block_on(self.inner.has_permission_for_user(user, permission))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CasbinClient;
+    use crate::casbin_proto::casbin_server::CasbinServer;
+    use crate::client::tests::{casbin, listen};
+    use crate::client::RetryPolicy;
+    use std::time::Duration;
+    use tonic::transport::Server;
+    use tonic::Code;
+
+    // serve serves an enforcer holding rules from a thread of its own, as the blocking client
+    // cannot run from async code.
+    fn serve(rules: &'static [[&'static str; 3]]) -> String {
+        let (addr, served) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async {
+                let casbin = casbin(rules).await;
+                let router = Server::builder().add_service(CasbinServer::new(casbin));
+                addr.send(listen(router).await).unwrap();
+                std::future::pending::<()>().await
+            })
+        });
+        served.recv().unwrap()
+    }
+
+    #[test]
+    fn test_blocking_client() {
+        let addr = serve(&[["alice", "data1", "read"]]);
+        let client = CasbinClient::builder(&addr)
+            .timeout(Duration::from_secs(5))
+            .retry(RetryPolicy::default())
+            .connect()
+            .unwrap();
+        assert_eq!(client.handle(), 0);
+
+        assert!(client.enforce(&["alice", "data1", "read"]).unwrap());
+        assert!(!client.enforce(&["bob", "data1", "read"]).unwrap());
+        assert!(client.add_role_for_user("bob", "alice").unwrap());
+        assert!(client.enforce(&["bob", "data1", "read"]).unwrap());
+        assert_eq!(client.get_users_for_role("alice").unwrap(), vec!["bob"]);
+        assert_eq!(
+            client.get_policy().unwrap(),
+            vec![vec!["alice", "data1", "read"]]
+        );
+
+        // Clones share the runtime, which outlives the client they were cloned from.
+        let clone = client.clone();
+        drop(client);
+        assert!(clone.add_policy(&["carol", "data2", "write"]).unwrap());
+        assert!(clone.has_policy(&["carol", "data2", "write"]).unwrap());
+
+        let model_text = clone.get_model().unwrap();
+        let other = clone.new_enforcer(&model_text, -1).unwrap();
+        assert!(other.get_policy().unwrap().is_empty());
+        let stale = clone.for_enforcer(other.handle());
+        other.delete_enforcer().unwrap();
+        assert_eq!(stale.get_policy().unwrap_err().code(), Code::NotFound);
+    }
+
+    #[test]
+    fn test_blocking_connect_fails() {
+        // Nothing listens on port 1.
+        let err = CasbinClient::builder("http://127.0.0.1:1")
+            .connect_timeout(Duration::from_secs(1))
+            .connect();
+        assert!(err.is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::adapter::adapter_error;
+use tonic::codec::CompressionEncoding;
+
+// ENCODINGS lists the compression encodings of calls this build supports. zstd is left for a
+// follow-up adding it behind a zstd feature, once the server is on tonic 0.10 or later, the
+// first with a zstd codec.
+static ENCODINGS: &[&str] = &["gzip"];
+
+// encoding parses the name of a compression encoding of calls, none for an empty name.
+pub fn encoding(name: &str) -> casbin::Result<Option<CompressionEncoding>> {
+    match name {
+        "" => Ok(None),
+        "gzip" => Ok(Some(CompressionEncoding::Gzip)),
+        _ => Err(adapter_error(format!(
+            "unsupported compression `{}`, this server supports: {}",
+            name,
+            ENCODINGS.join(", ")
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encoding;
+    use crate::casbin_proto::casbin_server::{Casbin, CasbinServer};
+    use crate::casbin_proto::{EmptyRequest, PolicyRequest};
+    use crate::client::{connect, connect_compressed};
+    use crate::server::enforcer;
+    use crate::CasbinGRPC;
+    use casbin::{DefaultModel, MemoryAdapter};
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::codec::CompressionEncoding;
+    use tonic::{Code, Request};
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(encoding("").unwrap(), None);
+        assert_eq!(encoding("gzip").unwrap(), Some(CompressionEncoding::Gzip));
+        assert!(encoding("brotli").is_err());
+    }
+
+    // serve serves an enforcer holding a rule, compressing with compression, getting its
+    // address.
+    async fn serve(compression: Option<CompressionEncoding>) -> String {
+        let casbin = CasbinGRPC::new_server();
+        let model_text = std::fs::read_to_string("examples/rbac_model.conf").unwrap();
+        let m = DefaultModel::from_str(&model_text).await.unwrap();
+        let e = enforcer::build_enforcer(m, MemoryAdapter::default())
+            .await
+            .unwrap();
+        let handle = casbin.add_enforcer(e, model_text).await;
+        casbin
+            .add_policy(Request::new(PolicyRequest {
+                enforcer_handler: handle,
+                p_type: "p".to_owned(),
+                params: vec!["alice".into(), "data1".into(), "read".into()],
+            }))
+            .await
+            .unwrap();
+        let mut service = CasbinServer::new(casbin);
+        if let Some(encoding) = compression {
+            service = service
+                .send_compressed(encoding)
+                .accept_compressed(encoding);
+        }
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        addr
+    }
+
+    fn get_policy() -> Request<EmptyRequest> {
+        Request::new(EmptyRequest { handler: 0 })
+    }
+
+    #[tokio::test]
+    async fn test_compressed_calls() {
+        let addr = serve(Some(CompressionEncoding::Gzip)).await;
+        let mut client = connect_compressed(&addr, CompressionEncoding::Gzip)
+            .await
+            .unwrap();
+        let reply = client.get_policy(get_policy()).await.unwrap();
+        assert_eq!(
+            reply.metadata().get("grpc-encoding").unwrap(),
+            "gzip",
+            "the reply is compressed"
+        );
+        assert_eq!(reply.into_inner().d2.len(), 1);
+
+        // Clients not compressing are served too, uncompressed.
+        let mut client = connect(&addr).await.unwrap();
+        let reply = client.get_policy(get_policy()).await.unwrap();
+        assert!(reply.metadata().get("grpc-encoding").is_none());
+        assert_eq!(reply.into_inner().d2.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_uncompressed_server_rejects_compressed_calls() {
+        let addr = serve(None).await;
+        let mut client = connect_compressed(&addr, CompressionEncoding::Gzip)
+            .await
+            .unwrap();
+        let status = client.get_policy(get_policy()).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+    }
+}
@@ -1,125 +1,841 @@
 use std::cmp::PartialEq;
-use std::fmt::Debug;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
 
+// Hashable keys are hashed with the hasher of the table they are in.
 pub trait Hashable {
-    fn hash(&self) -> usize;
+    fn hash_with<S: BuildHasher>(&self, hasher: &S) -> usize;
 }
 
-impl Hashable for String {
-    // http://www.cse.yorku.ca/~oz/hash.html
-    fn hash(&self) -> usize {
-        let mut result: usize = 5381;
-        for c in self.bytes() {
-            result = ((result << 5).wrapping_add(result)).wrapping_add(c.into());
+impl<T: Hash + ?Sized> Hashable for T {
+    fn hash_with<S: BuildHasher>(&self, hasher: &S) -> usize {
+        hasher.hash_one(self) as usize
+    }
+}
+
+// Djb2 hashes like djb2, faster than the default SipHash with random keys but open to keys
+// chosen to collide, so only for tables whose keys are trusted.
+// http://www.cse.yorku.ca/~oz/hash.html
+#[derive(Clone, Copy, Default)]
+pub struct Djb2;
+
+pub struct Djb2Hasher(u64);
+
+impl Hasher for Djb2Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for c in bytes {
+            self.0 = ((self.0 << 5).wrapping_add(self.0)).wrapping_add((*c).into());
         }
-        result
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
     }
 }
 
-impl Hashable for usize {
-    fn hash(&self) -> usize {
-        *self
+impl BuildHasher for Djb2 {
+    type Hasher = Djb2Hasher;
+
+    fn build_hasher(&self) -> Djb2Hasher {
+        Djb2Hasher(5381)
     }
 }
 
-#[derive(Default, Clone)]
-struct HashCell<Key, Value> {
-    key: Key,
-    value: Value,
-    taken: bool,
+// A cell whose entry was removed is left deleted rather than free, so that probing for the
+// keys inserted past it after a collision goes on instead of stopping there.
+enum HashCell<Key, Value> {
+    Free,
+    Deleted,
+    Taken(Key, Value),
+}
+
+impl<Key, Value> HashCell<Key, Value> {
+    fn holds(&self, key: &Key) -> bool
+    where
+        Key: PartialEq,
+    {
+        matches!(self, HashCell::Taken(k, _) if k == key)
+    }
+}
+
+// MAX_LOAD_PERCENT is how full the cells of a table may get, deleted ones included, before
+// they are rehashed, as probing slows down sharply past it.
+static MAX_LOAD_PERCENT: usize = 70;
+
+static INITIAL_CAPACITY: usize = 11;
+
+fn overloaded(entries: usize, cells: usize) -> bool {
+    entries * 100 > cells * MAX_LOAD_PERCENT
+}
+
+// cells_for is the fewest cells holding entries within the maximum load.
+fn cells_for(entries: usize) -> usize {
+    (entries * 100).div_ceil(MAX_LOAD_PERCENT).max(1)
+}
+
+fn free_cells<Key, Value>(capacity: usize) -> Vec<HashCell<Key, Value>> {
+    std::iter::repeat_with(|| HashCell::Free)
+        .take(capacity)
+        .collect()
 }
 
-pub struct HashTable<Key, Value> {
+// HashTable hashes its keys with SipHash keyed at random unless given another hasher, so that
+// keys taken from requests cannot be chosen to collide.
+pub struct HashTable<Key, Value, S = RandomState> {
     cells: Vec<HashCell<Key, Value>>,
     taken_count: usize,
+    deleted_count: usize,
+    hasher: S,
 }
 
-impl<Key, Value> HashTable<Key, Value>
+impl<Key, Value> HashTable<Key, Value, RandomState>
 where
-    Key: Clone + Default + Debug + PartialEq + Hashable,
-    Value: Clone + Default + Debug + Copy,
+    Key: PartialEq + Hashable,
 {
     pub fn new() -> Self {
-        const INITIAL_CAPACITY: usize = 11;
+        Self::with_hasher(RandomState::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<Key, Value, S> Default for HashTable<Key, Value, S>
+where
+    Key: PartialEq + Hashable,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<Key, Value, S> HashTable<Key, Value, S>
+where
+    Key: PartialEq + Hashable,
+    S: BuildHasher,
+{
+    pub fn with_hasher(hasher: S) -> Self {
         Self {
-            cells: vec![HashCell::<_, _>::default(); INITIAL_CAPACITY],
+            cells: free_cells(INITIAL_CAPACITY),
             taken_count: 0,
+            deleted_count: 0,
+            hasher,
         }
     }
 
-    pub fn extend(&mut self) {
-        assert!(self.cells.len() > 0);
-        let mut new_self = Self {
-            cells: vec![HashCell::<_, _>::default(); self.cells.len() * 2 + 1],
+    // with_capacity_and_hasher makes a table holding capacity entries before it grows.
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        Self {
+            cells: free_cells(cells_for(capacity)),
             taken_count: 0,
-        };
+            deleted_count: 0,
+            hasher,
+        }
+    }
 
-        for cell in self.cells.iter() {
-            if cell.taken {
-                new_self.insert(cell.key.clone(), cell.value.clone());
+    pub fn extend(&mut self) {
+        assert!(!self.cells.is_empty());
+        self.rehash(self.cells.len() * 2 + 1);
+    }
+
+    // rehash moves the entries to capacity cells, dropping the deleted ones.
+    fn rehash(&mut self, capacity: usize) {
+        let cells = std::mem::replace(&mut self.cells, free_cells(capacity));
+        self.taken_count = 0;
+        self.deleted_count = 0;
+        for cell in cells {
+            if let HashCell::Taken(key, value) = cell {
+                if let Err(index) = self.find_slot(&key) {
+                    self.fill(index, key, value);
+                }
             }
         }
-
-        *self = new_self;
     }
 
     pub fn insert(&mut self, key: Key, new_value: Value) {
-        if let Some(old_value) = self.get_mut(&key) {
-            *old_value = new_value;
-        } else {
-            if self.taken_count >= self.cells.len() {
-                self.extend();
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => {
+                entry.insert(new_value);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(new_value);
             }
-            assert!(self.taken_count < self.cells.len());
+        }
+    }
 
-            let mut index = key.hash() % self.cells.len();
+    // entry gets the entry of key, to be looked at, changed or filled in without probing for
+    // the key again.
+    pub fn entry(&mut self, key: Key) -> Entry<'_, Key, Value, S> {
+        let mut index = match self.find_slot(&key) {
+            Ok(index) => return Entry::Occupied(OccupiedEntry { table: self, index }),
+            Err(index) => index,
+        };
+        // Only a key to be inserted can take the table past its maximum load.
+        if overloaded(self.taken_count + 1, self.cells.len()) {
+            self.extend();
+            index = self.vacant_slot(&key);
+        } else if overloaded(self.taken_count + self.deleted_count + 1, self.cells.len()) {
+            // The deleted cells make probing as slow as taken ones would.
+            self.rehash(self.cells.len());
+            index = self.vacant_slot(&key);
+        }
+        Entry::Vacant(VacantEntry {
+            table: self,
+            index,
+            key,
+        })
+    }
 
-            while self.cells[index].taken {
-                index = (index + 1) % self.cells.len();
+    // vacant_slot finds the cell to insert key in, once the cells were rehashed.
+    fn vacant_slot(&self, key: &Key) -> usize {
+        match self.find_slot(key) {
+            Ok(_) => unreachable!("the key was not in the table before it was rehashed"),
+            Err(index) => index,
+        }
+    }
+
+    // find_slot finds the cell holding key, or else the cell to insert it in: the first
+    // deleted cell probed, or the free cell probing stopped at.
+    fn find_slot(&self, key: &Key) -> Result<usize, usize> {
+        let mut index = key.hash_with(&self.hasher) % self.cells.len();
+        let mut deleted = None;
+        for _ in 0..self.cells.len() {
+            match &self.cells[index] {
+                HashCell::Free => return Err(deleted.unwrap_or(index)),
+                HashCell::Deleted => {
+                    deleted.get_or_insert(index);
+                }
+                cell if cell.holds(key) => return Ok(index),
+                _ => {}
             }
+            index = (index + 1) % self.cells.len();
+        }
+        Err(deleted.expect("a table within its maximum load has a cell to insert in"))
+    }
 
-            self.cells[index].taken = true;
-            self.cells[index].key = key;
-            self.cells[index].value = new_value;
-            self.taken_count += 1;
+    fn fill(&mut self, index: usize, key: Key, value: Value) -> &mut Value {
+        if let HashCell::Deleted = self.cells[index] {
+            self.deleted_count -= 1;
+        }
+        self.cells[index] = HashCell::Taken(key, value);
+        self.taken_count += 1;
+        match &mut self.cells[index] {
+            HashCell::Taken(_, value) => value,
+            _ => unreachable!(),
         }
     }
 
     fn get_index(&self, key: &Key) -> Option<usize> {
-        let mut index = key.hash() % self.cells.len();
+        let mut index = key.hash_with(&self.hasher) % self.cells.len();
         for _ in 0..self.cells.len() {
-            if !self.cells[index].taken {
-                break;
+            match &self.cells[index] {
+                HashCell::Free => return None,
+                cell if cell.holds(key) => return Some(index),
+                _ => index = (index + 1) % self.cells.len(),
+            }
+        }
+        None
+    }
+
+    #[allow(dead_code)]
+    pub fn get(&self, key: &Key) -> Option<&Value> {
+        match &self.cells[self.get_index(key)?] {
+            HashCell::Taken(_, value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &Key) -> Option<&mut Value> {
+        let index = self.get_index(key)?;
+        match &mut self.cells[index] {
+            HashCell::Taken(_, value) => Some(value),
+            _ => None,
+        }
+    }
+
+    // remove removes the entry of key, returning its value.
+    pub fn remove(&mut self, key: &Key) -> Option<Value> {
+        let index = self.get_index(key)?;
+        match std::mem::replace(&mut self.cells[index], HashCell::Deleted) {
+            HashCell::Taken(_, value) => {
+                self.taken_count -= 1;
+                self.deleted_count += 1;
+                Some(value)
             }
+            _ => None,
+        }
+    }
 
-            if self.cells[index].key == *key {
-                break;
+    // retain removes the entries f does not keep.
+    pub fn retain<F: FnMut(&Key, &mut Value) -> bool>(&mut self, mut f: F) {
+        for cell in self.cells.iter_mut() {
+            if let HashCell::Taken(key, value) = cell {
+                if !f(key, value) {
+                    *cell = HashCell::Deleted;
+                    self.taken_count -= 1;
+                    self.deleted_count += 1;
+                }
             }
+        }
+    }
 
-            index = (index + 1) % self.cells.len();
+    pub fn len(&self) -> usize {
+        self.taken_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.taken_count == 0
+    }
+
+    // capacity is how many entries the table holds before it grows.
+    pub fn capacity(&self) -> usize {
+        self.cells.len() * MAX_LOAD_PERCENT / 100
+    }
+
+    // clear removes every entry, keeping the cells for the entries to come.
+    pub fn clear(&mut self) {
+        for cell in self.cells.iter_mut() {
+            *cell = HashCell::Free;
         }
+        self.taken_count = 0;
+        self.deleted_count = 0;
+    }
+
+    // shrink_to_fit rehashes the entries into as few cells as hold them.
+    pub fn shrink_to_fit(&mut self) {
+        self.rehash(cells_for(self.taken_count));
+        self.cells.shrink_to_fit();
+    }
 
-        if self.cells[index].taken && self.cells[index].key == *key {
-            Some(index)
-        } else {
-            None
+    // iter goes through the entries in the order of their cells, which is no order of theirs.
+    pub fn iter(&self) -> Iter<'_, Key, Value> {
+        self.into_iter()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Key> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+pub enum Entry<'a, Key, Value, S> {
+    Occupied(OccupiedEntry<'a, Key, Value, S>),
+    Vacant(VacantEntry<'a, Key, Value, S>),
+}
+
+pub struct OccupiedEntry<'a, Key, Value, S> {
+    table: &'a mut HashTable<Key, Value, S>,
+    index: usize,
+}
+
+pub struct VacantEntry<'a, Key, Value, S> {
+    table: &'a mut HashTable<Key, Value, S>,
+    index: usize,
+    key: Key,
+}
+
+impl<'a, Key, Value, S> Entry<'a, Key, Value, S>
+where
+    Key: PartialEq + Hashable,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &Key {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
         }
     }
 
-    #[allow(dead_code)]
-    pub fn get(&self, key: &Key) -> Option<Value> {
-        if let Some(index) = self.get_index(key) {
-            Some(self.cells[index].value)
-        } else {
-            None
+    pub fn or_insert(self, default: Value) -> &'a mut Value {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> Value>(self, default: F) -> &'a mut Value {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
         }
     }
 
-    pub fn get_mut(&mut self, key: &Key) -> Option<&mut Value> {
-        if let Some(index) = self.get_index(key) {
-            Some(&mut self.cells[index].value)
-        } else {
-            None
+    pub fn or_default(self) -> &'a mut Value
+    where
+        Value: Default,
+    {
+        self.or_insert_with(Value::default)
+    }
+
+    // and_modify changes the value of an occupied entry with f, leaving a vacant one be.
+    pub fn and_modify<F: FnOnce(&mut Value)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, Key, Value, S> OccupiedEntry<'a, Key, Value, S>
+where
+    Key: PartialEq + Hashable,
+    S: BuildHasher,
+{
+    fn cell(&self) -> (&Key, &Value) {
+        match &self.table.cells[self.index] {
+            HashCell::Taken(key, value) => (key, value),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn key(&self) -> &Key {
+        self.cell().0
+    }
+
+    pub fn get(&self) -> &Value {
+        self.cell().1
+    }
+
+    pub fn get_mut(&mut self) -> &mut Value {
+        match &mut self.table.cells[self.index] {
+            HashCell::Taken(_, value) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn into_mut(self) -> &'a mut Value {
+        match &mut self.table.cells[self.index] {
+            HashCell::Taken(_, value) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    // insert replaces the value of the entry, returning the one it held.
+    pub fn insert(&mut self, value: Value) -> Value {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> Value {
+        self.table.taken_count -= 1;
+        self.table.deleted_count += 1;
+        match std::mem::replace(&mut self.table.cells[self.index], HashCell::Deleted) {
+            HashCell::Taken(_, value) => value,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a, Key, Value, S> VacantEntry<'a, Key, Value, S>
+where
+    Key: PartialEq + Hashable,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+
+    pub fn into_key(self) -> Key {
+        self.key
+    }
+
+    pub fn insert(self, value: Value) -> &'a mut Value {
+        self.table.fill(self.index, self.key, value)
+    }
+}
+
+pub struct Iter<'a, Key, Value> {
+    cells: std::slice::Iter<'a, HashCell<Key, Value>>,
+}
+
+impl<'a, Key, Value> Iterator for Iter<'a, Key, Value> {
+    type Item = (&'a Key, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.cells.find_map(|cell| match cell {
+            HashCell::Taken(key, value) => Some((key, value)),
+            _ => None,
+        })
+    }
+}
+
+pub struct IntoIter<Key, Value> {
+    cells: std::vec::IntoIter<HashCell<Key, Value>>,
+}
+
+impl<Key, Value> Iterator for IntoIter<Key, Value> {
+    type Item = (Key, Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.cells.find_map(|cell| match cell {
+            HashCell::Taken(key, value) => Some((key, value)),
+            _ => None,
+        })
+    }
+}
+
+impl<'a, Key, Value, S> IntoIterator for &'a HashTable<Key, Value, S> {
+    type Item = (&'a Key, &'a Value);
+    type IntoIter = Iter<'a, Key, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Iter {
+            cells: self.cells.iter(),
+        }
+    }
+}
+
+// Consuming the table drains its entries.
+impl<Key, Value, S> IntoIterator for HashTable<Key, Value, S> {
+    type Item = (Key, Value);
+    type IntoIter = IntoIter<Key, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            cells: self.cells.into_iter(),
+        }
+    }
+}
+
+// A table is serialized as a map of its entries, leaving out the free and deleted cells, and
+// deserialized into a table sized for them, as loaded as one they were inserted into.
+#[cfg(feature = "serde")]
+mod serialize {
+    use super::{HashTable, Hashable};
+    use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+    use serde::ser::{Serialize, SerializeMap, Serializer};
+    use std::fmt;
+    use std::hash::BuildHasher;
+    use std::marker::PhantomData;
+
+    // PREALLOCATED bounds the entries a table is sized for up front, whatever the length the
+    // input claims, so a bogus one cannot make it allocate more than the entries it holds.
+    static PREALLOCATED: usize = 4096;
+
+    impl<Key, Value, S> Serialize for HashTable<Key, Value, S>
+    where
+        Key: Serialize,
+        Value: Serialize,
+    {
+        fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+            let mut map = serializer.serialize_map(Some(self.taken_count))?;
+            for (key, value) in self {
+                map.serialize_entry(key, value)?;
+            }
+            map.end()
+        }
+    }
+
+    struct TableVisitor<Key, Value, S>(PhantomData<HashTable<Key, Value, S>>);
+
+    impl<'de, Key, Value, S> Visitor<'de> for TableVisitor<Key, Value, S>
+    where
+        Key: Deserialize<'de> + PartialEq + Hashable,
+        Value: Deserialize<'de>,
+        S: BuildHasher + Default,
+    {
+        type Value = HashTable<Key, Value, S>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a map")
+        }
+
+        fn visit_map<M: MapAccess<'de>>(self, mut access: M) -> Result<Self::Value, M::Error> {
+            let capacity = access.size_hint().unwrap_or(0).min(PREALLOCATED);
+            let mut table = HashTable::with_capacity_and_hasher(capacity, S::default());
+            while let Some((key, value)) = access.next_entry()? {
+                table.insert(key, value);
+            }
+            Ok(table)
+        }
+    }
+
+    impl<'de, Key, Value, S> Deserialize<'de> for HashTable<Key, Value, S>
+    where
+        Key: Deserialize<'de> + PartialEq + Hashable,
+        Value: Deserialize<'de>,
+        S: BuildHasher + Default,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_map(TableVisitor(PhantomData))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Djb2, Entry, HashCell, HashTable, Hashable};
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    // Colliding hashes every key alike, so that each key is probed for past the others.
+    #[derive(Clone, Copy, Default)]
+    struct Colliding;
+
+    struct CollidingHasher;
+
+    impl Hasher for CollidingHasher {
+        fn write(&mut self, _: &[u8]) {}
+
+        fn finish(&self) -> u64 {
+            0
+        }
+    }
+
+    impl BuildHasher for Colliding {
+        type Hasher = CollidingHasher;
+
+        fn build_hasher(&self) -> CollidingHasher {
+            CollidingHasher
+        }
+    }
+
+    fn colliding(keys: &[&str]) -> HashTable<String, usize, Colliding> {
+        let mut table = HashTable::with_hasher(Colliding);
+        for (i, key) in keys.iter().enumerate() {
+            table.insert(key.to_string(), i);
+        }
+        table
+    }
+
+    fn index_of(table: &HashTable<String, usize, Colliding>, key: &str) -> Option<usize> {
+        table
+            .cells
+            .iter()
+            .position(|cell| matches!(cell, HashCell::Taken(k, _) if k == key))
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut table = colliding(&["a", "b", "c"]);
+        assert_eq!(table.remove(&"b".to_string()), Some(1));
+        assert_eq!(table.remove(&"b".to_string()), None);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get(&"b".to_string()), None);
+        // c was probed for past b, and still is past the cell b left deleted.
+        assert_eq!(table.get(&"c".to_string()), Some(&2));
+        assert_eq!(table.deleted_count, 1);
+    }
+
+    #[test]
+    fn test_insert_reuses_deleted_cell() {
+        let mut table = colliding(&["a", "b", "c"]);
+        let b = index_of(&table, "b");
+        table.remove(&"b".to_string());
+        table.insert("d".to_string(), 3);
+        assert_eq!(index_of(&table, "d"), b);
+        assert_eq!(table.deleted_count, 0);
+
+        // A key already in the table past a deleted cell is replaced, not inserted twice.
+        table.remove(&"a".to_string());
+        table.insert("c".to_string(), 4);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get(&"c".to_string()), Some(&4));
+        assert_eq!(table.deleted_count, 1);
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut table = colliding(&["a", "b", "c", "d"]);
+        table.remove(&"c".to_string());
+
+        let mut entries: Vec<(&String, &usize)> = table.iter().collect();
+        entries.sort();
+        let (a, b, d) = ("a".to_string(), "b".to_string(), "d".to_string());
+        assert_eq!(entries, vec![(&a, &0), (&b, &1), (&d, &3)]);
+
+        let mut keys: Vec<&String> = table.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["a", "b", "d"]);
+        let mut values: Vec<usize> = table.values().copied().collect();
+        values.sort();
+        assert_eq!(values, vec![0, 1, 3]);
+        assert_eq!((&table).into_iter().count(), 3);
+
+        let mut drained: Vec<(String, usize)> = table.into_iter().collect();
+        drained.sort();
+        assert_eq!(
+            drained,
+            vec![
+                ("a".to_string(), 0),
+                ("b".to_string(), 1),
+                ("d".to_string(), 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_values_neither_copy_nor_default() {
+        let mut table: HashTable<String, Vec<String>> = HashTable::new();
+        table.insert("alice".to_string(), vec!["admin".to_string()]);
+        table.insert("bob".to_string(), vec![]);
+        table
+            .get_mut(&"bob".to_string())
+            .unwrap()
+            .push("editor".to_string());
+        assert_eq!(table.get(&"alice".to_string()).unwrap(), &["admin"]);
+        assert_eq!(table.get(&"bob".to_string()).unwrap(), &["editor"]);
+        assert_eq!(
+            table.remove(&"alice".to_string()),
+            Some(vec!["admin".to_string()])
+        );
+        assert_eq!(table.get(&"alice".to_string()), None);
+    }
+
+    #[test]
+    fn test_hashers() {
+        let keys: Vec<String> = (0..100).map(|i| format!("user{}", i)).collect();
+        let mut djb2 = HashTable::with_hasher(Djb2);
+        let mut colliding = HashTable::with_hasher(Colliding);
+        let mut keyed = HashTable::new();
+        for (i, key) in keys.iter().enumerate() {
+            djb2.insert(key.clone(), i);
+            colliding.insert(key.clone(), i);
+            keyed.insert(key.clone(), i);
+        }
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(djb2.get(key), Some(&i));
+            assert_eq!(colliding.get(key), Some(&i));
+            assert_eq!(keyed.get(key), Some(&i));
         }
+
+        // Keyed hashers of two tables hash a key apart, so keys colliding in one do not in all.
+        let (a, b) = (RandomState::new(), RandomState::new());
+        assert_ne!("user0".hash_with(&a), "user0".hash_with(&b));
+        assert_eq!("user0".hash_with(&Djb2), "user0".hash_with(&Djb2));
+
+        // Anything hashable is a key.
+        let mut rules: HashTable<(&str, Option<&str>), bool> = HashTable::new();
+        rules.insert(("alice", Some("domain1")), true);
+        assert_eq!(rules.get(&("alice", Some("domain1"))), Some(&true));
+        assert_eq!(rules.get(&("alice", None)), None);
+    }
+
+    #[test]
+    fn test_grows_past_maximum_load() {
+        let mut table = HashTable::new();
+        assert_eq!((table.cells.len(), table.capacity()), (11, 7));
+        for i in 0..7 {
+            table.insert(i, i);
+        }
+        // 7 entries are within 70% of 11 cells, 8 are not.
+        assert_eq!(table.cells.len(), 11);
+        table.insert(7, 7);
+        assert_eq!(table.cells.len(), 23);
+        assert_eq!(table.len(), 8);
+        assert!((0..8).all(|i| table.get(&i) == Some(&i)));
+
+        let table: HashTable<i32, i32> = HashTable::with_capacity(100);
+        assert!(table.capacity() >= 100);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_rehashes_deleted_cells() {
+        let mut table = HashTable::new();
+        for i in 0..100 {
+            table.insert(i, i);
+            table.remove(&i);
+            assert!(table.deleted_count * 100 <= table.cells.len() * 70);
+        }
+        // Deleted cells are rehashed away rather than grown past.
+        assert_eq!(table.cells.len(), 11);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_clear_and_shrink_to_fit() {
+        let mut table = HashTable::new();
+        for i in 0..100 {
+            table.insert(i, i);
+        }
+        let cells = table.cells.len();
+        table.clear();
+        assert!(table.is_empty());
+        assert_eq!(table.cells.len(), cells);
+        assert_eq!(table.get(&1), None);
+
+        for i in 0..100 {
+            table.insert(i, i);
+        }
+        for i in 10..100 {
+            table.remove(&i);
+        }
+        table.shrink_to_fit();
+        assert_eq!(table.len(), 10);
+        assert!(table.cells.len() < cells);
+        assert!(table.capacity() >= 10);
+        assert_eq!(table.deleted_count, 0);
+        assert!((0..10).all(|i| table.get(&i) == Some(&i)));
+    }
+
+    #[test]
+    fn test_entry() {
+        let mut table: HashTable<String, Vec<usize>> = HashTable::new();
+        table.entry("a".to_string()).or_default().push(1);
+        table.entry("a".to_string()).or_default().push(2);
+        assert_eq!(table.get(&"a".to_string()).unwrap(), &[1, 2]);
+
+        table
+            .entry("a".to_string())
+            .and_modify(|v| v.clear())
+            .or_insert_with(|| vec![3]);
+        table
+            .entry("b".to_string())
+            .and_modify(|v| v.clear())
+            .or_insert_with(|| vec![3]);
+        assert_eq!(table.get(&"a".to_string()).unwrap(), &[] as &[usize]);
+        assert_eq!(table.get(&"b".to_string()).unwrap(), &[3]);
+
+        match table.entry("b".to_string()) {
+            Entry::Occupied(entry) => {
+                assert_eq!(entry.key(), "b");
+                assert_eq!(entry.remove(), vec![3]);
+            }
+            Entry::Vacant(_) => panic!("b is in the table"),
+        }
+        match table.entry("b".to_string()) {
+            Entry::Occupied(_) => panic!("b was removed"),
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), "b"),
+        }
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_entry_grows_only_to_insert() {
+        let mut table = HashTable::new();
+        for i in 0..7 {
+            table.insert(i, i);
+        }
+        // The table is as loaded as it may be: looking up its keys leaves it be.
+        for i in 0..7 {
+            *table.entry(i).or_insert(0) += 1;
+        }
+        assert_eq!(table.cells.len(), 11);
+        table.entry(7).or_insert(7);
+        assert_eq!(table.cells.len(), 23);
+        assert!((0..7).all(|i| table.get(&i) == Some(&(i + 1))));
+        assert_eq!(table.get(&7), Some(&7));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let mut table = colliding(&["a", "b", "c"]);
+        table.remove(&"b".to_string());
+        let json = serde_json::to_string(&table).unwrap();
+        assert_eq!(json, r#"{"a":0,"c":2}"#);
+
+        let loaded: HashTable<String, usize, Colliding> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.deleted_count, 0);
+        assert_eq!(loaded.get(&"a".to_string()), Some(&0));
+        assert_eq!(loaded.get(&"c".to_string()), Some(&2));
+
+        // An empty map loads an empty table, and anything but a map is refused.
+        let loaded: HashTable<u64, u64> = serde_json::from_str("{}").unwrap();
+        assert!(loaded.is_empty());
+        assert!(serde_json::from_str::<HashTable<u64, u64>>("[1]").is_err());
     }
 }
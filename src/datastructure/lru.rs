@@ -0,0 +1,621 @@
+use crate::datastructure::hashtable::{HashTable, Hashable};
+use casbin::Cache;
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::time::{Duration, Instant};
+
+// NIL is the slot linked to by the ends of the recency list.
+static NIL: usize = usize::MAX;
+
+// Evicted tells why a cache dropped an entry on its own: to make room for another past its
+// capacity, or as the entry expired.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Evicted {
+    Capacity,
+    Expired,
+}
+
+type OnEvict<Key, Value> = Box<dyn FnMut(Key, Value, Evicted) + Send + Sync>;
+
+struct Node<Key, Value> {
+    key: Key,
+    value: Value,
+    expires: Option<Instant>,
+    prev: usize,
+    next: usize,
+}
+
+impl<Key, Value> Node<Key, Value> {
+    fn expired(&self, now: Instant) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+// LruCache holds up to capacity entries, evicting the least recently used one to make room
+// for another, and none with capacity 0. Its entries are nodes of a slab, linked into a list
+// by recency, most recent first, and found through a hash table of their slots, so getting,
+// inserting and evicting an entry take one probe of the table. An entry expires after the ttl
+// it was inserted with, or the one of the cache, and is evicted once found expired. The
+// callback given to on_evict is called with what the cache evicts, never with what it is
+// asked to remove or clear.
+pub struct LruCache<Key, Value, S = RandomState> {
+    slots: HashTable<Key, usize, S>,
+    nodes: Vec<Option<Node<Key, Value>>>,
+    free: Vec<usize>,
+    head: usize,
+    tail: usize,
+    capacity: usize,
+    ttl: Duration,
+    on_evict: Option<OnEvict<Key, Value>>,
+}
+
+impl<Key, Value> LruCache<Key, Value, RandomState>
+where
+    Key: PartialEq + Hashable + Clone,
+{
+    pub fn new(capacity: usize) -> Self {
+        Self::with_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<Key, Value, S> LruCache<Key, Value, S>
+where
+    Key: PartialEq + Hashable + Clone,
+    S: BuildHasher,
+{
+    pub fn with_hasher(capacity: usize, hasher: S) -> Self {
+        LruCache {
+            slots: HashTable::with_hasher(hasher),
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: NIL,
+            tail: NIL,
+            capacity,
+            ttl: Duration::ZERO,
+            on_evict: None,
+        }
+    }
+
+    // with_ttl expires the entries inserted without a ttl of their own after ttl, unless it
+    // is zero.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    // on_evict calls f with every entry the cache evicts, and why.
+    pub fn on_evict<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(Key, Value, Evicted) + Send + Sync + 'static,
+    {
+        self.on_evict = Some(Box::new(f));
+        self
+    }
+
+    fn node(&self, slot: usize) -> &Node<Key, Value> {
+        self.nodes[slot]
+            .as_ref()
+            .expect("linked slot holds no node")
+    }
+
+    fn node_mut(&mut self, slot: usize) -> &mut Node<Key, Value> {
+        self.nodes[slot]
+            .as_mut()
+            .expect("linked slot holds no node")
+    }
+
+    fn unlink(&mut self, slot: usize) {
+        let (prev, next) = {
+            let node = self.node(slot);
+            (node.prev, node.next)
+        };
+        if prev == NIL {
+            self.head = next;
+        } else {
+            self.node_mut(prev).next = next;
+        }
+        if next == NIL {
+            self.tail = prev;
+        } else {
+            self.node_mut(next).prev = prev;
+        }
+    }
+
+    fn push_front(&mut self, slot: usize) {
+        let head = self.head;
+        let node = self.node_mut(slot);
+        node.prev = NIL;
+        node.next = head;
+        if head == NIL {
+            self.tail = slot;
+        } else {
+            self.node_mut(head).prev = slot;
+        }
+        self.head = slot;
+    }
+
+    // take unlinks the node in slot and frees the slot, getting the node.
+    fn take(&mut self, slot: usize) -> Node<Key, Value> {
+        self.unlink(slot);
+        let node = self.nodes[slot].take().expect("linked slot holds no node");
+        self.slots.remove(&node.key);
+        self.free.push(slot);
+        node
+    }
+
+    fn evict(&mut self, slot: usize, why: Evicted) {
+        let node = self.take(slot);
+        if let Some(on_evict) = self.on_evict.as_mut() {
+            on_evict(node.key, node.value, why);
+        }
+    }
+
+    // live gets the slot of key, evicting its entry if it expired.
+    fn live(&mut self, key: &Key) -> Option<usize> {
+        let slot = *self.slots.get(key)?;
+        if self.node(slot).expired(Instant::now()) {
+            self.evict(slot, Evicted::Expired);
+            return None;
+        }
+        Some(slot)
+    }
+
+    // get gets the value of key, making it the most recently used entry.
+    pub fn get(&mut self, key: &Key) -> Option<&Value> {
+        self.get_mut(key).map(|value| &*value)
+    }
+
+    pub fn get_mut(&mut self, key: &Key) -> Option<&mut Value> {
+        let slot = self.live(key)?;
+        if slot != self.head {
+            self.unlink(slot);
+            self.push_front(slot);
+        }
+        Some(&mut self.node_mut(slot).value)
+    }
+
+    // peek gets the value of key, leaving its recency as it is.
+    pub fn peek(&self, key: &Key) -> Option<&Value> {
+        let node = self.node(*self.slots.get(key)?);
+        (!node.expired(Instant::now())).then_some(&node.value)
+    }
+
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.peek(key).is_some()
+    }
+
+    // insert inserts value for key to expire after the ttl of the cache, getting the value it
+    // replaced.
+    pub fn insert(&mut self, key: Key, value: Value) -> Option<Value> {
+        let ttl = self.ttl;
+        self.insert_with_ttl(key, value, ttl)
+    }
+
+    // insert_with_ttl inserts value for key to expire after ttl, unless it is zero, as the
+    // most recently used entry, evicting the least recently used one if the cache is full. It
+    // gets the value replaced, unless that expired.
+    pub fn insert_with_ttl(&mut self, key: Key, value: Value, ttl: Duration) -> Option<Value> {
+        let expires = (!ttl.is_zero()).then(|| Instant::now() + ttl);
+        if let Some(slot) = self.live(&key) {
+            let node = self.node_mut(slot);
+            node.expires = expires;
+            let replaced = std::mem::replace(&mut node.value, value);
+            if slot != self.head {
+                self.unlink(slot);
+                self.push_front(slot);
+            }
+            return Some(replaced);
+        }
+        if self.capacity == 0 {
+            return None;
+        }
+        while self.slots.len() >= self.capacity {
+            self.evict_oldest();
+        }
+        let node = Node {
+            key: key.clone(),
+            value,
+            expires,
+            prev: NIL,
+            next: NIL,
+        };
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                self.nodes[slot] = Some(node);
+                slot
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        self.slots.insert(key, slot);
+        self.push_front(slot);
+        None
+    }
+
+    fn evict_oldest(&mut self) {
+        let slot = self.tail;
+        let why = if self.node(slot).expired(Instant::now()) {
+            Evicted::Expired
+        } else {
+            Evicted::Capacity
+        };
+        self.evict(slot, why);
+    }
+
+    // remove removes the entry of key, getting its value unless it expired.
+    pub fn remove(&mut self, key: &Key) -> Option<Value> {
+        let slot = *self.slots.get(key)?;
+        let node = self.take(slot);
+        (!node.expired(Instant::now())).then_some(node.value)
+    }
+
+    // retain keeps only the entries f returns true for, removing the others.
+    pub fn retain<F: FnMut(&Key, &mut Value) -> bool>(&mut self, mut f: F) {
+        let mut slot = self.head;
+        while slot != NIL {
+            let node = self.node_mut(slot);
+            let next = node.next;
+            if !f(&node.key, &mut node.value) {
+                self.take(slot);
+            }
+            slot = next;
+        }
+    }
+
+    // purge_expired evicts every entry that expired, getting how many there were.
+    pub fn purge_expired(&mut self) -> usize {
+        let now = Instant::now();
+        let mut purged = 0;
+        let mut slot = self.head;
+        while slot != NIL {
+            let node = self.node(slot);
+            let next = node.next;
+            if node.expired(now) {
+                self.evict(slot, Evicted::Expired);
+                purged += 1;
+            }
+            slot = next;
+        }
+        purged
+    }
+
+    // len gets how many entries the cache holds, counting those expired but not yet evicted.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // set_capacity bounds the cache to capacity entries, evicting the least recently used
+    // ones past it.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.slots.len() > capacity {
+            self.evict_oldest();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.nodes.clear();
+        self.free.clear();
+        self.head = NIL;
+        self.tail = NIL;
+    }
+
+    // iter iterates over the entries not expired, the most recently used first.
+    pub fn iter(&self) -> Iter<'_, Key, Value> {
+        Iter {
+            nodes: &self.nodes,
+            slot: self.head,
+            now: Instant::now(),
+        }
+    }
+}
+
+pub struct Iter<'a, Key, Value> {
+    nodes: &'a [Option<Node<Key, Value>>],
+    slot: usize,
+    now: Instant,
+}
+
+impl<'a, Key, Value> Iterator for Iter<'a, Key, Value> {
+    type Item = (&'a Key, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.slot != NIL {
+            let node = self.nodes[self.slot].as_ref()?;
+            self.slot = node.next;
+            if !node.expired(self.now) {
+                return Some((&node.key, &node.value));
+            }
+        }
+        None
+    }
+}
+
+// An LruCache can stand in for the cache of a CachedEnforcer, through set_cache.
+impl<Key, Value> Cache<Key, Value> for LruCache<Key, Value>
+where
+    Key: Eq + Hash + Clone + Send + Sync,
+    Value: Clone + Send + Sync,
+{
+    fn set_capacity(&mut self, c: usize) {
+        LruCache::set_capacity(self, c);
+    }
+
+    fn get(&mut self, k: &Key) -> Option<Cow<'_, Value>> {
+        LruCache::get(self, k).map(Cow::Borrowed)
+    }
+
+    fn has(&mut self, k: &Key) -> bool {
+        self.contains_key(k)
+    }
+
+    fn set(&mut self, k: Key, v: Value) {
+        self.insert(k, v);
+    }
+
+    fn clear(&mut self) {
+        LruCache::clear(self);
+    }
+}
+
+// A cache is serialized as its capacity, its ttl and its entries not expired, the most
+// recently used first, each with the time it expires at. It is deserialized with the entries
+// that did not expire meanwhile, in the same order, and no on_evict callback.
+#[cfg(feature = "serde")]
+mod serialize {
+    use super::{LruCache, NIL};
+    use crate::datastructure::hashtable::Hashable;
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::{Serialize, SerializeStruct, Serializer};
+    use std::hash::BuildHasher;
+    use std::time::{Duration, Instant, SystemTime};
+
+    struct Entries<'a, Key, Value, S>(&'a LruCache<Key, Value, S>);
+
+    impl<Key, Value, S> Serialize for Entries<'_, Key, Value, S>
+    where
+        Key: Serialize,
+        Value: Serialize,
+    {
+        fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+            let (now, system_now) = (Instant::now(), SystemTime::now());
+            let nodes = &self.0.nodes;
+            let mut slot = self.0.head;
+            serializer.collect_seq(std::iter::from_fn(|| {
+                while slot != NIL {
+                    let node = nodes[slot].as_ref()?;
+                    slot = node.next;
+                    if !node.expired(now) {
+                        let expires = node.expires.map(|at| system_now + (at - now));
+                        return Some((&node.key, &node.value, expires));
+                    }
+                }
+                None
+            }))
+        }
+    }
+
+    impl<Key, Value, S> Serialize for LruCache<Key, Value, S>
+    where
+        Key: Serialize,
+        Value: Serialize,
+    {
+        fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+            let mut cache = serializer.serialize_struct("LruCache", 3)?;
+            cache.serialize_field("capacity", &self.capacity)?;
+            cache.serialize_field("ttl", &self.ttl)?;
+            cache.serialize_field("entries", &Entries(self))?;
+            cache.end()
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename = "LruCache")]
+    struct Stored<Key, Value> {
+        capacity: usize,
+        ttl: Duration,
+        entries: Vec<(Key, Value, Option<SystemTime>)>,
+    }
+
+    impl<'de, Key, Value, S> Deserialize<'de> for LruCache<Key, Value, S>
+    where
+        Key: Deserialize<'de> + PartialEq + Hashable + Clone,
+        Value: Deserialize<'de>,
+        S: BuildHasher + Default,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let stored = Stored::<Key, Value>::deserialize(deserializer)?;
+            let mut cache =
+                LruCache::with_hasher(stored.capacity, S::default()).with_ttl(stored.ttl);
+            let system_now = SystemTime::now();
+            // The least recently used entries are inserted first, to be the least recent again.
+            for (key, value, expires) in stored.entries.into_iter().rev() {
+                let ttl = match expires.map(|at| at.duration_since(system_now)) {
+                    None => Duration::ZERO,
+                    Some(Ok(ttl)) if !ttl.is_zero() => ttl,
+                    Some(_) => continue,
+                };
+                cache.insert_with_ttl(key, value, ttl);
+            }
+            Ok(cache)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Evicted, LruCache};
+    use casbin::Cache;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    type Log = Arc<Mutex<Vec<(String, usize, Evicted)>>>;
+
+    fn logged(capacity: usize) -> (LruCache<String, usize>, Log) {
+        let log: Log = Default::default();
+        let evicted = log.clone();
+        let cache = LruCache::new(capacity).on_evict(move |key, value, why| {
+            evicted.lock().unwrap().push((key, value, why));
+        });
+        (cache, log)
+    }
+
+    fn keys(cache: &LruCache<String, usize>) -> Vec<&str> {
+        cache.iter().map(|(key, _)| key.as_str()).collect()
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let (mut cache, log) = logged(3);
+        for (i, key) in ["a", "b", "c"].iter().enumerate() {
+            cache.insert(key.to_string(), i);
+        }
+        assert_eq!(keys(&cache), vec!["c", "b", "a"]);
+
+        // Getting a makes it the most recent, peeking at b leaves it the least.
+        assert_eq!(cache.get(&"a".to_string()), Some(&0));
+        assert_eq!(cache.peek(&"b".to_string()), Some(&1));
+        cache.insert("d".to_string(), 3);
+        assert_eq!(keys(&cache), vec!["d", "a", "c"]);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![("b".to_string(), 1, Evicted::Capacity)]
+        );
+
+        // Replacing a value makes its entry the most recent, evicting nothing.
+        assert_eq!(cache.insert("c".to_string(), 4), Some(2));
+        assert_eq!(keys(&cache), vec!["c", "d", "a"]);
+        assert_eq!(log.lock().unwrap().len(), 1);
+
+        cache.set_capacity(1);
+        assert_eq!(keys(&cache), vec!["c"]);
+        assert_eq!(log.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_capacity_zero_holds_nothing() {
+        let (mut cache, log) = logged(0);
+        assert_eq!(cache.insert("a".to_string(), 0), None);
+        assert!(cache.is_empty());
+        assert!(log.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_expires() {
+        let (cache, log) = logged(10);
+        let mut cache = cache.with_ttl(Duration::from_millis(20));
+        cache.insert("a".to_string(), 0);
+        cache.insert_with_ttl("b".to_string(), 1, Duration::ZERO);
+        cache.insert_with_ttl("c".to_string(), 2, Duration::from_secs(60));
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert!(!cache.contains_key(&"a".to_string()));
+        assert_eq!(keys(&cache), vec!["c", "b"]);
+        // Expired entries are counted until they are found, and evicted then.
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.get(&"a".to_string()), None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![("a".to_string(), 0, Evicted::Expired)]
+        );
+
+        cache.insert_with_ttl("d".to_string(), 3, Duration::from_millis(1));
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.purge_expired(), 1);
+        assert_eq!(
+            log.lock().unwrap()[1],
+            ("d".to_string(), 3, Evicted::Expired)
+        );
+    }
+
+    #[test]
+    fn test_full_cache_evicts_expired_first_as_expired() {
+        let (mut cache, log) = logged(2);
+        cache.insert_with_ttl("a".to_string(), 0, Duration::from_millis(1));
+        cache.insert("b".to_string(), 1);
+        std::thread::sleep(Duration::from_millis(5));
+        cache.insert("c".to_string(), 2);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![("a".to_string(), 0, Evicted::Expired)]
+        );
+        assert_eq!(keys(&cache), vec!["c", "b"]);
+    }
+
+    #[test]
+    fn test_removals_are_not_evictions() {
+        let (mut cache, log) = logged(10);
+        for (i, key) in ["a", "b", "c", "d"].iter().enumerate() {
+            cache.insert(key.to_string(), i);
+        }
+        assert_eq!(cache.remove(&"a".to_string()), Some(0));
+        cache.retain(|_, value| *value % 2 == 1);
+        assert_eq!(keys(&cache), vec!["d", "b"]);
+        cache.clear();
+        assert!(cache.is_empty());
+        assert!(log.lock().unwrap().is_empty());
+
+        // Slots freed are reused.
+        cache.insert("e".to_string(), 4);
+        assert_eq!(keys(&cache), vec!["e"]);
+    }
+
+    #[test]
+    fn test_casbin_cache() {
+        let mut cache: LruCache<u64, bool> = LruCache::new(2);
+        let c: &mut dyn Cache<u64, bool> = &mut cache;
+        c.set(1, true);
+        c.set(2, false);
+        assert!(c.has(&1));
+        assert_eq!(c.get(&1).map(|v| v.into_owned()), Some(true));
+        c.set(3, true);
+        // 2 was the least recently used once 1 was got.
+        assert!(!c.has(&2));
+        c.set_capacity(1);
+        assert!(c.has(&3));
+        assert!(!c.has(&1));
+        c.clear();
+        assert_eq!(c.get(&3), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let mut cache = LruCache::new(3).with_ttl(Duration::from_secs(60));
+        cache.insert("a".to_string(), 0);
+        cache.insert_with_ttl("b".to_string(), 1, Duration::from_millis(1));
+        cache.insert("c".to_string(), 2);
+        cache.get(&"a".to_string());
+        std::thread::sleep(Duration::from_millis(5));
+
+        let json = serde_json::to_value(&cache).unwrap();
+        assert_eq!(json["capacity"], 3);
+        assert_eq!(json["entries"].as_array().unwrap().len(), 2);
+
+        // The entry that expired is left out, the others keep their order and expiry.
+        let mut loaded: LruCache<String, usize> = serde_json::from_value(json).unwrap();
+        assert_eq!(keys(&loaded), vec!["a", "c"]);
+        assert_eq!(loaded.len(), 2);
+        loaded.insert("d".to_string(), 3);
+        loaded.insert("e".to_string(), 4);
+        assert_eq!(keys(&loaded), vec!["e", "d", "a"]);
+
+        // Entries stored to expire by now are not loaded.
+        let stored = r#"{"capacity":2,"ttl":{"secs":0,"nanos":0},"entries":[["a",0,{"secs_since_epoch":1,"nanos_since_epoch":0}],["b",1,null]]}"#;
+        let loaded: LruCache<String, usize> = serde_json::from_str(stored).unwrap();
+        assert_eq!(keys(&loaded), vec!["b"]);
+    }
+}
@@ -1 +1,3 @@
 pub mod hashtable;
+pub mod lru;
+pub mod sharded;
@@ -0,0 +1,191 @@
+use crate::datastructure::hashtable::{HashTable, Hashable};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::RwLock;
+
+// DEFAULT_SHARDS is how many shards a table is split into unless told otherwise.
+static DEFAULT_SHARDS: usize = 16;
+
+// ShardedHashTable splits its entries among tables locked apart, the shard of a key chosen by
+// its hash, so that threads reading and writing keys of different shards do not wait for each
+// other. The shards hash their keys with the hasher of the table too.
+pub struct ShardedHashTable<Key, Value, S = RandomState> {
+    shards: Vec<RwLock<HashTable<Key, Value, S>>>,
+    hasher: S,
+}
+
+impl<Key, Value> ShardedHashTable<Key, Value, RandomState>
+where
+    Key: PartialEq + Hashable,
+{
+    pub fn new(shards: usize) -> Self {
+        Self::with_hasher(shards, RandomState::new())
+    }
+}
+
+impl<Key, Value> Default for ShardedHashTable<Key, Value, RandomState>
+where
+    Key: PartialEq + Hashable,
+{
+    fn default() -> Self {
+        Self::new(DEFAULT_SHARDS)
+    }
+}
+
+impl<Key, Value, S> ShardedHashTable<Key, Value, S>
+where
+    Key: PartialEq + Hashable,
+    S: BuildHasher + Clone,
+{
+    pub fn with_hasher(shards: usize, hasher: S) -> Self {
+        Self {
+            shards: (0..shards.max(1))
+                .map(|_| RwLock::new(HashTable::with_hasher(hasher.clone())))
+                .collect(),
+            hasher,
+        }
+    }
+
+    // shard gets the shard of key. It is picked by the high bits of the hash, as the low ones
+    // pick the cell of the key within the shard.
+    fn shard(&self, key: &Key) -> &RwLock<HashTable<Key, Value, S>> {
+        let hash = key.hash_with(&self.hasher).rotate_left(usize::BITS / 2);
+        &self.shards[hash % self.shards.len()]
+    }
+
+    // get_with gets what f makes of the value of key, holding its shard for reading meanwhile.
+    pub fn get_with<R, F: FnOnce(&Value) -> R>(&self, key: &Key, f: F) -> Option<R> {
+        self.shard(key).read().unwrap().get(key).map(f)
+    }
+
+    pub fn get(&self, key: &Key) -> Option<Value>
+    where
+        Value: Clone,
+    {
+        self.get_with(key, Value::clone)
+    }
+
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.get_with(key, |_| ()).is_some()
+    }
+
+    pub fn insert(&self, key: Key, value: Value) {
+        self.shard(&key).write().unwrap().insert(key, value);
+    }
+
+    // get_or_insert_with gets the value of key, inserting the one default makes if there is
+    // none, with a single probe of its shard.
+    pub fn get_or_insert_with<F: FnOnce() -> Value>(&self, key: Key, default: F) -> Value
+    where
+        Value: Clone,
+    {
+        let mut shard = self.shard(&key).write().unwrap();
+        shard.entry(key).or_insert_with(default).clone()
+    }
+
+    pub fn remove(&self, key: &Key) -> Option<Value> {
+        self.shard(key).write().unwrap().remove(key)
+    }
+
+    // retain removes the entries f does not keep, a shard at a time.
+    pub fn retain<F: FnMut(&Key, &mut Value) -> bool>(&self, mut f: F) {
+        for shard in self.shards.iter() {
+            shard.write().unwrap().retain(&mut f);
+        }
+    }
+
+    // for_each goes through the entries a shard at a time, holding it for reading meanwhile.
+    pub fn for_each<F: FnMut(&Key, &Value)>(&self, mut f: F) {
+        for shard in self.shards.iter() {
+            for (key, value) in shard.read().unwrap().iter() {
+                f(key, value);
+            }
+        }
+    }
+
+    // len counts the entries of every shard, which may change while they are counted.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard.write().unwrap().clear();
+        }
+    }
+}
+
+// A sharded table is serialized as a map of the entries of all its shards, taken a shard at a
+// time, and deserialized into DEFAULT_SHARDS shards.
+#[cfg(feature = "serde")]
+mod serialize {
+    use super::{ShardedHashTable, DEFAULT_SHARDS};
+    use crate::datastructure::hashtable::{HashTable, Hashable};
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::{Serialize, SerializeMap, Serializer};
+    use std::hash::BuildHasher;
+
+    impl<Key, Value, S> Serialize for ShardedHashTable<Key, Value, S>
+    where
+        Key: Serialize + PartialEq + Hashable,
+        Value: Serialize,
+        S: BuildHasher,
+    {
+        fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+            let shards: Vec<_> = self.shards.iter().map(|s| s.read().unwrap()).collect();
+            let len = shards.iter().map(|shard| shard.len()).sum();
+            let mut map = serializer.serialize_map(Some(len))?;
+            for shard in shards.iter() {
+                for (key, value) in shard.iter() {
+                    map.serialize_entry(key, value)?;
+                }
+            }
+            map.end()
+        }
+    }
+
+    impl<'de, Key, Value, S> Deserialize<'de> for ShardedHashTable<Key, Value, S>
+    where
+        Key: Deserialize<'de> + PartialEq + Hashable,
+        Value: Deserialize<'de>,
+        S: BuildHasher + Clone + Default,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let entries: HashTable<Key, Value, S> = HashTable::deserialize(deserializer)?;
+            let table = ShardedHashTable::with_hasher(DEFAULT_SHARDS, S::default());
+            for (key, value) in entries {
+                table.insert(key, value);
+            }
+            Ok(table)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::{ShardedHashTable, DEFAULT_SHARDS};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_serde() {
+        let table = ShardedHashTable::new(4);
+        for i in 0..100u64 {
+            table.insert(i.to_string(), i);
+        }
+        let json = serde_json::to_string(&table).unwrap();
+        let entries: BTreeMap<String, u64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(entries.len(), 100);
+
+        let loaded: ShardedHashTable<String, u64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.shards.len(), DEFAULT_SHARDS);
+        assert_eq!(loaded.len(), 100);
+        assert!((0..100).all(|i| loaded.get(&i.to_string()) == Some(i)));
+    }
+}
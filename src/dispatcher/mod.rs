@@ -0,0 +1,106 @@
+use crate::adapter::adapter_error;
+use crate::casbin_proto::casbin_server::CasbinServer;
+use crate::server::registry::EnforcerEntry;
+use crate::CasbinGRPC;
+use openraft::error::{InitializeError, RaftError};
+use openraft::storage::Adaptor;
+use openraft::{BasicNode, Config, Raft, SnapshotPolicy};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Cursor;
+use std::sync::Arc;
+
+pub mod network;
+pub mod service;
+pub mod store;
+
+// TypeConfig ties the replicated writes and the nodes of a dispatcher cluster to openraft.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd)]
+pub struct TypeConfig;
+
+impl openraft::RaftTypeConfig for TypeConfig {
+    type D = Write;
+    type R = WriteReply;
+    type NodeId = u64;
+    type Node = BasicNode;
+    type Entry = openraft::Entry<TypeConfig>;
+    type SnapshotData = Cursor<Vec<u8>>;
+}
+
+pub type DispatcherRaft = Raft<
+    TypeConfig,
+    network::Network,
+    Adaptor<TypeConfig, store::Store>,
+    Adaptor<TypeConfig, store::Store>,
+>;
+
+// Write is a write RPC to the enforcer of the local config, as received: every node of the
+// cluster replays it on its own enforcer in log order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Write {
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+// WriteReply is the reply of the node replaying a write, relayed to the client by the node it
+// sent the write to.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WriteReply {
+    pub code: i32,
+    pub message: String,
+    pub body: Vec<u8>,
+}
+
+// start joins this node to the dispatcher cluster of nodes, keyed by id with the address of
+// their Casbin service, and makes service replay the writes of the cluster on entry. The
+// node with the lowest id forms the cluster the first time it starts.
+pub async fn start(
+    id: u64,
+    nodes: &HashMap<u64, String>,
+    dir: &str,
+    service: CasbinServer<CasbinGRPC>,
+    entry: Arc<EnforcerEntry>,
+) -> casbin::Result<DispatcherRaft> {
+    if !nodes.contains_key(&id) {
+        return Err(adapter_error(format!(
+            "raft_nodes does not list this node, {}",
+            id
+        )));
+    }
+    let config = Config {
+        cluster_name: String::from("casbin"),
+        heartbeat_interval: 250,
+        election_timeout_min: 1000,
+        election_timeout_max: 2000,
+        snapshot_policy: SnapshotPolicy::LogsSinceLast(1000),
+        max_in_snapshot_log_to_keep: 100,
+        ..Default::default()
+    }
+    .validate()
+    .map_err(adapter_error)?;
+
+    let store = store::Store::open(dir, service, entry).await?;
+    let (log_store, state_machine) = Adaptor::new(store);
+    let raft = Raft::new(
+        id,
+        Arc::new(config),
+        network::Network,
+        log_store,
+        state_machine,
+    )
+    .await
+    .map_err(adapter_error)?;
+
+    if nodes.keys().min() == Some(&id) {
+        let members: BTreeMap<u64, BasicNode> = nodes
+            .iter()
+            .map(|(id, addr)| (*id, BasicNode { addr: addr.clone() }))
+            .collect();
+        match raft.initialize(members).await {
+            // The cluster was formed before.
+            Ok(()) | Err(RaftError::APIError(InitializeError::NotAllowed(_))) => {}
+            Err(err) => return Err(adapter_error(err)),
+        }
+    }
+    Ok(raft)
+}
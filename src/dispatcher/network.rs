@@ -0,0 +1,181 @@
+use crate::dispatcher::{DispatcherRaft, TypeConfig};
+use crate::raft_proto::raft_client::RaftClient;
+use crate::raft_proto::raft_server;
+use crate::raft_proto::{RaftReply, RaftRequest};
+use openraft::error::{
+    InstallSnapshotError, NetworkError, RPCError, RaftError, RemoteError, Unreachable,
+};
+use openraft::network::RPCOption;
+use openraft::raft::{
+    AppendEntriesRequest, AppendEntriesResponse, InstallSnapshotRequest, InstallSnapshotResponse,
+    VoteRequest, VoteResponse,
+};
+use openraft::{BasicNode, RaftNetwork, RaftNetworkFactory};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Request, Response, Status};
+
+// Network connects a node to the others of its dispatcher cluster through their Raft service,
+// served alongside their Casbin service.
+pub struct Network;
+
+#[tonic::async_trait]
+impl RaftNetworkFactory<TypeConfig> for Network {
+    type Network = NetworkClient;
+
+    async fn new_client(&mut self, target: u64, node: &BasicNode) -> Self::Network {
+        NetworkClient {
+            target,
+            client: connect(&node.addr).map(RaftClient::new),
+        }
+    }
+}
+
+// connect makes a channel to the address of a node, connecting on its first call.
+pub fn connect(addr: &str) -> Result<Channel, tonic::transport::Error> {
+    let uri = if addr.contains("://") {
+        addr.to_owned()
+    } else {
+        format!("http://{}", addr)
+    };
+    Ok(Endpoint::from_shared(uri)?.connect_lazy())
+}
+
+pub struct NetworkClient {
+    target: u64,
+    client: Result<RaftClient<Channel>, tonic::transport::Error>,
+}
+
+type RPCResult<T, E> = Result<T, RPCError<u64, BasicNode, RaftError<u64, E>>>;
+
+impl NetworkClient {
+    // send makes a call of the Raft service of the target, timing out after the time openraft
+    // allows it.
+    async fn send<Req, Resp, E>(
+        &mut self,
+        method: Method,
+        rpc: Req,
+        option: RPCOption,
+    ) -> RPCResult<Resp, E>
+    where
+        Req: Serialize,
+        Resp: DeserializeOwned,
+        E: Error + Serialize + DeserializeOwned,
+    {
+        let client = match self.client.as_mut() {
+            Ok(client) => client,
+            Err(err) => return Err(RPCError::Unreachable(Unreachable::new(err))),
+        };
+        let data =
+            serde_json::to_vec(&rpc).map_err(|err| RPCError::Network(NetworkError::new(&err)))?;
+        let mut request = Request::new(RaftRequest { data });
+        request.set_timeout(option.hard_ttl());
+        let reply = match method {
+            Method::AppendEntries => client.append_entries(request).await,
+            Method::Vote => client.vote(request).await,
+            Method::InstallSnapshot => client.install_snapshot(request).await,
+        };
+        let reply = match reply {
+            Ok(reply) => reply.into_inner(),
+            Err(status) if status.code() == Code::Unavailable => {
+                return Err(RPCError::Unreachable(Unreachable::new(&status)))
+            }
+            Err(status) => return Err(RPCError::Network(NetworkError::new(&status))),
+        };
+        if !reply.error.is_empty() {
+            let err: RaftError<u64, E> = serde_json::from_slice(&reply.error)
+                .map_err(|err| RPCError::Network(NetworkError::new(&err)))?;
+            return Err(RPCError::RemoteError(RemoteError::new(self.target, err)));
+        }
+        serde_json::from_slice(&reply.data)
+            .map_err(|err| RPCError::Network(NetworkError::new(&err)))
+    }
+}
+
+enum Method {
+    AppendEntries,
+    Vote,
+    InstallSnapshot,
+}
+
+#[tonic::async_trait]
+impl RaftNetwork<TypeConfig> for NetworkClient {
+    async fn append_entries(
+        &mut self,
+        rpc: AppendEntriesRequest<TypeConfig>,
+        option: RPCOption,
+    ) -> RPCResult<AppendEntriesResponse<u64>, openraft::error::Infallible> {
+        self.send(Method::AppendEntries, rpc, option).await
+    }
+
+    async fn install_snapshot(
+        &mut self,
+        rpc: InstallSnapshotRequest<TypeConfig>,
+        option: RPCOption,
+    ) -> RPCResult<InstallSnapshotResponse<u64>, InstallSnapshotError> {
+        self.send(Method::InstallSnapshot, rpc, option).await
+    }
+
+    async fn vote(
+        &mut self,
+        rpc: VoteRequest<u64>,
+        option: RPCOption,
+    ) -> RPCResult<VoteResponse<u64>, openraft::error::Infallible> {
+        self.send(Method::Vote, rpc, option).await
+    }
+}
+
+// RaftService hands the messages of the other nodes of the cluster to the raft of this node.
+pub struct RaftService {
+    raft: DispatcherRaft,
+}
+
+impl RaftService {
+    pub fn new(raft: DispatcherRaft) -> Self {
+        RaftService { raft }
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn parse<T: DeserializeOwned>(request: Request<RaftRequest>) -> Result<T, Status> {
+    serde_json::from_slice(&request.into_inner().data)
+        .map_err(|err| Status::invalid_argument(err.to_string()))
+}
+
+#[allow(clippy::result_large_err)]
+fn reply<T: Serialize, E: Serialize>(res: Result<T, E>) -> Result<Response<RaftReply>, Status> {
+    let reply = match res {
+        Ok(resp) => RaftReply {
+            data: serde_json::to_vec(&resp).map_err(|err| Status::internal(err.to_string()))?,
+            error: vec![],
+        },
+        Err(err) => RaftReply {
+            data: vec![],
+            error: serde_json::to_vec(&err).map_err(|err| Status::internal(err.to_string()))?,
+        },
+    };
+    Ok(Response::new(reply))
+}
+
+#[tonic::async_trait]
+impl raft_server::Raft for RaftService {
+    async fn append_entries(
+        &self,
+        request: Request<RaftRequest>,
+    ) -> Result<Response<RaftReply>, Status> {
+        reply(self.raft.append_entries(parse(request)?).await)
+    }
+
+    async fn vote(&self, request: Request<RaftRequest>) -> Result<Response<RaftReply>, Status> {
+        reply(self.raft.vote(parse(request)?).await)
+    }
+
+    async fn install_snapshot(
+        &self,
+        request: Request<RaftRequest>,
+    ) -> Result<Response<RaftReply>, Status> {
+        reply(self.raft.install_snapshot(parse(request)?).await)
+    }
+}
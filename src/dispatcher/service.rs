@@ -0,0 +1,541 @@
+use crate::casbin_proto::casbin_server::CasbinServer;
+use crate::dispatcher::network::connect;
+use crate::dispatcher::{DispatcherRaft, Write, WriteReply};
+use crate::CasbinGRPC;
+use bytes::{Buf, Bytes};
+use openraft::error::{ClientWriteError, ForwardToLeader, RaftError};
+use prost::Message;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tonic::body::BoxBody;
+use tonic::codegen::{http, Body, BoxFuture, Service};
+use tonic::transport::{Channel, NamedService};
+use tonic::{Code, Status};
+
+// WRITES lists the RPCs changing the policy, model or functions of an enforcer, which go
+// through the raft log when made on the enforcer of the local config.
+static WRITES: &[&str] = &[
+    "SetModel",
+    "AddCustomFunction",
+    "SavePolicy",
+    "ClearPolicy",
+    "EnableAutoSave",
+    "AddPolicy",
+    "AddNamedPolicy",
+    "AddPolicies",
+    "AddNamedPolicies",
+    "RemovePolicy",
+    "RemoveNamedPolicy",
+    "RemovePolicies",
+    "RemoveNamedPolicies",
+    "UpdatePolicy",
+    "UpdateNamedPolicy",
+    "UpdatePolicies",
+    "UpdateNamedPolicies",
+    "RemoveFilteredPolicy",
+    "RemoveFilteredNamedPolicy",
+    "AddGroupingPolicy",
+    "AddNamedGroupingPolicy",
+    "RemoveGroupingPolicy",
+    "RemoveNamedGroupingPolicy",
+    "AddGroupingPolicies",
+    "AddNamedGroupingPolicies",
+    "RemoveGroupingPolicies",
+    "RemoveNamedGroupingPolicies",
+    "RemoveFilteredGroupingPolicy",
+    "RemoveFilteredNamedGroupingPolicy",
+    "AddRoleForUser",
+    "DeleteRoleForUser",
+    "DeleteRolesForUser",
+    "DeleteUser",
+    "DeleteRole",
+    "DeletePermission",
+    "AddPermissionForUser",
+    "DeletePermissionForUser",
+    "DeletePermissionsForUser",
+    "AddRoleForUserInDomain",
+    "DeleteRoleForUserInDomain",
+    "DeleteDomains",
+];
+
+// LOADS lists the RPCs reading the policy of a node's own adapter, which would make the nodes
+// diverge.
+static LOADS: &[&str] = &["LoadPolicy", "LoadFilteredPolicy"];
+
+// LOG_INDEX_HEADER carries the index of the raft log entry of a write in its reply, so the node
+// forwarding it to the leader can wait until it applied the entry itself.
+static LOG_INDEX_HEADER: &str = "x-raft-log-index";
+
+// FORWARD_TIMEOUT bounds how long a node waits to apply a write it forwarded to the leader.
+static FORWARD_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Handle decodes the enforcer handle of a write request, field 1 of every one of them.
+#[derive(Clone, PartialEq, Message)]
+struct Handle {
+    #[prost(int32, tag = "1")]
+    handle: i32,
+}
+
+// DispatchService serves the Casbin service of a node in a dispatcher cluster: writes to the
+// enforcer of the local config are appended to the raft log, through the leader, and every
+// node replays them on its own enforcer once committed. Everything else, including writes to
+// the enforcers created through NewEnforcer, is served by the node alone.
+#[derive(Clone)]
+pub struct DispatchService {
+    service: CasbinServer<CasbinGRPC>,
+    raft: DispatcherRaft,
+    leaders: Arc<Mutex<HashMap<String, Channel>>>,
+}
+
+impl DispatchService {
+    pub fn new(service: CasbinServer<CasbinGRPC>, raft: DispatcherRaft) -> Self {
+        DispatchService {
+            service,
+            raft,
+            leaders: Default::default(),
+        }
+    }
+
+    // leader gets the channel to the leader at addr, reusing it across writes.
+    fn leader(&self, addr: &str) -> Result<Channel, tonic::transport::Error> {
+        let mut leaders = self.leaders.lock().unwrap();
+        if let Some(channel) = leaders.get(addr) {
+            return Ok(channel.clone());
+        }
+        let channel = connect(addr)?;
+        leaders.insert(addr.to_owned(), channel.clone());
+        Ok(channel)
+    }
+
+    async fn dispatch(self, parts: http::request::Parts, body: Bytes) -> http::Response<BoxBody> {
+        let path = parts.uri.path().to_owned();
+        let write = Write {
+            path: path.clone(),
+            body: body.to_vec(),
+        };
+        match self.raft.client_write(write).await {
+            Ok(resp) => {
+                let mut response = reply_response(resp.data);
+                response
+                    .headers_mut()
+                    .insert(LOG_INDEX_HEADER, resp.log_id.index.into());
+                response
+            }
+            Err(RaftError::APIError(ClientWriteError::ForwardToLeader(ForwardToLeader {
+                leader_node: Some(node),
+                ..
+            }))) => self.forward(&node.addr, parts, body).await,
+            Err(err) => {
+                Status::unavailable(format!("the write was not committed: {}", err)).to_http()
+            }
+        }
+    }
+
+    // forward makes a write through the leader at addr, with the headers it was made with,
+    // then waits until this node applied it too, so the client reads its own write from this
+    // node afterwards.
+    async fn forward(
+        &self,
+        addr: &str,
+        parts: http::request::Parts,
+        body: Bytes,
+    ) -> http::Response<BoxBody> {
+        let mut channel = match self.leader(addr) {
+            Ok(channel) => channel,
+            Err(err) => return Status::unavailable(err.to_string()).to_http(),
+        };
+        let mut request = http::Request::new(ReplyBody::new(body, None).boxed_unsync());
+        *request.method_mut() = http::Method::POST;
+        *request.headers_mut() = parts.headers;
+        match parts.uri.path().parse() {
+            Ok(uri) => *request.uri_mut() = uri,
+            Err(err) => return Status::internal(format!("{}", err)).to_http(),
+        }
+        if let Err(err) = futures::future::poll_fn(|cx| channel.poll_ready(cx)).await {
+            return Status::unavailable(err.to_string()).to_http();
+        }
+        let response = match channel.call(request).await {
+            Ok(response) => response,
+            Err(err) => return Status::unavailable(err.to_string()).to_http(),
+        };
+        let index = response
+            .headers()
+            .get(LOG_INDEX_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        if let Some(index) = index {
+            let applied = self
+                .raft
+                .wait(Some(FORWARD_TIMEOUT))
+                .log_at_least(Some(index), "applying a forwarded write")
+                .await;
+            if let Err(err) = applied {
+                return Status::unavailable(format!(
+                    "the leader committed the write, but this node did not apply it: {}",
+                    err
+                ))
+                .to_http();
+            }
+        }
+        response.map(|body| {
+            body.map_err(|err| Status::from_error(Box::new(err)))
+                .boxed_unsync()
+        })
+    }
+}
+
+// reply_response makes the response of a write from the reply of the node that replayed it.
+fn reply_response(reply: WriteReply) -> http::Response<BoxBody> {
+    if reply.code != Code::Ok as i32 {
+        return Status::new(Code::from(reply.code), reply.message).to_http();
+    }
+    let mut trailers = http::HeaderMap::new();
+    trailers.insert("grpc-status", http::HeaderValue::from_static("0"));
+    let mut response =
+        http::Response::new(ReplyBody::new(Bytes::from(reply.body), Some(trailers)).boxed_unsync());
+    response.headers_mut().insert(
+        "content-type",
+        http::HeaderValue::from_static("application/grpc"),
+    );
+    response
+}
+
+// ReplyBody is a body sent in one piece, followed by its trailers if any.
+struct ReplyBody {
+    data: Option<Bytes>,
+    trailers: Option<http::HeaderMap>,
+}
+
+impl ReplyBody {
+    fn new(data: Bytes, trailers: Option<http::HeaderMap>) -> Self {
+        ReplyBody {
+            data: Some(data).filter(|data| !data.is_empty()),
+            trailers,
+        }
+    }
+}
+
+impl Body for ReplyBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        Poll::Ready(self.data.take().map(Ok))
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        Poll::Ready(Ok(self.trailers.take()))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.data.is_none() && self.trailers.is_none()
+    }
+}
+
+// handle_of decodes the handle of a framed write request.
+fn handle_of(body: &[u8]) -> Option<i32> {
+    if body.len() < 5 || body[0] != 0 {
+        return None;
+    }
+    Handle::decode(&body[5..]).ok().map(|h| h.handle)
+}
+
+impl<B> Service<http::Request<B>> for DispatchService
+where
+    B: Body<Data = Bytes> + Unpin + Send + 'static,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Service::<http::Request<tonic::transport::Body>>::poll_ready(&mut self.service, cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let method = request
+            .uri()
+            .path()
+            .strip_prefix("/proto.Casbin/")
+            .unwrap_or_default()
+            .to_owned();
+        if !WRITES.contains(&method.as_str()) && !LOADS.contains(&method.as_str()) {
+            return Box::pin(self.service.call(request));
+        }
+        let mut this = self.clone();
+        Box::pin(async move {
+            let (parts, mut body) = request.into_parts();
+            let mut data = vec![];
+            while let Some(chunk) = body.data().await {
+                match chunk {
+                    Ok(chunk) => data.extend_from_slice(chunk.chunk()),
+                    Err(err) => return Ok(Status::from_error(err.into()).to_http()),
+                }
+            }
+            // Raft replicates writes as sent, and a compressed one would pass the log by, as
+            // its handle cannot be read.
+            if data.first() == Some(&1) {
+                return Ok(Status::unimplemented(
+                    "raft nodes only take uncompressed writes, send them without grpc-encoding",
+                )
+                .to_http());
+            }
+            if handle_of(&data) != Some(0) {
+                let request = http::Request::from_parts(parts, tonic::transport::Body::from(data));
+                return this.service.call(request).await;
+            }
+            if LOADS.contains(&method.as_str()) {
+                return Ok(Status::failed_precondition(
+                    "the enforcer of the local config is replicated through raft, so its policy is only changed through writes",
+                )
+                .to_http());
+            }
+            Ok(this.dispatch(parts, Bytes::from(data)).await)
+        })
+    }
+}
+
+impl NamedService for DispatchService {
+    const NAME: &'static str = "proto.Casbin";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DispatchService;
+    use crate::casbin_proto::casbin_server::CasbinServer;
+    use crate::casbin_proto::{BoolReply, PolicyRequest};
+    use crate::dispatcher::network::RaftService;
+    use crate::dispatcher::{self, DispatcherRaft};
+    use crate::raft_proto::raft_server::RaftServer;
+    use crate::server::enforcer;
+    use crate::server::registry::EnforcerEntry;
+    use crate::CasbinGRPC;
+    use casbin::{DefaultModel, MemoryAdapter, MgmtApi};
+    use prost::Message;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::codegen::{http, Body, Service};
+    use tonic::Code;
+
+    static TIMEOUT: Duration = Duration::from_secs(10);
+
+    // node starts raft node id of the cluster of nodes, on an empty enforcer of the local
+    // config.
+    async fn node(
+        id: u64,
+        nodes: &HashMap<u64, String>,
+        dir: &str,
+    ) -> (DispatchService, DispatcherRaft, Arc<EnforcerEntry>) {
+        let casbin = CasbinGRPC::new_server();
+        let model_text = std::fs::read_to_string("examples/rbac_model.conf").unwrap();
+        let m = DefaultModel::from_str(&model_text).await.unwrap();
+        let e = enforcer::build_enforcer(m, MemoryAdapter::default())
+            .await
+            .unwrap();
+        let handle = casbin.add_enforcer(e, model_text).await;
+        assert_eq!(handle, 0);
+        let entry = casbin.enforcers.get(handle).await.unwrap();
+        let service = CasbinServer::new(casbin);
+        let raft = dispatcher::start(id, nodes, dir, service.clone(), entry.clone())
+            .await
+            .unwrap();
+        (DispatchService::new(service, raft.clone()), raft, entry)
+    }
+
+    async fn leader(raft: &DispatcherRaft) -> u64 {
+        raft.wait(Some(TIMEOUT))
+            .metrics(|m| m.current_leader.is_some(), "electing a leader")
+            .await
+            .unwrap()
+            .current_leader
+            .unwrap()
+    }
+
+    // add_policy makes an AddPolicy request of the enforcer of the local config, framed with
+    // the compression flag given.
+    fn add_policy(params: &[&str], compressed: u8) -> http::Request<tonic::transport::Body> {
+        let message = PolicyRequest {
+            enforcer_handler: 0,
+            p_type: "p".to_owned(),
+            params: params.iter().map(|p| p.to_string()).collect(),
+        }
+        .encode_to_vec();
+        let mut body = vec![compressed];
+        body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        body.extend_from_slice(&message);
+        http::Request::builder()
+            .method(http::Method::POST)
+            .uri("/proto.Casbin/AddPolicy")
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(tonic::transport::Body::from(body))
+            .unwrap()
+    }
+
+    async fn call(
+        service: &mut DispatchService,
+        request: http::Request<tonic::transport::Body>,
+    ) -> Result<bool, Code> {
+        let response = match service.call(request).await {
+            Ok(response) => response,
+            Err(never) => match never {},
+        };
+        let (parts, mut body) = response.into_parts();
+        if let Some(status) = tonic::Status::from_header_map(&parts.headers) {
+            if status.code() != Code::Ok {
+                return Err(status.code());
+            }
+        }
+        let mut data = vec![];
+        while let Some(chunk) = body.data().await {
+            data.extend_from_slice(&chunk.unwrap());
+        }
+        let trailers = body.trailers().await.unwrap().unwrap_or_default();
+        if let Some(status) = tonic::Status::from_header_map(&trailers) {
+            if status.code() != Code::Ok {
+                return Err(status.code());
+            }
+        }
+        Ok(BoolReply::decode(&data[5..]).unwrap().res)
+    }
+
+    async fn policy(entry: &EnforcerEntry) -> Vec<Vec<String>> {
+        entry.enforcer.read().await.get_policy()
+    }
+
+    fn single(addr: &str) -> HashMap<u64, String> {
+        HashMap::from([(1, addr.to_owned())])
+    }
+
+    #[tokio::test]
+    async fn test_single_node_commits_writes() {
+        let (mut service, raft, entry) = node(1, &single("127.0.0.1:1"), "").await;
+        assert_eq!(leader(&raft).await, 1);
+
+        let added = call(&mut service, add_policy(&["alice", "data1", "read"], 0)).await;
+        assert_eq!(added, Ok(true));
+        assert_eq!(policy(&entry).await, vec![vec!["alice", "data1", "read"]]);
+        let again = call(&mut service, add_policy(&["alice", "data1", "read"], 0)).await;
+        assert_eq!(again, Ok(false));
+        // The writes went through the log, after the entries forming the cluster.
+        let applied = raft.metrics().borrow().last_applied.unwrap().index;
+        assert!(applied >= 2, "applied up to {}", applied);
+
+        raft.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_compressed_writes_are_rejected() {
+        let (mut service, raft, entry) = node(1, &single("127.0.0.1:1"), "").await;
+        leader(&raft).await;
+        let applied = raft.metrics().borrow().last_applied;
+
+        // The service alone rejects compressed requests too, as raft nodes do not accept them,
+        // so the write must be stopped before it passes the log by.
+        let response = match service
+            .call(add_policy(&["alice", "data1", "read"], 1))
+            .await
+        {
+            Ok(response) => response,
+            Err(never) => match never {},
+        };
+        let status = tonic::Status::from_header_map(response.headers()).unwrap();
+        assert_eq!(status.code(), Code::Unimplemented);
+        assert!(status.message().starts_with("raft nodes"), "{:?}", status);
+        assert!(policy(&entry).await.is_empty());
+        assert_eq!(raft.metrics().borrow().last_applied, applied);
+
+        raft.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_writes_are_forwarded_to_the_leader() {
+        let listeners = [
+            TcpListener::bind("127.0.0.1:0").await.unwrap(),
+            TcpListener::bind("127.0.0.1:0").await.unwrap(),
+        ];
+        let nodes: HashMap<u64, String> = listeners
+            .iter()
+            .enumerate()
+            .map(|(i, l)| (i as u64 + 1, l.local_addr().unwrap().to_string()))
+            .collect();
+        let mut started = vec![];
+        for (i, listener) in listeners.into_iter().enumerate() {
+            let (service, raft, entry) = node(i as u64 + 1, &nodes, "").await;
+            let server = tonic::transport::Server::builder()
+                .add_service(RaftServer::new(RaftService::new(raft.clone())))
+                .add_service(service.clone())
+                .serve_with_incoming(TcpListenerStream::new(listener));
+            tokio::spawn(server);
+            started.push((service, raft, entry));
+        }
+
+        let leader = leader(&started[0].1).await;
+        let (service, raft, entry) = &mut started[if leader == 1 { 1 } else { 0 }];
+        // Writes made before the follower hears of the leader are unavailable.
+        raft.wait(Some(TIMEOUT))
+            .current_leader(leader, "hearing of the leader")
+            .await
+            .unwrap();
+        let added = call(service, add_policy(&["alice", "data1", "read"], 0)).await;
+        assert_eq!(added, Ok(true));
+        // The follower applied the write before replying, and so did the leader.
+        assert_eq!(policy(entry).await, vec![vec!["alice", "data1", "read"]]);
+        let leader_entry = &started[leader as usize - 1].2;
+        assert_eq!(
+            policy(leader_entry).await,
+            vec![vec!["alice", "data1", "read"]]
+        );
+
+        for (_, raft, _) in started {
+            raft.shutdown().await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_log_is_replayed_after_restart() {
+        let dir = std::env::temp_dir().join(format!("raft-restart-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let dir_str = dir.to_string_lossy().into_owned();
+        let nodes = single("127.0.0.1:1");
+
+        let (mut service, raft, _) = node(1, &nodes, &dir_str).await;
+        leader(&raft).await;
+        let added = call(&mut service, add_policy(&["alice", "data1", "read"], 0)).await;
+        assert_eq!(added, Ok(true));
+        let index = raft.metrics().borrow().last_applied.unwrap().index;
+        raft.shutdown().await.unwrap();
+
+        // The node stopped before recording that it applied the write, so it replays the log
+        // on its empty enforcer when it starts again.
+        std::fs::remove_file(dir.join("applied.json")).unwrap();
+        let (mut service, raft, entry) = node(1, &nodes, &dir_str).await;
+        raft.wait(Some(TIMEOUT))
+            .log_at_least(Some(index), "replaying the log")
+            .await
+            .unwrap();
+        assert_eq!(policy(&entry).await, vec![vec!["alice", "data1", "read"]]);
+
+        // The log goes on from where it was.
+        let added = call(&mut service, add_policy(&["bob", "data2", "write"], 0)).await;
+        assert_eq!(added, Ok(true));
+        assert!(raft.metrics().borrow().last_applied.unwrap().index > index);
+
+        raft.shutdown().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
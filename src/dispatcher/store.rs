@@ -0,0 +1,467 @@
+use crate::adapter::adapter_error;
+use crate::casbin_proto::casbin_server::CasbinServer;
+use crate::dispatcher::{TypeConfig, Write, WriteReply};
+use crate::server::functions::CustomFunction;
+use crate::server::priority;
+use crate::server::registry::EnforcerEntry;
+use crate::CasbinGRPC;
+use casbin::{CachedApi, CoreApi, DefaultModel};
+use openraft::{
+    BasicNode, Entry, EntryPayload, LogId, LogState, RaftLogReader, RaftSnapshotBuilder,
+    RaftStorage, Snapshot, SnapshotMeta, StorageError, StorageIOError, StoredMembership, Vote,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::io::Cursor;
+use std::ops::RangeBounds;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tonic::codegen::{http, Body, Service};
+use tonic::{Code, Status};
+
+type StorageResult<T> = Result<T, StorageError<u64>>;
+
+// Log holds the raft log of a node along with its vote.
+#[derive(Default, Serialize, Deserialize)]
+struct Log {
+    vote: Option<Vote<u64>>,
+    last_purged: Option<LogId<u64>>,
+    entries: BTreeMap<u64, Entry<TypeConfig>>,
+}
+
+// Applied records how far the enforcer has replayed the log.
+#[derive(Default, Serialize, Deserialize)]
+struct Applied {
+    last_applied: Option<LogId<u64>>,
+    last_membership: StoredMembership<u64, BasicNode>,
+}
+
+// PolicySnapshot is the state of the enforcer at a point of the log: its model, its rules by
+// section and policy type, and the functions defined for its matchers.
+#[derive(Serialize, Deserialize)]
+struct PolicySnapshot {
+    model_text: String,
+    rules: Vec<(String, String, Vec<Vec<String>>)>,
+    #[serde(default)]
+    functions: Vec<CustomFunction>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredSnapshot {
+    meta: SnapshotMeta<u64, BasicNode>,
+    data: Vec<u8>,
+}
+
+impl StoredSnapshot {
+    fn to_snapshot(&self) -> Snapshot<TypeConfig> {
+        Snapshot {
+            meta: self.meta.clone(),
+            snapshot: Box::new(Cursor::new(self.data.clone())),
+        }
+    }
+}
+
+// Store keeps the raft log of a node and replays its writes on the enforcer of the local
+// config through the Casbin service of the node. The enforcer saves the rules through its
+// adapter, which every node has its own of. With a directory, the log, the vote and how far
+// the log was replayed are kept in files, so the node rejoins with them after a restart;
+// without one, a restarted node rejoins empty and catches up from the others.
+pub struct Store {
+    dir: Option<PathBuf>,
+    log: Arc<RwLock<Log>>,
+    applied: Applied,
+    snapshot: Arc<RwLock<Option<StoredSnapshot>>>,
+    service: CasbinServer<CasbinGRPC>,
+    entry: Arc<EnforcerEntry>,
+}
+
+impl Store {
+    pub async fn open(
+        dir: &str,
+        service: CasbinServer<CasbinGRPC>,
+        entry: Arc<EnforcerEntry>,
+    ) -> casbin::Result<Self> {
+        let dir = if dir.is_empty() {
+            None
+        } else {
+            tokio::fs::create_dir_all(dir).await?;
+            Some(PathBuf::from(dir))
+        };
+        let (log, applied, snapshot) = match &dir {
+            Some(dir) => (
+                read_json(dir, LOG_FILE).await?,
+                read_json(dir, APPLIED_FILE).await?,
+                read_json(dir, SNAPSHOT_FILE).await?,
+            ),
+            None => Default::default(),
+        };
+        Ok(Store {
+            dir,
+            log: Arc::new(RwLock::new(log.unwrap_or_default())),
+            applied: applied.unwrap_or_default(),
+            snapshot: Arc::new(RwLock::new(snapshot)),
+            service,
+            entry,
+        })
+    }
+
+    async fn save_log(&self, log: &Log) -> StorageResult<()> {
+        match &self.dir {
+            Some(dir) => write_json(dir, LOG_FILE, log)
+                .await
+                .map_err(|err| StorageIOError::write_logs(&err).into()),
+            None => Ok(()),
+        }
+    }
+
+    async fn save_applied(&self) -> StorageResult<()> {
+        match &self.dir {
+            Some(dir) => write_json(dir, APPLIED_FILE, &self.applied)
+                .await
+                .map_err(|err| StorageIOError::write_state_machine(&err).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+static LOG_FILE: &str = "log.json";
+static APPLIED_FILE: &str = "applied.json";
+static SNAPSHOT_FILE: &str = "snapshot.json";
+
+async fn read_json<T: DeserializeOwned>(dir: &Path, name: &str) -> casbin::Result<Option<T>> {
+    match tokio::fs::read(dir.join(name)).await {
+        Ok(data) => serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(adapter_error),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// write_json replaces a file through a temporary one, so a crash leaves either the old or the
+// new content.
+async fn write_json<T: Serialize>(dir: &Path, name: &str, value: &T) -> std::io::Result<()> {
+    let tmp = dir.join(format!("{}.tmp", name));
+    tokio::fs::write(&tmp, serde_json::to_vec(value)?).await?;
+    tokio::fs::rename(&tmp, dir.join(name)).await
+}
+
+// LogReader reads the raft log of a node to replicate it to the others.
+pub struct LogReader {
+    log: Arc<RwLock<Log>>,
+}
+
+async fn log_state(log: &RwLock<Log>) -> LogState<TypeConfig> {
+    let log = log.read().await;
+    let last_log_id = log
+        .entries
+        .values()
+        .next_back()
+        .map(|entry| entry.log_id)
+        .or(log.last_purged);
+    LogState {
+        last_purged_log_id: log.last_purged,
+        last_log_id,
+    }
+}
+
+async fn log_entries<RB: RangeBounds<u64>>(log: &RwLock<Log>, range: RB) -> Vec<Entry<TypeConfig>> {
+    log.read()
+        .await
+        .entries
+        .range(range)
+        .map(|(_, entry)| entry.clone())
+        .collect()
+}
+
+#[tonic::async_trait]
+impl RaftLogReader<TypeConfig> for LogReader {
+    async fn get_log_state(&mut self) -> StorageResult<LogState<TypeConfig>> {
+        Ok(log_state(&self.log).await)
+    }
+
+    async fn try_get_log_entries<RB: RangeBounds<u64> + Clone + Debug + Send + Sync>(
+        &mut self,
+        range: RB,
+    ) -> StorageResult<Vec<Entry<TypeConfig>>> {
+        Ok(log_entries(&self.log, range).await)
+    }
+}
+
+#[tonic::async_trait]
+impl RaftLogReader<TypeConfig> for Store {
+    async fn get_log_state(&mut self) -> StorageResult<LogState<TypeConfig>> {
+        Ok(log_state(&self.log).await)
+    }
+
+    async fn try_get_log_entries<RB: RangeBounds<u64> + Clone + Debug + Send + Sync>(
+        &mut self,
+        range: RB,
+    ) -> StorageResult<Vec<Entry<TypeConfig>>> {
+        Ok(log_entries(&self.log, range).await)
+    }
+}
+
+// SnapshotBuilder writes out the state of the enforcer captured when the snapshot was asked
+// for, so it matches the point of the log it was replayed to.
+pub struct SnapshotBuilder {
+    dir: Option<PathBuf>,
+    meta: SnapshotMeta<u64, BasicNode>,
+    policy: PolicySnapshot,
+    snapshot: Arc<RwLock<Option<StoredSnapshot>>>,
+}
+
+#[tonic::async_trait]
+impl RaftSnapshotBuilder<TypeConfig> for SnapshotBuilder {
+    async fn build_snapshot(&mut self) -> StorageResult<Snapshot<TypeConfig>> {
+        let data = serde_json::to_vec(&self.policy)
+            .map_err(|err| StorageIOError::write_snapshot(None, &err))?;
+        let stored = StoredSnapshot {
+            meta: self.meta.clone(),
+            data,
+        };
+        if let Some(dir) = &self.dir {
+            write_json(dir, SNAPSHOT_FILE, &stored)
+                .await
+                .map_err(|err| StorageIOError::write_snapshot(None, &err))?;
+        }
+        *self.snapshot.write().await = Some(stored.clone());
+        Ok(stored.to_snapshot())
+    }
+}
+
+#[tonic::async_trait]
+impl RaftStorage<TypeConfig> for Store {
+    type LogReader = LogReader;
+    type SnapshotBuilder = SnapshotBuilder;
+
+    async fn save_vote(&mut self, vote: &Vote<u64>) -> StorageResult<()> {
+        let mut log = self.log.write().await;
+        log.vote = Some(*vote);
+        self.save_log(&log).await
+    }
+
+    async fn read_vote(&mut self) -> StorageResult<Option<Vote<u64>>> {
+        Ok(self.log.read().await.vote)
+    }
+
+    async fn get_log_reader(&mut self) -> Self::LogReader {
+        LogReader {
+            log: self.log.clone(),
+        }
+    }
+
+    async fn append_to_log<I>(&mut self, entries: I) -> StorageResult<()>
+    where
+        I: IntoIterator<Item = Entry<TypeConfig>> + Send,
+    {
+        let mut log = self.log.write().await;
+        for entry in entries {
+            log.entries.insert(entry.log_id.index, entry);
+        }
+        self.save_log(&log).await
+    }
+
+    async fn delete_conflict_logs_since(&mut self, log_id: LogId<u64>) -> StorageResult<()> {
+        let mut log = self.log.write().await;
+        log.entries.split_off(&log_id.index);
+        self.save_log(&log).await
+    }
+
+    async fn purge_logs_upto(&mut self, log_id: LogId<u64>) -> StorageResult<()> {
+        let mut log = self.log.write().await;
+        log.entries = log.entries.split_off(&(log_id.index + 1));
+        log.last_purged = Some(log_id);
+        self.save_log(&log).await
+    }
+
+    async fn last_applied_state(
+        &mut self,
+    ) -> StorageResult<(Option<LogId<u64>>, StoredMembership<u64, BasicNode>)> {
+        Ok((
+            self.applied.last_applied,
+            self.applied.last_membership.clone(),
+        ))
+    }
+
+    async fn apply_to_state_machine(
+        &mut self,
+        entries: &[Entry<TypeConfig>],
+    ) -> StorageResult<Vec<WriteReply>> {
+        let mut replies = Vec::with_capacity(entries.len());
+        for entry in entries {
+            self.applied.last_applied = Some(entry.log_id);
+            replies.push(match &entry.payload {
+                EntryPayload::Normal(write) => replay(&mut self.service, write).await,
+                EntryPayload::Membership(membership) => {
+                    self.applied.last_membership =
+                        StoredMembership::new(Some(entry.log_id), membership.clone());
+                    WriteReply::default()
+                }
+                EntryPayload::Blank => WriteReply::default(),
+            });
+        }
+        self.save_applied().await?;
+        Ok(replies)
+    }
+
+    async fn get_snapshot_builder(&mut self) -> Self::SnapshotBuilder {
+        let last_applied = self.applied.last_applied;
+        let policy = capture(&self.entry).await;
+        SnapshotBuilder {
+            dir: self.dir.clone(),
+            meta: SnapshotMeta {
+                last_log_id: last_applied,
+                last_membership: self.applied.last_membership.clone(),
+                snapshot_id: format!(
+                    "{}-{}",
+                    last_applied.map_or(0, |log_id| log_id.index),
+                    crate::server::registry::now_millis()
+                ),
+            },
+            policy,
+            snapshot: self.snapshot.clone(),
+        }
+    }
+
+    async fn begin_receiving_snapshot(&mut self) -> StorageResult<Box<Cursor<Vec<u8>>>> {
+        Ok(Box::new(Cursor::new(Vec::new())))
+    }
+
+    async fn install_snapshot(
+        &mut self,
+        meta: &SnapshotMeta<u64, BasicNode>,
+        snapshot: Box<Cursor<Vec<u8>>>,
+    ) -> StorageResult<()> {
+        let stored = StoredSnapshot {
+            meta: meta.clone(),
+            data: snapshot.into_inner(),
+        };
+        let policy: PolicySnapshot = serde_json::from_slice(&stored.data)
+            .map_err(|err| StorageIOError::read_snapshot(None, &err))?;
+        restore(&self.entry, policy)
+            .await
+            .map_err(|err| StorageIOError::write_state_machine(&err))?;
+        self.applied = Applied {
+            last_applied: meta.last_log_id,
+            last_membership: meta.last_membership.clone(),
+        };
+        self.save_applied().await?;
+        if let Some(dir) = &self.dir {
+            write_json(dir, SNAPSHOT_FILE, &stored)
+                .await
+                .map_err(|err| StorageIOError::write_snapshot(None, &err))?;
+        }
+        *self.snapshot.write().await = Some(stored);
+        Ok(())
+    }
+
+    async fn get_current_snapshot(&mut self) -> StorageResult<Option<Snapshot<TypeConfig>>> {
+        Ok(self
+            .snapshot
+            .read()
+            .await
+            .as_ref()
+            .map(StoredSnapshot::to_snapshot))
+    }
+}
+
+// replay runs a write through the Casbin service of this node and collects its reply.
+async fn replay(service: &mut CasbinServer<CasbinGRPC>, write: &Write) -> WriteReply {
+    let request = http::Request::builder()
+        .method(http::Method::POST)
+        .uri(write.path.as_str())
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        .body(tonic::transport::Body::from(write.body.clone()));
+    let request = match request {
+        Ok(request) => request,
+        Err(err) => return status_reply(Status::internal(err.to_string())),
+    };
+    let response = match service.call(request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    };
+    let (parts, mut body) = response.into_parts();
+    // Errors are replied with the status in the headers, without a body.
+    if let Some(status) = Status::from_header_map(&parts.headers) {
+        if status.code() != Code::Ok {
+            return status_reply(status);
+        }
+    }
+    let mut data = vec![];
+    while let Some(chunk) = body.data().await {
+        match chunk {
+            Ok(chunk) => data.extend_from_slice(&chunk),
+            Err(status) => return status_reply(status),
+        }
+    }
+    match body.trailers().await {
+        Ok(trailers) => {
+            if let Some(status) = trailers.as_ref().and_then(Status::from_header_map) {
+                if status.code() != Code::Ok {
+                    return status_reply(status);
+                }
+            }
+        }
+        Err(status) => return status_reply(status),
+    }
+    WriteReply {
+        code: Code::Ok as i32,
+        message: String::new(),
+        body: data,
+    }
+}
+
+fn status_reply(status: Status) -> WriteReply {
+    WriteReply {
+        code: status.code() as i32,
+        message: status.message().to_owned(),
+        body: vec![],
+    }
+}
+
+// capture copies the model and the rules of the enforcer.
+async fn capture(entry: &EnforcerEntry) -> PolicySnapshot {
+    let e = entry.enforcer.read().await;
+    let mut rules = vec![];
+    for sec in ["p", "g"] {
+        if let Some(assertions) = e.get_model().get_model().get(sec) {
+            for (ptype, ast) in assertions.iter() {
+                let policy = ast.get_policy().iter().cloned().collect();
+                rules.push((sec.to_owned(), ptype.clone(), policy));
+            }
+        }
+    }
+    PolicySnapshot {
+        model_text: entry.model_text(),
+        rules,
+        functions: entry.enforcer.functions(),
+    }
+}
+
+// restore replaces the model, the rules and the functions of the enforcer with those of a
+// snapshot, saving the rules through its adapter.
+async fn restore(entry: &EnforcerEntry, policy: PolicySnapshot) -> casbin::Result<()> {
+    let mut e = entry.enforcer.write().await;
+    if policy.model_text != entry.model_text() {
+        let m = DefaultModel::from_str(&policy.model_text).await?;
+        e.set_model(m).await?;
+        entry.set_model_text(policy.model_text);
+    }
+    for f in policy.functions {
+        entry.enforcer.add_function(&mut e, f);
+    }
+    e.get_mut_model().clear_policy();
+    for (sec, ptype, rules) in policy.rules {
+        e.get_mut_model().add_policies(&sec, &ptype, rules);
+    }
+    e.build_role_links()?;
+    entry.feed.roles().invalidate();
+    priority::sort_policies(&mut e);
+    e.get_mut_cache().clear();
+    e.save_policy().await
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+pub mod casbin_proto {
+    tonic::include_proto!("proto");
+
+    // FILE_DESCRIPTOR_SET describes the Casbin service to the reflection service.
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("casbin_descriptor");
+}
+#[cfg(feature = "raft")]
+pub mod raft_proto {
+    tonic::include_proto!("raft");
+}
+pub mod adapter;
+pub mod client;
+pub mod compression;
+pub mod datastructure;
+#[cfg(feature = "raft")]
+pub mod dispatcher;
+pub mod proto;
+pub mod server;
+pub mod watcher;
+use casbin::Adapter;
+use futures::lock::Mutex;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::adapter::Drivers;
+use crate::server::audit::AuditLog;
+use crate::server::auth::ApiKeys;
+pub use crate::server::builder::CasbinGRPCBuilder;
+use crate::server::registry::{DecisionCacheOptions, EnforcerRegistry};
+use crate::server::reload::Reloader;
+use crate::server::tls::TlsConfig;
+// Arc is used to share data betweeen the threads, threads in rust?
+
+// AdapterMap holds the adapters of a server by handle.
+type AdapterMap = Arc<RwLock<HashMap<i32, Arc<Mutex<Box<dyn Adapter>>>>>>;
+
+#[derive(Default)]
+pub struct CasbinGRPC {
+    enforcers: Arc<EnforcerRegistry>,
+    // use RefCell and check if thread safe, failed
+    // use mutex+ arc
+    // something similar to RefCell but thread safe
+    // drop mutex guard
+    // Atomic
+    // RefCell with Arc, failed
+    // Arc+Rwlock
+    // HashBrown
+    adapter_map: AdapterMap,
+    drivers: Drivers,
+    tls: Option<TlsConfig>,
+    api_keys: ApiKeys,
+    // api_key_tenants are the tenants api_keys are bound to, by key.
+    api_key_tenants: HashMap<String, String>,
+    unix_socket: Option<String>,
+    audit: Option<Arc<AuditLog>>,
+    decision_cache: DecisionCacheOptions,
+    batch_parallelism: usize,
+    reloader: Option<Arc<Reloader>>,
+    // max_rules bounds the rules each enforcer may be given, 0 for no limit.
+    max_rules: usize,
+    // tenant_model is the model of the tenant served, if the server serves one, which its
+    // enforcers are created from unless given one as text.
+    tenant_model: Option<String>,
+}
@@ -1,43 +1,165 @@
-use std::collections::HashMap;
-pub mod casbin_proto {
-    tonic::include_proto!("proto");
+use casbin_grpc::server::adapter::{self, ConfigOverrides};
+use casbin_grpc::server::logger;
+use casbin_grpc::CasbinGRPC;
+use clap::{Args, Parser, Subcommand};
+use std::net::{IpAddr, SocketAddr};
+
+// casbin-grpc serves the Casbin service, on the enforcer of the local config and those clients
+// create, unless asked to validate the local config or for its version instead.
+#[derive(Parser)]
+#[command(
+    name = "casbin-grpc",
+    about = "Serves Casbin enforcers over gRPC",
+    version
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    options: Options,
 }
-pub mod datastructure;
-pub mod proto;
-pub mod server;
-use casbin::{Adapter, CachedEnforcer};
-use futures::lock::Mutex;
-use std::cell::RefCell;
-use std::sync::Arc;
 
-use crate::datastructure::hashtable::HashTable;
-// Arc is used to share data betweeen the threads, threads in rust?
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Check the local config, its model and its policy, then exit")]
+    Validate,
+    #[command(about = "Print the version and the features of the server")]
+    Version,
+}
 
-#[derive(Default)]
-pub struct CasbinGRPC {
-    enforcer_map: HashMap<i32, Arc<Mutex<CachedEnforcer>>>,
-    // use RefCell and check if thread safe, failed
-    // use mutex+ arc
-    // something similar to RefCell but thread safe
-    // drop mutex guard
-    // Atomic
-    // RefCell with Arc, failed
-    // Arc+Rwlock
-    // HashBrown
-    adapter_map: HashMap<i32, Arc<Mutex<Box<dyn Adapter>>>>,
+#[derive(Args)]
+struct Options {
+    #[arg(
+        long,
+        global = true,
+        help = "Address to listen on, instead of the one of listen_addr [default: ::1]"
+    )]
+    address: Option<IpAddr>,
+    #[arg(
+        long,
+        global = true,
+        help = "Port to listen on, instead of the one of listen_addr [default: 50051]"
+    )]
+    port: Option<u16>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "Local config to load [default: config/connection_config.json]"
+    )]
+    config: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Model of the local enforcer, a file or an http(s) URL"
+    )]
+    model: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "CONNECTION",
+        help = "Policy of the local enforcer, the connection string of its adapter, a CSV file for the file adapter"
+    )]
+    policy: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "DRIVER",
+        help = "Adapter driver of the local enforcer, such as file or postgres"
+    )]
+    adapter: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "PEM",
+        requires = "tls_key",
+        help = "Certificate chain to serve over TLS with"
+    )]
+    tls_cert: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "PEM",
+        requires = "tls_cert",
+        help = "Private key of the TLS certificate"
+    )]
+    tls_key: Option<String>,
 }
 
+// LISTEN_ADDR is where the Casbin service is served unless told otherwise.
+static LISTEN_ADDR: &str = "[::1]:50051";
+
+// FEATURES are the optional features a server may be built with.
+static FEATURES: &[(&str, bool)] = &[
+    ("postgres", cfg!(feature = "postgres")),
+    ("mysql", cfg!(feature = "mysql")),
+    ("sqlite", cfg!(feature = "sqlite")),
+    ("s3", cfg!(feature = "s3")),
+    ("etcd", cfg!(feature = "etcd")),
+    ("nats", cfg!(feature = "nats")),
+    ("raft", cfg!(feature = "raft")),
+    ("web", cfg!(feature = "web")),
+    ("rest", cfg!(feature = "rest")),
+    ("metrics", cfg!(feature = "metrics")),
+    ("otel", cfg!(feature = "otel")),
+    ("serde", cfg!(feature = "serde")),
+];
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let addr: String = "[::1]:50051".parse().unwrap();
-    let casbin = CasbinGRPC::default();
-    // let mut hash = HashTable::<i32, Box<dyn Adapter>>::new();
-
-    println!("Server listening on: {}", addr);
-    //Server::builder()
-    //    .add_service(CasbinServer::new(casbin))
-    //    .serve(addr)
-    //    .await?;
+    let cli = Cli::parse();
+    let options = cli.options;
+    adapter::set_local_config(
+        options.config,
+        ConfigOverrides {
+            driver: options.adapter,
+            connection: options.policy,
+            enforcer: options.model,
+            tls_cert: options.tls_cert,
+            tls_key: options.tls_key,
+        },
+    );
 
-    Ok(())
+    match cli.command {
+        Some(Command::Version) => {
+            let features: Vec<&str> = FEATURES
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| *name)
+                .collect();
+            println!(
+                "casbin-grpc {} (features: {})",
+                env!("CARGO_PKG_VERSION"),
+                features.join(", ")
+            );
+            Ok(())
+        }
+        Some(Command::Validate) => match CasbinGRPC::new_server().validate().await {
+            Ok(summary) => {
+                println!("OK: {}", summary);
+                Ok(())
+            }
+            Err(err) => {
+                eprintln!("Invalid: {}", err);
+                std::process::exit(1);
+            }
+        },
+        None => {
+            let cfg = adapter::load_local_configuration().await?;
+            logger::init_tracing(&cfg.log_level)?;
+            let mut addr: SocketAddr = match cfg.listen_addr.as_str() {
+                "" => LISTEN_ADDR,
+                listen_addr => listen_addr,
+            }
+            .parse()?;
+            if let Some(address) = options.address {
+                addr.set_ip(address);
+            }
+            if let Some(port) = options.port {
+                addr.set_port(port);
+            }
+            CasbinGRPC::new_server().serve(addr).await
+        }
+    }
 }
@@ -1,9 +1,17 @@
+/// The model is given as text, or else as the path of a model file on the server or an
+/// https:// or s3:// URL fetched with modelHeaders, falling back to the model named in the
+/// server's connection config. An adapterHandle of -1 creates an enforcer without an adapter.
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct NewEnforcerRequest {
     #[prost(string, tag = "1")]
     pub model_text: ::prost::alloc::string::String,
     #[prost(int32, tag = "2")]
     pub adapter_handle: i32,
+    #[prost(string, tag = "3")]
+    pub model_path: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "4")]
+    pub model_headers:
+        ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct NewEnforcerReply {
@@ -11,6 +19,69 @@ pub struct NewEnforcerReply {
     pub handler: i32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListEnforcersRequest {}
+/// lastUsed is in milliseconds since the Unix epoch. Pinned enforcers are never evicted for
+/// being idle.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EnforcerInfo {
+    #[prost(int32, tag = "1")]
+    pub handler: i32,
+    #[prost(string, tag = "2")]
+    pub model_hash: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub rule_count: i64,
+    #[prost(int64, tag = "4")]
+    pub last_used: i64,
+    #[prost(bool, tag = "5")]
+    pub pinned: bool,
+    #[prost(bool, tag = "6")]
+    pub auto_save: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListEnforcersReply {
+    #[prost(message, repeated, tag = "1")]
+    pub enforcers: ::prost::alloc::vec::Vec<EnforcerInfo>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ModelReply {
+    #[prost(string, tag = "1")]
+    pub model_text: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub model_hash: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetModelRequest {
+    #[prost(int32, tag = "1")]
+    pub enforcer_handler: i32,
+    #[prost(string, tag = "2")]
+    pub model_text: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidateModelRequest {
+    #[prost(string, tag = "1")]
+    pub model_text: ::prost::alloc::string::String,
+}
+/// line is 1-based, 0 when the diagnostic is about something missing from the model.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ModelDiagnostic {
+    #[prost(string, tag = "1")]
+    pub section: ::prost::alloc::string::String,
+    #[prost(int32, tag = "2")]
+    pub line: i32,
+    #[prost(string, tag = "3")]
+    pub message: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidateModelReply {
+    #[prost(bool, tag = "1")]
+    pub valid: bool,
+    #[prost(message, repeated, tag = "2")]
+    pub diagnostics: ::prost::alloc::vec::Vec<ModelDiagnostic>,
+}
+/// driverName is one of the drivers the server is built with, e.g. file, postgres or sqlite,
+/// and connectString the policy file path or database URL. Both default to the server's
+/// connection config.
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct NewAdapterRequest {
     #[prost(string, tag = "1")]
     pub adapter_name: ::prost::alloc::string::String,
@@ -34,17 +105,109 @@ pub struct EnforceRequest {
     pub params: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EnforceWithMatcherRequest {
+    #[prost(int32, tag = "1")]
+    pub enforcer_handler: i32,
+    #[prost(string, tag = "2")]
+    pub matcher: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "3")]
+    pub params: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchEnforceRequest {
+    #[prost(int32, tag = "1")]
+    pub enforcer_handler: i32,
+    #[prost(message, repeated, tag = "2")]
+    pub requests: ::prost::alloc::vec::Vec<batch_enforce_request::D>,
+}
+/// Nested message and enum types in `BatchEnforceRequest`.
+pub mod batch_enforce_request {
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct D {
+        #[prost(string, repeated, tag = "1")]
+        pub params: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StreamEnforceRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(int32, tag = "2")]
+    pub enforcer_handler: i32,
+    #[prost(string, repeated, tag = "3")]
+    pub params: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StreamEnforceReply {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub res: bool,
+    #[prost(string, tag = "3")]
+    pub error: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct BoolReply {
     #[prost(bool, tag = "1")]
     pub res: bool,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BoolArrayReply {
+    #[prost(bool, repeated, tag = "1")]
+    pub res: ::prost::alloc::vec::Vec<bool>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EnforceExReply {
+    #[prost(bool, tag = "1")]
+    pub res: bool,
+    #[prost(message, repeated, tag = "2")]
+    pub explain: ::prost::alloc::vec::Vec<enforce_ex_reply::D>,
+}
+/// Nested message and enum types in `EnforceExReply`.
+pub mod enforce_ex_reply {
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct D {
+        #[prost(string, repeated, tag = "1")]
+        pub d1: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EmptyRequest {
     #[prost(int32, tag = "1")]
     pub handler: i32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EmptyReply {}
+/// LoadFilteredPolicy loads only the policy rules whose fields equal the non-empty values of
+/// p, and the grouping rules whose fields equal those of g, e.g. p = ["", "domain1"] for the
+/// rules of one domain. An enforcer holding a filtered policy cannot save it.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadFilteredPolicyRequest {
+    #[prost(int32, tag = "1")]
+    pub handler: i32,
+    #[prost(string, repeated, tag = "2")]
+    pub p: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "3")]
+    pub g: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// With auto-save on, the default, every rule change is written through the enforcer's
+/// adapter as it is made. With it off, changes stay in memory until SavePolicy.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EnableAutoSaveRequest {
+    #[prost(int32, tag = "1")]
+    pub enforcer_handler: i32,
+    #[prost(bool, tag = "2")]
+    pub enable: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClearPolicyRequest {
+    #[prost(int32, tag = "1")]
+    pub enforcer_handler: i32,
+    #[prost(bool, tag = "2")]
+    pub confirm: bool,
+    #[prost(int64, optional, tag = "3")]
+    pub expected_rule_count: ::core::option::Option<i64>,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PolicyRequest {
     #[prost(int32, tag = "1")]
@@ -55,6 +218,53 @@ pub struct PolicyRequest {
     pub params: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoliciesRequest {
+    #[prost(int32, tag = "1")]
+    pub enforcer_handler: i32,
+    #[prost(string, tag = "2")]
+    pub p_type: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "3")]
+    pub rules: ::prost::alloc::vec::Vec<policies_request::D>,
+}
+/// Nested message and enum types in `PoliciesRequest`.
+pub mod policies_request {
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct D {
+        #[prost(string, repeated, tag = "1")]
+        pub params: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdatePolicyRequest {
+    #[prost(int32, tag = "1")]
+    pub enforcer_handler: i32,
+    #[prost(string, tag = "2")]
+    pub p_type: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "3")]
+    pub old_rule: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "4")]
+    pub new_rule: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdatePoliciesRequest {
+    #[prost(int32, tag = "1")]
+    pub enforcer_handler: i32,
+    #[prost(string, tag = "2")]
+    pub p_type: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "3")]
+    pub old_rules: ::prost::alloc::vec::Vec<update_policies_request::D>,
+    #[prost(message, repeated, tag = "4")]
+    pub new_rules: ::prost::alloc::vec::Vec<update_policies_request::D>,
+}
+/// Nested message and enum types in `UpdatePoliciesRequest`.
+pub mod update_policies_request {
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct D {
+        #[prost(string, repeated, tag = "1")]
+        pub params: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SimpleGetRequest {
     #[prost(int32, tag = "1")]
     pub enforcer_handler: i32,
@@ -77,6 +287,8 @@ pub struct FilteredPolicyRequest {
     #[prost(string, repeated, tag = "4")]
     pub field_values: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
+/// gType and pType name the grouping policy and policy a request works on, e.g. g2 or p2,
+/// and default to g and p.
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UserRoleRequest {
     #[prost(int32, tag = "1")]
@@ -85,6 +297,12 @@ pub struct UserRoleRequest {
     pub user: ::prost::alloc::string::String,
     #[prost(string, tag = "3")]
     pub role: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub domain: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub g_type: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub p_type: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PermissionRequest {
@@ -94,6 +312,19 @@ pub struct PermissionRequest {
     pub user: ::prost::alloc::string::String,
     #[prost(string, repeated, tag = "3")]
     pub permissions: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag = "4")]
+    pub domain: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub p_type: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub g_type: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DomainsRequest {
+    #[prost(int32, tag = "1")]
+    pub enforcer_handler: i32,
+    #[prost(string, repeated, tag = "2")]
+    pub domains: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Array2DReply {
@@ -191,10 +422,10 @@ pub mod casbin_client {
             let path = http::uri::PathAndQuery::from_static("/proto.Casbin/NewEnforcer");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn new_adapter(
+        pub async fn delete_enforcer(
             &mut self,
-            request: impl tonic::IntoRequest<super::NewAdapterRequest>,
-        ) -> Result<tonic::Response<super::NewAdapterReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -202,13 +433,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/NewAdapter");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/DeleteEnforcer");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn enforce(
+        pub async fn list_enforcers(
             &mut self,
-            request: impl tonic::IntoRequest<super::EnforceRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::ListEnforcersRequest>,
+        ) -> Result<tonic::Response<super::ListEnforcersReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -216,13 +447,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/Enforce");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/ListEnforcers");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn load_policy(
+        pub async fn new_adapter(
             &mut self,
-            request: impl tonic::IntoRequest<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::NewAdapterRequest>,
+        ) -> Result<tonic::Response<super::NewAdapterReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -230,13 +461,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/LoadPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/NewAdapter");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn save_policy(
+        pub async fn get_model(
             &mut self,
             request: impl tonic::IntoRequest<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status> {
+        ) -> Result<tonic::Response<super::ModelReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -244,13 +475,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/SavePolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetModel");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn add_policy(
+        pub async fn set_model(
             &mut self,
-            request: impl tonic::IntoRequest<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::SetModelRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -258,13 +489,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/SetModel");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn add_named_policy(
+        pub async fn validate_model(
             &mut self,
-            request: impl tonic::IntoRequest<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::ValidateModelRequest>,
+        ) -> Result<tonic::Response<super::ValidateModelReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -272,12 +503,12 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddNamedPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/ValidateModel");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn remove_policy(
+        pub async fn enforce(
             &mut self,
-            request: impl tonic::IntoRequest<super::PolicyRequest>,
+            request: impl tonic::IntoRequest<super::EnforceRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -286,13 +517,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/RemovePolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/Enforce");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn remove_named_policy(
+        pub async fn batch_enforce(
             &mut self,
-            request: impl tonic::IntoRequest<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::BatchEnforceRequest>,
+        ) -> Result<tonic::Response<super::BoolArrayReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -300,13 +531,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveNamedPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/BatchEnforce");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn remove_filtered_policy(
+        pub async fn enforce_ex(
             &mut self,
-            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::EnforceRequest>,
+        ) -> Result<tonic::Response<super::EnforceExReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -314,12 +545,12 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveFilteredPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/EnforceEx");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn remove_filtered_named_policy(
+        pub async fn enforce_with_matcher(
             &mut self,
-            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
+            request: impl tonic::IntoRequest<super::EnforceWithMatcherRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -328,14 +559,16 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path =
-                http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveFilteredNamedPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/EnforceWithMatcher");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_policy(
+        pub async fn stream_enforce(
             &mut self,
-            request: impl tonic::IntoRequest<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
+            request: impl tonic::IntoStreamingRequest<Message = super::StreamEnforceRequest>,
+        ) -> Result<
+            tonic::Response<tonic::codec::Streaming<super::StreamEnforceReply>>,
+            tonic::Status,
+        > {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -343,13 +576,15 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetPolicy");
-            self.inner.unary(request.into_request(), path, codec).await
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/StreamEnforce");
+            self.inner
+                .streaming(request.into_streaming_request(), path, codec)
+                .await
         }
-        pub async fn get_named_policy(
+        pub async fn load_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -357,13 +592,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetNamedPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/LoadPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_filtered_policy(
+        pub async fn load_filtered_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::LoadFilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -371,13 +606,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetFilteredPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/LoadFilteredPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_filtered_named_policy(
+        pub async fn save_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -385,13 +620,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetFilteredNamedPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/SavePolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn add_grouping_policy(
+        pub async fn clear_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::ClearPolicyRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -399,13 +634,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddGroupingPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/ClearPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn add_named_grouping_policy(
+        pub async fn enable_auto_save(
             &mut self,
-            request: impl tonic::IntoRequest<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::EnableAutoSaveRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -413,10 +648,10 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddNamedGroupingPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/EnableAutoSave");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn remove_grouping_policy(
+        pub async fn add_policy(
             &mut self,
             request: impl tonic::IntoRequest<super::PolicyRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
@@ -427,10 +662,10 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveGroupingPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn remove_named_grouping_policy(
+        pub async fn add_named_policy(
             &mut self,
             request: impl tonic::IntoRequest<super::PolicyRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
@@ -441,13 +676,12 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path =
-                http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveNamedGroupingPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddNamedPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn remove_filtered_grouping_policy(
+        pub async fn add_policies(
             &mut self,
-            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
+            request: impl tonic::IntoRequest<super::PoliciesRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -456,13 +690,12 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path =
-                http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveFilteredGroupingPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddPolicies");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn remove_filtered_named_grouping_policy(
+        pub async fn add_named_policies(
             &mut self,
-            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
+            request: impl tonic::IntoRequest<super::PoliciesRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -471,15 +704,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/proto.Casbin/RemoveFilteredNamedGroupingPolicy",
-            );
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddNamedPolicies");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_grouping_policy(
+        pub async fn remove_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -487,13 +718,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetGroupingPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/RemovePolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_named_grouping_policy(
+        pub async fn remove_named_policy(
             &mut self,
             request: impl tonic::IntoRequest<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -501,13 +732,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetNamedGroupingPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveNamedPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_filtered_grouping_policy(
+        pub async fn remove_policies(
             &mut self,
-            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::PoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -515,14 +746,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path =
-                http::uri::PathAndQuery::from_static("/proto.Casbin/GetFilteredGroupingPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/RemovePolicies");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_filtered_named_grouping_policy(
+        pub async fn remove_named_policies(
             &mut self,
-            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::PoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -530,15 +760,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/proto.Casbin/GetFilteredNamedGroupingPolicy",
-            );
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveNamedPolicies");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_all_subjects(
+        pub async fn update_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::UpdatePolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -546,13 +774,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllSubjects");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/UpdatePolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_all_named_subjects(
+        pub async fn update_named_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::SimpleGetRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::UpdatePolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -560,13 +788,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllNamedSubjects");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/UpdateNamedPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_all_objects(
+        pub async fn update_policies(
             &mut self,
-            request: impl tonic::IntoRequest<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::UpdatePoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -574,13 +802,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllObjects");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/UpdatePolicies");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_all_named_objects(
+        pub async fn update_named_policies(
             &mut self,
-            request: impl tonic::IntoRequest<super::SimpleGetRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::UpdatePoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -588,13 +816,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllNamedObjects");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/UpdateNamedPolicies");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_all_actions(
+        pub async fn remove_filtered_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -602,13 +830,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllActions");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveFilteredPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_all_named_actions(
+        pub async fn remove_filtered_named_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::SimpleGetRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -616,13 +844,14 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllNamedActions");
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveFilteredNamedPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_all_roles(
+        pub async fn get_policy(
             &mut self,
             request: impl tonic::IntoRequest<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -630,13 +859,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllRoles");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_all_named_roles(
+        pub async fn get_named_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::SimpleGetRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -644,13 +873,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllNamedRoles");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetNamedPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn has_policy(
+        pub async fn get_filtered_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -658,13 +887,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/HasPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetFilteredPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn has_named_policy(
+        pub async fn get_filtered_named_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -672,10 +901,10 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/HasNamedPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetFilteredNamedPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn has_grouping_policy(
+        pub async fn add_grouping_policy(
             &mut self,
             request: impl tonic::IntoRequest<super::PolicyRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
@@ -686,10 +915,10 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/HasGroupingPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddGroupingPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn has_named_grouping_policy(
+        pub async fn add_named_grouping_policy(
             &mut self,
             request: impl tonic::IntoRequest<super::PolicyRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
@@ -700,13 +929,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/HasNamedGroupingPolicy");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddNamedGroupingPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_roles_for_user(
+        pub async fn remove_grouping_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -714,13 +943,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetRolesForUser");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveGroupingPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_implicit_roles_for_user(
+        pub async fn remove_named_grouping_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -729,13 +958,13 @@ pub mod casbin_client {
             })?;
             let codec = tonic::codec::ProstCodec::default();
             let path =
-                http::uri::PathAndQuery::from_static("/proto.Casbin/GetImplicitRolesForUser");
+                http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveNamedGroupingPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_users_for_role(
+        pub async fn add_grouping_policies(
             &mut self,
-            request: impl tonic::IntoRequest<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::PoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -743,12 +972,12 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetUsersForRole");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddGroupingPolicies");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn has_role_for_user(
+        pub async fn add_named_grouping_policies(
             &mut self,
-            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+            request: impl tonic::IntoRequest<super::PoliciesRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -757,12 +986,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/HasRoleForUser");
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/AddNamedGroupingPolicies");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn add_role_for_user(
+        pub async fn remove_grouping_policies(
             &mut self,
-            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+            request: impl tonic::IntoRequest<super::PoliciesRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -771,12 +1001,12 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddRoleForUser");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveGroupingPolicies");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn delete_role_for_user(
+        pub async fn remove_named_grouping_policies(
             &mut self,
-            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+            request: impl tonic::IntoRequest<super::PoliciesRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -785,12 +1015,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/DeleteRoleForUser");
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveNamedGroupingPolicies");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn delete_roles_for_user(
+        pub async fn remove_filtered_grouping_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -799,12 +1030,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/DeleteRolesForUser");
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/RemoveFilteredGroupingPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn delete_user(
+        pub async fn remove_filtered_named_grouping_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -813,13 +1045,15 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/DeleteUser");
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Casbin/RemoveFilteredNamedGroupingPolicy",
+            );
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn delete_role(
+        pub async fn get_grouping_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -827,12 +1061,12 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/DeleteRole");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetGroupingPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_permissions_for_user(
+        pub async fn get_named_grouping_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::PermissionRequest>,
+            request: impl tonic::IntoRequest<super::PolicyRequest>,
         ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -841,12 +1075,12 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetPermissionsForUser");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetNamedGroupingPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_implicit_permissions_for_user(
+        pub async fn get_filtered_grouping_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::PermissionRequest>,
+            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
         ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -856,13 +1090,13 @@ pub mod casbin_client {
             })?;
             let codec = tonic::codec::ProstCodec::default();
             let path =
-                http::uri::PathAndQuery::from_static("/proto.Casbin/GetImplicitPermissionsForUser");
+                http::uri::PathAndQuery::from_static("/proto.Casbin/GetFilteredGroupingPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn delete_permission(
+        pub async fn get_filtered_named_grouping_policy(
             &mut self,
-            request: impl tonic::IntoRequest<super::PermissionRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -870,13 +1104,15 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/DeletePermission");
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Casbin/GetFilteredNamedGroupingPolicy",
+            );
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn add_permission_for_user(
+        pub async fn get_all_subjects(
             &mut self,
-            request: impl tonic::IntoRequest<super::PermissionRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -884,13 +1120,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddPermissionForUser");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllSubjects");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn delete_permission_for_user(
+        pub async fn get_all_named_subjects(
             &mut self,
-            request: impl tonic::IntoRequest<super::PermissionRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::SimpleGetRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -898,14 +1134,13 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path =
-                http::uri::PathAndQuery::from_static("/proto.Casbin/DeletePermissionForUser");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllNamedSubjects");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn delete_permissions_for_user(
+        pub async fn get_all_objects(
             &mut self,
-            request: impl tonic::IntoRequest<super::PermissionRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -913,13 +1148,82 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path =
-                http::uri::PathAndQuery::from_static("/proto.Casbin/DeletePermissionsForUser");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllObjects");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn has_permission_for_user(
+        pub async fn get_all_named_objects(
             &mut self,
-            request: impl tonic::IntoRequest<super::PermissionRequest>,
+            request: impl tonic::IntoRequest<super::SimpleGetRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllNamedObjects");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_all_actions(
+            &mut self,
+            request: impl tonic::IntoRequest<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllActions");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_all_named_actions(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SimpleGetRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllNamedActions");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_all_roles(
+            &mut self,
+            request: impl tonic::IntoRequest<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllRoles");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_all_named_roles(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SimpleGetRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllNamedRoles");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn has_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PolicyRequest>,
         ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -928,297 +1232,1400 @@ pub mod casbin_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/HasPermissionForUser");
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/HasPolicy");
             self.inner.unary(request.into_request(), path, codec).await
         }
-    }
-}
-/// Generated server implementations.
-pub mod casbin_server {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
-    ///Generated trait containing gRPC methods that should be implemented for use with CasbinServer.
-    #[async_trait]
-    pub trait Casbin: Send + Sync + 'static {
-        async fn new_enforcer(
-            &self,
-            request: tonic::Request<super::NewEnforcerRequest>,
-        ) -> Result<tonic::Response<super::NewEnforcerReply>, tonic::Status>;
-        async fn new_adapter(
-            &self,
-            request: tonic::Request<super::NewAdapterRequest>,
-        ) -> Result<tonic::Response<super::NewAdapterReply>, tonic::Status>;
-        async fn enforce(
-            &self,
-            request: tonic::Request<super::EnforceRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn load_policy(
-            &self,
-            request: tonic::Request<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status>;
-        async fn save_policy(
-            &self,
-            request: tonic::Request<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status>;
-        async fn add_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn add_named_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn remove_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn remove_named_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn remove_filtered_policy(
-            &self,
-            request: tonic::Request<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn remove_filtered_named_policy(
-            &self,
-            request: tonic::Request<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn get_policy(
-            &self,
-            request: tonic::Request<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
-        async fn get_named_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
-        async fn get_filtered_policy(
-            &self,
-            request: tonic::Request<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
-        async fn get_filtered_named_policy(
-            &self,
-            request: tonic::Request<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
-        async fn add_grouping_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn add_named_grouping_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn remove_grouping_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn remove_named_grouping_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn remove_filtered_grouping_policy(
-            &self,
-            request: tonic::Request<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn remove_filtered_named_grouping_policy(
-            &self,
-            request: tonic::Request<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn get_grouping_policy(
-            &self,
-            request: tonic::Request<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
-        async fn get_named_grouping_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
-        async fn get_filtered_grouping_policy(
-            &self,
-            request: tonic::Request<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
-        async fn get_filtered_named_grouping_policy(
-            &self,
-            request: tonic::Request<super::FilteredPolicyRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
-        async fn get_all_subjects(
-            &self,
-            request: tonic::Request<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
-        async fn get_all_named_subjects(
-            &self,
-            request: tonic::Request<super::SimpleGetRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
-        async fn get_all_objects(
-            &self,
-            request: tonic::Request<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
-        async fn get_all_named_objects(
-            &self,
-            request: tonic::Request<super::SimpleGetRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
-        async fn get_all_actions(
-            &self,
-            request: tonic::Request<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
-        async fn get_all_named_actions(
-            &self,
-            request: tonic::Request<super::SimpleGetRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
-        async fn get_all_roles(
-            &self,
-            request: tonic::Request<super::EmptyRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
-        async fn get_all_named_roles(
-            &self,
-            request: tonic::Request<super::SimpleGetRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
-        async fn has_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn has_named_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn has_grouping_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn has_named_grouping_policy(
-            &self,
-            request: tonic::Request<super::PolicyRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn get_roles_for_user(
-            &self,
-            request: tonic::Request<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
-        async fn get_implicit_roles_for_user(
-            &self,
-            request: tonic::Request<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
-        async fn get_users_for_role(
-            &self,
-            request: tonic::Request<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
-        async fn has_role_for_user(
-            &self,
-            request: tonic::Request<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn add_role_for_user(
-            &self,
-            request: tonic::Request<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn delete_role_for_user(
-            &self,
-            request: tonic::Request<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn delete_roles_for_user(
-            &self,
-            request: tonic::Request<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn delete_user(
-            &self,
-            request: tonic::Request<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn delete_role(
-            &self,
-            request: tonic::Request<super::UserRoleRequest>,
-        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status>;
-        async fn get_permissions_for_user(
-            &self,
-            request: tonic::Request<super::PermissionRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
-        async fn get_implicit_permissions_for_user(
-            &self,
-            request: tonic::Request<super::PermissionRequest>,
-        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
-        async fn delete_permission(
-            &self,
-            request: tonic::Request<super::PermissionRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn add_permission_for_user(
-            &self,
-            request: tonic::Request<super::PermissionRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn delete_permission_for_user(
-            &self,
-            request: tonic::Request<super::PermissionRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn delete_permissions_for_user(
-            &self,
-            request: tonic::Request<super::PermissionRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-        async fn has_permission_for_user(
-            &self,
-            request: tonic::Request<super::PermissionRequest>,
-        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
-    }
-    /// The Casbin service definition.
-    #[derive(Debug)]
-    pub struct CasbinServer<T: Casbin> {
-        inner: _Inner<T>,
-        accept_compression_encodings: EnabledCompressionEncodings,
-        send_compression_encodings: EnabledCompressionEncodings,
-    }
-    struct _Inner<T>(Arc<T>);
-    impl<T: Casbin> CasbinServer<T> {
-        pub fn new(inner: T) -> Self {
-            Self::from_arc(Arc::new(inner))
-        }
-        pub fn from_arc(inner: Arc<T>) -> Self {
-            let inner = _Inner(inner);
-            Self {
-                inner,
-                accept_compression_encodings: Default::default(),
-                send_compression_encodings: Default::default(),
-            }
-        }
-        pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
-        where
-            F: tonic::service::Interceptor,
-        {
-            InterceptedService::new(Self::new(inner), interceptor)
+        pub async fn has_named_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/HasNamedPolicy");
+            self.inner.unary(request.into_request(), path, codec).await
         }
-        /// Enable decompressing requests with the given encoding.
-        #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.accept_compression_encodings.enable(encoding);
-            self
+        pub async fn has_grouping_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/HasGroupingPolicy");
+            self.inner.unary(request.into_request(), path, codec).await
         }
-        /// Compress responses with the given encoding, if the client supports it.
-        #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.send_compression_encodings.enable(encoding);
-            self
+        pub async fn has_named_grouping_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/HasNamedGroupingPolicy");
+            self.inner.unary(request.into_request(), path, codec).await
         }
-    }
-    impl<T, B> tonic::codegen::Service<http::Request<B>> for CasbinServer<T>
-    where
-        T: Casbin,
-        B: Body + Send + 'static,
-        B::Error: Into<StdError> + Send + 'static,
-    {
-        type Response = http::Response<tonic::body::BoxBody>;
-        type Error = std::convert::Infallible;
-        type Future = BoxFuture<Self::Response, Self::Error>;
-        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-            Poll::Ready(Ok(()))
+        pub async fn get_roles_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetRolesForUser");
+            self.inner.unary(request.into_request(), path, codec).await
         }
-        fn call(&mut self, req: http::Request<B>) -> Self::Future {
-            let inner = self.inner.clone();
-            match req.uri().path() {
-                "/proto.Casbin/NewEnforcer" => {
+        pub async fn get_implicit_roles_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/GetImplicitRolesForUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_users_for_role(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetUsersForRole");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn has_role_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/HasRoleForUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn add_role_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddRoleForUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn delete_role_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/DeleteRoleForUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn delete_roles_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/DeleteRolesForUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn delete_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/DeleteUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn delete_role(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/DeleteRole");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_permissions_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetPermissionsForUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_implicit_permissions_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/GetImplicitPermissionsForUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_implicit_users_for_permission(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/GetImplicitUsersForPermission");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn delete_permission(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/DeletePermission");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn add_permission_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddPermissionForUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn delete_permission_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/DeletePermissionForUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn delete_permissions_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/DeletePermissionsForUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn has_permission_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/HasPermissionForUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_roles_for_user_in_domain(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/GetRolesForUserInDomain");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_users_for_role_in_domain(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/GetUsersForRoleInDomain");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_permissions_for_user_in_domain(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/GetPermissionsForUserInDomain");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn add_role_for_user_in_domain(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/AddRoleForUserInDomain");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn delete_role_for_user_in_domain(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/proto.Casbin/DeleteRoleForUserInDomain");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_all_domains(
+            &mut self,
+            request: impl tonic::IntoRequest<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetAllDomains");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn get_domains_for_user(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/GetDomainsForUser");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn delete_domains(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DomainsRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Casbin/DeleteDomains");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod casbin_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    ///Generated trait containing gRPC methods that should be implemented for use with CasbinServer.
+    #[async_trait]
+    pub trait Casbin: Send + Sync + 'static {
+        async fn new_enforcer(
+            &self,
+            request: tonic::Request<super::NewEnforcerRequest>,
+        ) -> Result<tonic::Response<super::NewEnforcerReply>, tonic::Status>;
+        async fn delete_enforcer(
+            &self,
+            request: tonic::Request<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status>;
+        async fn list_enforcers(
+            &self,
+            request: tonic::Request<super::ListEnforcersRequest>,
+        ) -> Result<tonic::Response<super::ListEnforcersReply>, tonic::Status>;
+        async fn new_adapter(
+            &self,
+            request: tonic::Request<super::NewAdapterRequest>,
+        ) -> Result<tonic::Response<super::NewAdapterReply>, tonic::Status>;
+        async fn get_model(
+            &self,
+            request: tonic::Request<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ModelReply>, tonic::Status>;
+        async fn set_model(
+            &self,
+            request: tonic::Request<super::SetModelRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status>;
+        async fn validate_model(
+            &self,
+            request: tonic::Request<super::ValidateModelRequest>,
+        ) -> Result<tonic::Response<super::ValidateModelReply>, tonic::Status>;
+        async fn enforce(
+            &self,
+            request: tonic::Request<super::EnforceRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn batch_enforce(
+            &self,
+            request: tonic::Request<super::BatchEnforceRequest>,
+        ) -> Result<tonic::Response<super::BoolArrayReply>, tonic::Status>;
+        async fn enforce_ex(
+            &self,
+            request: tonic::Request<super::EnforceRequest>,
+        ) -> Result<tonic::Response<super::EnforceExReply>, tonic::Status>;
+        async fn enforce_with_matcher(
+            &self,
+            request: tonic::Request<super::EnforceWithMatcherRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        /// Server streaming response type for the StreamEnforce method.
+        type StreamEnforceStream: futures_core::Stream<Item = Result<super::StreamEnforceReply, tonic::Status>>
+            + Send
+            + 'static;
+        async fn stream_enforce(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::StreamEnforceRequest>>,
+        ) -> Result<tonic::Response<Self::StreamEnforceStream>, tonic::Status>;
+        async fn load_policy(
+            &self,
+            request: tonic::Request<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status>;
+        async fn load_filtered_policy(
+            &self,
+            request: tonic::Request<super::LoadFilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status>;
+        async fn save_policy(
+            &self,
+            request: tonic::Request<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status>;
+        async fn clear_policy(
+            &self,
+            request: tonic::Request<super::ClearPolicyRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status>;
+        async fn enable_auto_save(
+            &self,
+            request: tonic::Request<super::EnableAutoSaveRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status>;
+        async fn add_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn add_named_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn add_policies(
+            &self,
+            request: tonic::Request<super::PoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn add_named_policies(
+            &self,
+            request: tonic::Request<super::PoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn remove_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn remove_named_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn remove_policies(
+            &self,
+            request: tonic::Request<super::PoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn remove_named_policies(
+            &self,
+            request: tonic::Request<super::PoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn update_policy(
+            &self,
+            request: tonic::Request<super::UpdatePolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn update_named_policy(
+            &self,
+            request: tonic::Request<super::UpdatePolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn update_policies(
+            &self,
+            request: tonic::Request<super::UpdatePoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn update_named_policies(
+            &self,
+            request: tonic::Request<super::UpdatePoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn remove_filtered_policy(
+            &self,
+            request: tonic::Request<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn remove_filtered_named_policy(
+            &self,
+            request: tonic::Request<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn get_policy(
+            &self,
+            request: tonic::Request<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
+        async fn get_named_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
+        async fn get_filtered_policy(
+            &self,
+            request: tonic::Request<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
+        async fn get_filtered_named_policy(
+            &self,
+            request: tonic::Request<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
+        async fn add_grouping_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn add_named_grouping_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn remove_grouping_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn remove_named_grouping_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn add_grouping_policies(
+            &self,
+            request: tonic::Request<super::PoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn add_named_grouping_policies(
+            &self,
+            request: tonic::Request<super::PoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn remove_grouping_policies(
+            &self,
+            request: tonic::Request<super::PoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn remove_named_grouping_policies(
+            &self,
+            request: tonic::Request<super::PoliciesRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn remove_filtered_grouping_policy(
+            &self,
+            request: tonic::Request<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn remove_filtered_named_grouping_policy(
+            &self,
+            request: tonic::Request<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn get_grouping_policy(
+            &self,
+            request: tonic::Request<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
+        async fn get_named_grouping_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
+        async fn get_filtered_grouping_policy(
+            &self,
+            request: tonic::Request<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
+        async fn get_filtered_named_grouping_policy(
+            &self,
+            request: tonic::Request<super::FilteredPolicyRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
+        async fn get_all_subjects(
+            &self,
+            request: tonic::Request<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn get_all_named_subjects(
+            &self,
+            request: tonic::Request<super::SimpleGetRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn get_all_objects(
+            &self,
+            request: tonic::Request<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn get_all_named_objects(
+            &self,
+            request: tonic::Request<super::SimpleGetRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn get_all_actions(
+            &self,
+            request: tonic::Request<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn get_all_named_actions(
+            &self,
+            request: tonic::Request<super::SimpleGetRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn get_all_roles(
+            &self,
+            request: tonic::Request<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn get_all_named_roles(
+            &self,
+            request: tonic::Request<super::SimpleGetRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn has_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn has_named_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn has_grouping_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn has_named_grouping_policy(
+            &self,
+            request: tonic::Request<super::PolicyRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn get_roles_for_user(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn get_implicit_roles_for_user(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn get_users_for_role(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn has_role_for_user(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn add_role_for_user(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn delete_role_for_user(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn delete_roles_for_user(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn delete_user(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn delete_role(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::EmptyReply>, tonic::Status>;
+        async fn get_permissions_for_user(
+            &self,
+            request: tonic::Request<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
+        async fn get_implicit_permissions_for_user(
+            &self,
+            request: tonic::Request<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
+        async fn get_implicit_users_for_permission(
+            &self,
+            request: tonic::Request<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn delete_permission(
+            &self,
+            request: tonic::Request<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn add_permission_for_user(
+            &self,
+            request: tonic::Request<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn delete_permission_for_user(
+            &self,
+            request: tonic::Request<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn delete_permissions_for_user(
+            &self,
+            request: tonic::Request<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn has_permission_for_user(
+            &self,
+            request: tonic::Request<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn get_roles_for_user_in_domain(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn get_users_for_role_in_domain(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn get_permissions_for_user_in_domain(
+            &self,
+            request: tonic::Request<super::PermissionRequest>,
+        ) -> Result<tonic::Response<super::Array2DReply>, tonic::Status>;
+        async fn add_role_for_user_in_domain(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn delete_role_for_user_in_domain(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+        async fn get_all_domains(
+            &self,
+            request: tonic::Request<super::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn get_domains_for_user(
+            &self,
+            request: tonic::Request<super::UserRoleRequest>,
+        ) -> Result<tonic::Response<super::ArrayReply>, tonic::Status>;
+        async fn delete_domains(
+            &self,
+            request: tonic::Request<super::DomainsRequest>,
+        ) -> Result<tonic::Response<super::BoolReply>, tonic::Status>;
+    }
+    /// The Casbin service definition.
+    #[derive(Debug)]
+    pub struct CasbinServer<T: Casbin> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: Casbin> CasbinServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for CasbinServer<T>
+    where
+        T: Casbin,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/proto.Casbin/NewEnforcer" => {
+                    #[allow(non_camel_case_types)]
+                    struct NewEnforcerSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::NewEnforcerRequest> for NewEnforcerSvc<T> {
+                        type Response = super::NewEnforcerReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::NewEnforcerRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).new_enforcer(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = NewEnforcerSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/DeleteEnforcer" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteEnforcerSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::EmptyRequest> for DeleteEnforcerSvc<T> {
+                        type Response = super::EmptyReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::EmptyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).delete_enforcer(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteEnforcerSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/ListEnforcers" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListEnforcersSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::ListEnforcersRequest> for ListEnforcersSvc<T> {
+                        type Response = super::ListEnforcersReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListEnforcersRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).list_enforcers(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListEnforcersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/NewAdapter" => {
+                    #[allow(non_camel_case_types)]
+                    struct NewAdapterSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::NewAdapterRequest> for NewAdapterSvc<T> {
+                        type Response = super::NewAdapterReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::NewAdapterRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).new_adapter(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = NewAdapterSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/GetModel" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetModelSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::EmptyRequest> for GetModelSvc<T> {
+                        type Response = super::ModelReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::EmptyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_model(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetModelSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/SetModel" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetModelSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::SetModelRequest> for SetModelSvc<T> {
+                        type Response = super::EmptyReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SetModelRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).set_model(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SetModelSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/ValidateModel" => {
+                    #[allow(non_camel_case_types)]
+                    struct ValidateModelSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::ValidateModelRequest> for ValidateModelSvc<T> {
+                        type Response = super::ValidateModelReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ValidateModelRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).validate_model(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ValidateModelSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/Enforce" => {
+                    #[allow(non_camel_case_types)]
+                    struct EnforceSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::EnforceRequest> for EnforceSvc<T> {
+                        type Response = super::BoolReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::EnforceRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).enforce(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = EnforceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/BatchEnforce" => {
+                    #[allow(non_camel_case_types)]
+                    struct BatchEnforceSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::BatchEnforceRequest> for BatchEnforceSvc<T> {
+                        type Response = super::BoolArrayReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BatchEnforceRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).batch_enforce(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BatchEnforceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/EnforceEx" => {
+                    #[allow(non_camel_case_types)]
+                    struct EnforceExSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::EnforceRequest> for EnforceExSvc<T> {
+                        type Response = super::EnforceExReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::EnforceRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).enforce_ex(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = EnforceExSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/EnforceWithMatcher" => {
+                    #[allow(non_camel_case_types)]
+                    struct EnforceWithMatcherSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::EnforceWithMatcherRequest>
+                        for EnforceWithMatcherSvc<T>
+                    {
+                        type Response = super::BoolReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::EnforceWithMatcherRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).enforce_with_matcher(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = EnforceWithMatcherSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/StreamEnforce" => {
+                    #[allow(non_camel_case_types)]
+                    struct StreamEnforceSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::StreamingService<super::StreamEnforceRequest>
+                        for StreamEnforceSvc<T>
+                    {
+                        type Response = super::StreamEnforceReply;
+                        type ResponseStream = T::StreamEnforceStream;
+                        type Future =
+                            BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<tonic::Streaming<super::StreamEnforceRequest>>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).stream_enforce(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = StreamEnforceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/LoadPolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct LoadPolicySvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::EmptyRequest> for LoadPolicySvc<T> {
+                        type Response = super::EmptyReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::EmptyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).load_policy(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = LoadPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/LoadFilteredPolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct LoadFilteredPolicySvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::LoadFilteredPolicyRequest>
+                        for LoadFilteredPolicySvc<T>
+                    {
+                        type Response = super::EmptyReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::LoadFilteredPolicyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).load_filtered_policy(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = LoadFilteredPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/SavePolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct SavePolicySvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::EmptyRequest> for SavePolicySvc<T> {
+                        type Response = super::EmptyReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::EmptyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).save_policy(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SavePolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/ClearPolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct ClearPolicySvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::ClearPolicyRequest> for ClearPolicySvc<T> {
+                        type Response = super::EmptyReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ClearPolicyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).clear_policy(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ClearPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/EnableAutoSave" => {
+                    #[allow(non_camel_case_types)]
+                    struct EnableAutoSaveSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::EnableAutoSaveRequest> for EnableAutoSaveSvc<T> {
+                        type Response = super::EmptyReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::EnableAutoSaveRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).enable_auto_save(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = EnableAutoSaveSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/AddPolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct AddPolicySvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PolicyRequest> for AddPolicySvc<T> {
+                        type Response = super::BoolReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PolicyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).add_policy(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AddPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/AddNamedPolicy" => {
                     #[allow(non_camel_case_types)]
-                    struct NewEnforcerSvc<T: Casbin>(pub Arc<T>);
-                    impl<T: Casbin> tonic::server::UnaryService<super::NewEnforcerRequest> for NewEnforcerSvc<T> {
-                        type Response = super::NewEnforcerReply;
+                    struct AddNamedPolicySvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PolicyRequest> for AddNamedPolicySvc<T> {
+                        type Response = super::BoolReply;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::NewEnforcerRequest>,
+                            request: tonic::Request<super::PolicyRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).new_enforcer(request).await };
+                            let fut = async move { (*inner).add_named_policy(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -1227,7 +2634,7 @@ pub mod casbin_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = NewEnforcerSvc(inner);
+                        let method = AddNamedPolicySvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -1238,18 +2645,18 @@ pub mod casbin_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Casbin/NewAdapter" => {
+                "/proto.Casbin/AddPolicies" => {
                     #[allow(non_camel_case_types)]
-                    struct NewAdapterSvc<T: Casbin>(pub Arc<T>);
-                    impl<T: Casbin> tonic::server::UnaryService<super::NewAdapterRequest> for NewAdapterSvc<T> {
-                        type Response = super::NewAdapterReply;
+                    struct AddPoliciesSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PoliciesRequest> for AddPoliciesSvc<T> {
+                        type Response = super::BoolReply;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::NewAdapterRequest>,
+                            request: tonic::Request<super::PoliciesRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).new_adapter(request).await };
+                            let fut = async move { (*inner).add_policies(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -1258,7 +2665,7 @@ pub mod casbin_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = NewAdapterSvc(inner);
+                        let method = AddPoliciesSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -1269,18 +2676,18 @@ pub mod casbin_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Casbin/Enforce" => {
+                "/proto.Casbin/AddNamedPolicies" => {
                     #[allow(non_camel_case_types)]
-                    struct EnforceSvc<T: Casbin>(pub Arc<T>);
-                    impl<T: Casbin> tonic::server::UnaryService<super::EnforceRequest> for EnforceSvc<T> {
+                    struct AddNamedPoliciesSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PoliciesRequest> for AddNamedPoliciesSvc<T> {
                         type Response = super::BoolReply;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::EnforceRequest>,
+                            request: tonic::Request<super::PoliciesRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).enforce(request).await };
+                            let fut = async move { (*inner).add_named_policies(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -1289,7 +2696,7 @@ pub mod casbin_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = EnforceSvc(inner);
+                        let method = AddNamedPoliciesSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -1300,18 +2707,18 @@ pub mod casbin_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Casbin/LoadPolicy" => {
+                "/proto.Casbin/RemovePolicy" => {
                     #[allow(non_camel_case_types)]
-                    struct LoadPolicySvc<T: Casbin>(pub Arc<T>);
-                    impl<T: Casbin> tonic::server::UnaryService<super::EmptyRequest> for LoadPolicySvc<T> {
-                        type Response = super::EmptyReply;
+                    struct RemovePolicySvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PolicyRequest> for RemovePolicySvc<T> {
+                        type Response = super::BoolReply;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::EmptyRequest>,
+                            request: tonic::Request<super::PolicyRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).load_policy(request).await };
+                            let fut = async move { (*inner).remove_policy(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -1320,7 +2727,7 @@ pub mod casbin_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = LoadPolicySvc(inner);
+                        let method = RemovePolicySvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -1331,18 +2738,18 @@ pub mod casbin_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Casbin/SavePolicy" => {
+                "/proto.Casbin/RemoveNamedPolicy" => {
                     #[allow(non_camel_case_types)]
-                    struct SavePolicySvc<T: Casbin>(pub Arc<T>);
-                    impl<T: Casbin> tonic::server::UnaryService<super::EmptyRequest> for SavePolicySvc<T> {
-                        type Response = super::EmptyReply;
+                    struct RemoveNamedPolicySvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PolicyRequest> for RemoveNamedPolicySvc<T> {
+                        type Response = super::BoolReply;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::EmptyRequest>,
+                            request: tonic::Request<super::PolicyRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).save_policy(request).await };
+                            let fut = async move { (*inner).remove_named_policy(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -1351,7 +2758,7 @@ pub mod casbin_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = SavePolicySvc(inner);
+                        let method = RemoveNamedPolicySvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -1362,18 +2769,18 @@ pub mod casbin_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Casbin/AddPolicy" => {
+                "/proto.Casbin/RemovePolicies" => {
                     #[allow(non_camel_case_types)]
-                    struct AddPolicySvc<T: Casbin>(pub Arc<T>);
-                    impl<T: Casbin> tonic::server::UnaryService<super::PolicyRequest> for AddPolicySvc<T> {
+                    struct RemovePoliciesSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PoliciesRequest> for RemovePoliciesSvc<T> {
                         type Response = super::BoolReply;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::PolicyRequest>,
+                            request: tonic::Request<super::PoliciesRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).add_policy(request).await };
+                            let fut = async move { (*inner).remove_policies(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -1382,7 +2789,7 @@ pub mod casbin_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = AddPolicySvc(inner);
+                        let method = RemovePoliciesSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -1393,18 +2800,18 @@ pub mod casbin_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Casbin/AddNamedPolicy" => {
+                "/proto.Casbin/RemoveNamedPolicies" => {
                     #[allow(non_camel_case_types)]
-                    struct AddNamedPolicySvc<T: Casbin>(pub Arc<T>);
-                    impl<T: Casbin> tonic::server::UnaryService<super::PolicyRequest> for AddNamedPolicySvc<T> {
+                    struct RemoveNamedPoliciesSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PoliciesRequest> for RemoveNamedPoliciesSvc<T> {
                         type Response = super::BoolReply;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::PolicyRequest>,
+                            request: tonic::Request<super::PoliciesRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).add_named_policy(request).await };
+                            let fut = async move { (*inner).remove_named_policies(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -1413,7 +2820,7 @@ pub mod casbin_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = AddNamedPolicySvc(inner);
+                        let method = RemoveNamedPoliciesSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -1424,18 +2831,18 @@ pub mod casbin_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Casbin/RemovePolicy" => {
+                "/proto.Casbin/UpdatePolicy" => {
                     #[allow(non_camel_case_types)]
-                    struct RemovePolicySvc<T: Casbin>(pub Arc<T>);
-                    impl<T: Casbin> tonic::server::UnaryService<super::PolicyRequest> for RemovePolicySvc<T> {
+                    struct UpdatePolicySvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::UpdatePolicyRequest> for UpdatePolicySvc<T> {
                         type Response = super::BoolReply;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::PolicyRequest>,
+                            request: tonic::Request<super::UpdatePolicyRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).remove_policy(request).await };
+                            let fut = async move { (*inner).update_policy(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -1444,7 +2851,7 @@ pub mod casbin_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = RemovePolicySvc(inner);
+                        let method = UpdatePolicySvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -1455,18 +2862,20 @@ pub mod casbin_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Casbin/RemoveNamedPolicy" => {
+                "/proto.Casbin/UpdateNamedPolicy" => {
                     #[allow(non_camel_case_types)]
-                    struct RemoveNamedPolicySvc<T: Casbin>(pub Arc<T>);
-                    impl<T: Casbin> tonic::server::UnaryService<super::PolicyRequest> for RemoveNamedPolicySvc<T> {
+                    struct UpdateNamedPolicySvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::UpdatePolicyRequest>
+                        for UpdateNamedPolicySvc<T>
+                    {
                         type Response = super::BoolReply;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::PolicyRequest>,
+                            request: tonic::Request<super::UpdatePolicyRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).remove_named_policy(request).await };
+                            let fut = async move { (*inner).update_named_policy(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -1475,7 +2884,71 @@ pub mod casbin_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = RemoveNamedPolicySvc(inner);
+                        let method = UpdateNamedPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/UpdatePolicies" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdatePoliciesSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::UpdatePoliciesRequest> for UpdatePoliciesSvc<T> {
+                        type Response = super::BoolReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdatePoliciesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).update_policies(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdatePoliciesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/UpdateNamedPolicies" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateNamedPoliciesSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::UpdatePoliciesRequest>
+                        for UpdateNamedPoliciesSvc<T>
+                    {
+                        type Response = super::BoolReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdatePoliciesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).update_named_policies(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateNamedPoliciesSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -1799,7 +3272,141 @@ pub mod casbin_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = RemoveNamedGroupingPolicySvc(inner);
+                        let method = RemoveNamedGroupingPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/AddGroupingPolicies" => {
+                    #[allow(non_camel_case_types)]
+                    struct AddGroupingPoliciesSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PoliciesRequest> for AddGroupingPoliciesSvc<T> {
+                        type Response = super::BoolReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PoliciesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).add_grouping_policies(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AddGroupingPoliciesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/AddNamedGroupingPolicies" => {
+                    #[allow(non_camel_case_types)]
+                    struct AddNamedGroupingPoliciesSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PoliciesRequest>
+                        for AddNamedGroupingPoliciesSvc<T>
+                    {
+                        type Response = super::BoolReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PoliciesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut =
+                                async move { (*inner).add_named_grouping_policies(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AddNamedGroupingPoliciesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/RemoveGroupingPolicies" => {
+                    #[allow(non_camel_case_types)]
+                    struct RemoveGroupingPoliciesSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PoliciesRequest>
+                        for RemoveGroupingPoliciesSvc<T>
+                    {
+                        type Response = super::BoolReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PoliciesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut =
+                                async move { (*inner).remove_grouping_policies(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RemoveGroupingPoliciesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/RemoveNamedGroupingPolicies" => {
+                    #[allow(non_camel_case_types)]
+                    struct RemoveNamedGroupingPoliciesSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PoliciesRequest>
+                        for RemoveNamedGroupingPoliciesSvc<T>
+                    {
+                        type Response = super::BoolReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PoliciesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).remove_named_grouping_policies(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RemoveNamedGroupingPoliciesSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -2738,6 +4345,41 @@ pub mod casbin_server {
                     };
                     Box::pin(fut)
                 }
+                "/proto.Casbin/GetImplicitUsersForPermission" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetImplicitUsersForPermissionSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PermissionRequest>
+                        for GetImplicitUsersForPermissionSvc<T>
+                    {
+                        type Response = super::ArrayReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PermissionRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_implicit_users_for_permission(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetImplicitUsersForPermissionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/proto.Casbin/DeletePermission" => {
                     #[allow(non_camel_case_types)]
                     struct DeletePermissionSvc<T: Casbin>(pub Arc<T>);
@@ -2905,6 +4547,271 @@ pub mod casbin_server {
                     };
                     Box::pin(fut)
                 }
+                "/proto.Casbin/GetRolesForUserInDomain" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetRolesForUserInDomainSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::UserRoleRequest>
+                        for GetRolesForUserInDomainSvc<T>
+                    {
+                        type Response = super::ArrayReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UserRoleRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut =
+                                async move { (*inner).get_roles_for_user_in_domain(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetRolesForUserInDomainSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/GetUsersForRoleInDomain" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetUsersForRoleInDomainSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::UserRoleRequest>
+                        for GetUsersForRoleInDomainSvc<T>
+                    {
+                        type Response = super::ArrayReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UserRoleRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut =
+                                async move { (*inner).get_users_for_role_in_domain(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetUsersForRoleInDomainSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/GetPermissionsForUserInDomain" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetPermissionsForUserInDomainSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::PermissionRequest>
+                        for GetPermissionsForUserInDomainSvc<T>
+                    {
+                        type Response = super::Array2DReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PermissionRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_permissions_for_user_in_domain(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetPermissionsForUserInDomainSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/AddRoleForUserInDomain" => {
+                    #[allow(non_camel_case_types)]
+                    struct AddRoleForUserInDomainSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::UserRoleRequest>
+                        for AddRoleForUserInDomainSvc<T>
+                    {
+                        type Response = super::BoolReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UserRoleRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut =
+                                async move { (*inner).add_role_for_user_in_domain(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AddRoleForUserInDomainSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/DeleteRoleForUserInDomain" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteRoleForUserInDomainSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::UserRoleRequest>
+                        for DeleteRoleForUserInDomainSvc<T>
+                    {
+                        type Response = super::BoolReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UserRoleRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).delete_role_for_user_in_domain(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteRoleForUserInDomainSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/GetAllDomains" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAllDomainsSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::EmptyRequest> for GetAllDomainsSvc<T> {
+                        type Response = super::ArrayReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::EmptyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_all_domains(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetAllDomainsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/GetDomainsForUser" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetDomainsForUserSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::UserRoleRequest> for GetDomainsForUserSvc<T> {
+                        type Response = super::ArrayReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UserRoleRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_domains_for_user(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetDomainsForUserSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Casbin/DeleteDomains" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteDomainsSvc<T: Casbin>(pub Arc<T>);
+                    impl<T: Casbin> tonic::server::UnaryService<super::DomainsRequest> for DeleteDomainsSvc<T> {
+                        type Response = super::BoolReply;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DomainsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).delete_domains(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteDomainsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => Box::pin(async move {
                     Ok(http::Response::builder()
                         .status(200)
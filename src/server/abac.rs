@@ -1,53 +1,62 @@
-use std::collections::HashMap;
+use casbin::rhai::{serde::to_dynamic, Dynamic};
+use casbin::EnforceArgs;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 
-#[derive(Default)]
-pub struct AbacAttrList {
-    v0: String,
-    v1: String,
-    v2: String,
-    v3: String,
-    v4: String,
-    v5: String,
-    v6: String,
-    v7: String,
-    v8: String,
-    v9: String,
-    v10: String,
-    pub name_map: HashMap<String, String>,
+// A request value of the form `ABAC::{"Owner": "alice", "Age": 19}` carries an
+// attribute object instead of a plain string, so matchers like
+// `r.sub == r.obj.Owner` or `r.sub.Age > 18` can be evaluated.
+pub static ABAC_PREFIX: &str = "ABAC::";
+
+// AbacArgs holds the request values of an enforcement call, each of which is
+// either a plain string or a JSON attribute object.
+pub struct AbacArgs {
+    params: Vec<String>,
+    values: Vec<Value>,
+}
+
+// resolve_abac parses the request params, turning every param with the ABAC
+// prefix into an attribute object.
+pub fn resolve_abac(params: Vec<String>) -> Result<AbacArgs, String> {
+    let mut values = Vec::with_capacity(params.len());
+    for param in params.iter() {
+        match param.strip_prefix(ABAC_PREFIX) {
+            Some(attrs) => {
+                let value: Value = serde_json::from_str(attrs)
+                    .map_err(|err| format!("invalid ABAC attributes {}: {}", attrs, err))?;
+                if !value.is_object() {
+                    return Err(format!("ABAC attributes must be a JSON object: {}", attrs));
+                }
+                values.push(value);
+            }
+            None => values.push(Value::String(param.to_owned())),
+        }
+    }
+    Ok(AbacArgs { params, values })
 }
 
-pub fn to_upper_first_char(s: String) -> String {
-    format!("{}{}", &s[..1].to_uppercase(), &s[1..])
+impl AbacArgs {
+    // params are the request values as they were given, attribute objects still in JSON.
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
 }
-// TRY TO CONVERT INTO VECTOR OF STRING
-pub fn resolve_abac(s: String) -> Result<AbacAttrList, &'static str> {
-    // THIS MAP IS EMPTY
-    let json_map: HashMap<String, String> = HashMap::new();
-    let mut attr_list: AbacAttrList = AbacAttrList::default();
-    attr_list.name_map = HashMap::new();
-    // need to update json_map based on s
-    let mut i: usize = 0;
-    for (k, v) in json_map.iter() {
-        let key: String = to_upper_first_char(k.to_string());
-        let value: String = format!("{}{}", "v", &(i.to_string()));
-        // attr_list is empty
-        // attr_list.name_map[key] = "V" + strconv.Itoa(i);
 
-        match i {
-            0 => attr_list.v0 = value,
-            1 => attr_list.v1 = value,
-            2 => attr_list.v2 = value,
-            3 => attr_list.v3 = value,
-            4 => attr_list.v4 = value,
-            5 => attr_list.v5 = value,
-            6 => attr_list.v6 = value,
-            7 => attr_list.v7 = value,
-            8 => attr_list.v8 = value,
-            9 => attr_list.v9 = value,
-            10 => attr_list.v10 = value,
-            _ => (),
+impl EnforceArgs for AbacArgs {
+    fn try_into_vec(self) -> casbin::Result<Vec<Dynamic>> {
+        let mut rvals = Vec::with_capacity(self.values.len());
+        for value in self.values.into_iter() {
+            rvals.push(to_dynamic(value)?);
         }
-        i = i + 1;
+        Ok(rvals)
+    }
+
+    // cache_key only keys the cache of casbin, which the server never decides through; its
+    // own decision cache compares the params themselves.
+    fn cache_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.params.hash(&mut hasher);
+        hasher.finish()
     }
-    Ok(attr_list)
 }
@@ -1,75 +1,812 @@
+use crate::adapter::Drivers;
 use crate::casbin_proto::NewAdapterRequest;
-use casbin::{Adapter, FileAdapter};
+use crate::server::auth::Scope;
+use crate::server::error::casbin_status;
+use crate::server::trace;
+use casbin::{Adapter, Filter, Model};
+use futures::lock::Mutex;
 use regex::Regex;
-use serde::{Deserialize, Serialize};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
 use tokio::fs::File;
-use tonic::Request;
+use tokio::io::AsyncReadExt;
+use tonic::Status;
 
-pub static ERR_DRIVER_NAME: &str =
-    "currently supported DriverName: file | mysql | postgres | mssql";
+// SharedAdapter lets an adapter registered with NewAdapter back any number of enforcers.
+// Every call locks the registered adapter for its duration, and is traced as a round trip to
+// the store.
+#[derive(Clone)]
+pub struct SharedAdapter {
+    pub adapter: Arc<Mutex<Box<dyn Adapter>>>,
+    // filtered is whether the policy loaded through this adapter is a filtered one. It is
+    // read when the adapter is made and after each filtered load, so is_filtered never
+    // waits for the lock.
+    filtered: bool,
+}
+
+impl SharedAdapter {
+    pub async fn new(adapter: Arc<Mutex<Box<dyn Adapter>>>) -> Self {
+        let filtered = adapter.lock().await.is_filtered();
+        Self { adapter, filtered }
+    }
+}
+
+#[tonic::async_trait]
+impl Adapter for SharedAdapter {
+    async fn load_policy(&self, m: &mut dyn Model) -> casbin::Result<()> {
+        trace::adapter_call("adapter.load_policy", async {
+            self.adapter.lock().await.load_policy(m).await
+        })
+        .await
+    }
+
+    async fn load_filtered_policy<'a>(
+        &mut self,
+        m: &mut dyn Model,
+        f: Filter<'a>,
+    ) -> casbin::Result<()> {
+        trace::adapter_call("adapter.load_filtered_policy", async {
+            let mut a = self.adapter.lock().await;
+            let res = a.load_filtered_policy(m, f).await;
+            self.filtered = a.is_filtered();
+            res
+        })
+        .await
+    }
 
+    async fn save_policy(&mut self, m: &mut dyn Model) -> casbin::Result<()> {
+        trace::adapter_call("adapter.save_policy", async {
+            self.adapter.lock().await.save_policy(m).await
+        })
+        .await
+    }
+
+    async fn clear_policy(&mut self) -> casbin::Result<()> {
+        trace::adapter_call("adapter.clear_policy", async {
+            self.adapter.lock().await.clear_policy().await
+        })
+        .await
+    }
+
+    fn is_filtered(&self) -> bool {
+        self.filtered
+    }
+
+    async fn add_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        trace::adapter_call("adapter.add_policy", async {
+            self.adapter.lock().await.add_policy(sec, ptype, rule).await
+        })
+        .await
+    }
+
+    async fn add_policies(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        trace::adapter_call("adapter.add_policies", async {
+            self.adapter
+                .lock()
+                .await
+                .add_policies(sec, ptype, rules)
+                .await
+        })
+        .await
+    }
+
+    async fn remove_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        trace::adapter_call("adapter.remove_policy", async {
+            self.adapter
+                .lock()
+                .await
+                .remove_policy(sec, ptype, rule)
+                .await
+        })
+        .await
+    }
+
+    async fn remove_policies(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        trace::adapter_call("adapter.remove_policies", async {
+            self.adapter
+                .lock()
+                .await
+                .remove_policies(sec, ptype, rules)
+                .await
+        })
+        .await
+    }
+
+    async fn remove_filtered_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        field_index: usize,
+        field_values: Vec<String>,
+    ) -> casbin::Result<bool> {
+        trace::adapter_call("adapter.remove_filtered_policy", async {
+            self.adapter
+                .lock()
+                .await
+                .remove_filtered_policy(sec, ptype, field_index, field_values)
+                .await
+        })
+        .await
+    }
+}
+
+// new_adapter opens the adapter a NewAdapter request describes, or the one of the local config
+// when the request names no driver or connection string.
 pub async fn new_adapter(
-    request: &mut Request<NewAdapterRequest>,
-) -> Result<Box<dyn Adapter>, &'static str> {
-    let a: Box<dyn Adapter>;
-    check_local_config(request);
-    let support_driver_names = vec![
-        String::from("file"),
-        String::from("mysql"),
-        String::from("postgres"),
-        String::from("mssql"),
-    ];
-    let st = String::from("file");
-    let connect_string = &request.get_ref().connect_string;
-    match &request.get_ref().driver_name {
-        st => a = Box::new(FileAdapter::new(connect_string.to_string())),
-        _ => {
-            let mut support: bool = false;
-            for driver_name in support_driver_names.iter() {
-                if driver_name == &request.get_ref().driver_name {
-                    support = true;
-                    break;
-                }
+    drivers: &Drivers,
+    request: NewAdapterRequest,
+) -> Result<Box<dyn Adapter>, Status> {
+    let request = check_local_config(request).await?;
+    if !drivers.contains(&request.driver_name) {
+        return Err(Status::invalid_argument(format!(
+            "unsupported driver `{}`, supported drivers are: {}",
+            request.driver_name,
+            drivers.names().join(", ")
+        )));
+    }
+    drivers
+        .open(&request.driver_name, &request.connect_string)
+        .await
+        .map_err(casbin_status)
+}
+
+// CONFIG_FILE is the file the local config is loaded from, unless the server is started with
+// another.
+pub static CONFIG_FILE: &str = "config/connection_config.json";
+
+static LOCAL_CONFIG: OnceLock<(Option<String>, ConfigOverrides)> = OnceLock::new();
+
+// ConfigOverrides replace settings of the local config, as given on the command line.
+#[derive(Clone, Debug, Default)]
+pub struct ConfigOverrides {
+    pub driver: Option<String>,
+    pub connection: Option<String>,
+    pub enforcer: Option<String>,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+}
+
+impl ConfigOverrides {
+    // apply overrides the settings of cfg. A connection given without a driver, to a config
+    // without one, is the policy file of the file adapter.
+    pub fn apply(&self, cfg: &mut Config) {
+        if let Some(connection) = &self.connection {
+            cfg.connection = connection.clone();
+            if cfg.driver.is_empty() {
+                cfg.driver = "file".to_owned();
             }
-            if !support {
-                return Err(ERR_DRIVER_NAME);
+        }
+        let overrides = [
+            (&self.driver, &mut cfg.driver),
+            (&self.enforcer, &mut cfg.enforcer),
+            (&self.tls_cert, &mut cfg.tls_cert),
+            (&self.tls_key, &mut cfg.tls_key),
+        ];
+        for (value, setting) in overrides {
+            if let Some(value) = value {
+                *setting = value.clone();
             }
-            a = Box::new(FileAdapter::new(connect_string.to_string()));
         }
     }
-    Ok(a)
 }
 
-pub async fn check_local_config(request: &mut Request<NewAdapterRequest>) {
-    let cfg: Config = load_configuration("config/connection_config.json")
-        .await
-        .expect("connection_config.json file not found");
-    let x = request.get_mut();
-    if x.connect_string.is_empty() || x.driver_name == "" {
-        x.driver_name = cfg.driver;
-        x.connect_string = cfg.connection;
-        x.db_specified = cfg.db_specified;
+// ENV_PREFIX prefixes the environment variables overriding settings of the local config.
+pub static ENV_PREFIX: &str = "CASBIN_GRPC_";
+
+// apply_env overrides the settings of cfg with the environment variables named after them,
+// such as CASBIN_GRPC_DECISION_CACHE_TTL_MS for decision_cache_ttl_ms, or with the content of
+// the file named by the variable suffixed _FILE, such as CASBIN_GRPC_JWT_SECRET_FILE, for
+// secrets mounted as files. Text settings take the value as it is, the others parse it as
+// JSON, such as `true`, `5000` or `{"key": "admin"}`.
+pub async fn apply_env(cfg: Config) -> Result<Config, std::io::Error> {
+    let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+    let mut settings = match serde_json::to_value(&cfg)? {
+        serde_json::Value::Object(settings) => settings,
+        _ => return Ok(cfg),
+    };
+    let mut overridden = false;
+    for (key, setting) in settings.iter_mut() {
+        let name = format!("{}{}", ENV_PREFIX, key.to_uppercase());
+        let file_name = format!("{}_FILE", name);
+        let value = match (std::env::var(&name), std::env::var(&file_name)) {
+            (Ok(_), Ok(_)) => {
+                return Err(invalid(format!("both {} and {} are set", name, file_name)));
+            }
+            (Ok(value), _) => value,
+            (_, Ok(file)) => tokio::fs::read_to_string(&file)
+                .await
+                .map_err(|err| invalid(format!("{} {}: {}", file_name, file, err)))?
+                .trim_end_matches(['\r', '\n'])
+                .to_owned(),
+            _ => continue,
+        };
+        *setting = match setting {
+            serde_json::Value::String(_) => serde_json::Value::String(value),
+            _ => {
+                serde_json::from_str(&value).map_err(|err| invalid(format!("{}: {}", name, err)))?
+            }
+        };
+        overridden = true;
+    }
+    if !overridden {
+        return Ok(cfg);
+    }
+    serde_json::from_value(serde_json::Value::Object(settings))
+        .map_err(|err| invalid(format!("the {}* variables: {}", ENV_PREFIX, err)))
+}
+
+// set_local_config loads the local config from file instead of CONFIG_FILE, if given, and
+// applies overrides to it. Only the first call has any effect.
+pub fn set_local_config(file: Option<String>, overrides: ConfigOverrides) {
+    let _ = LOCAL_CONFIG.set((file, overrides));
+}
+
+// load_local_configuration loads the local config, from the file and with the overrides it was
+// set to, over those of the environment. Without a file set, a missing CONFIG_FILE stands for
+// an empty config.
+pub async fn load_local_configuration() -> Result<Config, std::io::Error> {
+    let (file, overrides) = LOCAL_CONFIG.get_or_init(Default::default);
+    let cfg = match (
+        file,
+        load_configuration(file.as_deref().unwrap_or(CONFIG_FILE)).await,
+    ) {
+        (None, Err(err)) if err.kind() == std::io::ErrorKind::NotFound => Config::default(),
+        (_, cfg) => cfg?,
+    };
+    let mut cfg = apply_env(cfg).await?;
+    overrides.apply(&mut cfg);
+    Ok(cfg)
+}
+
+pub async fn check_local_config(
+    mut request: NewAdapterRequest,
+) -> Result<NewAdapterRequest, Status> {
+    if request.connect_string.is_empty() || request.driver_name.is_empty() {
+        let cfg = load_local_configuration()
+            .await
+            .map_err(|err| Status::failed_precondition(err.to_string()))?;
+        request.driver_name = cfg.driver;
+        request.connect_string = cfg.connection;
+        request.db_specified = cfg.db_specified;
     }
+    Ok(request)
 }
 
 pub async fn load_configuration(file: &str) -> Result<Config, std::io::Error> {
     //Loads a default config from adapter_config in case a custom adapter isn't provided by the client.
     //DriverName, ConnectionString, and dbSpecified can be configured in the file. Defaults to 'file' mode.
 
-    let config_file = File::open(file).await.expect("file not found");
-    let decoder: Config = serde_json::from_str(file).expect("JSON was not well-formatted");
-    let config: Config = Config::default();
-    let re = Regex::new(r"\$\b((\w*))\b");
+    let mut data = String::new();
+    File::open(file).await?.read_to_string(&mut data).await?;
+    let mut config = parse_configuration(file, &data)?;
+
+    // Expand `$VAR` references in the connection string, the model source and its headers
+    // from the environment.
+    config.connection = expand_env(&config.connection);
+    config.enforcer = expand_env(&config.enforcer);
+    for value in config.model_headers.values_mut() {
+        *value = expand_env(value);
+    }
 
-    // config.connection
     Ok(config)
 }
 
+// parse_configuration parses the config of file, in TOML or YAML by the extension of its name
+// and in JSON otherwise. Unknown settings are rejected, so a misspelled one fails the load
+// instead of being left at its default.
+fn parse_configuration(file: &str, data: &str) -> Result<Config, std::io::Error> {
+    let extension = std::path::Path::new(file)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default();
+    let config = match extension {
+        "toml" => toml::from_str(data).map_err(|err| err.to_string()),
+        "yaml" | "yml" => serde_yaml::from_str(data).map_err(|err| err.to_string()),
+        _ => serde_json::from_str(data).map_err(|err| err.to_string()),
+    };
+    config.map_err(|err| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("invalid config {}: {}", file, err),
+        )
+    })
+}
+
+// node_ids reads raft_nodes with the ids as numbers or as strings, as the keys of a TOML
+// table always are.
+fn node_ids<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<u64, String>, D::Error> {
+    #[derive(Deserialize, PartialEq, Eq, Hash)]
+    #[serde(untagged)]
+    enum NodeId {
+        Number(u64),
+        Text(String),
+    }
+    HashMap::<NodeId, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(id, addr)| match id {
+            NodeId::Number(id) => Ok((id, addr)),
+            NodeId::Text(id) => id
+                .parse()
+                .map(|id| (id, addr))
+                .map_err(|_| D::Error::custom(format!("raft node id {} is not a number", id))),
+        })
+        .collect()
+}
+
+// expand_env replaces `$VAR` references with the value of the environment variable, or with
+// nothing when it is unset.
+pub fn expand_env(s: &str) -> String {
+    let re = Regex::new(r"\$\b((\w*))\b").unwrap();
+    re.replace_all(s, |caps: &regex::Captures| {
+        std::env::var(&caps[1]).unwrap_or_default()
+    })
+    .into_owned()
+}
+
+// Config is the local config of the server, loaded from a JSON, TOML or YAML file, see
+// config/example.toml for every setting. The settings of reload::RELOADABLE take effect when
+// the config is reloaded, the others once the server restarts.
 #[derive(Default, Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
+    // Adapter driver, such as `file` or `postgres`, and connection string of the store of the
+    // enforcer served as handle 0, and its model, a file or an http(s) URL.
     pub driver: String,
     pub connection: String,
     pub enforcer: String,
+    #[serde(default)]
     pub db_specified: bool,
+    // Address to serve the Casbin service on, `[::1]:50051` when empty. The --address and
+    // --port of the command line override it.
+    #[serde(default)]
+    pub listen_addr: String,
+    // Seconds after which an unused enforcer created through NewEnforcer is dropped, 0 keeps
+    // enforcers until they are deleted.
+    #[serde(default)]
+    pub idle_ttl_secs: u64,
+    // Seconds between health checks loading the policy of the store above, reported through
+    // the gRPC health service, 0 disables them.
+    #[serde(default)]
+    pub health_check_secs: u64,
+    // Model, a file or an http(s) URL, of the enforcers of the tenants, which the calls are
+    // served by. A call is for the tenant of its credentials: the jwt_tenant_claim of its JWT,
+    // or the tenant api_key_tenants binds its API key to. Only the calls of admin API keys
+    // bound to no tenant may name a tenant known in advance or loaded with the x-tenant-id
+    // metadata key, or reach the enforcers of the server by naming none. The enforcer of a
+    // tenant is created on its first call, holding only the rules of the store above whose
+    // tenant_policy_field, or tenant_grouping_field for the role links, is the tenant, and
+    // only taking rules for it. Empty serves no tenants.
+    #[serde(default)]
+    pub tenant_model: String,
+    // Field of the rules, and of the role links, naming their tenant, 1 and 2 when 0, as in
+    // `p, alice, tenant1, data1, read` and `g, alice, admin, tenant1`.
+    #[serde(default)]
+    pub tenant_policy_field: usize,
+    #[serde(default)]
+    pub tenant_grouping_field: usize,
+    // Tenants known in advance, along with those of api_key_tenants, which are never dropped.
+    #[serde(default)]
+    pub tenants: Vec<String>,
+    // Tenants kept in memory, 1000 when 0. Past that, the least recently called tenant that
+    // is not known in advance and holds nothing but its enforcer as loaded is dropped, and
+    // its enforcer loaded again on its next call. With none such, a call for another tenant
+    // fails with RESOURCE_EXHAUSTED.
+    #[serde(default)]
+    pub max_tenants: usize,
+    // Limits of every tenant: the rules each of its enforcers may be given, the enforcers it
+    // may have, counting the one of its first call, the calls per second it may make, in
+    // bursts of up to tenant_rate_limit_burst calls or a second of calls when 0, and the
+    // decisions the caches of its enforcers may hold together, a count of decisions and not
+    // of bytes, as each takes about the size of its request and the rules it matched. The
+    // calls past them fail with RESOURCE_EXHAUSTED, and 0 lifts them.
+    #[serde(default)]
+    pub tenant_max_rules: usize,
+    #[serde(default)]
+    pub tenant_max_enforcers: usize,
+    #[serde(default)]
+    pub tenant_rate_limit_per_sec: f64,
+    #[serde(default)]
+    pub tenant_rate_limit_burst: f64,
+    #[serde(default)]
+    pub tenant_max_cached_decisions: usize,
+    // Seconds the server keeps serving once asked to stop, while reported as not serving, so
+    // load balancers stop sending it calls first.
+    #[serde(default)]
+    pub shutdown_drain_secs: u64,
+    // Seconds the calls still in flight once the server stops accepting them have to finish
+    // before they are dropped, 0 waits for them however long they take.
+    #[serde(default)]
+    pub shutdown_timeout_secs: u64,
+    // Whether to serve the gRPC reflection service, so tools such as grpcurl discover the
+    // Casbin service without its proto files.
+    #[serde(default)]
+    pub reflection: bool,
+    // Path of a unix socket to serve on besides the TCP address, for clients on the same host
+    // such as sidecars.
+    #[serde(default)]
+    pub unix_socket: String,
+    // Whether to serve on the unix socket alone, without the TCP address.
+    #[serde(default)]
+    pub unix_socket_only: bool,
+    // Encoding of the replies to clients accepting it, and of the requests taken besides
+    // uncompressed ones: gzip. Empty disables compression. Raft nodes only take uncompressed
+    // requests.
+    #[serde(default)]
+    pub compression: String,
+    // Largest message in bytes a call may send the server, and the server reply with, 0 for no
+    // limit.
+    #[serde(default)]
+    pub max_recv_message_bytes: usize,
+    #[serde(default)]
+    pub max_send_message_bytes: usize,
+    // Milliseconds a call whose client sets no deadline may run for, 0 for as long as it takes,
+    // overridden per method name, such as `AddPolicies`, by method_deadline_ms.
+    #[serde(default)]
+    pub deadline_ms: u64,
+    #[serde(default)]
+    pub method_deadline_ms: HashMap<String, u64>,
+    // Calls per second each client may make, told apart by their API key, JWT subject, client
+    // certificate or else IP address, in bursts of up to rate_limit_burst calls, a second of
+    // calls when 0. A rate of 0 does not limit them.
+    #[serde(default)]
+    pub rate_limit_per_sec: f64,
+    #[serde(default)]
+    pub rate_limit_burst: f64,
+    // Calls per second each client may make of a method, such as `BatchEnforce`, in place of
+    // rate_limit_per_sec, in bursts of a second of calls.
+    #[serde(default)]
+    pub method_rate_limits_per_sec: HashMap<String, f64>,
+    // Whether to serve gRPC-Web besides gRPC, so browsers call the server without a proxy,
+    // from the origins of grpc_web_origins, such as `https://admin.example.com`, or any origin
+    // when empty. Needs a server built with the web feature.
+    #[serde(default)]
+    pub grpc_web: bool,
+    #[serde(default)]
+    pub grpc_web_origins: Vec<String>,
+    // Address to serve the REST gateway on, such as `0.0.0.0:8080`, which transcodes JSON calls
+    // such as `POST /v1/enforce` to calls of the Casbin service, over TLS when the server is.
+    // Empty disables it. Needs a server built with the rest feature.
+    #[serde(default)]
+    pub rest_addr: String,
+    // Address, such as `0.0.0.0:9090`, on which to serve the Prometheus metrics of the server
+    // as `GET /metrics`. Empty disables them. Needs a server built with the metrics feature.
+    #[serde(default)]
+    pub metrics_addr: String,
+    // Level of the tracing events printed, the logs of the enforcers whose logs are enabled
+    // among them: `off`, `error`, `warn`, `info`, `debug` or `trace`, `info` when empty.
+    #[serde(default)]
+    pub log_level: String,
+    // OTLP collector, such as `http://collector:4317`, to which to export the spans of the calls,
+    // of their decisions and of the round trips to the policy stores. Empty disables tracing.
+    // Needs a server built with the otel feature.
+    #[serde(default)]
+    pub otlp_endpoint: String,
+    // Where to write a JSON entry for every decision, with who asked for it and the rules that
+    // matched: `stdout`, or the path of a file to append them to. Empty disables the audit log.
+    #[serde(default)]
+    pub audit_log: String,
+    // Milliseconds a decision may take before it is logged with its request and the number of
+    // rules that matched, to find the matchers that are slow on some requests. 0 logs none.
+    #[serde(default)]
+    pub slow_decision_ms: u64,
+    // Decisions cached for each enforcer until its policy or roles change, none when 0. The
+    // least recently used are dropped to make room.
+    #[serde(default)]
+    pub decision_cache_max_entries: usize,
+    // Milliseconds a cached decision is used for at most. 0 keeps it until the policy changes.
+    #[serde(default)]
+    pub decision_cache_ttl_ms: u64,
+    // Whether to decide every request anew, caching no decisions.
+    #[serde(default)]
+    pub disable_decision_cache: bool,
+    // Workers deciding the requests of a BatchEnforce side by side, 0 for one per core. Batches
+    // of fewer than 64 requests per worker are decided by fewer workers.
+    #[serde(default)]
+    pub batch_parallelism: usize,
+    // Redis, etcd or NATS server, such as `redis://host:6379`, `etcd://host:2379` or
+    // `nats://host:4222`, through which replicas sharing the store above tell each other to
+    // reload its policy after changing it. Empty disables the watcher.
+    #[serde(default)]
+    pub watcher: String,
+    // Redis channel or etcd key the watcher notifies changes on, `/casbin` when empty, or the
+    // NATS subject prefix, `casbin.policy` when empty.
+    #[serde(default)]
+    pub watcher_channel: String,
+    // Headers sent when `enforcer` names a remote model, e.g. an Authorization token.
+    #[serde(default)]
+    pub model_headers: HashMap<String, String>,
+    // Directory holding the last fetched copy of each remote model, defaults to a directory
+    // under the system temp dir.
+    #[serde(default)]
+    pub model_cache_dir: String,
+    // Directory of the model files clients may name by path in NewEnforcer, relative to it or
+    // not. Empty lets them name none.
+    #[serde(default)]
+    pub model_root: String,
+    // Hosts of the https:// and s3:// model URLs clients may name in NewEnforcer, for s3 the
+    // host of the bucket endpoint, such as `models.s3.amazonaws.com`. Empty lets them name
+    // none.
+    #[serde(default)]
+    pub model_hosts: Vec<String>,
+    // Id of this node in a dispatcher cluster, which replicates the writes to the enforcer
+    // above through raft so every node applies them in the same order. 0 disables it.
+    #[serde(default)]
+    pub raft_node_id: u64,
+    // Nodes of the dispatcher cluster, this one included, by id with the address of their
+    // Casbin service, such as `10.0.0.1:50051`.
+    #[serde(default, deserialize_with = "node_ids")]
+    pub raft_nodes: HashMap<u64, String>,
+    // Directory keeping the raft log of this node across restarts. Empty keeps it in memory,
+    // so a restarted node catches up from the others.
+    #[serde(default)]
+    pub raft_dir: String,
+    // Certificate chain and private key to serve over TLS, each the path of a PEM file or PEM
+    // text. Files are reloaded when they change. Empty serves plaintext.
+    #[serde(default)]
+    pub tls_cert: String,
+    #[serde(default)]
+    pub tls_key: String,
+    // CA certificates, a PEM file or PEM text, that client certificates must be signed by.
+    // Empty lets clients connect without one.
+    #[serde(default)]
+    pub tls_client_ca: String,
+    // API keys clients must send, as `authorization: Bearer <key>` or `x-api-key: <key>`, each
+    // with its scope: `read` for Enforce and the RPCs reading the policy, `admin` for every
+    // RPC. Without any keys, here or in api_keys_file, any client may call every RPC.
+    #[serde(default)]
+    pub api_keys: HashMap<String, Scope>,
+    // JSON file of more API keys, shaped like api_keys.
+    #[serde(default)]
+    pub api_keys_file: String,
+    // Tenants of API keys, whose calls then go to that tenant only, see tenant_model.
+    #[serde(default)]
+    pub api_key_tenants: HashMap<String, String>,
+    // Shared secret of the HS256 JWTs clients may authenticate with, sent as
+    // `authorization: Bearer <token>`.
+    #[serde(default)]
+    pub jwt_secret: String,
+    // JWKS URL of the keys the JWTs are signed with, instead of a shared secret.
+    #[serde(default)]
+    pub jwt_jwks_url: String,
+    // The `iss` and `aud` the JWTs must carry, unchecked when empty.
+    #[serde(default)]
+    pub jwt_issuer: String,
+    #[serde(default)]
+    pub jwt_audience: String,
+    // Claim naming the subject of a JWT, `sub` when empty.
+    #[serde(default)]
+    pub jwt_subject_claim: String,
+    // Claim naming the tenant of a JWT, whose calls then go to that tenant only, see
+    // tenant_model. The calls of JWTs without it are refused.
+    #[serde(default)]
+    pub jwt_tenant_claim: String,
+    // Model and policy file of the meta enforcer authorizing the RPCs of JWT subjects, asked
+    // `(subject, service, method)`, see examples/meta_model.conf. Empty lets every valid
+    // token call every RPC.
+    #[serde(default)]
+    pub meta_model: String,
+    #[serde(default)]
+    pub meta_policy: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SharedAdapter;
+    use super::{apply_env, load_configuration, parse_configuration, Config, ConfigOverrides};
+    use crate::server::auth::Scope;
+    use casbin::{Adapter, DefaultModel, FileAdapter, Filter};
+    use futures::lock::Mutex;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_shared_adapter_is_filtered() {
+        let a: Box<dyn Adapter> = Box::new(FileAdapter::new("examples/rbac_policy.csv"));
+        let mut shared = SharedAdapter::new(Arc::new(Mutex::new(a))).await;
+        assert!(!shared.is_filtered());
+
+        let mut m = DefaultModel::from_file("examples/rbac_model.conf")
+            .await
+            .unwrap();
+        let f = Filter {
+            p: vec!["alice"],
+            g: vec![],
+        };
+        shared.load_filtered_policy(&mut m, f).await.unwrap();
+
+        // Another enforcer holding the adapter must not hide that the policy is filtered.
+        let other = shared.clone();
+        let _guard = other.adapter.lock().await;
+        assert!(shared.is_filtered());
+    }
+
+    #[test]
+    fn test_config_formats() {
+        let json = r#"{"driver": "file", "connection": "policy.csv", "enforcer": "model.conf",
+            "deadline_ms": 500, "api_keys": {"key": "admin"}, "raft_nodes": {"1": "a:50051"}}"#;
+        let toml = r#"
+            driver = "file"
+            connection = "policy.csv"
+            enforcer = "model.conf"
+            deadline_ms = 500
+            [api_keys]
+            key = "admin"
+            [raft_nodes]
+            1 = "a:50051"
+        "#;
+        let yaml = "driver: file\nconnection: policy.csv\nenforcer: model.conf\n\
+                    deadline_ms: 500\napi_keys:\n  key: admin\nraft_nodes:\n  1: a:50051\n";
+        for (file, data) in [
+            ("config.json", json),
+            ("config.toml", toml),
+            ("config.yaml", yaml),
+            ("config.yml", yaml),
+        ] {
+            let cfg = parse_configuration(file, data).unwrap();
+            assert_eq!(cfg.connection, "policy.csv", "{}", file);
+            assert_eq!(cfg.deadline_ms, 500, "{}", file);
+            assert_eq!(cfg.api_keys["key"], Scope::Admin, "{}", file);
+            assert_eq!(cfg.raft_nodes[&1], "a:50051", "{}", file);
+            // Settings left out take their defaults.
+            assert_eq!(cfg.batch_parallelism, 0, "{}", file);
+        }
+    }
+
+    #[test]
+    fn test_config_rejects_unknown_settings() {
+        let err = parse_configuration(
+            "config.toml",
+            "driver = \"file\"\nconnection = \"\"\nenforcer = \"\"\ndeadline = 5\n",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("invalid config config.toml"),
+            "{}",
+            err
+        );
+        assert!(err.to_string().contains("deadline"), "{}", err);
+
+        let err = parse_configuration("config.toml", "[raft_nodes]\none = \"a\"\n").unwrap_err();
+        assert!(err.to_string().contains("raft node id one"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_load_configuration() {
+        // The example documents every setting, and loads as it is.
+        let cfg = load_configuration("config/example.toml").await.unwrap();
+        assert_eq!(cfg.driver, "file");
+        load_configuration("config/connection_config.json")
+            .await
+            .unwrap();
+
+        let file = std::env::temp_dir().join(format!("config-{}.toml", std::process::id()));
+        std::fs::write(
+            &file,
+            "driver = \"postgres\"\nconnection = \"postgres://$CONFIG_TEST_USER@db\"\nenforcer = \"\"\n",
+        )
+        .unwrap();
+        std::env::set_var("CONFIG_TEST_USER", "casbin");
+        let cfg = load_configuration(file.to_str().unwrap()).await.unwrap();
+        std::env::remove_var("CONFIG_TEST_USER");
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(cfg.connection, "postgres://casbin@db");
+
+        let err = load_configuration("config/missing.toml").await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    // The overrides are checked in a single test, as the environment is shared by the tests.
+    #[tokio::test]
+    async fn test_env_overrides() {
+        let secret = std::env::temp_dir().join(format!("jwt-secret-{}", std::process::id()));
+        std::fs::write(&secret, "s3cret\n").unwrap();
+        let set = [
+            ("CASBIN_GRPC_CONNECTION", "postgres://db"),
+            ("CASBIN_GRPC_DECISION_CACHE_TTL_MS", "5000"),
+            ("CASBIN_GRPC_REFLECTION", "true"),
+            ("CASBIN_GRPC_API_KEYS", r#"{"key": "read"}"#),
+            ("CASBIN_GRPC_JWT_SECRET_FILE", secret.to_str().unwrap()),
+        ];
+        for (name, value) in set {
+            std::env::set_var(name, value);
+        }
+        let cfg = Config {
+            driver: "postgres".to_owned(),
+            connection: "postgres://localhost".to_owned(),
+            ..Default::default()
+        };
+        let res = apply_env(cfg).await;
+
+        // Both a setting and its _FILE are refused, and values not parsing as JSON.
+        std::env::set_var("CASBIN_GRPC_JWT_SECRET", "other");
+        let both = apply_env(Config::default()).await.err();
+        std::env::remove_var("CASBIN_GRPC_JWT_SECRET");
+        std::env::set_var("CASBIN_GRPC_DECISION_CACHE_TTL_MS", "soon");
+        let invalid = apply_env(Config::default()).await.err();
+        for (name, _) in set {
+            std::env::remove_var(name);
+        }
+        std::fs::remove_file(&secret).unwrap();
+
+        let cfg = res.unwrap();
+        assert_eq!(cfg.driver, "postgres");
+        assert_eq!(cfg.connection, "postgres://db");
+        assert_eq!(cfg.decision_cache_ttl_ms, 5000);
+        assert!(cfg.reflection);
+        assert_eq!(cfg.api_keys["key"], Scope::Read);
+        assert_eq!(cfg.jwt_secret, "s3cret");
+        let both = both.unwrap().to_string();
+        assert!(both.contains("both CASBIN_GRPC_JWT_SECRET and"), "{}", both);
+        let invalid = invalid.unwrap().to_string();
+        assert!(
+            invalid.contains("CASBIN_GRPC_DECISION_CACHE_TTL_MS"),
+            "{}",
+            invalid
+        );
+    }
+
+    #[test]
+    fn test_config_overrides() {
+        // A connection alone is a policy file.
+        let mut cfg = Config::default();
+        let overrides = ConfigOverrides {
+            connection: Some("examples/rbac_policy.csv".to_owned()),
+            ..ConfigOverrides::default()
+        };
+        overrides.apply(&mut cfg);
+        assert_eq!(cfg.driver, "file");
+        assert_eq!(cfg.connection, "examples/rbac_policy.csv");
+
+        // The driver of the config is kept, unless overridden too.
+        let mut cfg = Config {
+            driver: "postgres".to_owned(),
+            enforcer: "model.conf".to_owned(),
+            tls_cert: "cert.pem".to_owned(),
+            ..Config::default()
+        };
+        let overrides = ConfigOverrides {
+            connection: Some("postgres://db".to_owned()),
+            tls_key: Some("key.pem".to_owned()),
+            ..ConfigOverrides::default()
+        };
+        overrides.apply(&mut cfg);
+        assert_eq!(cfg.driver, "postgres");
+        assert_eq!(cfg.connection, "postgres://db");
+        assert_eq!(cfg.enforcer, "model.conf");
+        assert_eq!(
+            (cfg.tls_cert.as_str(), cfg.tls_key.as_str()),
+            ("cert.pem", "key.pem")
+        );
+
+        let overrides = ConfigOverrides {
+            driver: Some("mysql".to_owned()),
+            enforcer: Some("other.conf".to_owned()),
+            ..ConfigOverrides::default()
+        };
+        overrides.apply(&mut cfg);
+        assert_eq!(cfg.driver, "mysql");
+        assert_eq!(cfg.enforcer, "other.conf");
+    }
 }
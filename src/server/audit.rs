@@ -0,0 +1,166 @@
+use crate::server::auth::{AuthKey, AuthSubject};
+use crate::server::registry::model_hash;
+use crate::server::tenants::Tenant;
+use crate::server::tls::TlsConnectInfo;
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::fs::OpenOptions;
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio::sync::{mpsc, oneshot};
+use tonic::transport::server::TcpConnectInfo;
+
+// AUDIT_BUFFER bounds the entries waiting to be written. Decisions only wait for the sink once
+// it falls this far behind, entries are never dropped.
+static AUDIT_BUFFER: usize = 8192;
+
+// Caller is who made a call, as far as the server can tell: the JWT subject or a fingerprint
+// of the API key it authenticated with, the name of its client certificate, its address, and
+// the tenant it called for. Callers on the unix socket have no address.
+#[derive(Default, Serialize)]
+pub struct Caller {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cert: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub addr: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+}
+
+impl Caller {
+    // of identifies the caller of a call from what the server learned of it on the way in.
+    pub fn of<T>(request: &tonic::Request<T>) -> Self {
+        let extensions = request.extensions();
+        let mut caller = Caller {
+            subject: extensions
+                .get::<AuthSubject>()
+                .map(|AuthSubject(subject)| subject.clone()),
+            // The key itself is a secret.
+            api_key: extensions
+                .get::<AuthKey>()
+                .map(|AuthKey(key)| model_hash(key)),
+            tenant: extensions
+                .get::<Tenant>()
+                .map(|Tenant(tenant)| tenant.clone()),
+            ..Default::default()
+        };
+        if let Some(info) = extensions.get::<TlsConnectInfo>() {
+            caller.cert = info.identity().map(|identity| identity.name().to_owned());
+            caller.addr = info.remote_addr().map(|addr| addr.to_string());
+        } else if let Some(info) = extensions.get::<TcpConnectInfo>() {
+            caller.addr = info.remote_addr().map(|addr| addr.to_string());
+        }
+        caller
+    }
+}
+
+// Entry is the record of a decision: who asked which enforcer about which request values,
+// what it decided, and the rules that matched.
+#[derive(Serialize)]
+pub struct Entry<'a> {
+    pub caller: &'a Caller,
+    pub method: &'a str,
+    pub enforcer: i32,
+    pub request: &'a [String],
+    #[serde(skip_serializing_if = "str::is_empty")]
+    pub matcher: &'a str,
+    pub allowed: bool,
+    pub rules: &'a [Vec<String>],
+}
+
+// Line is what the writer of an audit log is handed: an entry, or a request to flush the
+// entries before it.
+enum Line {
+    Entry(Vec<u8>),
+    Flush(oneshot::Sender<()>),
+}
+
+// AuditLog writes an entry of JSON per line for every decision it is told of, from a task of
+// its own so the decisions do not wait for the sink.
+pub struct AuditLog {
+    lines: mpsc::Sender<Line>,
+}
+
+impl AuditLog {
+    // open starts writing to sink: `stdout`, or the path of a file the entries are appended
+    // to.
+    pub async fn open(sink: &str) -> std::io::Result<Self> {
+        let out: Box<dyn AsyncWrite + Send + Unpin> = if sink == "stdout" {
+            Box::new(tokio::io::stdout())
+        } else {
+            Box::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(sink)
+                    .await?,
+            )
+        };
+        let (lines, rx) = mpsc::channel(AUDIT_BUFFER);
+        tokio::spawn(write_lines(BufWriter::new(out), rx));
+        tracing::info!(%sink, "auditing decisions");
+        Ok(AuditLog { lines })
+    }
+
+    // record queues entry to be written, stamped with the current time.
+    pub async fn record(&self, entry: Entry<'_>) {
+        #[derive(Serialize)]
+        struct Stamped<'a> {
+            timestamp: String,
+            #[serde(flatten)]
+            entry: Entry<'a>,
+        }
+        let timestamp = OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .unwrap_or_default();
+        let mut line = match serde_json::to_vec(&Stamped { timestamp, entry }) {
+            Ok(line) => line,
+            Err(err) => {
+                tracing::error!(error = %err, "encoding an audit entry failed");
+                return;
+            }
+        };
+        line.push(b'\n');
+        let _ = self.lines.send(Line::Entry(line)).await;
+    }
+
+    // flush waits until the entries recorded so far are written out, as the server stops.
+    pub async fn flush(&self) {
+        let (done, flushed) = oneshot::channel();
+        if self.lines.send(Line::Flush(done)).await.is_ok() {
+            let _ = flushed.await;
+        }
+    }
+}
+
+// write_lines writes the entries of lines to out, flushing them whenever no more are waiting.
+async fn write_lines(
+    mut out: BufWriter<Box<dyn AsyncWrite + Send + Unpin>>,
+    mut lines: mpsc::Receiver<Line>,
+) {
+    while let Some(line) = lines.recv().await {
+        let mut next = Some(line);
+        let mut flushed = vec![];
+        while let Some(line) = next {
+            match line {
+                Line::Entry(entry) => {
+                    if let Err(err) = out.write_all(&entry).await {
+                        tracing::error!(error = %err, "writing the audit log failed");
+                    }
+                }
+                Line::Flush(done) => flushed.push(done),
+            }
+            next = lines.try_recv().ok();
+        }
+        if let Err(err) = out.flush().await {
+            tracing::error!(error = %err, "writing the audit log failed");
+        }
+        for done in flushed {
+            let _ = done.send(());
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use crate::server::jwt::JwtAuth;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::transport::NamedService;
+use tonic::Status;
+
+// READ_PREFIXES start the names of the RPCs that only read the policy, which the read scope
+// allows. Every other RPC, including ones added later, needs the admin scope. WatchDecisions
+// needs it too, since the decisions tell what the other clients asked for.
+static READ_PREFIXES: &[&str] = &[
+    "Enforce",
+    "BatchEnforce",
+    "StreamEnforce",
+    "Get",
+    "Has",
+    "List",
+    "Validate",
+    "WatchPolicyUpdates",
+];
+
+// Scope is what an API key may call: read allows Enforce and the RPCs reading the policy or
+// model, admin every RPC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Read,
+    Admin,
+}
+
+impl Scope {
+    fn allows(self, method: &str) -> bool {
+        self == Scope::Admin || READ_PREFIXES.iter().any(|p| method.starts_with(p))
+    }
+}
+
+// ApiKeys are the API keys clients authenticate with, by key. Without any, every client may
+// call every RPC.
+pub type ApiKeys = HashMap<String, Scope>;
+
+// load_api_keys reads a JSON file of API keys, shaped like the api_keys of the local config.
+pub async fn load_api_keys(file: &str) -> Result<ApiKeys, std::io::Error> {
+    let data = tokio::fs::read(file).await?;
+    serde_json::from_slice(&data)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+}
+
+// bearer_token gets the token of a request sent as `authorization: Bearer <token>`.
+fn bearer_token<B>(request: &http::Request<B>) -> Option<&str> {
+    let value = request.headers().get("authorization")?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+    Some(token.trim())
+}
+
+// AuthSubject is the subject of the JWT a request is authenticated with, in the extensions of
+// the request.
+#[derive(Clone, Debug)]
+pub struct AuthSubject(pub String);
+
+// AuthTenant is the tenant named by the jwt_tenant_claim of the JWT a request is authenticated
+// with, or the tenant of its API key, in the extensions of the request.
+#[derive(Clone, Debug)]
+pub struct AuthTenant(pub String);
+
+// AuthKey is the API key a request is authenticated with, and AuthScope its scope, in the
+// extensions of the request.
+#[derive(Clone, Debug)]
+pub struct AuthKey(pub String);
+
+#[derive(Clone, Copy, Debug)]
+pub struct AuthScope(pub Scope);
+
+// Authenticator checks the credentials of the calls to the Casbin service: API keys, sent as a
+// bearer token or as `x-api-key: <key>`, and JWTs, sent as a bearer token that is not an API
+// key. The keys of key_tenants are for the tenant they map to. Without keys or JWTs
+// configured, every call is let through.
+pub struct Authenticator {
+    keys: ApiKeys,
+    key_tenants: HashMap<String, String>,
+    jwt: Option<JwtAuth>,
+}
+
+impl Authenticator {
+    pub fn new(keys: ApiKeys, key_tenants: HashMap<String, String>, jwt: Option<JwtAuth>) -> Self {
+        Authenticator {
+            keys,
+            key_tenants,
+            jwt,
+        }
+    }
+
+    // is_enabled tells whether calls have to authenticate.
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty() || self.jwt.is_some()
+    }
+
+    async fn check<B>(&self, request: &mut http::Request<B>) -> Result<(), Status> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let path = request.uri().path().to_owned();
+        let (service, method) = path
+            .trim_start_matches('/')
+            .split_once('/')
+            .unwrap_or_default();
+        let token = bearer_token(request).or_else(|| {
+            request
+                .headers()
+                .get("x-api-key")
+                .and_then(|v| v.to_str().ok())
+        });
+        if let Some((key, scope)) = token.and_then(|key| self.keys.get_key_value(key)) {
+            if !scope.allows(method) {
+                return Err(Status::permission_denied(format!(
+                    "the API key does not allow {}",
+                    method
+                )));
+            }
+            if let Some(tenant) = self.key_tenants.get(key) {
+                request.extensions_mut().insert(AuthTenant(tenant.clone()));
+            }
+            let key = AuthKey(key.clone());
+            request.extensions_mut().insert(key);
+            request.extensions_mut().insert(AuthScope(*scope));
+            return Ok(());
+        }
+        let (jwt, token) = match (&self.jwt, bearer_token(request)) {
+            (Some(jwt), Some(token)) => (jwt, token),
+            (Some(_), None) => return Err(Status::unauthenticated("a bearer token is required")),
+            (None, _) => return Err(Status::unauthenticated("a valid API key is required")),
+        };
+        let (subject, tenant) = jwt.subject(token).await?;
+        jwt.authorize(&subject, service, method)?;
+        request.extensions_mut().insert(AuthSubject(subject));
+        if let Some(tenant) = tenant {
+            request.extensions_mut().insert(AuthTenant(tenant));
+        }
+        Ok(())
+    }
+}
+
+// AuthService checks the credentials of every call to the Casbin service before passing it
+// on, rejecting calls without valid ones as UNAUTHENTICATED and those their credentials do not
+// allow as PERMISSION_DENIED.
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    auth: Arc<Authenticator>,
+}
+
+impl<S> AuthService<S> {
+    pub fn new(inner: S, auth: Arc<Authenticator>) -> Self {
+        AuthService { inner, auth }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for AuthService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // The service polled ready is the one to call, so it is taken and a clone left behind.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let auth = self.auth.clone();
+        Box::pin(async move {
+            match auth.check(&mut request).await {
+                Ok(()) => inner.call(request).await,
+                Err(status) => Ok(status.to_http()),
+            }
+        })
+    }
+}
+
+impl<S: NamedService> NamedService for AuthService<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        load_api_keys, ApiKeys, AuthKey, AuthScope, AuthSubject, AuthTenant, Authenticator, Scope,
+    };
+    use crate::server::adapter::Config;
+    use crate::server::jwt::JwtAuth;
+    use std::collections::HashMap;
+    use tonic::codegen::http;
+    use tonic::Code;
+
+    fn request(method: &str, headers: &[(&str, &str)]) -> http::Request<()> {
+        let mut request = http::Request::builder().uri(format!("/proto.Casbin/{}", method));
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.body(()).unwrap()
+    }
+
+    fn keys() -> ApiKeys {
+        HashMap::from([
+            ("reader".to_owned(), Scope::Read),
+            ("admin".to_owned(), Scope::Admin),
+            ("tenant".to_owned(), Scope::Admin),
+        ])
+    }
+
+    async fn check(auth: &Authenticator, request: &mut http::Request<()>) -> Result<(), Code> {
+        auth.check(request).await.map_err(|status| status.code())
+    }
+
+    #[tokio::test]
+    async fn test_without_credentials_configured() {
+        let auth = Authenticator::new(ApiKeys::new(), HashMap::new(), None);
+        assert!(!auth.is_enabled());
+        assert_eq!(check(&auth, &mut request("AddPolicy", &[])).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_api_keys() {
+        let tenants = HashMap::from([("tenant".to_owned(), "domain1".to_owned())]);
+        let auth = Authenticator::new(keys(), tenants, None);
+        assert!(auth.is_enabled());
+
+        // Read keys call the RPCs reading the policy only.
+        let mut enforce = request("Enforce", &[("authorization", "Bearer reader")]);
+        assert_eq!(check(&auth, &mut enforce).await, Ok(()));
+        let mut add = request("AddPolicy", &[("x-api-key", "reader")]);
+        assert_eq!(check(&auth, &mut add).await, Err(Code::PermissionDenied));
+        let mut add = request("AddPolicy", &[("authorization", "bearer admin")]);
+        assert_eq!(check(&auth, &mut add).await, Ok(()));
+        assert_eq!(add.extensions().get::<AuthKey>().unwrap().0, "admin");
+        assert_eq!(add.extensions().get::<AuthScope>().unwrap().0, Scope::Admin);
+        assert!(add.extensions().get::<AuthTenant>().is_none());
+
+        let mut bound = request("GetPolicy", &[("x-api-key", "tenant")]);
+        assert_eq!(check(&auth, &mut bound).await, Ok(()));
+        assert_eq!(bound.extensions().get::<AuthTenant>().unwrap().0, "domain1");
+
+        for headers in [
+            &[][..],
+            &[("x-api-key", "unknown")][..],
+            &[("authorization", "Basic admin")][..],
+        ] {
+            let mut call = request("Enforce", headers);
+            assert_eq!(
+                check(&auth, &mut call).await,
+                Err(Code::Unauthenticated),
+                "{:?}",
+                headers
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_api_keys() {
+        let file = std::env::temp_dir().join(format!("api-keys-{}.json", std::process::id()));
+        std::fs::write(&file, r#"{"k1": "read", "k2": "admin"}"#).unwrap();
+        let keys = load_api_keys(file.to_str().unwrap()).await.unwrap();
+        assert_eq!(keys["k1"], Scope::Read);
+        assert_eq!(keys["k2"], Scope::Admin);
+
+        std::fs::write(&file, r#"{"k1": "write"}"#).unwrap();
+        let err = load_api_keys(file.to_str().unwrap()).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_scopes() {
+        for method in [
+            "Enforce",
+            "BatchEnforce",
+            "GetPolicy",
+            "HasPolicy",
+            "ListEnforcers",
+        ] {
+            assert!(Scope::Read.allows(method), "{}", method);
+        }
+        for method in ["AddPolicy", "SetModel", "WatchDecisions", "NewEnforcer"] {
+            assert!(!Scope::Read.allows(method), "{}", method);
+            assert!(Scope::Admin.allows(method), "{}", method);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_jwts() {
+        let cfg = Config {
+            jwt_secret: "s3cret".to_owned(),
+            jwt_tenant_claim: "org".to_owned(),
+            meta_model: "examples/meta_model.conf".to_owned(),
+            meta_policy: "examples/meta_policy.csv".to_owned(),
+            ..Default::default()
+        };
+        let jwt = JwtAuth::from_config(&cfg).await.unwrap();
+        let auth = Authenticator::new(keys(), HashMap::new(), jwt);
+        let token = |sub: &str| {
+            let claims = serde_json::json!({
+                "sub": sub,
+                "org": "acme",
+                "exp": jsonwebtoken::get_current_timestamp() + 3600,
+            });
+            let key = jsonwebtoken::EncodingKey::from_secret(b"s3cret");
+            format!(
+                "Bearer {}",
+                jsonwebtoken::encode(&Default::default(), &claims, &key).unwrap()
+            )
+        };
+
+        let reader = token("reader");
+        let mut enforce = request("Enforce", &[("authorization", &reader)]);
+        assert_eq!(check(&auth, &mut enforce).await, Ok(()));
+        assert_eq!(
+            enforce.extensions().get::<AuthSubject>().unwrap().0,
+            "reader"
+        );
+        assert_eq!(enforce.extensions().get::<AuthTenant>().unwrap().0, "acme");
+        // The meta enforcer decides what the subject may call.
+        let mut add = request("AddPolicy", &[("authorization", &reader)]);
+        assert_eq!(check(&auth, &mut add).await, Err(Code::PermissionDenied));
+
+        // API keys still authenticate alongside JWTs, but a key is not a JWT.
+        let mut add = request("AddPolicy", &[("authorization", "Bearer admin")]);
+        assert_eq!(check(&auth, &mut add).await, Ok(()));
+        let mut unknown = request("Enforce", &[("authorization", "Bearer unknown")]);
+        assert_eq!(check(&auth, &mut unknown).await, Err(Code::Unauthenticated));
+        let mut none = request("Enforce", &[("x-api-key", "unknown")]);
+        assert_eq!(check(&auth, &mut none).await, Err(Code::Unauthenticated));
+    }
+}
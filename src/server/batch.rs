@@ -0,0 +1,63 @@
+use crate::server::abac::AbacArgs;
+use crate::server::registry::EnforcerSnapshot;
+use std::sync::Arc;
+use tonic::Status;
+
+// BATCH_CHUNK is the fewest requests of a batch worth handing to a worker of their own. Smaller
+// batches are decided in the call, as the workers would cost more than they save.
+static BATCH_CHUNK: usize = 64;
+
+// Decided is a decision of a batch, with the rules that matched.
+pub type Decided = casbin::Result<(bool, Arc<[Vec<String>]>)>;
+
+// parallelism gets how many workers a batch may be decided by, the number of cores when 0.
+pub fn parallelism(parallelism: usize) -> usize {
+    match parallelism {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+// enforce_batch decides the requests of a batch against snapshot, split among up to workers
+// blocking tasks, and returns the decisions in the order of the requests. Every worker decides
+// against the same rules, as the enforcer cannot change while the snapshot is held.
+pub async fn enforce_batch(
+    snapshot: EnforcerSnapshot,
+    requests: Vec<AbacArgs>,
+    workers: usize,
+) -> Result<Vec<Decided>, Status> {
+    let workers = workers.min(requests.len() / BATCH_CHUNK).max(1);
+    if workers == 1 {
+        let e = snapshot.read();
+        return Ok(requests
+            .into_iter()
+            .map(|rvals| e.enforce_explained(rvals))
+            .collect());
+    }
+
+    let chunk = requests.len().div_ceil(workers);
+    let mut requests = requests.into_iter();
+    let mut tasks = Vec::with_capacity(workers);
+    loop {
+        let chunk: Vec<AbacArgs> = requests.by_ref().take(chunk).collect();
+        if chunk.is_empty() {
+            break;
+        }
+        let snapshot = snapshot.clone();
+        tasks.push(tokio::task::spawn_blocking(move || {
+            let e = snapshot.read();
+            chunk
+                .into_iter()
+                .map(|rvals| e.enforce_explained(rvals))
+                .collect::<Vec<Decided>>()
+        }));
+    }
+    let mut decided = Vec::with_capacity(tasks.len() * chunk);
+    for task in tasks {
+        let chunk = task
+            .await
+            .map_err(|err| Status::internal(format!("deciding the batch failed: {}", err)))?;
+        decided.extend(chunk);
+    }
+    Ok(decided)
+}
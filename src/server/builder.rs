@@ -0,0 +1,587 @@
+use crate::adapter::Adapter;
+use crate::casbin_proto::{self, casbin_server::CasbinServer};
+use crate::compression;
+#[cfg(feature = "raft")]
+use crate::dispatcher::{self, network::RaftService, service::DispatchService};
+#[cfg(feature = "raft")]
+use crate::raft_proto::raft_server::RaftServer;
+use crate::server::adapter::{load_local_configuration, Config, SharedAdapter};
+use crate::server::audit::AuditLog;
+use crate::server::auth::{self, AuthService, Authenticator, Scope};
+use crate::server::enforcer;
+use crate::server::explain;
+use crate::server::functions::MatcherFunction;
+use crate::server::health;
+use crate::server::jwt::JwtAuth;
+use crate::server::limits::{LimitService, Limits};
+use crate::server::logger::{self, Logger};
+#[cfg(feature = "metrics")]
+use crate::server::metrics;
+use crate::server::metrics::MetricsService;
+use crate::server::model_source;
+use crate::server::ratelimit::{RateLimitService, RateLimiter};
+use crate::server::registry::{DecisionCacheOptions, EnforcerEntry};
+use crate::server::reload::{self, Reloader};
+#[cfg(feature = "rest")]
+use crate::server::rest;
+use crate::server::tenants::{TenantService, Tenants};
+use crate::server::tls::{self, TlsConfig};
+use crate::server::trace::{self, TraceService};
+use crate::server::unix;
+use crate::server::web;
+use crate::watcher;
+use crate::watcher::feed::FeedWatcher;
+use crate::watcher::WatcherEx;
+use crate::CasbinGRPC;
+use casbin::{CachedEnforcer, CoreApi, DefaultModel};
+use futures::future::LocalBoxFuture;
+use futures::lock::Mutex;
+use futures::{future, FutureExt, TryFutureExt};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tonic::transport::Server;
+
+// FLUSH_TIMEOUT bounds how long a stopping server waits for the watcher to notify the policy
+// changes of its last calls.
+static FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// check_config checks the local config for settings this server cannot serve with.
+pub fn check_config(cfg: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    if !cfg.listen_addr.is_empty() {
+        cfg.listen_addr
+            .parse::<SocketAddr>()
+            .map_err(|err| format!("listen_addr {}: {}", cfg.listen_addr, err))?;
+    }
+    logger::parse_level(&cfg.log_level)?;
+    if cfg.raft_node_id != 0 {
+        if cfg!(not(feature = "raft")) {
+            return Err("raft_node_id is set, but this server is built without raft".into());
+        }
+        if cfg.driver.is_empty() {
+            return Err("raft_node_id is set, but there is no local config to replicate".into());
+        }
+        if !cfg.watcher.is_empty() {
+            return Err("raft_node_id and watcher cannot both be set".into());
+        }
+    }
+    if !cfg.tenant_model.is_empty() {
+        if cfg.driver.is_empty() {
+            return Err(
+                "tenant_model is set, but there is no store to load the tenants from".into(),
+            );
+        }
+        if cfg.raft_node_id != 0 {
+            return Err("raft_node_id and tenant_model cannot both be set".into());
+        }
+    } else if !cfg.jwt_tenant_claim.is_empty() {
+        return Err("jwt_tenant_claim is set, but tenant_model is not".into());
+    }
+    if !cfg.rest_addr.is_empty() && cfg!(not(feature = "rest")) {
+        return Err("rest_addr is set, but this server is built without the REST gateway".into());
+    }
+    if !cfg.metrics_addr.is_empty() && cfg!(not(feature = "metrics")) {
+        return Err("metrics_addr is set, but this server is built without metrics".into());
+    }
+    Ok(())
+}
+
+// CasbinGRPCBuilder sets up a server embedded in another application, which can plug in its
+// own policy stores as adapter drivers, and its own functions for matchers to call:
+//
+//     CasbinGRPC::builder()
+//         .adapter_driver("acme", |connection| async move { AcmeAdapter::open(&connection).await })
+//         .register_function("inBusinessHours", BusinessHours::new("09:00", "17:00"))
+//         .build()
+//         .serve(addr)
+//         .await
+//
+// A registered driver is used for NewAdapter requests and for the local config like the
+// built-in ones. `.tls(cert, key)` serves the server over TLS, `.mtls(cert, key,
+// client_ca)` over mutual TLS, `.api_key(key, scope)` requires clients to authenticate,
+// `.unix_socket(path)` serves on a unix socket as well, and `.register_function(name, f)` lets
+// matchers call a function of the application.
+#[derive(Default)]
+pub struct CasbinGRPCBuilder {
+    server: CasbinGRPC,
+}
+
+impl CasbinGRPCBuilder {
+    pub fn adapter_driver<F, Fut, A>(mut self, name: &str, open: F) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = casbin::Result<A>> + Send + 'static,
+        A: Adapter + 'static,
+    {
+        self.server.drivers.register(name, open);
+        self
+    }
+
+    // tls serves the server over TLS with a certificate chain and private key, each the path
+    // of a PEM file or PEM text, instead of the ones of the local config.
+    pub fn tls(mut self, cert: &str, key: &str) -> Self {
+        self.server.tls = Some(TlsConfig::new(cert, key));
+        self
+    }
+
+    // api_key lets clients authenticate with key, on top of the keys of the local config.
+    pub fn api_key(mut self, key: &str, scope: Scope) -> Self {
+        self.server.api_keys.insert(key.to_owned(), scope);
+        self
+    }
+
+    // tenant_api_key lets clients authenticate with key like api_key, for the calls of tenant
+    // alone when the local config serves tenants.
+    pub fn tenant_api_key(mut self, key: &str, scope: Scope, tenant: &str) -> Self {
+        self.server.api_keys.insert(key.to_owned(), scope);
+        self.server
+            .api_key_tenants
+            .insert(key.to_owned(), tenant.to_owned());
+        self
+    }
+
+    // mtls serves the server over mutual TLS, requiring clients to present a certificate signed
+    // by the CAs of client_ca, a PEM file or PEM text.
+    pub fn mtls(mut self, cert: &str, key: &str, client_ca: &str) -> Self {
+        self.server.tls = Some(TlsConfig::new(cert, key).with_client_ca(client_ca));
+        self
+    }
+
+    // unix_socket serves the server on a unix socket at path too, or on it alone with the
+    // unix_socket_only of the local config, for clients on the same host such as sidecars.
+    pub fn unix_socket(mut self, path: &str) -> Self {
+        self.server.unix_socket = Some(path.to_owned());
+        self
+    }
+
+    // logger makes the enforcers log through logger while their logs are enabled, instead of as
+    // tracing events. The logger is shared by every server of the process.
+    pub fn logger<L: Logger + 'static>(self, logger: L) -> Self {
+        logger::set_logger(Arc::new(logger));
+        self
+    }
+
+    // register_function lets matchers call f by name, for functions of the domain of the
+    // application such as inBusinessHours(). The function is called by the enforcers of the
+    // server and of its tenants, and not by the meta enforcer. The decisions it is called for
+    // are not cached unless it is cacheable.
+    pub fn register_function<F: MatcherFunction + 'static>(self, name: &str, f: F) -> Self {
+        self.server
+            .enforcers
+            .functions()
+            .register(name, Arc::new(f));
+        self
+    }
+
+    pub fn build(self) -> CasbinGRPC {
+        self.server
+    }
+}
+
+impl CasbinGRPC {
+    pub fn builder() -> CasbinGRPCBuilder {
+        CasbinGRPCBuilder::default()
+    }
+
+    // open_local opens the adapter of the local config and builds its enforcer, loading the
+    // policy, getting them along with the text of the model.
+    async fn open_local(
+        &self,
+        cfg: &Config,
+    ) -> Result<(Arc<Mutex<Box<dyn Adapter>>>, CachedEnforcer, String), Box<dyn std::error::Error>>
+    {
+        let a = Arc::new(Mutex::new(
+            self.drivers.open(&cfg.driver, &cfg.connection).await?,
+        ));
+        let model_text =
+            model_source::read_model(&cfg.enforcer, &cfg.model_headers, &cfg.model_cache_dir)
+                .await?;
+        let m = DefaultModel::from_str(&model_text).await?;
+        let e = enforcer::build_enforcer(m, SharedAdapter::new(a.clone()).await).await?;
+        Ok((a, e, model_text))
+    }
+
+    // validate checks the local config, and that its model parses and its policy loads,
+    // without serving anything. It gets what the enforcer of the local config holds.
+    pub async fn validate(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let cfg = load_local_configuration().await?;
+        check_config(&cfg)?;
+        if cfg.driver.is_empty() {
+            return Ok("the config is valid, without an enforcer of its own".to_owned());
+        }
+        let (_, e, _) = self.open_local(&cfg).await?;
+        let model = e.get_model().get_model();
+        let count = |sec: &str| {
+            model.get(sec).map_or(0, |asts| {
+                asts.values()
+                    .map(|ast| ast.get_policy().len())
+                    .sum::<usize>()
+            })
+        };
+        Ok(format!(
+            "the model {} is valid, and the {} policy at {} holds {} rules and {} role links",
+            cfg.enforcer,
+            cfg.driver,
+            cfg.connection,
+            count("p"),
+            count("g")
+        ))
+    }
+
+    // serve registers the adapter and enforcer of the local config, then serves the Casbin
+    // service on addr until it fails.
+    pub async fn serve(mut self, addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
+        // Register the adapter and enforcer described by the local config as handle 0,
+        // so Enforce is usable without a prior NewEnforcer call.
+        let cfg = load_local_configuration().await?;
+        check_config(&cfg)?;
+        if !cfg.otlp_endpoint.is_empty() {
+            trace::init(&cfg.otlp_endpoint)?;
+        }
+        explain::set_slow_decision(Duration::from_millis(cfg.slow_decision_ms));
+        self.decision_cache = DecisionCacheOptions::new(
+            cfg.disable_decision_cache,
+            cfg.decision_cache_max_entries,
+            Duration::from_millis(cfg.decision_cache_ttl_ms),
+        );
+        self.batch_parallelism = cfg.batch_parallelism;
+        if !cfg.audit_log.is_empty() {
+            self.audit = Some(Arc::new(AuditLog::open(&cfg.audit_log).await?));
+        }
+        let mut local = None;
+        if !cfg.driver.is_empty() {
+            let (a, e, model_text) = self.open_local(&cfg).await?;
+            self.add_adapter(a).await;
+            let handle = self.add_enforcer(e, model_text).await;
+            self.enforcers.pin(handle).await;
+            let entry = self
+                .enforcers
+                .get(handle)
+                .await
+                .ok_or("No enforcer found")?;
+            if !cfg.watcher.is_empty() {
+                watch_policy(entry.clone(), handle, &cfg).await?;
+            }
+            local = Some((entry, handle));
+        }
+
+        // Evict enforcers created through NewEnforcer once they go unused for the configured TTL.
+        if cfg.idle_ttl_secs > 0 {
+            let ttl = Duration::from_secs(cfg.idle_ttl_secs);
+            let enforcers = self.enforcers.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(ttl.min(Duration::from_secs(60)));
+                loop {
+                    interval.tick().await;
+                    for handle in enforcers.evict_idle(ttl).await {
+                        tracing::info!(handle, "evicted an idle enforcer");
+                    }
+                }
+            });
+        }
+
+        let (mut reporter, health_service) = tonic_health::server::health_reporter();
+        reporter.set_serving::<CasbinServer<CasbinGRPC>>().await;
+        let shutting_down = Arc::new(AtomicBool::new(false));
+        if !cfg.driver.is_empty() && cfg.health_check_secs > 0 {
+            health::spawn_health_checks(
+                self.drivers.clone(),
+                cfg.driver.clone(),
+                cfg.connection.clone(),
+                reporter.clone(),
+                Duration::from_secs(cfg.health_check_secs),
+                shutting_down.clone(),
+            );
+        }
+        let signal = health::shutdown_signal(
+            reporter,
+            shutting_down,
+            Duration::from_secs(cfg.shutdown_drain_secs),
+        );
+        let (enforcers, (stopping, stopped)) = (self.enforcers.clone(), oneshot::channel());
+        let shutdown = async move {
+            signal.await;
+            // The WatchPolicyUpdates and WatchDecisions streams would otherwise keep the server
+            // waiting.
+            for (_, entry) in enforcers.list().await {
+                entry.feed.close();
+                entry.decisions.close();
+            }
+            let _ = stopping.send(());
+        };
+        let timeout = Duration::from_secs(cfg.shutdown_timeout_secs);
+
+        let tls = self.tls.clone().or_else(|| {
+            (!cfg.tls_cert.is_empty()).then(|| {
+                TlsConfig::new(&cfg.tls_cert, &cfg.tls_key).with_client_ca(&cfg.tls_client_ca)
+            })
+        });
+        let mut api_keys = cfg.api_keys.clone();
+        if !cfg.api_keys_file.is_empty() {
+            api_keys.extend(auth::load_api_keys(&cfg.api_keys_file).await?);
+        }
+        api_keys.extend(self.api_keys.clone());
+        let mut key_tenants = cfg.api_key_tenants.clone();
+        key_tenants.extend(self.api_key_tenants.clone());
+        let auth = Arc::new(Authenticator::new(
+            api_keys,
+            key_tenants,
+            JwtAuth::from_config(&cfg).await?,
+        ));
+        let unix_socket = self
+            .unix_socket
+            .clone()
+            .or_else(|| (!cfg.unix_socket.is_empty()).then(|| cfg.unix_socket.clone()));
+        if unix_socket.is_none() && cfg.unix_socket_only {
+            return Err("unix_socket_only is set, but there is no unix socket to serve on".into());
+        }
+        if unix_socket.is_some() && cfg.raft_node_id != 0 {
+            return Err(
+                "raft nodes talk to each other over TCP, so cannot be served on a unix socket"
+                    .into(),
+            );
+        }
+        if tls.is_some() && cfg.raft_node_id != 0 {
+            return Err(
+                "raft nodes talk to each other in plaintext, so cannot be served over TLS".into(),
+            );
+        }
+        let compression = compression::encoding(&cfg.compression)?;
+        #[cfg(feature = "rest")]
+        let rest_addr = if cfg.rest_addr.is_empty() {
+            None
+        } else {
+            Some(cfg.rest_addr.parse::<SocketAddr>()?)
+        };
+        let rate_limiter = Arc::new(RateLimiter::from_config(&cfg));
+        let limits = Arc::new(RwLock::new(Limits::from_config(&cfg)));
+        let reloader = Arc::new(Reloader::new(
+            &cfg,
+            rate_limiter.clone(),
+            limits.clone(),
+            local.clone(),
+            self.tls.is_none() && tls.is_some(),
+        ));
+        reload::spawn_on_hangup(reloader.clone());
+        self.reloader = Some(reloader);
+        let tenants = Tenants::from_config(&cfg, &self, compression)
+            .await?
+            .map(Arc::new);
+        if tenants.is_some() && !auth.is_enabled() {
+            return Err(
+                "tenant_model is set, but no API keys or JWTs tell the tenants of the calls".into(),
+            );
+        }
+        #[cfg(feature = "metrics")]
+        let registry = self.enforcers.clone();
+        let audit = self.audit.clone();
+        let mut service = CasbinServer::new(self);
+        if let Some(encoding) = compression {
+            service = service.send_compressed(encoding);
+            // Raft replicates writes as sent, so the nodes only take uncompressed ones.
+            if cfg.raft_node_id == 0 {
+                service = service.accept_compressed(encoding);
+            }
+        }
+        let reflection = if cfg.reflection {
+            let service = tonic_reflection::server::Builder::configure()
+                .register_encoded_file_descriptor_set(casbin_proto::FILE_DESCRIPTOR_SET)
+                .register_encoded_file_descriptor_set(
+                    tonic_health::proto::GRPC_HEALTH_V1_FILE_DESCRIPTOR_SET,
+                )
+                .build()?;
+            Some(service)
+        } else {
+            None
+        };
+        let web = if cfg.grpc_web {
+            Some(web::layer(&cfg.grpc_web_origins)?)
+        } else {
+            None
+        };
+        let router = || {
+            // Browsers call gRPC-Web over HTTP/1.1 as well as HTTP/2.
+            Server::builder()
+                .accept_http1(web.is_some())
+                .layer(tower::util::option_layer(web.clone()))
+                .add_service(health_service.clone())
+                .add_optional_service(reflection.clone())
+        };
+        let shutdown = shutdown.shared();
+        let mut serves: Vec<LocalBoxFuture<Result<(), Box<dyn std::error::Error>>>> = vec![];
+        #[cfg(feature = "metrics")]
+        if !cfg.metrics_addr.is_empty() {
+            let serve =
+                metrics::serve_metrics(cfg.metrics_addr.parse()?, registry, shutdown.clone());
+            serves.push(serve.boxed_local());
+        }
+        #[cfg(feature = "raft")]
+        if let (true, Some((entry, _))) = (cfg.raft_node_id != 0, local) {
+            let raft = dispatcher::start(
+                cfg.raft_node_id,
+                &cfg.raft_nodes,
+                &cfg.raft_dir,
+                service.clone(),
+                entry,
+            )
+            .await?;
+            tracing::info!(%addr, node = cfg.raft_node_id, "server listening as a raft node");
+            let service = TraceService::new(MetricsService::new(AuthService::new(
+                RateLimitService::new(
+                    LimitService::new(DispatchService::new(service, raft.clone()), limits),
+                    rate_limiter,
+                ),
+                auth,
+            )));
+            #[cfg(feature = "rest")]
+            if let Some(rest_addr) = rest_addr {
+                let serve = rest::serve(rest_addr, None, service.clone(), shutdown.clone());
+                serves.push(serve.boxed_local());
+            }
+            let serve = router()
+                .add_service(RaftServer::new(RaftService::new(raft.clone())))
+                .add_service(service)
+                .serve_with_shutdown(addr, shutdown);
+            serves.push(serve.err_into().boxed_local());
+            let serve = future::try_join_all(serves).map_ok(|_| ());
+            let res = until_stopped(serve, stopped, timeout).await;
+            trace::shutdown().await;
+            if let Some(audit) = &audit {
+                audit.flush().await;
+            }
+            res?;
+            if let Err(err) = raft.shutdown().await {
+                tracing::error!(error = %err, "stopping raft failed");
+            }
+            return Ok(());
+        }
+        #[cfg(not(feature = "raft"))]
+        drop(local);
+
+        let service = TraceService::new(MetricsService::new(AuthService::new(
+            RateLimitService::new(
+                LimitService::new(TenantService::new(service, tenants), limits),
+                rate_limiter,
+            ),
+            auth,
+        )));
+        #[cfg(feature = "rest")]
+        if let Some(rest_addr) = rest_addr {
+            let serve = rest::serve(rest_addr, tls.clone(), service.clone(), shutdown.clone());
+            serves.push(serve.boxed_local());
+        }
+        if let Some(path) = &unix_socket {
+            let incoming = unix::incoming(path)?;
+            tracing::info!(%path, "server listening on the unix socket");
+            let router = router().add_service(service.clone());
+            serves.push(
+                router
+                    .serve_with_incoming_shutdown(incoming, shutdown.clone())
+                    .err_into()
+                    .boxed_local(),
+            );
+        }
+        if !cfg.unix_socket_only {
+            let router = router().add_service(service);
+            match tls {
+                Some(tls) => {
+                    let incoming = tls::incoming(addr, tls).await?;
+                    tracing::info!(%addr, "server listening over TLS");
+                    serves.push(
+                        router
+                            .serve_with_incoming_shutdown(incoming, shutdown)
+                            .err_into()
+                            .boxed_local(),
+                    );
+                }
+                None => {
+                    tracing::info!(%addr, "server listening");
+                    serves.push(
+                        router
+                            .serve_with_shutdown(addr, shutdown)
+                            .err_into()
+                            .boxed_local(),
+                    );
+                }
+            }
+        }
+        let serve = future::try_join_all(serves).map_ok(|_| ());
+        let res = until_stopped(serve, stopped, timeout).await;
+        trace::shutdown().await;
+        if let Some(audit) = &audit {
+            audit.flush().await;
+        }
+        if let Some(path) = &unix_socket {
+            unix::remove(path);
+        }
+        res?;
+        Ok(())
+    }
+}
+
+// until_stopped runs a server until it stops, giving up on the calls still in flight once it
+// has been stopping for timeout, unless 0, then waits for the watchers to notify the changes
+// those calls made.
+async fn until_stopped<F, E>(
+    serve: F,
+    stopped: oneshot::Receiver<()>,
+    timeout: Duration,
+) -> Result<(), E>
+where
+    F: Future<Output = Result<(), E>>,
+{
+    tokio::pin!(serve);
+    let res = tokio::select! {
+        res = &mut serve => res,
+        Ok(()) = stopped, if !timeout.is_zero() => {
+            match tokio::time::timeout(timeout, &mut serve).await {
+                Ok(res) => res,
+                Err(_) => {
+                    tracing::warn!(?timeout, "dropped the calls still in flight");
+                    Ok(())
+                }
+            }
+        }
+    };
+    if !watcher::flush(FLUSH_TIMEOUT).await {
+        tracing::warn!("stopped before the watcher notified every policy change");
+    }
+    res
+}
+
+// watch_policy makes the enforcer of the local config notify its policy changes through the
+// watcher and apply the changes other replicas notify, reloading its policy for those that
+// carry no rules.
+pub async fn watch_policy(
+    entry: Arc<EnforcerEntry>,
+    handle: i32,
+    cfg: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let inner = watcher::open_watcher(&cfg.watcher, &cfg.watcher_channel, handle).await?;
+    // The watcher is wrapped so the changes still reach WatchPolicyUpdates subscribers.
+    let mut watcher = FeedWatcher::new(entry.feed.clone(), Some(inner));
+    let (enforcer, feed, muted) = (entry.enforcer.clone(), entry.feed.clone(), watcher.muted());
+    watcher.set_update_handler(Box::new(move |msg| {
+        let (enforcer, feed, muted) = (enforcer.clone(), feed.clone(), muted.clone());
+        tokio::spawn(async move {
+            let mut e = enforcer.write().await;
+            // The replica that made the change notified the others already.
+            muted.store(true, Ordering::Relaxed);
+            let res = match enforcer::apply_update(&mut e, msg).await {
+                Ok(false) => enforcer::reload_policy(&mut e)
+                    .await
+                    .map(|_| feed.publish("LoadPolicy", "", vec![])),
+                res => res.map(|_| ()),
+            };
+            muted.store(false, Ordering::Relaxed);
+            if let Err(err) = res {
+                tracing::error!(error = %err, "applying the policy change of another replica failed");
+            }
+        });
+    }));
+    entry.enforcer.write().await.set_watcher(Box::new(watcher));
+    Ok(())
+}
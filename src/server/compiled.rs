@@ -0,0 +1,534 @@
+use crate::server::explain::ExplainEffector;
+use crate::server::functions::{self, CustomFunction, Functions};
+use crate::server::roles::RoleCache;
+use casbin::error::{ModelError, PolicyError, RequestError};
+use casbin::rhai::packages::{
+    ArithmeticPackage, BasicArrayPackage, BasicMapPackage, LogicPackage, Package,
+};
+use casbin::rhai::{Dynamic, Engine, EvalAltResult, ImmutableString, Scope, AST};
+use casbin::{Assertion, CachedEnforcer, CoreApi, EffectKind, Effector, EnforceArgs, RoleManager};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+// MATCHERS_CAPACITY bounds the matchers given to EnforceWithMatcher that are kept compiled for
+// an enforcer, the least recently used being dropped to make room.
+static MATCHERS_CAPACITY: usize = 128;
+
+// escape_assertion and escape_eval rewrite a matcher the way casbin does before compiling it:
+// `r.sub` to `r_sub`, and `eval(p.rule)` to evaluate the rule escaped likewise.
+fn escape_assertion(s: &str) -> String {
+    static ESC_A: OnceLock<Regex> = OnceLock::new();
+    ESC_A
+        .get_or_init(|| Regex::new(r"\b(r\d*|p\d*)\.").unwrap())
+        .replace_all(s, "${1}_")
+        .to_string()
+}
+
+fn escape_eval(s: &str) -> String {
+    static ESC_E: OnceLock<Regex> = OnceLock::new();
+    ESC_E
+        .get_or_init(|| Regex::new(r"\beval\(([^)]*)\)").unwrap())
+        .replace_all(s, "eval(escape_assertion(${1}))")
+        .to_string()
+}
+
+// definition gets the definition named like its section, failing like casbin when it is
+// missing.
+fn definition<'a>(
+    e: &'a CachedEnforcer,
+    sec: &str,
+    err: fn(String) -> ModelError,
+    name: &str,
+) -> casbin::Result<&'a Assertion> {
+    let asts = e
+        .get_model()
+        .get_model()
+        .get(sec)
+        .ok_or_else(|| err(format!("Missing {} definition in conf file", name)))?;
+    Ok(asts
+        .get(sec)
+        .ok_or_else(|| err(format!("Missing {} section in conf file", name)))?)
+}
+
+// roles lists the role definitions of the model of e with the number of their arguments, the
+// functions an engine is built with.
+fn roles(e: &CachedEnforcer) -> Vec<(String, usize)> {
+    e.get_model()
+        .get_model()
+        .get("g")
+        .map(|asts| {
+            asts.iter()
+                .map(|(name, ast)| (name.clone(), ast.value.matches('_').count()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// MAX_HIERARCHY_LEVEL is how many links the role manager of casbin follows from a user to a
+// role at most.
+static MAX_HIERARCHY_LEVEL: usize = 10;
+
+// reaches tells whether name1 reaches name2, in domain if given, through the links of rm, like
+// the has_link of the role manager. That takes the role manager for itself to cache what it
+// finds, so the links are followed back from name2 through get_users instead, which only
+// reads it and lets the decisions resolving roles side by side go on.
+fn reaches(rm: &dyn RoleManager, name1: &str, name2: &str, domain: Option<&str>) -> bool {
+    if name1 == name2 {
+        return true;
+    }
+    let mut seen = HashSet::from([name2.to_owned()]);
+    let mut roles = vec![name2.to_owned()];
+    for _ in 0..MAX_HIERARCHY_LEVEL {
+        let mut users = Vec::new();
+        for role in roles.iter() {
+            for user in rm.get_users(role, domain) {
+                if user == name1 {
+                    return true;
+                }
+                if seen.insert(user.clone()) {
+                    users.push(user);
+                }
+            }
+        }
+        if users.is_empty() {
+            break;
+        }
+        roles = users;
+    }
+    false
+}
+
+// Evaluator decides requests over the policy of an enforcer like casbin does, with its own
+// engine so the matchers are compiled once instead of on every decision. The engine is built
+// for the role definitions and the role manager of the enforcer, and never outlives them. The
+// role definitions resolve their links through the role cache of the enforcer.
+struct Evaluator {
+    engine: Engine,
+    roles: Vec<(String, usize)>,
+    role_manager: usize,
+    model: RwLock<Option<(String, Arc<AST>)>>,
+    matchers: Mutex<HashMap<String, (Arc<AST>, u64)>>,
+    uses: AtomicU64,
+}
+
+// engine builds an engine with the packages and the functions of the matchers of casbin, and
+// the functions of the server.
+fn engine(functions: &Functions) -> Engine {
+    let mut engine = Engine::new_raw();
+    engine.register_global_module(ArithmeticPackage::new().as_shared_module());
+    engine.register_global_module(LogicPackage::new().as_shared_module());
+    engine.register_global_module(BasicArrayPackage::new().as_shared_module());
+    engine.register_global_module(BasicMapPackage::new().as_shared_module());
+    engine.register_fn("escape_assertion", |s: ImmutableString| {
+        escape_assertion(&s)
+    });
+    functions::register(&mut engine, functions);
+    engine
+}
+
+impl Evaluator {
+    fn new(
+        e: &CachedEnforcer,
+        cache: &Arc<RoleCache>,
+        custom: &[CustomFunction],
+        functions: &Functions,
+    ) -> casbin::Result<Self> {
+        let mut engine = engine(functions);
+        if !custom.is_empty() {
+            functions::register_custom(&mut engine, &Arc::new(self::engine(functions)), custom)
+                .map_err(ModelError::M)?;
+        }
+        let roles = roles(e);
+        for (name, args) in roles.iter() {
+            let (rm, cache, role) = (e.get_role_manager(), cache.clone(), name.clone());
+            match args {
+                2 => {
+                    engine.register_fn(
+                        name.as_str(),
+                        move |a: ImmutableString, b: ImmutableString| {
+                            cache.has_link(&role, &a, &b, None, || {
+                                reaches(&*rm.read(), &a, &b, None)
+                            })
+                        },
+                    );
+                }
+                3 => {
+                    engine.register_fn(
+                        name.as_str(),
+                        move |a: ImmutableString, b: ImmutableString, domain: ImmutableString| {
+                            cache.has_link(&role, &a, &b, Some(&domain), || {
+                                reaches(&*rm.read(), &a, &b, Some(&domain))
+                            })
+                        },
+                    );
+                }
+                _ => {
+                    return Err(ModelError::P(
+                        r#"the number of "_" in role definition should be at least 2"#.to_owned(),
+                    )
+                    .into())
+                }
+            }
+        }
+        // The links cached so far may have been resolved by another role manager.
+        cache.invalidate();
+        Ok(Evaluator {
+            engine,
+            roles,
+            role_manager: Arc::as_ptr(&e.get_role_manager()) as *const () as usize,
+            model: Default::default(),
+            matchers: Default::default(),
+            uses: Default::default(),
+        })
+    }
+
+    // fits tells whether the engine was built for the role definitions and manager of e.
+    fn fits(&self, e: &CachedEnforcer) -> bool {
+        let rm = Arc::as_ptr(&e.get_role_manager()) as *const () as usize;
+        let asts = e.get_model().get_model().get("g");
+        rm == self.role_manager
+            && asts.map_or(0, |asts| asts.len()) == self.roles.len()
+            && asts.is_none_or(|asts| {
+                asts.iter()
+                    .zip(self.roles.iter())
+                    .all(|((name, ast), role)| {
+                        *name == role.0 && ast.value.matches('_').count() == role.1
+                    })
+            })
+    }
+
+    fn compile(&self, matcher: &str) -> casbin::Result<Arc<AST>> {
+        let ast = self
+            .engine
+            .compile_expression(escape_eval(matcher))
+            .map_err(Into::<Box<EvalAltResult>>::into)?;
+        Ok(Arc::new(ast))
+    }
+
+    // model_matcher gets the matcher of the model compiled, compiling it again if it changed.
+    fn model_matcher(&self, m_ast: &Assertion) -> casbin::Result<Arc<AST>> {
+        if let Some((text, ast)) = self.model.read().unwrap().as_ref() {
+            if *text == m_ast.value {
+                return Ok(ast.clone());
+            }
+        }
+        let ast = self.compile(&m_ast.value)?;
+        *self.model.write().unwrap() = Some((m_ast.value.clone(), ast.clone()));
+        Ok(ast)
+    }
+
+    // matcher gets a matcher given to EnforceWithMatcher compiled, escaped the way the matcher
+    // of a model is.
+    fn matcher(&self, matcher: &str) -> casbin::Result<Arc<AST>> {
+        let used = self.uses.fetch_add(1, Ordering::Relaxed);
+        if let Some((ast, last)) = self.matchers.lock().unwrap().get_mut(matcher) {
+            *last = used;
+            return Ok(ast.clone());
+        }
+        let text = matcher.find('#').map_or(matcher, |i| &matcher[..i]);
+        let ast = self.compile(&escape_assertion(text.trim_end()))?;
+        let mut matchers = self.matchers.lock().unwrap();
+        if matchers.len() >= MATCHERS_CAPACITY {
+            let oldest = matchers
+                .iter()
+                .min_by_key(|(_, (_, last))| *last)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                matchers.remove(&oldest);
+            }
+        }
+        matchers.insert(matcher.to_owned(), (ast.clone(), used));
+        Ok(ast)
+    }
+
+    // decide decides rvals with the compiled matcher, like the private_enforce of casbin,
+    // returning the positions of the rules that explain the decision.
+    fn decide(
+        &self,
+        e: &CachedEnforcer,
+        matcher: &AST,
+        rvals: &[Dynamic],
+    ) -> casbin::Result<(bool, Option<Vec<usize>>)> {
+        if !e.is_enabled() {
+            return Ok((true, None));
+        }
+        let r_ast = definition(e, "r", ModelError::R, "request")?;
+        let p_ast = definition(e, "p", ModelError::P, "policy")?;
+        let e_ast = definition(e, "e", ModelError::E, "effector")?;
+        if r_ast.tokens.len() != rvals.len() {
+            return Err(
+                RequestError::UnmatchRequestDefinition(r_ast.tokens.len(), rvals.len()).into(),
+            );
+        }
+
+        let mut scope = Scope::new();
+        for (rtoken, rval) in r_ast.tokens.iter().zip(rvals.iter()) {
+            scope.push_constant_dynamic(rtoken, rval.to_owned());
+        }
+        let policies = p_ast.get_policy();
+        let scope_len = scope.len();
+        let mut eft_stream = ExplainEffector.new_stream(&e_ast.value, policies.len().max(1));
+
+        if policies.is_empty() {
+            for token in p_ast.tokens.iter() {
+                scope.push_constant(token, String::new());
+            }
+            let eft = if self
+                .engine
+                .eval_ast_with_scope::<bool>(&mut scope, matcher)?
+            {
+                EffectKind::Allow
+            } else {
+                EffectKind::Indeterminate
+            };
+            eft_stream.push_effect(eft);
+            return Ok((eft_stream.next(), None));
+        }
+
+        let eft_index = p_ast.tokens.iter().position(|x| x == "p_eft");
+        for pvals in policies {
+            scope.rewind(scope_len);
+            if p_ast.tokens.len() != pvals.len() {
+                return Err(
+                    PolicyError::UnmatchPolicyDefinition(p_ast.tokens.len(), pvals.len()).into(),
+                );
+            }
+            for (ptoken, pval) in p_ast.tokens.iter().zip(pvals.iter()) {
+                scope.push_constant(ptoken, pval.to_owned());
+            }
+            let matched = self
+                .engine
+                .eval_ast_with_scope::<bool>(&mut scope, matcher)?;
+            let eft = match eft_index {
+                Some(j) if matched => match pvals[j].as_str() {
+                    "deny" => EffectKind::Deny,
+                    "allow" => EffectKind::Allow,
+                    _ => EffectKind::Indeterminate,
+                },
+                None if matched => EffectKind::Allow,
+                _ => EffectKind::Indeterminate,
+            };
+            if eft_stream.push_effect(eft) {
+                break;
+            }
+        }
+        Ok((eft_stream.next(), eft_stream.explain()))
+    }
+}
+
+// Matchers keeps the matchers of an enforcer compiled across its decisions: the matcher of its
+// model, until it changes, and the matchers most recently given to EnforceWithMatcher.
+pub struct Matchers {
+    evaluator: RwLock<Option<Arc<Evaluator>>>,
+    roles: Arc<RoleCache>,
+    // custom are the functions defined for the matchers with AddCustomFunction, kept whatever
+    // model the enforcer runs.
+    custom: RwLock<Vec<CustomFunction>>,
+    functions: Arc<Functions>,
+}
+
+impl Matchers {
+    // new compiles the matchers of an enforcer resolving its roles through roles, and letting
+    // them call functions.
+    pub fn new(roles: Arc<RoleCache>, functions: Arc<Functions>) -> Self {
+        Matchers {
+            evaluator: Default::default(),
+            roles,
+            custom: Default::default(),
+            functions,
+        }
+    }
+
+    // add_function lets the matchers call f, in place of the function of the same name defined
+    // before, if any. The matchers are compiled again for their next decision.
+    pub fn add_function(&self, f: CustomFunction) {
+        let mut custom = self.custom.write().unwrap();
+        custom.retain(|other| other.name != f.name);
+        custom.push(f);
+        *self.evaluator.write().unwrap() = None;
+    }
+
+    pub fn functions(&self) -> Vec<CustomFunction> {
+        self.custom.read().unwrap().clone()
+    }
+
+    fn evaluator(&self, e: &CachedEnforcer) -> casbin::Result<Arc<Evaluator>> {
+        if let Some(evaluator) = self.evaluator.read().unwrap().as_ref() {
+            if evaluator.fits(e) {
+                return Ok(evaluator.clone());
+            }
+        }
+        let custom = self.custom.read().unwrap();
+        let evaluator = Arc::new(Evaluator::new(e, &self.roles, &custom, &self.functions)?);
+        *self.evaluator.write().unwrap() = Some(evaluator.clone());
+        Ok(evaluator)
+    }
+
+    // enforce decides rvals like the enforce of casbin, logging the decision and the rules
+    // explaining it through the logger of e, with matcher in place of the matcher of the model
+    // unless it is empty.
+    pub fn enforce<ARGS: EnforceArgs>(
+        &self,
+        e: &CachedEnforcer,
+        matcher: &str,
+        rvals: ARGS,
+    ) -> casbin::Result<bool> {
+        let rvals = rvals.try_into_vec()?;
+        let evaluator = self.evaluator(e)?;
+        let ast = if matcher.is_empty() {
+            evaluator.model_matcher(definition(e, "m", ModelError::M, "matcher")?)?
+        } else {
+            evaluator.matcher(matcher)?
+        };
+        let (authorized, indices) = evaluator.decide(e, &ast, &rvals)?;
+
+        let logger = e.get_logger();
+        logger.print_enforce_log(
+            rvals.iter().map(|x| x.to_string()).collect(),
+            authorized,
+            false,
+        );
+        if let Some(indices) = indices {
+            let indices: HashSet<usize> = indices.into_iter().collect();
+            let rules: Vec<String> = definition(e, "p", ModelError::P, "policy")?
+                .get_policy()
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| indices.contains(i))
+                .map(|(_, rule)| rule.join(", "))
+                .collect();
+            logger.print_explain_log(rules);
+        }
+        Ok(authorized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Matchers;
+    use crate::server::abac::resolve_abac;
+    use crate::server::enforcer;
+    use casbin::{CachedEnforcer, CoreApi, DefaultModel, FileAdapter, MemoryAdapter, MgmtApi};
+    use std::collections::BTreeSet;
+
+    async fn load(model: &str, policy: &'static str) -> CachedEnforcer {
+        let m = DefaultModel::from_file(model).await.unwrap();
+        enforcer::build_enforcer(m, FileAdapter::new(policy))
+            .await
+            .unwrap()
+    }
+
+    // requests makes every request of tokens values out of the values of the rules of e, and a
+    // value none of them has.
+    fn requests(e: &CachedEnforcer, tokens: usize) -> Vec<Vec<String>> {
+        let mut values: BTreeSet<String> = e
+            .get_policy()
+            .into_iter()
+            .chain(e.get_grouping_policy())
+            .flatten()
+            .collect();
+        values.insert("nobody".to_owned());
+        let mut requests = vec![vec![]];
+        for _ in 0..tokens {
+            requests = requests
+                .into_iter()
+                .flat_map(|request: Vec<String>| {
+                    values.iter().map(move |value| {
+                        let mut request = request.clone();
+                        request.push(value.clone());
+                        request
+                    })
+                })
+                .collect();
+        }
+        requests
+    }
+
+    // assert_decides_like_casbin checks that the compiled matchers decide each request like
+    // the enforce of casbin does.
+    fn assert_decides_like_casbin(e: &CachedEnforcer, requests: Vec<Vec<String>>) {
+        let matchers = Matchers::new(Default::default(), Default::default());
+        let mut allowed = 0;
+        for request in requests {
+            let want = e.enforce(resolve_abac(request.clone()).unwrap()).unwrap();
+            let got = matchers
+                .enforce(e, "", resolve_abac(request.clone()).unwrap())
+                .unwrap();
+            assert_eq!(got, want, "{:?}", request);
+            allowed += usize::from(got);
+        }
+        assert!(allowed > 0);
+    }
+
+    #[tokio::test]
+    async fn test_rbac_like_casbin() {
+        let e = load("examples/rbac_model.conf", "examples/rbac_policy.csv").await;
+        assert_decides_like_casbin(&e, requests(&e, 3));
+    }
+
+    #[tokio::test]
+    async fn test_rbac_with_domains_like_casbin() {
+        let e = load(
+            "examples/rbac_with_domains_model.conf",
+            "examples/rbac_with_domains_policy.csv",
+        )
+        .await;
+        assert_decides_like_casbin(&e, requests(&e, 4));
+    }
+
+    #[tokio::test]
+    async fn test_rbac_with_deny_like_casbin() {
+        let e = load(
+            "examples/rbac_with_deny_model.conf",
+            "examples/rbac_with_deny_policy.csv",
+        )
+        .await;
+        assert_decides_like_casbin(&e, requests(&e, 3));
+    }
+
+    #[tokio::test]
+    async fn test_priority_like_casbin() {
+        let e = load(
+            "examples/priority_model.conf",
+            "examples/priority_policy.csv",
+        )
+        .await;
+        assert_decides_like_casbin(&e, requests(&e, 3));
+    }
+
+    #[tokio::test]
+    async fn test_keymatch2_like_casbin() {
+        let e = load(
+            "examples/keymatch2_model.conf",
+            "examples/keymatch2_policy.csv",
+        )
+        .await;
+        assert_decides_like_casbin(&e, requests(&e, 3));
+    }
+
+    #[tokio::test]
+    async fn test_abac_like_casbin() {
+        let m = DefaultModel::from_file("examples/abac_model.conf")
+            .await
+            .unwrap();
+        let mut e = enforcer::build_enforcer(m, MemoryAdapter::default())
+            .await
+            .unwrap();
+        e.add_policy(vec!["any".into(), "data1".into(), "read".into()])
+            .await
+            .unwrap();
+        let mut requests = Vec::new();
+        for sub in ["alice", "bob"] {
+            for owner in ["alice", "bob", "cathy"] {
+                requests.push(vec![
+                    sub.to_owned(),
+                    format!(r#"ABAC::{{"Owner": "{}"}}"#, owner),
+                    "read".to_owned(),
+                ]);
+            }
+        }
+        assert_decides_like_casbin(&e, requests);
+    }
+}
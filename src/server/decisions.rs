@@ -0,0 +1,193 @@
+use crate::casbin_proto::{decision_event, DecisionEvent, DecisionFilter};
+use crate::server::audit::{AuditLog, Caller, Entry};
+use crate::server::registry::{now_millis, EnforcerEntry};
+use crate::CasbinGRPC;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use tokio::sync::{watch, Notify};
+use tonic::Request;
+
+// DECISIONS_BUFFER is how many decisions the server keeps for a WatchDecisions subscriber that
+// did not ask for a buffer of its own.
+pub static DECISIONS_BUFFER: usize = 1024;
+
+// DecisionFeed passes the decisions of an enforcer on to the clients subscribed through
+// WatchDecisions, each through a buffer of its own.
+pub struct DecisionFeed {
+    subscribers: Mutex<Vec<Arc<Subscriber>>>,
+    watched: AtomicUsize,
+    closed: watch::Sender<bool>,
+}
+
+impl Default for DecisionFeed {
+    fn default() -> Self {
+        DecisionFeed {
+            subscribers: Default::default(),
+            watched: AtomicUsize::new(0),
+            closed: watch::channel(false).0,
+        }
+    }
+}
+
+// Subscriber is a client subscribed to a feed, with the decisions matching its filter that it
+// has yet to be sent.
+struct Subscriber {
+    filter: DecisionFilter,
+    capacity: usize,
+    events: Mutex<VecDeque<DecisionEvent>>,
+    ready: Notify,
+}
+
+impl Subscriber {
+    fn wants(&self, entry: &Entry) -> bool {
+        let filter = &self.filter;
+        (filter.methods.is_empty() || filter.methods.iter().any(|m| m == entry.method))
+            && match filter.decision.as_str() {
+                "allow" => entry.allowed,
+                "deny" => !entry.allowed,
+                _ => true,
+            }
+            && filter
+                .params
+                .iter()
+                .enumerate()
+                .all(|(i, p)| p.is_empty() || entry.request.get(i) == Some(p))
+    }
+
+    // push queues event, dropping the oldest decision queued when the buffer is full. The
+    // decision after it then counts the decisions missed.
+    fn push(&self, mut event: DecisionEvent) {
+        let mut events = self.events.lock().unwrap();
+        if events.len() >= self.capacity {
+            let missed = events.pop_front().map_or(0, |dropped| dropped.missed + 1);
+            match events.front_mut() {
+                Some(next) => next.missed += missed,
+                None => event.missed += missed,
+            }
+        }
+        events.push_back(event);
+        drop(events);
+        self.ready.notify_one();
+    }
+}
+
+impl DecisionFeed {
+    // subscribe subscribes to the decisions matching filter, keeping up to capacity of them
+    // while the subscriber falls behind.
+    pub fn subscribe(self: &Arc<Self>, filter: DecisionFilter, capacity: usize) -> Subscription {
+        let subscriber = Arc::new(Subscriber {
+            filter,
+            capacity: capacity.max(1),
+            events: Default::default(),
+            ready: Notify::new(),
+        });
+        self.subscribers.lock().unwrap().push(subscriber.clone());
+        self.watched.fetch_add(1, Ordering::Relaxed);
+        Subscription {
+            subscriber,
+            feed: Arc::downgrade(self),
+        }
+    }
+
+    // watched tells whether any client is subscribed, so decisions are only described for one.
+    pub fn watched(&self) -> bool {
+        self.watched.load(Ordering::Relaxed) > 0
+    }
+
+    // closed gets a flag set once the feed is closed, when its subscribers should end their
+    // streams.
+    pub fn closed(&self) -> watch::Receiver<bool> {
+        self.closed.subscribe()
+    }
+
+    // close ends the streams of the subscribers, as the server is stopping.
+    pub fn close(&self) {
+        self.closed.send_replace(true);
+    }
+
+    // publish passes a decision on to the subscribers whose filter it matches.
+    pub fn publish(&self, entry: &Entry) {
+        if !self.watched() {
+            return;
+        }
+        let mut event = None;
+        for subscriber in self.subscribers.lock().unwrap().iter() {
+            if !subscriber.wants(entry) {
+                continue;
+            }
+            let event = event.get_or_insert_with(|| DecisionEvent {
+                timestamp_ms: now_millis(),
+                method: entry.method.to_owned(),
+                params: entry.request.to_vec(),
+                matcher: entry.matcher.to_owned(),
+                allowed: entry.allowed,
+                rules: entry
+                    .rules
+                    .iter()
+                    .map(|params| decision_event::D {
+                        params: params.clone(),
+                    })
+                    .collect(),
+                subject: entry.caller.subject.clone().unwrap_or_default(),
+                api_key: entry.caller.api_key.clone().unwrap_or_default(),
+                cert: entry.caller.cert.clone().unwrap_or_default(),
+                addr: entry.caller.addr.clone().unwrap_or_default(),
+                missed: 0,
+            });
+            subscriber.push(event.clone());
+        }
+    }
+}
+
+// Subscription is the subscription of a client to a feed, ended once dropped.
+pub struct Subscription {
+    subscriber: Arc<Subscriber>,
+    feed: Weak<DecisionFeed>,
+}
+
+impl Subscription {
+    // next waits for the next decision for the subscriber.
+    pub async fn next(&self) -> DecisionEvent {
+        loop {
+            if let Some(event) = self.subscriber.events.lock().unwrap().pop_front() {
+                return event;
+            }
+            self.subscriber.ready.notified().await;
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(feed) = self.feed.upgrade() {
+            feed.subscribers
+                .lock()
+                .unwrap()
+                .retain(|s| !Arc::ptr_eq(s, &self.subscriber));
+            feed.watched.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+// record reports a decision to the audit log, if there is one, and to the subscribers of the
+// decisions of the enforcer making it.
+pub async fn record(audit: Option<&AuditLog>, entry: &EnforcerEntry, decision: Entry<'_>) {
+    entry.decisions.publish(&decision);
+    if let Some(audit) = audit {
+        audit.record(decision).await;
+    }
+}
+
+impl CasbinGRPC {
+    // caller_of identifies the caller of request, when the decisions it asks entry for are to be
+    // reported.
+    pub fn caller_of<T>(&self, request: &Request<T>, entry: &EnforcerEntry) -> Option<Caller> {
+        (self.audit.is_some() || entry.decisions.watched()).then(|| Caller::of(request))
+    }
+
+    // record reports a decision of entry to the audit log and the subscribers of its decisions.
+    pub async fn record(&self, entry: &EnforcerEntry, decision: Entry<'_>) {
+        record(self.audit.as_deref(), entry, decision).await
+    }
+}
@@ -0,0 +1,120 @@
+use crate::CasbinGRPC;
+
+use casbin::{Assertion, CachedEnforcer, CoreApi, MgmtApi};
+
+// A rule that belongs to a domain, grouped by the section and policy type holding it.
+type DomainRules = (&'static str, String, Vec<Vec<String>>);
+
+impl CasbinGRPC {
+    // domain_index finds the field holding the domain in the rules of an assertion: the `dom`
+    // token of a policy definition, or the third field of a role definition.
+    fn domain_index(&self, sec: &str, ptype: &str, ast: &Assertion) -> Option<usize> {
+        if sec == "g" {
+            if ast.value.matches('_').count() >= 3 {
+                Some(2)
+            } else {
+                None
+            }
+        } else {
+            let dom = format!("{}_dom", ptype);
+            ast.tokens.iter().position(|token| *token == dom)
+        }
+    }
+
+    // domain_rules collects, per section and policy type, the rules whose domain field
+    // satisfies is_match.
+    fn domain_rules<F>(&self, e: &CachedEnforcer, is_match: F) -> Vec<DomainRules>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut res = vec![];
+        for sec in ["p", "g"] {
+            if let Some(assertions) = e.get_model().get_model().get(sec) {
+                for (ptype, ast) in assertions.iter() {
+                    if let Some(index) = self.domain_index(sec, ptype, ast) {
+                        let rules: Vec<Vec<String>> = ast
+                            .get_policy()
+                            .iter()
+                            .filter(|rule| rule.get(index).is_some_and(|dom| is_match(dom)))
+                            .cloned()
+                            .collect();
+                        if !rules.is_empty() {
+                            res.push((sec, ptype.to_owned(), rules));
+                        }
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    // domains_where gets the distinct domains of the p and g rules satisfying keep.
+    fn domains_where<F>(&self, e: &CachedEnforcer, keep: F) -> Vec<String>
+    where
+        F: Fn(&[String]) -> bool,
+    {
+        let mut domains = vec![];
+        for sec in ["p", "g"] {
+            if let Some(assertions) = e.get_model().get_model().get(sec) {
+                for (ptype, ast) in assertions.iter() {
+                    if let Some(index) = self.domain_index(sec, ptype, ast) {
+                        domains.extend(
+                            ast.get_policy()
+                                .iter()
+                                .filter(|rule| keep(rule))
+                                .filter_map(|rule| rule.get(index).cloned()),
+                        );
+                    }
+                }
+            }
+        }
+        domains.sort();
+        domains.dedup();
+        domains
+    }
+
+    // list_domains gets the distinct domains referenced by any p or g rule.
+    pub fn list_domains(&self, e: &CachedEnforcer) -> Vec<String> {
+        self.domains_where(e, |_| true)
+    }
+
+    // domains_for_user gets the distinct domains in which the user has a role or a permission.
+    pub fn domains_for_user(&self, e: &CachedEnforcer, user: &str) -> Vec<String> {
+        self.domains_where(e, |rule| rule.first().is_some_and(|sub| sub == user))
+    }
+
+    // remove_domains removes every p and g rule belonging to one of the domains. The rules are
+    // removed one policy type at a time while the caller holds the enforcer, and those already
+    // removed are restored if a later removal does not go through.
+    // Returns false if no rule belongs to the domains (aka not affected).
+    pub async fn remove_domains(
+        &self,
+        e: &mut CachedEnforcer,
+        domains: &[String],
+    ) -> casbin::Result<bool> {
+        let targets = self.domain_rules(e, |dom| domains.iter().any(|d| d == dom));
+        let mut removed: Vec<DomainRules> = vec![];
+        for (sec, ptype, rules) in targets {
+            let res = if sec == "p" {
+                e.remove_named_policies(&ptype, rules.to_vec()).await
+            } else {
+                e.remove_named_grouping_policies(&ptype, rules.to_vec())
+                    .await
+            };
+            match res {
+                Ok(true) => removed.push((sec, ptype, rules)),
+                res => {
+                    for (sec, ptype, rules) in removed.into_iter().rev() {
+                        if sec == "p" {
+                            e.add_named_policies(&ptype, rules).await?;
+                        } else {
+                            e.add_named_grouping_policies(&ptype, rules).await?;
+                        }
+                    }
+                    return res;
+                }
+            }
+        }
+        Ok(!removed.is_empty())
+    }
+}
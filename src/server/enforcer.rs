@@ -1,61 +1,125 @@
-use std::{borrow::BorrowMut, cell::RefCell, collections::HashMap, ops::Deref};
-
-use crate::server::abac;
+use crate::server::explain::{ExplainEffector, ExplainLogger};
+use crate::server::functions;
+use crate::server::logger;
+use crate::server::priority;
+use crate::server::registry::SharedEnforcer;
+use crate::watcher::Message;
 use crate::CasbinGRPC;
-use casbin::{Adapter, CachedEnforcer};
+use casbin::{
+    Adapter, CachedApi, CachedEnforcer, CoreApi, DefaultModel, Event, EventEmitter, InternalApi,
+    TryIntoAdapter,
+};
 
 use futures::lock::Mutex;
 use std::sync::Arc;
 
+// build_enforcer creates the enforcer stored behind a handle, with the ExplainLogger and
+// ExplainEffector that EnforceEx relies on to report the matched rules, the standard matcher
+// functions, and its policies kept in priority order.
+pub async fn build_enforcer<A: TryIntoAdapter>(
+    m: DefaultModel,
+    a: A,
+) -> casbin::Result<CachedEnforcer> {
+    let mut e = CachedEnforcer::new(m, a).await?;
+    e.set_logger(Box::new(ExplainLogger::default()));
+    e.set_effector(Box::new(ExplainEffector));
+    functions::add_functions(&mut e);
+    e.on(Event::PolicyChange, priority::sort_on_change);
+    priority::sort_policies(&mut e);
+    Ok(e)
+}
+
+// reload_policy replaces the rules of an enforcer with those of its adapter, keeping them in
+// priority order and dropping the decisions cached against the old ones.
+pub async fn reload_policy(e: &mut CachedEnforcer) -> casbin::Result<()> {
+    e.load_policy().await?;
+    priority::sort_policies(e);
+    e.get_mut_cache().clear();
+    logger::log_loaded(e);
+    Ok(())
+}
+
+// apply_update applies a policy change notified by another replica to the rules in memory,
+// without saving it again through the adapter the replica saved it to. It returns false for
+// changes that carry no rules, for which the whole policy has to be reloaded instead.
+pub async fn apply_update(e: &mut CachedEnforcer, msg: Message) -> casbin::Result<bool> {
+    let (sec, ptype) = (msg.sec.as_str(), msg.ptype.as_str());
+    if sec.is_empty() || ptype.is_empty() {
+        return Ok(false);
+    }
+    let auto_save = e.has_auto_save_enabled();
+    e.enable_auto_save(false);
+    let res = match msg.method.as_str() {
+        "UpdateForAddPolicy" => e.add_policy_internal(sec, ptype, msg.new_rule).await,
+        "UpdateForAddPolicies" => e.add_policies_internal(sec, ptype, msg.new_rules).await,
+        "UpdateForRemovePolicy" => e.remove_policy_internal(sec, ptype, msg.new_rule).await,
+        "UpdateForRemovePolicies" => e.remove_policies_internal(sec, ptype, msg.new_rules).await,
+        "UpdateForRemoveFilteredPolicy" => e
+            .remove_filtered_policy_internal(sec, ptype, msg.field_index, msg.field_values)
+            .await
+            .map(|(removed, _)| removed),
+        "UpdateForUpdatePolicy" => match e.remove_policy_internal(sec, ptype, msg.old_rule).await {
+            Ok(_) => e.add_policy_internal(sec, ptype, msg.new_rule).await,
+            err => err,
+        },
+        "UpdateForUpdatePolicies" => {
+            match e.remove_policies_internal(sec, ptype, msg.old_rules).await {
+                Ok(_) => e.add_policies_internal(sec, ptype, msg.new_rules).await,
+                err => err,
+            }
+        }
+        _ => {
+            e.enable_auto_save(auto_save);
+            return Ok(false);
+        }
+    };
+    e.enable_auto_save(auto_save);
+    res.map(|_| true)
+}
+
 impl CasbinGRPC {
     pub fn new_server() -> Self {
         Self {
-            enforcer_map: HashMap::new(),
-            adapter_map: HashMap::new(),
+            enforcers: Default::default(),
+            adapter_map: Default::default(),
+            drivers: Default::default(),
+            tls: None,
+            api_keys: Default::default(),
+            api_key_tenants: Default::default(),
+            unix_socket: None,
+            audit: None,
+            decision_cache: Default::default(),
+            batch_parallelism: 0,
+            reloader: None,
+            max_rules: 0,
+            tenant_model: None,
         }
     }
 
-    pub async fn get_enforcer(&self, handle: i32) -> Result<Arc<Mutex<CachedEnforcer>>, &str> {
-        let wrapped_cachedenforcer = self.enforcer_map.get(&handle).unwrap().to_owned();
-        Ok(wrapped_cachedenforcer)
-        // self.enforcer_map.get(&handle).ok_or("No enforcer found")
+    pub async fn get_enforcer(&self, handle: i32) -> Result<Arc<SharedEnforcer>, &str> {
+        self.enforcers
+            .get(handle)
+            .await
+            .map(|entry| entry.enforcer.clone())
+            .ok_or("No enforcer found")
     }
     pub async fn get_adapter(&self, handle: i32) -> Result<Arc<Mutex<Box<dyn Adapter>>>, &str> {
-        // let wrapped_adapter = self.adapter_map.get(&handle).unwrap().to_owned();
-        // let x = self.adapter_map.get(&handle).unwrap().to_owned();
-        // Ok(wrapped_adapter)
-        let x = self.adapter_map.get(&handle).unwrap().to_owned();
-        let y = self.adapter_map.get_key_value(&handle);
-        Ok(x)
-        // self.adapter_map.get(&handle).ok_or("No adapter found")
+        self.adapter_map
+            .read()
+            .await
+            .get(&handle)
+            .cloned()
+            .ok_or("No adapter found")
     }
-    pub fn add_enforcer(&mut self, e: Arc<Mutex<CachedEnforcer>>) -> i32 {
-        let cnt: i32 = self.enforcer_map.len() as i32;
-        self.enforcer_map.insert(cnt, e);
-        cnt
+    pub async fn add_enforcer(&self, e: CachedEnforcer, model_text: String) -> i32 {
+        self.enforcers
+            .insert(e, model_text, self.decision_cache)
+            .await
     }
-    pub fn add_adapter(&mut self, a: Arc<Mutex<Box<dyn Adapter>>>) -> i32 {
-        let cnt: i32 = self.adapter_map.len() as i32;
-        self.adapter_map.insert(cnt, a);
+    pub async fn add_adapter(&self, a: Arc<Mutex<Box<dyn Adapter>>>) -> i32 {
+        let mut adapter_map = self.adapter_map.write().await;
+        let cnt: i32 = adapter_map.len() as i32;
+        adapter_map.insert(cnt, a);
         cnt
     }
-    pub fn parse_param(
-        &self,
-        param: String,
-        matcher: &mut String,
-    ) -> Result<abac::AbacAttrList, String> {
-        if param.starts_with("ABAC::") {
-            let attr_list = abac::resolve_abac(String::from(param)).expect("");
-            for (k, v) in attr_list.name_map.iter() {
-                let old: String = format!("{}{}", ".", &k);
-                let new: String = format!("{}{}", ".", &v);
-                if matcher.contains(&old) {
-                    matcher.replace(&old, &new);
-                }
-            }
-            return Ok(attr_list);
-        } else {
-            return Err(param);
-        }
-    }
 }
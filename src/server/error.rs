@@ -0,0 +1,26 @@
+use crate::server::metrics;
+use casbin::error::{ModelError, RbacError};
+use casbin::Error;
+use std::io::ErrorKind;
+use tonic::Status;
+
+// casbin_status converts a casbin error into a gRPC status whose code tells the
+// client whether the request, the enforcer's model or the backing store is at fault.
+pub fn casbin_status(err: Error) -> Status {
+    metrics::failed(&err);
+    let message = err.to_string();
+    match err {
+        Error::IoError(ref io_err) => match io_err.kind() {
+            ErrorKind::NotFound => Status::not_found(message),
+            ErrorKind::PermissionDenied => Status::permission_denied(message),
+            ErrorKind::NotConnected | ErrorKind::TimedOut => Status::unavailable(message),
+            _ => Status::internal(message),
+        },
+        Error::ModelError(ModelError::M(_)) => Status::invalid_argument(message),
+        Error::ModelError(_) => Status::failed_precondition(message),
+        Error::PolicyError(_) | Error::RequestError(_) => Status::invalid_argument(message),
+        Error::RhaiError(_) | Error::RhaiParseError(_) => Status::invalid_argument(message),
+        Error::RbacError(RbacError::NotFound(_)) => Status::not_found(message),
+        Error::AdapterError(_) => Status::unavailable(message),
+    }
+}
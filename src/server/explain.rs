@@ -0,0 +1,229 @@
+use crate::server::logger;
+use crate::server::metrics;
+use crate::server::priority;
+use crate::server::registry::EnforcerRead;
+use crate::server::trace;
+use casbin::{
+    DefaultEffector, EffectKind, Effector, EffectorStream, EnforceArgs, EventData, Logger,
+};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+thread_local! {
+    // Rules reported by the last explained enforcement on this thread.
+    static EXPLAINED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+    // Request and decision of the enforcement on this thread awaiting its rules to be logged.
+    static PENDING: RefCell<Option<(Vec<String>, bool)>> = const { RefCell::new(None) };
+    // Request of the last enforcement on this thread, kept while slow decisions are logged.
+    static REQUEST: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+// SLOW_DECISION_MICROS is how long a decision may take before log_if_slow logs it, 0 when
+// decisions are not logged however long they take.
+static SLOW_DECISION_MICROS: AtomicU64 = AtomicU64::new(0);
+
+// set_slow_decision makes log_if_slow log the decisions taking longer than threshold, or none
+// when it is 0.
+pub fn set_slow_decision(threshold: Duration) {
+    SLOW_DECISION_MICROS.store(threshold.as_micros() as u64, Ordering::Relaxed);
+}
+
+// log_if_slow logs the decision just made on this thread by method, with its request and the
+// number of rules that matched, if its elapsed time is over the threshold. It must be called
+// before the rules are taken.
+pub fn log_if_slow(method: &str, elapsed: Duration) {
+    let threshold = SLOW_DECISION_MICROS.load(Ordering::Relaxed);
+    if threshold == 0 || elapsed.as_micros() as u64 <= threshold {
+        return;
+    }
+    let request = REQUEST
+        .with(|request| request.borrow_mut().take())
+        .unwrap_or_default();
+    let matched = EXPLAINED.with(|explained| explained.borrow().as_ref().map_or(0, Vec::len));
+    tracing::warn!(
+        target: "casbin",
+        method,
+        ?elapsed,
+        request = %request.join(", "),
+        matched,
+        "slow decision"
+    );
+}
+
+// ExplainLogger records the policy rules casbin reports as matched so they can be returned to
+// clients. While enabled, it logs the decisions through the logger of the enforcers, those not
+// taken from the cache of casbin along with these rules once take_explained takes them.
+#[derive(Default)]
+pub struct ExplainLogger {
+    enabled: bool,
+}
+
+impl Logger for ExplainLogger {
+    fn enable_log(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn print_enforce_log(&self, rvals: Vec<String>, authorized: bool, cached: bool) {
+        if SLOW_DECISION_MICROS.load(Ordering::Relaxed) > 0 {
+            // Decisions from the cache of casbin have no request to log.
+            let rvals = (!cached).then(|| rvals.clone());
+            REQUEST.with(|request| *request.borrow_mut() = rvals);
+        }
+        if !self.enabled {
+            return;
+        }
+        if cached {
+            logger::logger().log_enforce(&rvals, authorized, &[]);
+            return;
+        }
+        // A decision left pending was made by a caller not taking its rules.
+        if let Some((rvals, authorized)) =
+            PENDING.with(|pending| pending.borrow_mut().replace((rvals, authorized)))
+        {
+            logger::logger().log_enforce(&rvals, authorized, &[]);
+        }
+    }
+
+    fn print_mgmt_log(&self, _d: &EventData) {}
+
+    fn print_explain_log(&self, rules: Vec<String>) {
+        EXPLAINED.with(|explained| *explained.borrow_mut() = Some(rules));
+    }
+
+    fn print_status_log(&self, _enabled: bool) {}
+}
+
+static PRIORITY: &str = "priority(p_eft) || deny";
+
+// ExplainEffector behaves like casbin's DefaultEffector, but also explains
+// decisions over a policy holding a single rule and priority decisions, which
+// DefaultEffectStream leaves unexplained. It decides subject priority like
+// priority, relying on priority::sort_policies to order the rules.
+#[derive(Default)]
+pub struct ExplainEffector;
+
+impl Effector for ExplainEffector {
+    fn new_stream(&self, expr: &str, cap: usize) -> Box<dyn EffectorStream> {
+        let expr = if expr == priority::SUBJECT_PRIORITY {
+            PRIORITY
+        } else {
+            expr
+        };
+        let inner = DefaultEffector.new_stream(expr, cap);
+        if expr == PRIORITY {
+            return Box::new(PriorityStream {
+                inner,
+                idx: 0,
+                decided: None,
+            });
+        }
+        if cap > 1 {
+            return inner;
+        }
+        Box::new(SingleRuleStream {
+            inner,
+            expr: expr.to_owned(),
+            matched: false,
+        })
+    }
+}
+
+// SingleRuleStream records whether the only rule decided the effect, using
+// the same conditions under which DefaultEffectStream records a rule.
+struct SingleRuleStream {
+    inner: Box<dyn EffectorStream>,
+    expr: String,
+    matched: bool,
+}
+
+impl EffectorStream for SingleRuleStream {
+    fn next(&self) -> bool {
+        self.inner.next()
+    }
+
+    fn explain(&self) -> Option<Vec<usize>> {
+        if self.matched {
+            Some(vec![0])
+        } else {
+            None
+        }
+    }
+
+    fn push_effect(&mut self, eft: EffectKind) -> bool {
+        self.matched = match self.expr.as_str() {
+            "some(where (p_eft == allow))" => eft == EffectKind::Allow,
+            "some(where (p_eft == allow)) && !some(where (p_eft == deny))" => {
+                eft != EffectKind::Indeterminate
+            }
+            "!some(where (p_eft == deny))" => eft == EffectKind::Deny,
+            _ => false,
+        };
+        self.inner.push_effect(eft)
+    }
+}
+
+// PriorityStream records the first rule with a definite effect, which is the
+// rule that decides under priority.
+struct PriorityStream {
+    inner: Box<dyn EffectorStream>,
+    idx: usize,
+    decided: Option<usize>,
+}
+
+impl EffectorStream for PriorityStream {
+    fn next(&self) -> bool {
+        self.inner.next()
+    }
+
+    fn explain(&self) -> Option<Vec<usize>> {
+        self.decided.map(|idx| vec![idx])
+    }
+
+    fn push_effect(&mut self, eft: EffectKind) -> bool {
+        if self.decided.is_none() && eft != EffectKind::Indeterminate {
+            self.decided = Some(self.idx);
+        }
+        self.idx += 1;
+        self.inner.push_effect(eft)
+    }
+}
+
+// enforce_ex enforces without consulting the decision cache, so the matcher is
+// always evaluated, and returns the decision with the rules that matched.
+// The enforcer must have an ExplainLogger installed.
+pub fn enforce_ex<ARGS: EnforceArgs>(
+    e: &EnforcerRead,
+    rvals: ARGS,
+) -> casbin::Result<(bool, Vec<Vec<String>>)> {
+    take_explained();
+    let (start, mut span) = (Instant::now(), trace::Span::start("casbin.enforce_ex"));
+    let res = e.enforce_with("", rvals);
+    if let Err(err) = &res {
+        span.fail(err);
+    }
+    let res = res?;
+    metrics::decided("EnforceEx", res, start.elapsed());
+    log_if_slow("EnforceEx", start.elapsed());
+    span.set_bool("casbin.allowed", res);
+    Ok((res, take_explained()))
+}
+
+// take_explained takes the rules casbin reported as matched by the last enforcement on this
+// thread, each split into its values, and logs the decision with them if it is to be logged.
+pub fn take_explained() -> Vec<Vec<String>> {
+    let rules: Vec<Vec<String>> = EXPLAINED
+        .with(|explained| explained.borrow_mut().take())
+        .unwrap_or_default()
+        .iter()
+        .map(|rule| rule.split(", ").map(String::from).collect())
+        .collect();
+    if let Some((rvals, authorized)) = PENDING.with(|pending| pending.borrow_mut().take()) {
+        logger::logger().log_enforce(&rvals, authorized, &rules);
+    }
+    rules
+}
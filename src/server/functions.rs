@@ -0,0 +1,926 @@
+use crate::datastructure::lru::LruCache;
+use casbin::rhai::{
+    Dynamic, Engine, EvalAltResult, ImmutableString, NativeCallContext, Scope, AST,
+};
+use casbin::CoreApi;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::any::TypeId;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+// STANDARD are the names of the standard functions.
+static STANDARD: &[&str] = &[
+    "keyMatch",
+    "keyGet",
+    "keyMatch2",
+    "keyGet2",
+    "keyMatch3",
+    "keyGet3",
+    "keyMatch4",
+    "keyMatch5",
+    "regexMatch",
+    "ipMatch",
+    "globMatch",
+];
+
+// PATTERNS_CAPACITY bounds the patterns the functions keep compiled, the least recently used
+// being dropped to make room.
+static PATTERNS_CAPACITY: usize = 1024;
+
+// The functions of the standard library of casbin matchers, implemented as the Go casbin does.
+// A pattern that is not a valid regular expression, or an argument of ipMatch that is not an
+// address, fails the decision instead of panicking like the functions casbin comes with.
+
+// compile compiles pattern, or gets it compiled from the last patterns used.
+fn compile(pattern: &str) -> Result<Regex, String> {
+    static PATTERNS: OnceLock<Mutex<LruCache<String, Regex>>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| Mutex::new(LruCache::new(PATTERNS_CAPACITY)));
+    if let Some(re) = patterns.lock().unwrap().get(&pattern.to_owned()) {
+        return Ok(re.clone());
+    }
+    let re = Regex::new(pattern).map_err(|err| format!("invalid pattern {}: {}", pattern, err))?;
+    patterns
+        .lock()
+        .unwrap()
+        .insert(pattern.to_owned(), re.clone());
+    Ok(re)
+}
+
+fn static_regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
+
+// colon_vars matches the `:var` of keyMatch2 and keyGet2, and brace_vars the `{var}` of
+// keyMatch3 to keyMatch5 and keyGet3.
+fn colon_vars() -> &'static Regex {
+    static COLON: OnceLock<Regex> = OnceLock::new();
+    static_regex(&COLON, r":[^/]+")
+}
+
+fn brace_vars() -> &'static Regex {
+    static BRACE: OnceLock<Regex> = OnceLock::new();
+    static_regex(&BRACE, r"\{([^/]+?)\}")
+}
+
+// key_match tells whether key1 matches key2, which may end with a `*` matching anything, such
+// as /foo/bar matching /foo/*.
+pub fn key_match(key1: &str, key2: &str) -> bool {
+    match key2.find('*') {
+        Some(i) if key1.len() > i => key1.as_bytes()[..i] == key2.as_bytes()[..i],
+        Some(i) => key1 == &key2[..i],
+        None => key1 == key2,
+    }
+}
+
+// key_get returns what the `*` of key2 matches in key1, or nothing when key1 does not match,
+// such as bar for /foo/bar and /foo/*.
+pub fn key_get(key1: &str, key2: &str) -> String {
+    match key2.find('*') {
+        Some(i) if key1.len() > i && key1.as_bytes()[..i] == key2.as_bytes()[..i] => {
+            String::from_utf8_lossy(&key1.as_bytes()[i..]).into_owned()
+        }
+        _ => String::new(),
+    }
+}
+
+// key_match2 tells whether key1 matches key2, whose `:var` match a segment and `/*` anything,
+// such as /resource1 matching /:resource.
+pub fn key_match2(key1: &str, key2: &str) -> Result<bool, String> {
+    let key2 = key2.replace("/*", "/.*");
+    let key2 = colon_vars().replace_all(&key2, "[^/]+");
+    regex_match(key1, &format!("^{}$", key2))
+}
+
+// key_get2 returns the segment of key1 the `:var` of key2 named var matches, or nothing, such
+// as 123 for /resource/123, /resource/:id and id.
+pub fn key_get2(key1: &str, key2: &str, var: &str) -> Result<String, String> {
+    let key2 = key2.replace("/*", "/.*");
+    let vars: Vec<&str> = colon_vars()
+        .find_iter(&key2)
+        .map(|var| &var.as_str()[1..])
+        .collect();
+    let pattern = colon_vars().replace_all(&key2, "([^/]+)");
+    let captures = match compile(&format!("^{}$", pattern))?.captures(key1) {
+        Some(captures) => captures,
+        None => return Ok(String::new()),
+    };
+    Ok(vars
+        .iter()
+        .position(|name| *name == var)
+        .and_then(|i| captures.get(i + 1))
+        .map_or_else(String::new, |value| value.as_str().to_owned()))
+}
+
+// key_match3 tells whether key1 matches key2, whose `{var}` match a segment and `/*` anything,
+// such as /resource1 matching /{resource}.
+pub fn key_match3(key1: &str, key2: &str) -> Result<bool, String> {
+    let key2 = key2.replace("/*", "/.*");
+    let key2 = brace_vars().replace_all(&key2, "[^/]+");
+    regex_match(key1, &format!("^{}$", key2))
+}
+
+// key_get3 returns what the `{var}` of key2 named var matches in key1, or nothing, such as 123
+// for /resource/123, /resource/{id} and id.
+pub fn key_get3(key1: &str, key2: &str, var: &str) -> Result<String, String> {
+    let key2 = key2.replace("/*", "/.*");
+    let vars: Vec<&str> = brace_vars()
+        .captures_iter(&key2)
+        .filter_map(|var| var.get(1).map(|name| name.as_str()))
+        .collect();
+    let pattern = brace_vars().replace_all(&key2, "([^/]+?)");
+    let captures = match compile(&format!("^{}$", pattern))?.captures(key1) {
+        Some(captures) => captures,
+        None => return Ok(String::new()),
+    };
+    Ok(vars
+        .iter()
+        .position(|name| *name == var)
+        .and_then(|i| captures.get(i + 1))
+        .map_or_else(String::new, |value| value.as_str().to_owned()))
+}
+
+// key_match4 is key_match3, but for the `{var}` named alike having to match the same segment,
+// such as /parent/123/child/123 matching /parent/{id}/child/{id} but /parent/123/child/456
+// not.
+pub fn key_match4(key1: &str, key2: &str) -> Result<bool, String> {
+    let key2 = key2.replace("/*", "/.*");
+    let vars: Vec<&str> = brace_vars()
+        .captures_iter(&key2)
+        .filter_map(|var| var.get(1).map(|name| name.as_str()))
+        .collect();
+    let pattern = brace_vars().replace_all(&key2, "([^/]+)");
+    let captures = match compile(&format!("^{}$", pattern))?.captures(key1) {
+        Some(captures) => captures,
+        None => return Ok(false),
+    };
+    let mut values: HashMap<&str, &str> = HashMap::new();
+    for (i, var) in vars.iter().enumerate() {
+        let value = captures.get(i + 1).map_or("", |value| value.as_str());
+        if *values.entry(var).or_insert(value) != value {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// key_match5 is key_match3 for key1 stripped of its query, such as /foo/bar?status=1 matching
+// /foo/{bar}.
+pub fn key_match5(key1: &str, key2: &str) -> Result<bool, String> {
+    let key1 = key1.split('?').next().unwrap_or_default();
+    let key2 = key2.replace("/*", "/.*");
+    let key2 = brace_vars().replace_all(&key2, "[^/]+");
+    regex_match(key1, &format!("^{}$", key2))
+}
+
+// regex_match tells whether the regular expression key2 matches in key1.
+pub fn regex_match(key1: &str, key2: &str) -> Result<bool, String> {
+    Ok(compile(key2)?.is_match(key1))
+}
+
+// ip_match tells whether the address ip1 is ip2, an address or a CIDR block, such as
+// 192.168.2.123 being in 192.168.2.0/24. IPv4 addresses match their IPv4-mapped IPv6 form.
+pub fn ip_match(ip1: &str, ip2: &str) -> Result<bool, String> {
+    let address = |ip: &str| match ip.parse::<IpAddr>() {
+        Ok(IpAddr::V6(v6)) => Ok(v6.to_ipv4_mapped().map_or(IpAddr::V6(v6), IpAddr::V4)),
+        Ok(v4) => Ok(v4),
+        Err(_) => Err(format!("invalid IP address {}", ip)),
+    };
+    let ip1 = address(ip1)?;
+    let (network, bits) = match ip2.split_once('/') {
+        Some((network, bits)) => (network, Some(bits)),
+        None => (ip2, None),
+    };
+    let network = address(network)?;
+    let bits = match bits {
+        Some(bits) => bits
+            .parse::<u32>()
+            .map_err(|_| format!("invalid CIDR block {}", ip2))?,
+        None => return Ok(ip1 == network),
+    };
+    let (ip1, network, width) = match (ip1, network) {
+        (IpAddr::V4(a), IpAddr::V4(b)) => (u32::from(a) as u128, u32::from(b) as u128, 32),
+        (IpAddr::V6(a), IpAddr::V6(b)) => (u128::from(a), u128::from(b), 128),
+        _ => return Ok(false),
+    };
+    if bits > width {
+        return Err(format!("invalid CIDR block {}", ip2));
+    }
+    let mask = match bits {
+        0 => 0,
+        bits => u128::MAX << (width - bits),
+    };
+    Ok(ip1 & mask == network & mask)
+}
+
+// glob_match tells whether key1 matches the glob key2, whose `*` and `?` match within a
+// segment, `**` across segments, `[...]` one of a class, negated by a leading `!`, and
+// `{a,b}` either alternative, such as /abc/123 matching /abc/* but /abc/123/456 not.
+pub fn glob_match(key1: &str, key2: &str) -> Result<bool, String> {
+    let mut pattern = String::from("^");
+    let mut chars = key2.chars().peekable();
+    let mut alternatives = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                pattern.push_str(".*");
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            '[' => {
+                pattern.push('[');
+                if matches!(chars.peek(), Some('!') | Some('^')) {
+                    chars.next();
+                    pattern.push('^');
+                }
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        closed = true;
+                        break;
+                    }
+                    if matches!(c, '\\' | '[' | '&' | '~') {
+                        pattern.push('\\');
+                    }
+                    pattern.push(c);
+                }
+                if !closed {
+                    return Err(format!("invalid glob {}: unclosed class", key2));
+                }
+                pattern.push(']');
+            }
+            '{' => {
+                alternatives += 1;
+                pattern.push_str("(?:");
+            }
+            ',' if alternatives > 0 => pattern.push('|'),
+            '}' if alternatives > 0 => {
+                alternatives -= 1;
+                pattern.push(')');
+            }
+            '\\' => match chars.next() {
+                Some(c) => pattern.push_str(&regex::escape(&c.to_string())),
+                None => return Err(format!("invalid glob {}: trailing escape", key2)),
+            },
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    if alternatives > 0 {
+        return Err(format!("invalid glob {}: unclosed alternatives", key2));
+    }
+    pattern.push('$');
+    regex_match(key1, &pattern)
+}
+
+// MatcherFunction is a function an embedding application lets matchers call by the name it is
+// registered with, such as inBusinessHours() or ipInCidrSet(r.ip, p.cidrs). It is given the
+// strings its arguments evaluate to, and an error fails the decision.
+pub trait MatcherFunction: Send + Sync {
+    // arity is the number of arguments the function takes.
+    fn arity(&self) -> usize;
+    fn call(&self, args: &[&str]) -> Result<bool, String>;
+
+    // cacheable tells whether the decisions the function is called for may be cached, which
+    // they may not when it answers the same arguments differently over time, as
+    // inBusinessHours() does.
+    fn cacheable(&self) -> bool {
+        true
+    }
+}
+
+thread_local! {
+    // UNCACHEABLE records whether a function that is not cacheable was called on the thread
+    // since take_uncacheable was last called.
+    static UNCACHEABLE: Cell<bool> = const { Cell::new(false) };
+}
+
+// take_uncacheable tells whether a function that is not cacheable was called on the thread
+// since it was last called, so that the decision it was called for is not cached.
+pub fn take_uncacheable() -> bool {
+    UNCACHEABLE.with(|called| called.replace(false))
+}
+
+// Functions are the functions a server lets its matchers call on top of the standard ones,
+// registered with the register_function of its builder. They are shared by the enforcers of
+// the server and by those of its tenants, and by no other server.
+#[derive(Default)]
+pub struct Functions {
+    functions: RwLock<Vec<(String, Arc<dyn MatcherFunction>)>>,
+}
+
+impl Functions {
+    // register lets the matchers of the enforcers built from now on call f by name, in place
+    // of a function of the same name and arity, the standard ones included.
+    pub fn register(&self, name: &str, f: Arc<dyn MatcherFunction>) {
+        let mut functions = self.functions.write().unwrap();
+        functions.retain(|(other, g)| other != name || g.arity() != f.arity());
+        functions.push((name.to_owned(), f));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions
+            .read()
+            .unwrap()
+            .iter()
+            .any(|(other, _)| other == name)
+    }
+}
+
+// CustomFunction is a function defined for the matchers of an enforcer in their expression
+// language, through AddCustomFunction: the value of body, an expression in which params name
+// the arguments. The body may call the standard functions and those of the server, but
+// not the role definitions nor the other custom functions, so it can never recurse.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomFunction {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: String,
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl CustomFunction {
+    // new checks a definition: the name and the params have to be identifiers, the params
+    // distinct, the name not one of a standard function or of the functions of the server, and
+    // the body an expression.
+    pub fn new(
+        name: String,
+        params: Vec<String>,
+        body: String,
+        functions: &Functions,
+    ) -> Result<Self, String> {
+        if !is_identifier(&name) {
+            return Err(format!("function name {:?} is not an identifier", name));
+        }
+        if STANDARD.contains(&name.as_str()) || functions.contains(&name) {
+            return Err(format!("{} is a function of the server", name));
+        }
+        for (i, param) in params.iter().enumerate() {
+            if !is_identifier(param) {
+                return Err(format!("parameter {:?} is not an identifier", param));
+            }
+            if params[..i].contains(param) {
+                return Err(format!("parameter {} is given twice", param));
+            }
+        }
+        let f = CustomFunction { name, params, body };
+        f.compile(&Engine::new_raw())?;
+        Ok(f)
+    }
+
+    fn compile(&self, engine: &Engine) -> Result<AST, String> {
+        engine
+            .compile_expression(&self.body)
+            .map_err(|err| format!("body of {}: {}", self.name, err))
+    }
+}
+
+// register_custom registers functions on engine, evaluating their bodies with body_engine.
+pub fn register_custom(
+    engine: &mut Engine,
+    body_engine: &Arc<Engine>,
+    functions: &[CustomFunction],
+) -> Result<(), String> {
+    for f in functions {
+        let (ast, body_engine, params) = (
+            f.compile(body_engine)?,
+            body_engine.clone(),
+            f.params.clone(),
+        );
+        engine.register_raw_fn(
+            f.name.as_str(),
+            vec![TypeId::of::<Dynamic>(); params.len()],
+            move |_: NativeCallContext, args: &mut [&mut Dynamic]| {
+                let mut scope = Scope::new();
+                for (param, arg) in params.iter().zip(args.iter_mut()) {
+                    scope.push_dynamic(param.as_str(), std::mem::take(*arg));
+                }
+                body_engine.eval_ast_with_scope::<Dynamic>(&mut scope, &ast)
+            },
+        );
+    }
+    Ok(())
+}
+
+// register registers the standard functions and those of functions on an engine deciding
+// requests, their failures failing the decision.
+pub fn register(engine: &mut Engine, functions: &Functions) {
+    fn matcher(engine: &mut Engine, name: &str, f: fn(&str, &str) -> Result<bool, String>) {
+        engine.register_fn(
+            name,
+            move |key1: ImmutableString,
+                  key2: ImmutableString|
+                  -> Result<bool, Box<EvalAltResult>> { Ok(f(&key1, &key2)?) },
+        );
+    }
+    fn getter(engine: &mut Engine, name: &str, f: fn(&str, &str, &str) -> Result<String, String>) {
+        engine.register_fn(
+            name,
+            move |key1: ImmutableString,
+                  key2: ImmutableString,
+                  var: ImmutableString|
+                  -> Result<String, Box<EvalAltResult>> { Ok(f(&key1, &key2, &var)?) },
+        );
+    }
+    engine.register_fn(
+        "keyMatch",
+        |key1: ImmutableString, key2: ImmutableString| key_match(&key1, &key2),
+    );
+    engine.register_fn("keyGet", |key1: ImmutableString, key2: ImmutableString| {
+        key_get(&key1, &key2)
+    });
+    matcher(engine, "keyMatch2", key_match2);
+    getter(engine, "keyGet2", key_get2);
+    matcher(engine, "keyMatch3", key_match3);
+    getter(engine, "keyGet3", key_get3);
+    matcher(engine, "keyMatch4", key_match4);
+    matcher(engine, "keyMatch5", key_match5);
+    matcher(engine, "regexMatch", regex_match);
+    matcher(engine, "ipMatch", ip_match);
+    matcher(engine, "globMatch", glob_match);
+    for (name, f) in functions.functions.read().unwrap().iter() {
+        let f = f.clone();
+        engine.register_raw_fn(
+            name.as_str(),
+            vec![TypeId::of::<ImmutableString>(); f.arity()],
+            move |_: NativeCallContext, args: &mut [&mut Dynamic]| {
+                let args: Vec<ImmutableString> = args
+                    .iter()
+                    .map(|arg| arg.clone_cast::<ImmutableString>())
+                    .collect();
+                let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+                if !f.cacheable() {
+                    UNCACHEABLE.with(|called| called.set(true));
+                }
+                Ok(f.call(&args)?)
+            },
+        );
+    }
+}
+
+// add_functions adds the matching functions to the own engine of e, which the enforcers decide
+// with when a decision is not compiled, as for the meta enforcer. casbin only lets it be given
+// functions of two keys returning whether they match, so the getters and the functions of the
+// server are left out, and a function failing matches nothing.
+pub fn add_functions<E: CoreApi>(e: &mut E) {
+    e.add_function("keyMatch", |key1, key2| key_match(&key1, &key2));
+    e.add_function("keyMatch2", |key1, key2| {
+        key_match2(&key1, &key2).unwrap_or(false)
+    });
+    e.add_function("keyMatch3", |key1, key2| {
+        key_match3(&key1, &key2).unwrap_or(false)
+    });
+    e.add_function("keyMatch4", |key1, key2| {
+        key_match4(&key1, &key2).unwrap_or(false)
+    });
+    e.add_function("keyMatch5", |key1, key2| {
+        key_match5(&key1, &key2).unwrap_or(false)
+    });
+    e.add_function("regexMatch", |key1, key2| {
+        regex_match(&key1, &key2).unwrap_or(false)
+    });
+    e.add_function("ipMatch", |key1, key2| {
+        ip_match(&key1, &key2).unwrap_or(false)
+    });
+    e.add_function("globMatch", |key1, key2| {
+        glob_match(&key1, &key2).unwrap_or(false)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::casbin_proto::casbin_server::Casbin;
+    use crate::casbin_proto::{
+        CustomFunctionRequest, EnforceRequest, PolicyRequest, SetModelRequest,
+    };
+    use crate::server::enforcer;
+    use crate::server::registry::DecisionCacheOptions;
+    use crate::CasbinGRPC;
+    use casbin::{DefaultModel, FileAdapter, MemoryAdapter};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tonic::{Code, Request};
+
+    async fn serve_file(model: &str, policy: &'static str) -> (CasbinGRPC, i32) {
+        let casbin = CasbinGRPC::new_server();
+        let model_text = std::fs::read_to_string(model).unwrap();
+        let m = DefaultModel::from_str(&model_text).await.unwrap();
+        let e = enforcer::build_enforcer(m, FileAdapter::new(policy))
+            .await
+            .unwrap();
+        let handle = casbin.add_enforcer(e, model_text).await;
+        (casbin, handle)
+    }
+
+    // serve serves an enforcer matching requests of a subject and an object with matcher, over
+    // the rules given.
+    async fn serve(matcher: &str, rules: &[[&str; 2]]) -> (CasbinGRPC, i32) {
+        serve_on(CasbinGRPC::new_server(), matcher, rules).await
+    }
+
+    // serve_on serves such an enforcer on casbin.
+    async fn serve_on(casbin: CasbinGRPC, matcher: &str, rules: &[[&str; 2]]) -> (CasbinGRPC, i32) {
+        let model_text = format!(
+            "[request_definition]\nr = sub, obj\n\n[policy_definition]\np = sub, obj\n\n\
+             [policy_effect]\ne = some(where (p.eft == allow))\n\n[matchers]\nm = {}",
+            matcher
+        );
+        let m = DefaultModel::from_str(&model_text).await.unwrap();
+        let e = enforcer::build_enforcer(m, MemoryAdapter::default())
+            .await
+            .unwrap();
+        let handle = casbin.add_enforcer(e, model_text).await;
+        for rule in rules {
+            casbin
+                .add_policy(Request::new(PolicyRequest {
+                    enforcer_handler: handle,
+                    p_type: "p".to_string(),
+                    params: rule.iter().map(|p| p.to_string()).collect(),
+                }))
+                .await
+                .unwrap();
+        }
+        (casbin, handle)
+    }
+
+    async fn decide(casbin: &CasbinGRPC, handle: i32, params: &[&str]) -> Result<bool, Code> {
+        casbin
+            .enforce(Request::new(EnforceRequest {
+                enforcer_handler: handle,
+                params: params.iter().map(|p| p.to_string()).collect(),
+            }))
+            .await
+            .map(|reply| reply.into_inner().res)
+            .map_err(|status| status.code())
+    }
+
+    async fn enforce(casbin: &CasbinGRPC, handle: i32, params: &[&str]) -> bool {
+        decide(casbin, handle, params).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_key_match2_model() {
+        let (casbin, h) = serve_file(
+            "examples/keymatch2_model.conf",
+            "examples/keymatch2_policy.csv",
+        )
+        .await;
+        assert!(enforce(&casbin, h, &["alice", "/alice_data/resource1", "GET"]).await);
+        assert!(enforce(&casbin, h, &["alice", "/alice_data/resource1", "POST"]).await);
+        assert!(!enforce(&casbin, h, &["alice", "/alice_data/resource2", "POST"]).await);
+        assert!(enforce(&casbin, h, &["bob", "/alice_data/resource2", "GET"]).await);
+        assert!(enforce(&casbin, h, &["bob", "/bob_data/resource1", "POST"]).await);
+        assert!(!enforce(&casbin, h, &["bob", "/bob_data/resource1", "GET"]).await);
+        assert!(enforce(&casbin, h, &["cathy", "/cathy_data", "GET"]).await);
+        assert!(enforce(&casbin, h, &["cathy", "/cathy_data", "POST"]).await);
+        assert!(!enforce(&casbin, h, &["cathy", "/cathy_data", "DELETE"]).await);
+        assert!(enforce(&casbin, h, &["anyone", "/users/1/orders/2", "GET"]).await);
+        assert!(!enforce(&casbin, h, &["anyone", "/users/1/orders", "GET"]).await);
+        assert!(!enforce(&casbin, h, &["anyone", "/users/1/2/orders/3", "GET"]).await);
+    }
+
+    #[tokio::test]
+    async fn test_key_match() {
+        let (casbin, h) = serve(
+            "r.sub == p.sub && keyMatch(r.obj, p.obj)",
+            &[["alice", "/foo/*"], ["bob", "/bar"]],
+        )
+        .await;
+        assert!(enforce(&casbin, h, &["alice", "/foo/bar"]).await);
+        assert!(enforce(&casbin, h, &["alice", "/foo/bar/baz"]).await);
+        assert!(!enforce(&casbin, h, &["alice", "/fo"]).await);
+        assert!(enforce(&casbin, h, &["bob", "/bar"]).await);
+        assert!(!enforce(&casbin, h, &["bob", "/bar/baz"]).await);
+        // A pattern cut inside a character is compared byte by byte rather than panicking.
+        assert!(!enforce(&casbin, h, &["alice", "/fé"]).await);
+    }
+
+    #[tokio::test]
+    async fn test_key_get() {
+        let (casbin, h) = serve(
+            r#"keyGet(r.obj, "/users/*") == p.sub"#,
+            &[["alice", "/users/*"]],
+        )
+        .await;
+        assert!(enforce(&casbin, h, &["", "/users/alice"]).await);
+        assert!(!enforce(&casbin, h, &["", "/users/bob"]).await);
+        assert!(!enforce(&casbin, h, &["", "/groups/alice"]).await);
+
+        let (casbin, h) = serve(
+            r#"keyGet2(r.obj, p.obj, "id") == r.sub"#,
+            &[["", "/users/:id/orders/:order"]],
+        )
+        .await;
+        assert!(enforce(&casbin, h, &["alice", "/users/alice/orders/1"]).await);
+        assert!(!enforce(&casbin, h, &["bob", "/users/alice/orders/1"]).await);
+
+        let (casbin, h) = serve(
+            r#"keyGet3(r.obj, p.obj, "id") == r.sub"#,
+            &[["", "/users/{id}/orders/{order}"]],
+        )
+        .await;
+        assert!(enforce(&casbin, h, &["alice", "/users/alice/orders/1"]).await);
+        assert!(!enforce(&casbin, h, &["bob", "/users/alice/orders/1"]).await);
+        assert!(!enforce(&casbin, h, &["alice", "/users/alice"]).await);
+    }
+
+    #[tokio::test]
+    async fn test_key_match3_to_5() {
+        let (casbin, h) = serve(
+            "r.sub == p.sub && keyMatch3(r.obj, p.obj)",
+            &[["alice", "/foo/{bar}/baz"], ["bob", "/bob_data/*"]],
+        )
+        .await;
+        assert!(enforce(&casbin, h, &["alice", "/foo/1/baz"]).await);
+        assert!(!enforce(&casbin, h, &["alice", "/foo/1/2/baz"]).await);
+        assert!(enforce(&casbin, h, &["bob", "/bob_data/1/2"]).await);
+
+        let (casbin, h) = serve(
+            "keyMatch4(r.obj, p.obj)",
+            &[["", "/parent/{id}/child/{id}"]],
+        )
+        .await;
+        assert!(enforce(&casbin, h, &["", "/parent/123/child/123"]).await);
+        assert!(!enforce(&casbin, h, &["", "/parent/123/child/456"]).await);
+
+        let (casbin, h) = serve("keyMatch5(r.obj, p.obj)", &[["", "/foo/{bar}"]]).await;
+        assert!(enforce(&casbin, h, &["", "/foo/bar?status=1&type=2"]).await);
+        assert!(enforce(&casbin, h, &["", "/foo/bar"]).await);
+        assert!(!enforce(&casbin, h, &["", "/foo/bar/baz?status=1"]).await);
+    }
+
+    #[tokio::test]
+    async fn test_regex_match() {
+        let (casbin, h) = serve("regexMatch(r.obj, p.obj)", &[["", "^/data/[0-9]+$"]]).await;
+        assert!(enforce(&casbin, h, &["", "/data/42"]).await);
+        assert!(!enforce(&casbin, h, &["", "/data/x"]).await);
+
+        // An invalid pattern fails the decision instead of the server.
+        let (casbin, h) = serve("regexMatch(r.obj, p.obj)", &[["", "(unclosed"]]).await;
+        assert_eq!(
+            decide(&casbin, h, &["", "/data"]).await,
+            Err(Code::InvalidArgument)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ip_match() {
+        let (casbin, h) = serve(
+            "r.sub == p.sub && ipMatch(r.obj, p.obj)",
+            &[
+                ["lan", "192.168.2.0/24"],
+                ["host", "10.0.0.1"],
+                ["v6", "2001:db8::/32"],
+            ],
+        )
+        .await;
+        assert!(enforce(&casbin, h, &["lan", "192.168.2.123"]).await);
+        assert!(!enforce(&casbin, h, &["lan", "192.168.1.134"]).await);
+        assert!(enforce(&casbin, h, &["host", "10.0.0.1"]).await);
+        assert!(enforce(&casbin, h, &["host", "::ffff:10.0.0.1"]).await);
+        assert!(!enforce(&casbin, h, &["host", "10.0.0.2"]).await);
+        assert!(enforce(&casbin, h, &["v6", "2001:db8:1::1"]).await);
+        assert!(!enforce(&casbin, h, &["v6", "2001:db9::1"]).await);
+        assert!(!enforce(&casbin, h, &["v6", "10.0.0.1"]).await);
+        assert!(decide(&casbin, h, &["lan", "I am alice"]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_glob_match() {
+        let (casbin, h) = serve(
+            "globMatch(r.obj, p.obj)",
+            &[
+                ["", "/abc/*"],
+                ["", "/deep/**"],
+                ["", "/file.{txt,md}"],
+                ["", "/[!a]?"],
+            ],
+        )
+        .await;
+        assert!(enforce(&casbin, h, &["", "/abc/123"]).await);
+        assert!(!enforce(&casbin, h, &["", "/abc/123/456"]).await);
+        assert!(enforce(&casbin, h, &["", "/deep/123/456"]).await);
+        assert!(enforce(&casbin, h, &["", "/file.md"]).await);
+        assert!(!enforce(&casbin, h, &["", "/fileXmd"]).await);
+        assert!(enforce(&casbin, h, &["", "/bc"]).await);
+        assert!(!enforce(&casbin, h, &["", "/ac"]).await);
+    }
+
+    // InSet tells whether its first argument is one of the comma separated values of its
+    // second, failing for an empty set.
+    struct InSet;
+
+    impl super::MatcherFunction for InSet {
+        fn arity(&self) -> usize {
+            2
+        }
+
+        fn call(&self, args: &[&str]) -> Result<bool, String> {
+            if args[1].is_empty() {
+                return Err("empty set".to_owned());
+            }
+            Ok(args[1].split(',').any(|value| value.trim() == args[0]))
+        }
+    }
+
+    struct Always;
+
+    impl super::MatcherFunction for Always {
+        fn arity(&self) -> usize {
+            0
+        }
+
+        fn call(&self, _: &[&str]) -> Result<bool, String> {
+            Ok(true)
+        }
+    }
+
+    // Toggle answers alternately true and false, starting with true, so that its decisions
+    // are not to be cached.
+    #[derive(Default)]
+    struct Toggle(AtomicBool);
+
+    impl super::MatcherFunction for Toggle {
+        fn arity(&self) -> usize {
+            0
+        }
+
+        fn call(&self, _: &[&str]) -> Result<bool, String> {
+            Ok(!self.0.fetch_xor(true, Ordering::Relaxed))
+        }
+
+        fn cacheable(&self) -> bool {
+            false
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_function() {
+        let server = || {
+            CasbinGRPC::builder()
+                .register_function("inSet", InSet)
+                .register_function("always", Always)
+                .build()
+        };
+        let (casbin, h) = serve_on(
+            server(),
+            "inSet(r.sub, p.sub) && keyMatch2(r.obj, p.obj) && always()",
+            &[["alice, bob", "/data/:id"]],
+        )
+        .await;
+        assert!(enforce(&casbin, h, &["alice", "/data/1"]).await);
+        assert!(enforce(&casbin, h, &["bob", "/data/1"]).await);
+        assert!(!enforce(&casbin, h, &["carol", "/data/1"]).await);
+        assert!(!enforce(&casbin, h, &["alice", "/other"]).await);
+
+        let (casbin, h) = serve_on(server(), "inSet(r.sub, p.sub)", &[["", "/data"]]).await;
+        assert_eq!(
+            decide(&casbin, h, &["alice", "/data"]).await,
+            Err(Code::InvalidArgument)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_registered_functions_stay_with_their_server() {
+        let with = CasbinGRPC::builder()
+            .register_function("inSet", InSet)
+            .build();
+        let (casbin, h) = serve_on(with, "inSet(r.sub, p.sub)", &[["alice", ""]]).await;
+        assert!(enforce(&casbin, h, &["alice", ""]).await);
+        assert_eq!(
+            add_function(&casbin, h, "inSet", &["a", "b"], "true").await,
+            Err(Code::InvalidArgument)
+        );
+
+        let (casbin, h) = serve("inSet(r.sub, p.sub)", &[["alice", ""]]).await;
+        assert_eq!(
+            decide(&casbin, h, &["alice", ""]).await,
+            Err(Code::InvalidArgument)
+        );
+        add_function(&casbin, h, "inSet", &["a", "b"], "a == b")
+            .await
+            .unwrap();
+        assert!(enforce(&casbin, h, &["alice", ""]).await);
+    }
+
+    #[tokio::test]
+    async fn test_uncacheable_function_is_not_cached() {
+        let mut server = CasbinGRPC::builder()
+            .register_function("toggle", Toggle::default())
+            .build();
+        server.decision_cache = DecisionCacheOptions {
+            max_entries: 100,
+            ..Default::default()
+        };
+        let (casbin, h) = serve_on(server, "r.sub == p.sub && toggle()", &[["alice", ""]]).await;
+        assert!(enforce(&casbin, h, &["alice", ""]).await);
+        assert!(!enforce(&casbin, h, &["alice", ""]).await);
+        assert!(enforce(&casbin, h, &["alice", ""]).await);
+    }
+
+    async fn add_function(
+        casbin: &CasbinGRPC,
+        handle: i32,
+        name: &str,
+        params: &[&str],
+        body: &str,
+    ) -> Result<(), Code> {
+        casbin
+            .add_custom_function(Request::new(CustomFunctionRequest {
+                enforcer_handler: handle,
+                name: name.to_string(),
+                params: params.iter().map(|p| p.to_string()).collect(),
+                body: body.to_string(),
+            }))
+            .await
+            .map(|_| ())
+            .map_err(|status| status.code())
+    }
+
+    #[tokio::test]
+    async fn test_custom_function() {
+        let (casbin, h) = serve("isOwner(r.sub, r.obj) || r.sub == p.sub", &[["root", ""]]).await;
+        assert_eq!(
+            decide(&casbin, h, &["alice", "/users/alice/1"]).await,
+            Err(Code::InvalidArgument)
+        );
+        add_function(
+            &casbin,
+            h,
+            "isOwner",
+            &["sub", "obj"],
+            r#"keyMatch2(obj, "/users/" + sub + "/*")"#,
+        )
+        .await
+        .unwrap();
+        assert!(enforce(&casbin, h, &["alice", "/users/alice/1"]).await);
+        assert!(!enforce(&casbin, h, &["alice", "/users/bob/1"]).await);
+        assert!(enforce(&casbin, h, &["root", "/users/bob/1"]).await);
+
+        // A function defined again replaces the one before.
+        add_function(&casbin, h, "isOwner", &["sub", "obj"], "false")
+            .await
+            .unwrap();
+        assert!(!enforce(&casbin, h, &["alice", "/users/alice/1"]).await);
+        add_function(
+            &casbin,
+            h,
+            "isOwner",
+            &["sub", "obj"],
+            r#"keyGet2(obj, "/users/:owner/*", "owner") == sub"#,
+        )
+        .await
+        .unwrap();
+        assert!(enforce(&casbin, h, &["alice", "/users/alice/1"]).await);
+
+        // The function is kept when the model is replaced.
+        casbin
+            .set_model(Request::new(SetModelRequest {
+                enforcer_handler: h,
+                model_text: "[request_definition]\nr = sub, obj\n\n[policy_definition]\n\
+                             p = sub, obj\n\n[policy_effect]\n\
+                             e = some(where (p.eft == allow))\n\n[matchers]\n\
+                             m = isOwner(r.sub, r.obj) && p.sub == \"root\""
+                    .to_string(),
+            }))
+            .await
+            .unwrap();
+        assert!(enforce(&casbin, h, &["alice", "/users/alice/1"]).await);
+        assert!(!enforce(&casbin, h, &["bob", "/users/alice/1"]).await);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_custom_function() {
+        let (casbin, h) = serve("r.sub == p.sub", &[]).await;
+        let invalid = [
+            ("is-owner", vec!["sub"], "true"),
+            ("keyMatch2", vec!["a", "b"], "true"),
+            ("isOwner", vec!["1sub"], "true"),
+            ("isOwner", vec!["sub", "sub"], "true"),
+            ("isOwner", vec!["sub"], "sub =="),
+            ("isOwner", vec!["sub"], "let x = 1; x"),
+        ];
+        for (name, params, body) in invalid {
+            assert_eq!(
+                add_function(&casbin, h, name, &params, body).await,
+                Err(Code::InvalidArgument),
+                "{} {:?} {}",
+                name,
+                params,
+                body
+            );
+        }
+        assert_eq!(
+            add_function(&casbin, 99, "isOwner", &[], "true").await,
+            Err(Code::NotFound)
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use crate::adapter::{Adapter, Drivers};
+use crate::casbin_proto::casbin_server::CasbinServer;
+use crate::CasbinGRPC;
+use casbin::DefaultModel;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+
+// set_status reports the Casbin service, and the server as a whole under the empty service
+// name that health probes ask for by default.
+async fn set_status(reporter: &mut HealthReporter, status: ServingStatus) {
+    reporter.set_service_status("", status).await;
+    match status {
+        ServingStatus::Serving => reporter.set_serving::<CasbinServer<CasbinGRPC>>().await,
+        _ => reporter.set_not_serving::<CasbinServer<CasbinGRPC>>().await,
+    }
+}
+
+// spawn_health_checks loads the policy of the local config's store every interval and reports
+// the server as not serving through the gRPC health service while the load fails. The probe
+// uses an adapter of its own, so it never holds up requests nor touches the state of the
+// served adapter, and does not retry, so an outage is reported at the next check. A failed
+// probe adapter is reopened at the following one. Once the server is shutting down, it stays
+// reported as not serving.
+pub fn spawn_health_checks(
+    drivers: Drivers,
+    driver: String,
+    connection: String,
+    mut reporter: HealthReporter,
+    interval: Duration,
+    shutting_down: Arc<AtomicBool>,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        let mut probe: Option<Box<dyn Adapter>> = None;
+        let mut healthy = true;
+        loop {
+            ticker.tick().await;
+            let res = match probe.as_ref() {
+                Some(adapter) => adapter.load_policy(&mut DefaultModel::default()).await,
+                None => match drivers.connect(&driver, &connection).await {
+                    Ok(adapter) => {
+                        let res = adapter.load_policy(&mut DefaultModel::default()).await;
+                        probe = Some(adapter);
+                        res
+                    }
+                    Err(err) => Err(err),
+                },
+            };
+            if let Err(err) = &res {
+                tracing::warn!(%driver, error = %err, "health check of the adapter failed");
+                probe = None;
+            }
+            if shutting_down.load(Ordering::Relaxed) {
+                return;
+            }
+            if res.is_ok() != healthy {
+                healthy = res.is_ok();
+                if healthy {
+                    tracing::info!(%driver, "the adapter is healthy again");
+                    set_status(&mut reporter, ServingStatus::Serving).await;
+                } else {
+                    set_status(&mut reporter, ServingStatus::NotServing).await;
+                }
+            }
+        }
+    });
+}
+
+// shutdown_signal resolves once the server is asked to stop, by SIGTERM or Ctrl-C, after
+// reporting it as not serving and then waiting for drain, so probes and load balancers stop
+// sending it calls before it stops accepting them. Calls in flight then run to completion.
+pub async fn shutdown_signal(
+    mut reporter: HealthReporter,
+    shutting_down: Arc<AtomicBool>,
+    drain: Duration,
+) {
+    #[cfg(unix)]
+    {
+        let mut term =
+            match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+                Ok(term) => term,
+                Err(err) => {
+                    tracing::error!(error = %err, "listening for SIGTERM failed");
+                    std::future::pending::<()>().await;
+                    return;
+                }
+            };
+        tokio::select! {
+            _ = term.recv() => {}
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+
+    tracing::info!(?drain, "shutting down, draining the calls");
+    shutting_down.store(true, Ordering::Relaxed);
+    set_status(&mut reporter, ServingStatus::NotServing).await;
+    tokio::time::sleep(drain).await;
+}
@@ -0,0 +1,276 @@
+use crate::server::priority;
+use casbin::{Assertion, CachedEnforcer, CoreApi, Event, EventData, EventEmitter};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+type Rule = Arc<[String]>;
+
+// PolicyIndex indexes the rules of an enforcer by the values of their fields, so that filtered
+// queries and removals look up the rules they match instead of going through all of them. A
+// field is indexed the first time a filter looks it up, then kept up to date with the changes
+// published to the feed of the enforcer. Changes that replace the policy as a whole drop the
+// index, which is built again when next needed.
+#[derive(Default)]
+pub struct PolicyIndex {
+    policies: RwLock<HashMap<String, Indexed>>,
+}
+
+// Indexed is the index of a policy: the order its rules are held in, how many rules of each
+// length it holds, and the rules by the value of each indexed field.
+#[derive(Default)]
+struct Indexed {
+    order: HashMap<Rule, u64>,
+    next: u64,
+    lengths: BTreeMap<usize, usize>,
+    fields: HashMap<usize, HashMap<String, HashSet<Rule>>>,
+}
+
+impl Indexed {
+    fn build(ast: &Assertion) -> Self {
+        let mut indexed = Indexed::default();
+        for rule in ast.get_policy() {
+            indexed.add(rule.clone());
+        }
+        indexed
+    }
+
+    fn add(&mut self, rule: Vec<String>) {
+        if self.order.contains_key(rule.as_slice()) {
+            return;
+        }
+        let rule: Rule = rule.into();
+        for (field, values) in self.fields.iter_mut() {
+            if let Some(value) = rule.get(*field) {
+                values
+                    .entry(value.clone())
+                    .or_default()
+                    .insert(rule.clone());
+            }
+        }
+        *self.lengths.entry(rule.len()).or_default() += 1;
+        self.order.insert(rule, self.next);
+        self.next += 1;
+    }
+
+    fn remove(&mut self, rule: &[String]) {
+        if self.order.remove(rule).is_none() {
+            return;
+        }
+        for (field, values) in self.fields.iter_mut() {
+            let value = match rule.get(*field) {
+                Some(value) => value,
+                None => continue,
+            };
+            if let Some(rules) = values.get_mut(value) {
+                rules.remove(rule);
+                if rules.is_empty() {
+                    values.remove(value);
+                }
+            }
+        }
+        if let Some(n) = self.lengths.get_mut(&rule.len()) {
+            *n -= 1;
+            if *n == 0 {
+                self.lengths.remove(&rule.len());
+            }
+        }
+    }
+
+    fn index_field(&mut self, field: usize) {
+        if self.fields.contains_key(&field) {
+            return;
+        }
+        let mut values: HashMap<String, HashSet<Rule>> = HashMap::new();
+        for rule in self.order.keys() {
+            if let Some(value) = rule.get(field) {
+                values
+                    .entry(value.clone())
+                    .or_default()
+                    .insert(rule.clone());
+            }
+        }
+        self.fields.insert(field, values);
+    }
+
+    // matching gets the rules whose fields, starting at field_index, match field_values, in
+    // the order they were added. field is the indexed field holding the first value given.
+    fn matching(
+        &self,
+        field: usize,
+        field_index: usize,
+        field_values: &[String],
+    ) -> Vec<Vec<String>> {
+        let rules = match self
+            .fields
+            .get(&field)
+            .and_then(|values| values.get(&field_values[field - field_index]))
+        {
+            Some(rules) => rules,
+            None => return vec![],
+        };
+        let mut rules: Vec<(u64, &Rule)> = rules
+            .iter()
+            .filter(|rule| {
+                field_values
+                    .iter()
+                    .enumerate()
+                    .all(|(i, v)| v.is_empty() || rule.get(field_index + i) == Some(v))
+            })
+            .map(|rule| (self.order.get(rule).copied().unwrap_or_default(), rule))
+            .collect();
+        rules.sort_unstable_by_key(|(order, _)| *order);
+        rules.into_iter().map(|(_, rule)| rule.to_vec()).collect()
+    }
+}
+
+// assertion gets the definition of the policy ptype in sec, holding its rules.
+fn assertion<'a>(e: &'a CachedEnforcer, sec: &str, ptype: &str) -> Option<&'a Assertion> {
+    e.get_model().get_model().get(sec)?.get(ptype)
+}
+
+impl PolicyIndex {
+    // ensure indexes the policy ptype, and field of its rules if given. The index of a policy
+    // holding another number of rules than the enforcer missed a change, and is built again.
+    fn ensure(&self, ast: &Assertion, ptype: &str, field: Option<usize>) {
+        let len = ast.get_policy().len();
+        if let Some(indexed) = self.policies.read().unwrap().get(ptype) {
+            if indexed.order.len() == len
+                && field.is_none_or(|field| indexed.fields.contains_key(&field))
+            {
+                return;
+            }
+        }
+        let mut policies = self.policies.write().unwrap();
+        let indexed = policies.entry(ptype.to_owned()).or_default();
+        if indexed.order.len() != len {
+            *indexed = Indexed::build(ast);
+        }
+        if let Some(field) = field {
+            indexed.index_field(field);
+        }
+    }
+
+    // lookup gets the rules of the policy ptype in sec whose fields, starting at field_index,
+    // match field_values, in the order they were added. An empty field value matches anything.
+    fn lookup(
+        &self,
+        e: &CachedEnforcer,
+        sec: &str,
+        ptype: &str,
+        field_index: usize,
+        field_values: &[String],
+    ) -> Vec<Vec<String>> {
+        let ast = match assertion(e, sec, ptype) {
+            Some(ast) => ast,
+            None => return vec![],
+        };
+        let field = match field_values.iter().position(|v| !v.is_empty()) {
+            Some(i) => field_index + i,
+            None => return ast.get_policy().iter().cloned().collect(),
+        };
+        self.ensure(ast, ptype, Some(field));
+        self.policies
+            .read()
+            .unwrap()
+            .get(ptype)
+            .map_or_else(Vec::new, |indexed| {
+                indexed.matching(field, field_index, field_values)
+            })
+    }
+
+    // filtered gets the rules of the policy ptype in sec whose fields, starting at field_index,
+    // match field_values, like get_filtered_policy. The rules of policies sorted by priority
+    // are kept in an order the index does not follow, so they are still gone through.
+    pub fn filtered(
+        &self,
+        e: &CachedEnforcer,
+        sec: &str,
+        ptype: &str,
+        field_index: usize,
+        field_values: Vec<String>,
+    ) -> Vec<Vec<String>> {
+        if sec == "p" && priority::sorts(e, ptype) {
+            return e
+                .get_model()
+                .get_filtered_policy(sec, ptype, field_index, field_values);
+        }
+        self.lookup(e, sec, ptype, field_index, &field_values)
+    }
+
+    // shortest gets the length of the shortest rule of the policy ptype in sec.
+    pub fn shortest(&self, e: &CachedEnforcer, sec: &str, ptype: &str) -> Option<usize> {
+        self.ensure(assertion(e, sec, ptype)?, ptype, None);
+        self.policies
+            .read()
+            .unwrap()
+            .get(ptype)?
+            .lengths
+            .keys()
+            .next()
+            .copied()
+    }
+
+    // apply keeps the index up to date with a change to the policy ptype, as published to the
+    // feed of the enforcer.
+    pub fn apply(&self, op: &str, ptype: &str, rules: &[Vec<String>]) {
+        let mut policies = self.policies.write().unwrap();
+        match op {
+            "AddPolicy" | "AddPolicies" => {
+                if let Some(indexed) = policies.get_mut(ptype) {
+                    for rule in rules {
+                        indexed.add(rule.clone());
+                    }
+                }
+            }
+            "RemovePolicy" | "RemovePolicies" | "RemoveFilteredPolicy" => {
+                if let Some(indexed) = policies.get_mut(ptype) {
+                    for rule in rules {
+                        indexed.remove(rule);
+                    }
+                }
+            }
+            // The other changes reload, clear or restore the policy as a whole.
+            _ => policies.clear(),
+        }
+    }
+}
+
+// remove_filtered removes the rules of the policy ptype in sec whose fields, starting at
+// field_index, match field_values, like remove_filtered_named_policy, looking them up through
+// index. The adapter is still asked to remove them by the filter, as its store may hold rules
+// the enforcer did not load.
+pub async fn remove_filtered(
+    e: &mut CachedEnforcer,
+    index: &PolicyIndex,
+    sec: &str,
+    ptype: &str,
+    field_index: usize,
+    field_values: Vec<String>,
+) -> casbin::Result<bool> {
+    if e.has_auto_save_enabled()
+        && !e
+            .get_mut_adapter()
+            .remove_filtered_policy(sec, ptype, field_index, field_values.clone())
+            .await?
+    {
+        return Ok(false);
+    }
+    if field_values.is_empty() {
+        return Ok(false);
+    }
+    let rules = index.lookup(e, sec, ptype, field_index, &field_values);
+    if rules.is_empty() {
+        return Ok(false);
+    }
+    e.get_mut_model().remove_policies(sec, ptype, rules.clone());
+    let removed =
+        || EventData::RemoveFilteredPolicy(sec.to_owned(), ptype.to_owned(), rules.clone());
+    if e.has_auto_notify_watcher_enabled() {
+        e.emit(Event::PolicyChange, removed());
+    }
+    e.emit(Event::ClearCache, EventData::ClearCache);
+    if sec == "g" && e.has_auto_build_role_links_enabled() {
+        e.build_incremental_role_links(removed())?;
+    }
+    Ok(true)
+}
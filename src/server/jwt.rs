@@ -0,0 +1,321 @@
+use crate::adapter::adapter_error;
+use crate::server::adapter::Config;
+use crate::server::functions;
+use crate::server::model_source;
+use casbin::{CoreApi, DefaultModel, Enforcer, FileAdapter};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tonic::Status;
+
+static FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// JWKS_REFRESH is how long the keys of a JWKS URL are kept before a token signed with an
+// unknown key makes them fetched again, bounding how often a bad token causes a fetch.
+static JWKS_REFRESH: Duration = Duration::from_secs(60);
+
+// JwtAuth authenticates clients by the JWTs they send, signed with a shared secret or a key of
+// a JWKS URL, and authorizes the RPCs of their subject through a meta enforcer, if any. The
+// meta enforcer is asked `(subject, service, method)`, e.g. `alice, proto.Casbin, AddPolicy`;
+// without one, every valid token may call every RPC.
+pub struct JwtAuth {
+    keys: Keys,
+    issuer: String,
+    audience: String,
+    subject_claim: String,
+    tenant_claim: String,
+    meta: Option<Enforcer>,
+}
+
+enum Keys {
+    Secret(DecodingKey),
+    Jwks {
+        url: String,
+        set: RwLock<(JwkSet, Instant)>,
+    },
+}
+
+impl JwtAuth {
+    // from_config sets up JWT authentication from the jwt_ fields of the local config, none
+    // when neither a secret nor a JWKS URL is set.
+    pub async fn from_config(cfg: &Config) -> casbin::Result<Option<Self>> {
+        let keys = match (cfg.jwt_secret.is_empty(), cfg.jwt_jwks_url.is_empty()) {
+            (true, true) => return Ok(None),
+            (false, true) => Keys::Secret(DecodingKey::from_secret(cfg.jwt_secret.as_bytes())),
+            (true, false) => Keys::Jwks {
+                url: cfg.jwt_jwks_url.clone(),
+                set: RwLock::new((fetch_jwks(&cfg.jwt_jwks_url).await?, Instant::now())),
+            },
+            (false, false) => {
+                return Err(adapter_error(
+                    "jwt_secret and jwt_jwks_url cannot both be set",
+                ))
+            }
+        };
+        let meta = if cfg.meta_model.is_empty() {
+            None
+        } else {
+            let text =
+                model_source::read_model(&cfg.meta_model, &cfg.model_headers, &cfg.model_cache_dir)
+                    .await?;
+            let m = DefaultModel::from_str(&text).await?;
+            let mut e = Enforcer::new(m, FileAdapter::new(cfg.meta_policy.clone())).await?;
+            functions::add_functions(&mut e);
+            Some(e)
+        };
+        Ok(Some(JwtAuth {
+            keys,
+            issuer: cfg.jwt_issuer.clone(),
+            audience: cfg.jwt_audience.clone(),
+            subject_claim: if cfg.jwt_subject_claim.is_empty() {
+                String::from("sub")
+            } else {
+                cfg.jwt_subject_claim.clone()
+            },
+            tenant_claim: cfg.jwt_tenant_claim.clone(),
+            meta,
+        }))
+    }
+
+    // subject validates a token and gets the subject its claims name, and the tenant when
+    // tenants are named by a claim the token carries.
+    pub async fn subject(&self, token: &str) -> Result<(String, Option<String>), Status> {
+        let header = jsonwebtoken::decode_header(token).map_err(unauthenticated)?;
+        let (key, mut validation) = match &self.keys {
+            Keys::Secret(key) => {
+                let mut validation = Validation::new(Algorithm::HS256);
+                validation.algorithms = vec![Algorithm::HS256, Algorithm::HS384, Algorithm::HS512];
+                (key.clone(), validation)
+            }
+            Keys::Jwks { .. } => (
+                self.jwk(header.kid.as_deref()).await?,
+                Validation::new(header.alg),
+            ),
+        };
+        if !self.issuer.is_empty() {
+            validation.set_issuer(&[&self.issuer]);
+        }
+        if !self.audience.is_empty() {
+            validation.set_audience(&[&self.audience]);
+        }
+        let claims = jsonwebtoken::decode::<serde_json::Value>(token, &key, &validation)
+            .map_err(unauthenticated)?
+            .claims;
+        let subject = claims
+            .get(&self.subject_claim)
+            .and_then(|subject| subject.as_str())
+            .ok_or_else(|| {
+                Status::unauthenticated(format!("the token has no `{}` claim", self.subject_claim))
+            })?;
+        let tenant = if self.tenant_claim.is_empty() {
+            None
+        } else {
+            claims
+                .get(&self.tenant_claim)
+                .and_then(|tenant| tenant.as_str())
+                .map(str::to_owned)
+        };
+        Ok((subject.to_owned(), tenant))
+    }
+
+    // jwk gets the key of the JWKS URL with id kid, fetching the keys again when none has it,
+    // or the only key for tokens without an id.
+    async fn jwk(&self, kid: Option<&str>) -> Result<DecodingKey, Status> {
+        let (url, set) = match &self.keys {
+            Keys::Jwks { url, set } => (url, set),
+            Keys::Secret(key) => return Ok(key.clone()),
+        };
+        let find = |keys: &JwkSet| match kid {
+            Some(kid) => keys.find(kid).cloned(),
+            None if keys.keys.len() == 1 => keys.keys.first().cloned(),
+            None => None,
+        };
+        let mut jwk = find(&set.read().await.0);
+        if jwk.is_none() {
+            let mut set = set.write().await;
+            if set.1.elapsed() >= JWKS_REFRESH {
+                match fetch_jwks(url).await {
+                    Ok(keys) => *set = (keys, Instant::now()),
+                    Err(err) => tracing::warn!(%url, error = %err, "fetching the JWKS failed"),
+                }
+            }
+            jwk = find(&set.0);
+        }
+        let jwk =
+            jwk.ok_or_else(|| Status::unauthenticated("the token is signed with an unknown key"))?;
+        DecodingKey::from_jwk(&jwk).map_err(unauthenticated)
+    }
+
+    // authorize asks the meta enforcer whether subject may call method of service.
+    #[allow(clippy::result_large_err)]
+    pub fn authorize(&self, subject: &str, service: &str, method: &str) -> Result<(), Status> {
+        let meta = match &self.meta {
+            Some(meta) => meta,
+            None => return Ok(()),
+        };
+        match meta.enforce((subject, service, method)) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(Status::permission_denied(format!(
+                "{} may not call {}",
+                subject, method
+            ))),
+            Err(err) => Err(Status::internal(format!(
+                "authorizing the call failed: {}",
+                err
+            ))),
+        }
+    }
+}
+
+fn unauthenticated(err: jsonwebtoken::errors::Error) -> Status {
+    Status::unauthenticated(format!("invalid token: {}", err))
+}
+
+async fn fetch_jwks(url: &str) -> casbin::Result<JwkSet> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(adapter_error)?;
+    let body = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(adapter_error)?
+        .bytes()
+        .await
+        .map_err(adapter_error)?;
+    serde_json::from_slice(&body).map_err(adapter_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JwtAuth;
+    use crate::server::adapter::Config;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+    use tonic::Code;
+
+    static SECRET: &str = "s3cret";
+
+    fn config() -> Config {
+        Config {
+            jwt_secret: SECRET.to_owned(),
+            jwt_issuer: "casbin".to_owned(),
+            jwt_tenant_claim: "org".to_owned(),
+            meta_model: "examples/meta_model.conf".to_owned(),
+            meta_policy: "examples/meta_policy.csv".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    // token signs claims with secret, valid for an hour unless they say otherwise.
+    fn token(secret: &str, claims: serde_json::Value) -> String {
+        let mut claims = claims;
+        if claims.get("exp").is_none() {
+            claims["exp"] = json!(jsonwebtoken::get_current_timestamp() + 3600);
+        }
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    async fn subject(auth: &JwtAuth, token: &str) -> Result<(String, Option<String>), Code> {
+        auth.subject(token).await.map_err(|status| status.code())
+    }
+
+    #[tokio::test]
+    async fn test_from_config() {
+        assert!(JwtAuth::from_config(&Config::default())
+            .await
+            .unwrap()
+            .is_none());
+        let both = Config {
+            jwt_jwks_url: "http://localhost/jwks".to_owned(),
+            ..config()
+        };
+        assert!(JwtAuth::from_config(&both).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subject() {
+        let auth = JwtAuth::from_config(&config()).await.unwrap().unwrap();
+        let valid = token(
+            SECRET,
+            json!({"sub": "alice", "iss": "casbin", "org": "acme"}),
+        );
+        assert_eq!(
+            subject(&auth, &valid).await,
+            Ok(("alice".to_owned(), Some("acme".to_owned())))
+        );
+        let no_tenant = token(SECRET, json!({"sub": "alice", "iss": "casbin"}));
+        assert_eq!(
+            subject(&auth, &no_tenant).await,
+            Ok(("alice".to_owned(), None))
+        );
+
+        let expired = json!({"sub": "alice", "iss": "casbin", "exp": 1});
+        for invalid in [
+            "not a token".to_owned(),
+            token("other", json!({"sub": "alice", "iss": "casbin"})),
+            token(SECRET, json!({"sub": "alice", "iss": "other"})),
+            token(SECRET, expired),
+            token(SECRET, json!({"iss": "casbin"})),
+        ] {
+            assert_eq!(
+                subject(&auth, &invalid).await,
+                Err(Code::Unauthenticated),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subject_claim() {
+        let cfg = Config {
+            jwt_subject_claim: "email".to_owned(),
+            jwt_audience: "casbin-grpc".to_owned(),
+            ..config()
+        };
+        let auth = JwtAuth::from_config(&cfg).await.unwrap().unwrap();
+        let claims = json!({"email": "a@b.c", "iss": "casbin", "aud": "casbin-grpc"});
+        assert_eq!(
+            subject(&auth, &token(SECRET, claims)).await.unwrap().0,
+            "a@b.c"
+        );
+        let claims = json!({"email": "a@b.c", "iss": "casbin", "aud": "other"});
+        assert_eq!(
+            subject(&auth, &token(SECRET, claims)).await,
+            Err(Code::Unauthenticated)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_authorize() {
+        let auth = JwtAuth::from_config(&config()).await.unwrap().unwrap();
+        let authorize = |subject, method| {
+            auth.authorize(subject, "proto.Casbin", method)
+                .map_err(|status| status.code())
+        };
+        assert_eq!(authorize("reader", "Enforce"), Ok(()));
+        assert_eq!(
+            authorize("reader", "AddPolicy"),
+            Err(Code::PermissionDenied)
+        );
+        assert_eq!(authorize("admin", "AddPolicy"), Ok(()));
+        assert_eq!(authorize("alice", "Enforce"), Err(Code::PermissionDenied));
+
+        // Without a meta enforcer, every subject may call every RPC.
+        let cfg = Config {
+            meta_model: String::new(),
+            ..config()
+        };
+        let auth = JwtAuth::from_config(&cfg).await.unwrap().unwrap();
+        assert!(auth.authorize("alice", "proto.Casbin", "AddPolicy").is_ok());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::server::adapter::Config;
+use bytes::{Buf, Bytes};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tonic::body::BoxBody;
+use tonic::codegen::{http, Body, BoxFuture, Service};
+use tonic::transport::NamedService;
+use tonic::Status;
+
+// Limits bound what a call to the Casbin service may take: the size of the messages it
+// receives and sends, 0 for no limit, and how long it runs when the client sets no deadline,
+// per method or else by default, 0 for as long as it takes.
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+    pub max_recv_message_bytes: usize,
+    pub max_send_message_bytes: usize,
+    pub deadline: Duration,
+    pub method_deadlines: HashMap<String, Duration>,
+}
+
+impl Limits {
+    // from_config gets the limits of the local config.
+    pub fn from_config(cfg: &Config) -> Self {
+        Limits {
+            max_recv_message_bytes: cfg.max_recv_message_bytes,
+            max_send_message_bytes: cfg.max_send_message_bytes,
+            deadline: Duration::from_millis(cfg.deadline_ms),
+            method_deadlines: cfg
+                .method_deadline_ms
+                .iter()
+                .map(|(method, ms)| (method.clone(), Duration::from_millis(*ms)))
+                .collect(),
+        }
+    }
+
+    fn deadline(&self, method: &str) -> Duration {
+        self.method_deadlines
+            .get(method)
+            .copied()
+            .unwrap_or(self.deadline)
+    }
+}
+
+// LimitService enforces the Limits of the calls to the service it wraps. Messages too large to
+// receive fail their call with RESOURCE_EXHAUSTED, as do replies too large to send, and calls
+// running past their deadline with DEADLINE_EXCEEDED, though what they do still gets done.
+// The limits are shared, so replacing them applies to the calls that start afterwards.
+#[derive(Clone)]
+pub struct LimitService<S> {
+    inner: S,
+    limits: Arc<RwLock<Limits>>,
+}
+
+impl<S> LimitService<S> {
+    pub fn new(inner: S, limits: Arc<RwLock<Limits>>) -> Self {
+        LimitService { inner, limits }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for LimitService<S>
+where
+    S: Service<http::Request<LimitBody<B>>, Response = http::Response<BoxBody>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    B: Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let method = request.uri().path().rsplit('/').next().unwrap_or_default();
+        let limits = self.limits.read().unwrap();
+        // tonic already enforces the deadlines clients set.
+        let deadline = if request.headers().contains_key("grpc-timeout") {
+            Duration::ZERO
+        } else {
+            limits.deadline(method)
+        };
+        let max_send = limits.max_send_message_bytes;
+        let max_recv = limits.max_recv_message_bytes;
+        drop(limits);
+        let request = request.map(|body| LimitBody::new(body, max_recv, true));
+        // The service polled ready is the one to call, so it is taken and a clone left behind.
+        let clone = self.inner.clone();
+        let call = std::mem::replace(&mut self.inner, clone).call(request);
+        Box::pin(async move {
+            let response = if deadline.is_zero() {
+                call.await?
+            } else {
+                // The call runs on past its deadline rather than being dropped halfway through
+                // changing the policy.
+                match tokio::time::timeout(deadline, tokio::spawn(call)).await {
+                    Ok(Ok(response)) => response?,
+                    Ok(Err(err)) => return Ok(Status::internal(err.to_string()).to_http()),
+                    Err(_) => {
+                        return Ok(Status::deadline_exceeded(format!(
+                            "the call did not finish within {:?}",
+                            deadline
+                        ))
+                        .to_http())
+                    }
+                }
+            };
+            if max_send == 0 {
+                return Ok(response);
+            }
+            Ok(response.map(|body| LimitBody::new(body, max_send, false).boxed_unsync()))
+        })
+    }
+}
+
+impl<S: NamedService> NamedService for LimitService<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+// LimitBody passes on a body of gRPC messages until one is longer than max, then ends it with
+// a RESOURCE_EXHAUSTED status: as an error of a request body, which tonic fails the call with,
+// and in the trailers of a response body, in place of those of the reply.
+pub struct LimitBody<B> {
+    inner: B,
+    max: usize,
+    request: bool,
+    // header gathers the 5-byte prefix of the next message, then left counts its bytes to pass.
+    header: Vec<u8>,
+    left: usize,
+    exceeded: Option<Status>,
+    ended: bool,
+}
+
+impl<B> LimitBody<B> {
+    fn new(inner: B, max: usize, request: bool) -> Self {
+        LimitBody {
+            inner,
+            max,
+            request,
+            header: Vec::with_capacity(5),
+            left: 0,
+            exceeded: None,
+            ended: false,
+        }
+    }
+
+    // check reads the message prefixes in a chunk, getting the length of the chunk up to the
+    // first message too long, if any.
+    fn check(&mut self, chunk: &[u8]) -> Option<usize> {
+        let mut at = 0;
+        while at < chunk.len() {
+            if self.left > 0 {
+                let n = self.left.min(chunk.len() - at);
+                self.left -= n;
+                at += n;
+                continue;
+            }
+            let start = at;
+            let n = (5 - self.header.len()).min(chunk.len() - at);
+            self.header.extend_from_slice(&chunk[at..at + n]);
+            at += n;
+            if self.header.len() < 5 {
+                break;
+            }
+            let len = u32::from_be_bytes([
+                self.header[1],
+                self.header[2],
+                self.header[3],
+                self.header[4],
+            ]) as usize;
+            self.header.clear();
+            if len > self.max {
+                self.exceeded = Some(Status::resource_exhausted(format!(
+                    "the message is {} bytes, over the limit of {}",
+                    len, self.max
+                )));
+                return Some(start);
+            }
+            self.left = len;
+        }
+        None
+    }
+}
+
+impl<B> Body for LimitBody<B>
+where
+    B: Body<Data = Bytes> + Unpin,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        if self.ended {
+            return Poll::Ready(None);
+        }
+        if self.exceeded.is_some() {
+            self.ended = true;
+            if self.request {
+                return Poll::Ready(self.exceeded.take().map(Err));
+            }
+            return Poll::Ready(None);
+        }
+        let mut chunk = match Pin::new(&mut self.inner).poll_data(cx) {
+            Poll::Ready(Some(Ok(chunk))) => chunk,
+            Poll::Ready(Some(Err(err))) => {
+                return Poll::Ready(Some(Err(Status::from_error(err.into()))))
+            }
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+        if self.max == 0 {
+            return Poll::Ready(Some(Ok(chunk)));
+        }
+        match self.check(chunk.chunk()) {
+            None => Poll::Ready(Some(Ok(chunk))),
+            // The messages before the one too long are passed on first.
+            Some(len) if len > 0 => Poll::Ready(Some(Ok(chunk.split_to(len)))),
+            Some(_) => self.poll_data(cx),
+        }
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        if let Some(status) = self.exceeded.take() {
+            let mut trailers = status.to_http().into_parts().0.headers;
+            trailers.remove(http::header::CONTENT_TYPE);
+            return Poll::Ready(Ok(Some(trailers)));
+        }
+        Pin::new(&mut self.inner)
+            .poll_trailers(cx)
+            .map_err(|err| Status::from_error(err.into()))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match &self.exceeded {
+            Some(_) => false,
+            None => self.ended || self.inner.is_end_stream(),
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use casbin::{CachedEnforcer, CoreApi};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+// Logger is told what the enforcers do while their logs are enabled through EnableLog, like
+// the Logger of casbin: the model and policy they load, the roles these give, and the
+// decisions they make with the rules that matched.
+pub trait Logger: Send + Sync {
+    // log_model is given the model as its section, key and value.
+    fn log_model(&self, model: &[Vec<String>]);
+    fn log_enforce(&self, request: &[String], result: bool, explains: &[Vec<String>]);
+    // log_role is given the roles as `user < role`, or `user < role (domain)` in domains.
+    fn log_role(&self, roles: &[String]);
+    // log_policy is given the rules by policy type.
+    fn log_policy(&self, policy: &HashMap<String, Vec<Vec<String>>>);
+}
+
+// TracingLogger logs as tracing events of the casbin target, at the info level.
+#[derive(Default)]
+pub struct TracingLogger;
+
+impl Logger for TracingLogger {
+    fn log_model(&self, model: &[Vec<String>]) {
+        for line in model {
+            tracing::info!(target: "casbin", model = ?line);
+        }
+    }
+
+    fn log_enforce(&self, request: &[String], result: bool, explains: &[Vec<String>]) {
+        let explains: Vec<String> = explains.iter().map(|rule| rule.join(", ")).collect();
+        tracing::info!(
+            target: "casbin",
+            request = %request.join(", "),
+            result,
+            explains = ?explains,
+        );
+    }
+
+    fn log_role(&self, roles: &[String]) {
+        tracing::info!(target: "casbin", roles = ?roles);
+    }
+
+    fn log_policy(&self, policy: &HashMap<String, Vec<Vec<String>>>) {
+        for (ptype, rules) in policy {
+            for rule in rules {
+                tracing::info!(target: "casbin", ptype = %ptype, rule = %rule.join(", "));
+            }
+        }
+    }
+}
+
+// LOGGER is the logger of the enforcers, TracingLogger until set_logger replaces it.
+static LOGGER: RwLock<Option<Arc<dyn Logger>>> = RwLock::new(None);
+
+// set_logger makes every enforcer log through logger.
+pub fn set_logger(logger: Arc<dyn Logger>) {
+    *LOGGER.write().unwrap() = Some(logger);
+}
+
+// logger gets the logger of the enforcers.
+pub fn logger() -> Arc<dyn Logger> {
+    LOGGER
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| Arc::new(TracingLogger))
+}
+
+// LOG_LEVEL switches the level of the tracing events init_tracing prints.
+static LOG_LEVEL: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+// parse_level parses the log_level of the local config, info when empty.
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    if level.is_empty() {
+        return Ok(LevelFilter::INFO);
+    }
+    level.parse().map_err(|_| {
+        format!(
+            "unknown log_level `{}`, expected off, error, warn, info, debug or trace",
+            level
+        )
+    })
+}
+
+// init_tracing prints the tracing events of the process at level and above, those of
+// TracingLogger among them, until set_log_level picks another level.
+pub fn init_tracing(level: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let (filter, handle) = reload::Layer::new(parse_level(level)?);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .try_init()?;
+    let _ = LOG_LEVEL.set(handle);
+    Ok(())
+}
+
+// set_log_level switches the level of the events printed, if init_tracing set up the printing.
+pub fn set_log_level(level: &str) -> Result<(), String> {
+    let level = parse_level(level)?;
+    match LOG_LEVEL.get() {
+        Some(handle) => handle
+            .modify(|filter| *filter = level)
+            .map_err(|err| err.to_string()),
+        None => Ok(()),
+    }
+}
+
+// log_loaded logs the model, policy and roles an enforcer has loaded, if its logs are enabled.
+pub fn log_loaded(e: &CachedEnforcer) {
+    if !e.get_logger().is_enabled() {
+        return;
+    }
+    let logger = logger();
+    let (mut model, mut policy, mut roles) = (vec![], HashMap::new(), vec![]);
+    for (sec, asts) in e.get_model().get_model() {
+        for (key, ast) in asts {
+            model.push(vec![sec.clone(), key.clone(), ast.value.clone()]);
+            if sec == "p" || sec == "g" {
+                policy.insert(key.clone(), ast.get_policy().iter().cloned().collect());
+            }
+            if sec != "g" {
+                continue;
+            }
+            for rule in ast.get_policy() {
+                match rule.as_slice() {
+                    [user, role] => roles.push(format!("{} < {}", user, role)),
+                    [user, role, domain, ..] => {
+                        roles.push(format!("{} < {} ({})", user, role, domain))
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    model.sort();
+    logger.log_model(&model);
+    logger.log_policy(&policy);
+    logger.log_role(&roles);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::logger;
+    use crate::server::explain;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tracing_subscriber::fmt::MakeWriter;
+
+    // Captured collects what a tracing subscriber prints.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Captured {
+        type Writer = Captured;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn test_logs_go_through_tracing() {
+        let captured = Captured::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(captured.clone())
+            .with_ansi(false)
+            .with_max_level(tracing::Level::INFO)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let rule = vec!["alice".to_owned(), "data1".to_owned(), "read".to_owned()];
+            logger().log_enforce(&rule, true, std::slice::from_ref(&rule));
+            explain::set_slow_decision(Duration::from_micros(1));
+            explain::log_if_slow("Enforce", Duration::from_millis(5));
+            explain::set_slow_decision(Duration::ZERO);
+        });
+        let printed = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        assert!(
+            printed.contains("request=alice, data1, read"),
+            "{}",
+            printed
+        );
+        assert!(
+            printed.contains("WARN casbin: slow decision"),
+            "{}",
+            printed
+        );
+
+        // Events under the level of the subscriber are filtered out.
+        let captured = Captured::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(captured.clone())
+            .with_max_level(tracing::Level::ERROR)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            logger().log_role(&["alice < admin".to_owned()]);
+        });
+        assert!(captured.0.lock().unwrap().is_empty());
+    }
+}
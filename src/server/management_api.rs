@@ -1,26 +1,172 @@
 use crate::casbin_proto;
+use crate::server::index::PolicyIndex;
 use crate::CasbinGRPC;
 //use casbin::{Adapter, Enforcer};
 //use tonic::Response;
 
+use casbin::{CachedEnforcer, CoreApi, MgmtApi};
 use casbin_proto::{array2_d_reply, Array2DReply};
+use std::collections::HashSet;
+use tonic::Status;
 
 impl CasbinGRPC {
-    pub fn wrap_plain_policy(&self, policy: Vec<Vec<String>>) -> Array2DReply {
-        if policy.len() == 0 {
-            return Array2DReply {
-                d2: vec![array2_d_reply::D {
-                    d1: vec![String::from("not found.")],
-                }],
-            };
+    // check_field_filter validates a field filter against the rules it is applied to, as
+    // casbin does not bounds-check field_index when matching rules.
+    #[allow(clippy::result_large_err)]
+    pub fn check_field_filter(
+        &self,
+        e: &CachedEnforcer,
+        index: &PolicyIndex,
+        sec: &str,
+        ptype: &str,
+        field_index: i32,
+        field_values: &[String],
+    ) -> Result<usize, Status> {
+        if field_index < 0 {
+            return Err(Status::invalid_argument("field index must not be negative"));
+        }
+        let end = field_index as usize + field_values.len();
+        if index
+            .shortest(e, sec, ptype)
+            .is_some_and(|shortest| shortest < end)
+        {
+            return Err(Status::invalid_argument(
+                "field filter is longer than the policy rules",
+            ));
+        }
+        Ok(field_index as usize)
+    }
+
+    // require_ptype checks that the named policy is defined by the enforcer's model, as casbin
+    // treats an undefined one as holding no rules. The default `p` and `g` are let through so
+    // that models without a role definition keep answering with no rules.
+    #[allow(clippy::result_large_err)]
+    pub fn require_ptype(&self, e: &CachedEnforcer, sec: &str, ptype: &str) -> Result<(), Status> {
+        if ptype == sec
+            || e.get_model()
+                .get_model()
+                .get(sec)
+                .is_some_and(|x| x.contains_key(ptype))
+        {
+            return Ok(());
+        }
+        let kind = if sec == "g" {
+            "grouping policy"
         } else {
-            let mut policy_reply: Array2DReply = Array2DReply { d2: vec![] };
-            for e in 1..policy.len() {
-                policy_reply.d2.push(array2_d_reply::D {
-                    d1: policy[e].to_vec(),
-                })
+            "policy"
+        };
+        Err(Status::invalid_argument(format!(
+            "{} type `{}` is not defined by the model",
+            kind, ptype
+        )))
+    }
+
+    // get_values_for_field gets the distinct values of a field in the named policy. The field is
+    // located through its token, e.g. `p_obj`, so that models with extra fields such as a domain
+    // are handled, falling back to default_index for role definitions which have no tokens.
+    pub fn get_values_for_field(
+        &self,
+        e: &CachedEnforcer,
+        sec: &str,
+        ptype: &str,
+        field: Option<&str>,
+        default_index: usize,
+    ) -> Vec<String> {
+        let ast = match e
+            .get_model()
+            .get_model()
+            .get(sec)
+            .and_then(|x| x.get(ptype))
+        {
+            Some(ast) => ast,
+            None => return vec![],
+        };
+        let index = field
+            .and_then(|field| {
+                let token = format!("{}_{}", ptype, field);
+                ast.tokens.iter().position(|t| *t == token)
+            })
+            .unwrap_or(default_index);
+        let mut seen = HashSet::new();
+        ast.get_policy()
+            .iter()
+            .filter_map(|rule| rule.get(index))
+            .filter(|value| seen.insert(value.to_owned()))
+            .cloned()
+            .collect()
+    }
+
+    // count_rules counts the p and g rules an enforcer currently holds.
+    pub fn count_rules(&self, e: &CachedEnforcer) -> usize {
+        let model = e.get_model().get_model();
+        ["p", "g"]
+            .iter()
+            .filter_map(|sec| model.get(*sec))
+            .flat_map(|assertions| assertions.values())
+            .map(|ast| ast.get_policy().len())
+            .sum()
+    }
+
+    // check_room refuses adding rules to e past the rules an enforcer may hold, with
+    // RESOURCE_EXHAUSTED. Rules already held count as added too.
+    #[allow(clippy::result_large_err)]
+    pub fn check_room(&self, e: &CachedEnforcer, adding: usize) -> Result<(), Status> {
+        if self.max_rules == 0 {
+            return Ok(());
+        }
+        let count = self.count_rules(e);
+        if count + adding > self.max_rules {
+            return Err(Status::resource_exhausted(format!(
+                "the enforcer holds {} rules, {} more would pass the limit of {}",
+                count, adding, self.max_rules
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn wrap_plain_policy(&self, policy: Vec<Vec<String>>) -> Array2DReply {
+        Array2DReply {
+            d2: policy
+                .into_iter()
+                .map(|d1| array2_d_reply::D { d1 })
+                .collect(),
+        }
+    }
+
+    // replace_named_policies replaces old_rules with new_rules in the named policy. casbin
+    // has no update primitive, so this runs as a remove followed by an add while the caller
+    // holds the enforcer, restoring old_rules if the add does not go through.
+    // Returns false, changing nothing, unless every old rule exists and the new rules are
+    // distinct and not present yet.
+    pub async fn replace_named_policies(
+        &self,
+        e: &mut CachedEnforcer,
+        ptype: &str,
+        old_rules: Vec<Vec<String>>,
+        new_rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        if old_rules.is_empty()
+            || old_rules.len() != new_rules.len()
+            || !old_rules
+                .iter()
+                .all(|rule| e.has_named_policy(ptype, rule.to_vec()))
+            || new_rules
+                .iter()
+                .any(|rule| e.has_named_policy(ptype, rule.to_vec()))
+            || new_rules.iter().collect::<HashSet<_>>().len() != new_rules.len()
+        {
+            return Ok(false);
+        }
+
+        if !e.remove_named_policies(ptype, old_rules.to_vec()).await? {
+            return Ok(false);
+        }
+        match e.add_named_policies(ptype, new_rules).await {
+            Ok(true) => Ok(true),
+            res => {
+                e.add_named_policies(ptype, old_rules).await?;
+                res
             }
-            policy_reply
         }
     }
 }
@@ -0,0 +1,337 @@
+use crate::datastructure::lru::Evicted;
+#[cfg(feature = "metrics")]
+use crate::server::registry::EnforcerRegistry;
+#[cfg(feature = "metrics")]
+use casbin::CoreApi;
+#[cfg(feature = "metrics")]
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::convert::Infallible;
+#[cfg(feature = "metrics")]
+use std::future::Future;
+#[cfg(feature = "metrics")]
+use std::net::SocketAddr;
+#[cfg(feature = "metrics")]
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::transport::NamedService;
+use tonic::Code;
+
+// Metrics are the Prometheus metrics of the server, kept once serve_metrics starts serving
+// them.
+#[cfg(feature = "metrics")]
+struct Metrics {
+    registry: Registry,
+    calls: IntCounterVec,
+    call_seconds: HistogramVec,
+    decisions: IntCounterVec,
+    decision_seconds: HistogramVec,
+    cache: IntCounterVec,
+    evictions: IntCounterVec,
+    policy_changes: IntCounterVec,
+    errors: IntCounterVec,
+    rules: IntGaugeVec,
+}
+
+#[cfg(feature = "metrics")]
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+#[cfg(feature = "metrics")]
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new();
+        let calls = IntCounterVec::new(
+            Opts::new("casbin_grpc_calls_total", "Calls by method and status code"),
+            &["method", "code"],
+        )?;
+        // Calls mostly take well under a millisecond.
+        let call_seconds = HistogramVec::new(
+            HistogramOpts::new("casbin_grpc_call_seconds", "How long calls took by method")
+                .buckets(exponential_buckets(0.0001, 4.0, 10)?),
+            &["method"],
+        )?;
+        let decisions = IntCounterVec::new(
+            Opts::new("casbin_decisions_total", "Enforce decisions by outcome"),
+            &["decision"],
+        )?;
+        let decision_seconds = HistogramVec::new(
+            HistogramOpts::new("casbin_enforce_seconds", "How long Enforce decisions took")
+                .buckets(exponential_buckets(0.00001, 4.0, 9)?),
+            &["method"],
+        )?;
+        let cache = IntCounterVec::new(
+            Opts::new(
+                "casbin_decision_cache_lookups_total",
+                "Lookups of the decision cache by whether they hit",
+            ),
+            &["result"],
+        )?;
+        let evictions = IntCounterVec::new(
+            Opts::new(
+                "casbin_decision_cache_evictions_total",
+                "Decisions evicted from the decision cache by reason",
+            ),
+            &["reason"],
+        )?;
+        let policy_changes = IntCounterVec::new(
+            Opts::new("casbin_policy_changes_total", "Policy changes by operation"),
+            &["op"],
+        )?;
+        let errors = IntCounterVec::new(
+            Opts::new("casbin_errors_total", "Casbin errors of the calls by kind"),
+            &["kind"],
+        )?;
+        let rules = IntGaugeVec::new(
+            Opts::new(
+                "casbin_policy_rules",
+                "Rules of each enforcer by policy type",
+            ),
+            &["enforcer", "ptype"],
+        )?;
+        registry.register(Box::new(calls.clone()))?;
+        registry.register(Box::new(call_seconds.clone()))?;
+        registry.register(Box::new(decisions.clone()))?;
+        registry.register(Box::new(decision_seconds.clone()))?;
+        registry.register(Box::new(cache.clone()))?;
+        registry.register(Box::new(evictions.clone()))?;
+        registry.register(Box::new(policy_changes.clone()))?;
+        registry.register(Box::new(errors.clone()))?;
+        registry.register(Box::new(rules.clone()))?;
+        Ok(Metrics {
+            registry,
+            calls,
+            call_seconds,
+            decisions,
+            decision_seconds,
+            cache,
+            evictions,
+            policy_changes,
+            errors,
+            rules,
+        })
+    }
+
+    // render counts the rules of every enforcer of enforcers, then encodes the metrics in the
+    // Prometheus text format.
+    async fn render(&self, enforcers: &EnforcerRegistry) -> Vec<u8> {
+        self.rules.reset();
+        for (handle, entry) in enforcers.list().await {
+            let e = entry.enforcer.read().await;
+            let model = e.get_model().get_model();
+            for sec in ["p", "g"] {
+                for (ptype, ast) in model.get(sec).into_iter().flatten() {
+                    self.rules
+                        .with_label_values(&[&handle.to_string(), ptype])
+                        .set(ast.get_policy().len() as i64);
+                }
+            }
+        }
+        let mut buffer = Vec::new();
+        // Encoding only fails on metrics of mismatched labels.
+        let _ = TextEncoder::new().encode(&self.registry.gather(), &mut buffer);
+        buffer
+    }
+}
+
+// call counts a call to method ending with code after elapsed.
+#[cfg(feature = "metrics")]
+fn call(method: &str, code: Code, elapsed: Duration) {
+    if let Some(m) = METRICS.get() {
+        m.calls
+            .with_label_values(&[method, &format!("{:?}", code)])
+            .inc();
+        m.call_seconds
+            .with_label_values(&[method])
+            .observe(elapsed.as_secs_f64());
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+fn call(_method: &str, _code: Code, _elapsed: Duration) {}
+
+// decided counts a decision of method, made in elapsed.
+#[cfg(feature = "metrics")]
+pub fn decided(method: &str, allowed: bool, elapsed: Duration) {
+    if let Some(m) = METRICS.get() {
+        let decision = if allowed { "allow" } else { "deny" };
+        m.decisions.with_label_values(&[decision]).inc();
+        m.decision_seconds
+            .with_label_values(&[method])
+            .observe(elapsed.as_secs_f64());
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+pub fn decided(_method: &str, _allowed: bool, _elapsed: Duration) {}
+
+// cache_lookup counts a lookup of the decision cache of an enforcer.
+#[cfg(feature = "metrics")]
+pub fn cache_lookup(hit: bool) {
+    if let Some(m) = METRICS.get() {
+        let result = if hit { "hit" } else { "miss" };
+        m.cache.with_label_values(&[result]).inc();
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+pub fn cache_lookup(_hit: bool) {}
+
+// cache_evicted counts a decision the decision cache of an enforcer evicted, and why.
+#[cfg(feature = "metrics")]
+pub fn cache_evicted(why: Evicted) {
+    if let Some(m) = METRICS.get() {
+        let reason = match why {
+            Evicted::Capacity => "capacity",
+            Evicted::Expired => "expired",
+        };
+        m.evictions.with_label_values(&[reason]).inc();
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+pub fn cache_evicted(_why: Evicted) {}
+
+// policy_changed counts a change of the policy of an enforcer, named after the call making it.
+#[cfg(feature = "metrics")]
+pub fn policy_changed(op: &str) {
+    if let Some(m) = METRICS.get() {
+        m.policy_changes.with_label_values(&[op]).inc();
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+pub fn policy_changed(_op: &str) {}
+
+// failed counts an error of casbin by its kind, such as adapter for the failures of the
+// stores.
+#[cfg(feature = "metrics")]
+pub fn failed(err: &casbin::Error) {
+    use casbin::Error;
+    if let Some(m) = METRICS.get() {
+        let kind = match err {
+            Error::IoError(_) => "io",
+            Error::ModelError(_) => "model",
+            Error::PolicyError(_) => "policy",
+            Error::RequestError(_) => "request",
+            Error::RhaiError(_) | Error::RhaiParseError(_) => "matcher",
+            Error::RbacError(_) => "rbac",
+            Error::AdapterError(_) => "adapter",
+        };
+        m.errors.with_label_values(&[kind]).inc();
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+pub fn failed(_err: &casbin::Error) {}
+
+// serve_metrics serves the metrics as `GET /metrics` on addr, in the Prometheus text format,
+// collecting them from then on.
+#[cfg(feature = "metrics")]
+pub async fn serve_metrics<F>(
+    addr: SocketAddr,
+    enforcers: Arc<EnforcerRegistry>,
+    shutdown: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Future<Output = ()>,
+{
+    if METRICS.get().is_none() {
+        let _ = METRICS.set(Metrics::new()?);
+    }
+    let make = hyper::service::make_service_fn(move |_| {
+        let enforcers = enforcers.clone();
+        async move {
+            Ok::<_, Infallible>(hyper::service::service_fn(
+                move |request: http::Request<hyper::Body>| {
+                    let enforcers = enforcers.clone();
+                    async move {
+                        let response = if request.uri().path() != "/metrics" {
+                            http::Response::builder()
+                                .status(http::StatusCode::NOT_FOUND)
+                                .body(hyper::Body::empty())
+                        } else {
+                            let body = METRICS.get().unwrap().render(&enforcers).await;
+                            http::Response::builder()
+                                .header(
+                                    http::header::CONTENT_TYPE,
+                                    TextEncoder::new().format_type(),
+                                )
+                                .body(hyper::Body::from(body))
+                        };
+                        response
+                    }
+                },
+            ))
+        }
+    });
+    tracing::info!(%addr, "metrics listening");
+    hyper::Server::try_bind(&addr)?
+        .serve(make)
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    Ok(())
+}
+
+// MetricsService counts the calls to the service it wraps, and how long they took until the
+// reply started, by method and status code. A streaming call counts once, when it starts.
+#[derive(Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+}
+
+impl<S> MetricsService<S> {
+    pub fn new(inner: S) -> Self {
+        MetricsService { inner }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for MetricsService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let method = request
+            .uri()
+            .path()
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_owned();
+        let start = Instant::now();
+        let fut = self.inner.call(request);
+        Box::pin(async move {
+            let response = fut.await?;
+            // Failed calls reply with their status in the headers, the others in the trailers.
+            let code = response
+                .headers()
+                .get("grpc-status")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<i32>().ok())
+                .map_or(Code::Ok, Code::from);
+            call(&method, code, start.elapsed());
+            Ok(response)
+        })
+    }
+}
+
+impl<S: NamedService> NamedService for MetricsService<S> {
+    const NAME: &'static str = S::NAME;
+}
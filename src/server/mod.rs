@@ -1,6 +1,37 @@
 pub mod abac;
 pub mod adapter;
+pub mod audit;
+pub mod auth;
+pub mod batch;
+pub mod builder;
+pub mod compiled;
+pub mod decisions;
+pub mod domain_api;
 pub mod enforcer;
+pub mod error;
+pub mod explain;
+pub mod functions;
+pub mod health;
+pub mod index;
+pub mod jwt;
+pub mod limits;
+pub mod logger;
 pub mod management_api;
+pub mod metrics;
+pub mod model_api;
+pub mod model_source;
+pub mod priority;
+pub mod ratelimit;
+pub mod rbac_api;
 pub mod rbac_api_test;
+pub mod registry;
+pub mod reload;
+#[cfg(feature = "rest")]
+pub mod rest;
+pub mod roles;
 pub mod rpc_calls;
+pub mod tenants;
+pub mod tls;
+pub mod trace;
+pub mod unix;
+pub mod web;
@@ -0,0 +1,265 @@
+use crate::casbin_proto::ModelDiagnostic;
+use crate::server::error::casbin_status;
+use crate::server::priority;
+use crate::CasbinGRPC;
+
+use casbin::{CachedApi, CachedEnforcer, CoreApi, DefaultModel, Model};
+use regex::Regex;
+use std::collections::HashMap;
+use tonic::Status;
+
+// The sections of a model and the key prefix of the definitions each holds.
+static SECTIONS: [(&str, &str); 5] = [
+    ("request_definition", "r"),
+    ("policy_definition", "p"),
+    ("role_definition", "g"),
+    ("policy_effect", "e"),
+    ("matchers", "m"),
+];
+
+// The policy effects casbin's DefaultEffector supports.
+static EFFECTS: [&str; 5] = [
+    "some(where (p.eft == allow))",
+    "some(where (p.eft == allow)) && !some(where (p.eft == deny))",
+    "!some(where (p.eft == deny))",
+    "priority(p.eft) || deny",
+    "subjectPriority(p.eft) || deny",
+];
+
+fn diagnostic(section: &str, line: usize, message: String) -> ModelDiagnostic {
+    ModelDiagnostic {
+        section: section.to_owned(),
+        line: line as i32,
+        message,
+    }
+}
+
+// validate_model_text checks the structure of a model text and that its matchers and effects
+// only reference what the model defines. Lines are numbered from 1, and diagnostics about
+// something missing from the whole model use line 0.
+pub fn validate_model_text(text: &str) -> Vec<ModelDiagnostic> {
+    let mut diagnostics = vec![];
+    // Definitions by key, e.g. "p" or "g2", with their section, line and value.
+    let mut defs: HashMap<String, (&str, usize, String)> = HashMap::new();
+    let mut section: Option<&str> = None;
+
+    let lines: Vec<&str> = text.lines().collect();
+    let mut i = 0;
+    while i < lines.len() {
+        let start = i + 1;
+        let mut line = lines[i].trim().to_owned();
+        // A trailing backslash continues the definition on the next line.
+        while line.ends_with('\\') && i + 1 < lines.len() {
+            line.pop();
+            i += 1;
+            line.push_str(lines[i].trim());
+        }
+        i += 1;
+
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            let name = &line[1..line.len() - 1];
+            match SECTIONS.iter().find(|(sec, _)| *sec == name) {
+                Some((sec, _)) => section = Some(sec),
+                None => {
+                    section = None;
+                    diagnostics.push(diagnostic(
+                        name,
+                        start,
+                        format!("unknown section [{}]", name),
+                    ));
+                }
+            }
+            continue;
+        }
+        let sec = match section {
+            Some(sec) => sec,
+            None => {
+                diagnostics.push(diagnostic(
+                    "",
+                    start,
+                    String::from("definition outside of a section"),
+                ));
+                continue;
+            }
+        };
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => {
+                diagnostics.push(diagnostic(
+                    sec,
+                    start,
+                    format!("expected `key = value`, found `{}`", line),
+                ));
+                continue;
+            }
+        };
+        let prefix = SECTIONS.iter().find(|(name, _)| *name == sec).unwrap().1;
+        let suffix = key.strip_prefix(prefix).unwrap_or("x");
+        if !suffix.chars().all(|c| c.is_ascii_digit()) {
+            diagnostics.push(diagnostic(
+                sec,
+                start,
+                format!("key `{}` does not belong in [{}]", key, sec),
+            ));
+            continue;
+        }
+        if value.is_empty() {
+            diagnostics.push(diagnostic(sec, start, format!("`{}` has no value", key)));
+            continue;
+        }
+        if defs
+            .insert(key.to_owned(), (sec, start, value.to_owned()))
+            .is_some()
+        {
+            diagnostics.push(diagnostic(
+                sec,
+                start,
+                format!("`{}` is defined more than once", key),
+            ));
+        }
+    }
+
+    for (sec, key) in [
+        ("request_definition", "r"),
+        ("policy_definition", "p"),
+        ("policy_effect", "e"),
+        ("matchers", "m"),
+    ] {
+        if !defs.contains_key(key) {
+            diagnostics.push(diagnostic(sec, 0, format!("missing `{}` definition", key)));
+        }
+    }
+
+    let token_re = Regex::new(r"\b([rp][0-9]*)\.([A-Za-z_][A-Za-z0-9_]*)").unwrap();
+    let role_re = Regex::new(r"\b(g[0-9]*)\s*\(").unwrap();
+    let fields = |key: &str| -> Option<Vec<String>> {
+        defs.get(key)
+            .map(|(_, _, value)| value.split(',').map(|x| x.trim().to_owned()).collect())
+    };
+
+    let mut keys: Vec<&String> = defs.keys().collect();
+    keys.sort();
+    for key in keys {
+        let (sec, line, value) = &defs[key];
+        match *sec {
+            "role_definition" if value.matches('_').count() < 2 => {
+                diagnostics.push(diagnostic(
+                    sec,
+                    *line,
+                    format!("`{}` needs at least two `_` fields", key),
+                ));
+            }
+            "policy_effect" if !EFFECTS.contains(&value.as_str()) => {
+                diagnostics.push(diagnostic(
+                    sec,
+                    *line,
+                    format!("unsupported policy effect `{}`", value),
+                ));
+            }
+            "matchers" => {
+                let mut reported = vec![];
+                for caps in token_re.captures_iter(value) {
+                    let message = match fields(&caps[1]) {
+                        None => format!("`{}` is not defined", &caps[1]),
+                        Some(fields) if !fields.iter().any(|f| *f == caps[2]) => {
+                            format!(
+                                "`{}.{}` is not a field of `{}`",
+                                &caps[1], &caps[2], &caps[1]
+                            )
+                        }
+                        _ => continue,
+                    };
+                    if !reported.contains(&message) {
+                        reported.push(message);
+                    }
+                }
+                for caps in role_re.captures_iter(value) {
+                    if !defs.contains_key(&caps[1]) {
+                        let message = format!("role definition `{}` is not defined", &caps[1]);
+                        if !reported.contains(&message) {
+                            reported.push(message);
+                        }
+                    }
+                }
+                for message in reported {
+                    diagnostics.push(diagnostic(sec, *line, message));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    diagnostics
+}
+
+// parse_model builds a model from text, rejecting it with every problem validate_model_text
+// finds rather than only what casbin itself would catch.
+pub async fn parse_model(text: &str) -> Result<DefaultModel, Status> {
+    let diagnostics = validate_model_text(text);
+    if !diagnostics.is_empty() {
+        let messages: Vec<String> = diagnostics
+            .iter()
+            .map(|d| format!("line {}: {}", d.line, d.message))
+            .collect();
+        return Err(Status::invalid_argument(messages.join("; ")));
+    }
+    DefaultModel::from_str(text)
+        .await
+        .map_err(|err| Status::invalid_argument(err.to_string()))
+}
+
+impl CasbinGRPC {
+    // swap_model replaces the model of an enforcer with m, moving the rules the enforcer holds
+    // into m first. Fails, leaving the enforcer as it was, if m cannot hold those rules.
+    #[allow(clippy::result_large_err)]
+    pub fn swap_model(&self, e: &mut CachedEnforcer, mut m: DefaultModel) -> Result<(), Status> {
+        for sec in ["p", "g"] {
+            let assertions = match e.get_model().get_model().get(sec) {
+                Some(assertions) => assertions,
+                None => continue,
+            };
+            for (ptype, ast) in assertions.iter() {
+                let rules = ast.get_policy();
+                if rules.is_empty() {
+                    continue;
+                }
+                let new_ast = m
+                    .get_mut_model()
+                    .get_mut(sec)
+                    .and_then(|x| x.get_mut(ptype))
+                    .ok_or_else(|| {
+                        Status::failed_precondition(format!(
+                            "the new model does not define {}, which holds {} rules",
+                            ptype,
+                            rules.len()
+                        ))
+                    })?;
+                let arity = if sec == "g" {
+                    new_ast.value.matches('_').count()
+                } else {
+                    new_ast.tokens.len()
+                };
+                if rules.iter().any(|rule| rule.len() != arity) {
+                    return Err(Status::failed_precondition(format!(
+                        "the rules of {} do not fit its definition in the new model",
+                        ptype
+                    )));
+                }
+                new_ast.policy = rules.clone();
+            }
+        }
+
+        std::mem::swap(e.get_mut_model().get_mut_model(), m.get_mut_model());
+        if let Err(err) = e.build_role_links() {
+            std::mem::swap(e.get_mut_model().get_mut_model(), m.get_mut_model());
+            e.build_role_links().map_err(casbin_status)?;
+            return Err(casbin_status(err));
+        }
+        priority::sort_policies(e);
+        e.get_mut_cache().clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+use crate::server::adapter::Config;
+use crate::server::registry::model_hash;
+use reqwest::{redirect, Url};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+static FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// read_model reads model text from a local path or from an https:// or s3:// URL. Remote
+// models are fetched with the given headers and cached under cache_dir, and the cached copy
+// is used when a later fetch fails.
+pub async fn read_model(
+    source: &str,
+    headers: &HashMap<String, String>,
+    cache_dir: &str,
+) -> Result<String, Error> {
+    read(source, headers, cache_dir, None).await
+}
+
+// ModelSources are the model sources clients may name in NewEnforcer: the files under root,
+// and the URLs of hosts, so that they cannot have the server read its other files or call the
+// other services of its network.
+#[derive(Clone, Debug, Default)]
+pub struct ModelSources {
+    pub root: String,
+    pub hosts: Vec<String>,
+}
+
+impl ModelSources {
+    pub fn from_config(cfg: &Config) -> Self {
+        ModelSources {
+            root: cfg.model_root.clone(),
+            hosts: cfg.model_hosts.clone(),
+        }
+    }
+
+    fn allows(&self, url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|host| self.hosts.iter().any(|h| h.eq_ignore_ascii_case(host)))
+    }
+
+    // check gets the source to read for a source named by a client: the canonical path of a
+    // file under root, or a URL of one of hosts. Any other fails with PermissionDenied, a file
+    // that does not exist too, so clients cannot tell which files the server has.
+    pub async fn check(&self, source: &str) -> Result<String, Error> {
+        let denied = |what: &str| {
+            Error::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "the model {} `{}` is not one clients may name",
+                    what, source
+                ),
+            )
+        };
+        if let Some(url) = remote_url(source) {
+            return match Url::parse(&url) {
+                Ok(url) if self.allows(&url) => Ok(source.to_owned()),
+                _ => Err(denied("URL")),
+            };
+        }
+        if self.root.is_empty() {
+            return Err(denied("path"));
+        }
+        let root = tokio::fs::canonicalize(&self.root).await?;
+        match tokio::fs::canonicalize(root.join(source)).await {
+            Ok(path) if path.starts_with(&root) && path.is_file() => {
+                Ok(path.to_string_lossy().into_owned())
+            }
+            _ => Err(denied("path")),
+        }
+    }
+}
+
+// read_client_model reads the model of a source named by a client like read_model, if sources
+// allow it, following only the redirects to their hosts.
+pub async fn read_client_model(
+    source: &str,
+    headers: &HashMap<String, String>,
+    cache_dir: &str,
+    sources: &ModelSources,
+) -> Result<String, Error> {
+    let source = sources.check(source).await?;
+    read(&source, headers, cache_dir, Some(sources)).await
+}
+
+async fn read(
+    source: &str,
+    headers: &HashMap<String, String>,
+    cache_dir: &str,
+    sources: Option<&ModelSources>,
+) -> Result<String, Error> {
+    let url = match remote_url(source) {
+        Some(url) => url,
+        None => return tokio::fs::read_to_string(Path::new(source)).await,
+    };
+
+    let cached = cache_path(cache_dir, source);
+    match fetch(&url, headers, sources).await {
+        Ok(text) => {
+            if let Some(dir) = cached.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            tokio::fs::write(&cached, &text).await?;
+            Ok(text)
+        }
+        Err(err) => match tokio::fs::read_to_string(&cached).await {
+            Ok(text) => {
+                tracing::warn!(%source, error = %err, "using the cached model");
+                Ok(text)
+            }
+            Err(_) => Err(err),
+        },
+    }
+}
+
+// remote_url gets the URL a remote model source is fetched from. s3://bucket/key is read
+// from the bucket's virtual-hosted endpoint in AWS_REGION, without request signing, so
+// private buckets need a gateway that accepts the given headers.
+fn remote_url(source: &str) -> Option<String> {
+    if source.starts_with("https://") {
+        return Some(source.to_owned());
+    }
+    let (bucket, key) = source.strip_prefix("s3://")?.split_once('/')?;
+    Some(match std::env::var("AWS_REGION") {
+        Ok(region) if !region.is_empty() => {
+            format!("https://{}.s3.{}.amazonaws.com/{}", bucket, region, key)
+        }
+        _ => format!("https://{}.s3.amazonaws.com/{}", bucket, key),
+    })
+}
+
+fn cache_path(cache_dir: &str, source: &str) -> PathBuf {
+    let dir = if cache_dir.is_empty() {
+        std::env::temp_dir().join("casbin-grpc-models")
+    } else {
+        PathBuf::from(cache_dir)
+    };
+    dir.join(format!("{}.conf", model_hash(source)))
+}
+
+async fn fetch(
+    url: &str,
+    headers: &HashMap<String, String>,
+    sources: Option<&ModelSources>,
+) -> Result<String, Error> {
+    let mut client = reqwest::Client::builder().timeout(FETCH_TIMEOUT);
+    if let Some(sources) = sources.cloned() {
+        client = client.redirect(redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= 10 || !sources.allows(attempt.url()) {
+                return attempt.stop();
+            }
+            attempt.follow()
+        }));
+    }
+    let client = client.build().map_err(Error::other)?;
+    let mut request = client.get(url);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let response = request.send().await.map_err(|err| fetch_error(url, err))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let kind = match status.as_u16() {
+            401 | 403 => ErrorKind::PermissionDenied,
+            404 => ErrorKind::NotFound,
+            _ => ErrorKind::Other,
+        };
+        return Err(Error::new(
+            kind,
+            format!("fetching model from {} failed: {}", url, status),
+        ));
+    }
+    response.text().await.map_err(|err| fetch_error(url, err))
+}
+
+// fetch_error reports a model server that could not be reached or did not answer in time as
+// not connected or timed out, so callers can tell it from a bad model source.
+fn fetch_error(url: &str, err: reqwest::Error) -> Error {
+    let kind = if err.is_timeout() {
+        ErrorKind::TimedOut
+    } else if err.is_connect() {
+        ErrorKind::NotConnected
+    } else {
+        ErrorKind::Other
+    };
+    Error::new(kind, format!("fetching model from {} failed: {}", url, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources() -> ModelSources {
+        ModelSources {
+            root: "examples".to_owned(),
+            hosts: vec!["models.example.com".to_owned()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_paths() {
+        let sources = sources();
+        let path = sources.check("rbac_model.conf").await.unwrap();
+        assert!(path.ends_with("examples/rbac_model.conf"));
+        assert!(Path::new(&path).is_absolute());
+
+        for path in [
+            "/etc/passwd",
+            "../Cargo.toml",
+            "tls/../../Cargo.toml",
+            "missing.conf",
+        ] {
+            let err = sources.check(path).await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::PermissionDenied, "{}", path);
+        }
+
+        let err = ModelSources::default()
+            .check("rbac_model.conf")
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn test_check_hosts() {
+        let sources = sources();
+        let url = "https://Models.Example.com/rbac_model.conf";
+        assert_eq!(sources.check(url).await.unwrap(), url);
+
+        for url in [
+            "https://169.254.169.254/latest/meta-data",
+            "https://models.example.com.evil.com/rbac_model.conf",
+            "s3://models/rbac_model.conf",
+        ] {
+            let err = sources.check(url).await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::PermissionDenied, "{}", url);
+        }
+
+        let err = read_client_model(
+            "https://169.254.169.254/latest",
+            &HashMap::new(),
+            "",
+            &ModelSources::default(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    }
+}
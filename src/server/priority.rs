@@ -0,0 +1,232 @@
+use casbin::{CachedEnforcer, CoreApi, EventData};
+use std::collections::{HashMap, HashSet};
+
+pub static SUBJECT_PRIORITY: &str = "subjectPriority(p_eft) || deny";
+
+// sort_policies orders the rules of every policy so that `priority(p.eft) || deny` is decided
+// by the highest priority matching rule, as casbin applies rules in the order they are held.
+// Rules of a policy with a `priority` field are sorted by it, lowest first, and under
+// `subjectPriority(p.eft) || deny` rules of subjects deeper in the role hierarchy come first.
+pub fn sort_policies(e: &mut CachedEnforcer) {
+    let model = e.get_model().get_model();
+    let by_subject = model
+        .get("e")
+        .and_then(|x| x.get("e"))
+        .is_some_and(|ast| ast.value == SUBJECT_PRIORITY);
+    let depths = if by_subject {
+        subject_depths(e)
+    } else {
+        HashMap::new()
+    };
+
+    let assertions = match e.get_mut_model().get_mut_model().get_mut("p") {
+        Some(assertions) => assertions,
+        None => return,
+    };
+    for (ptype, ast) in assertions.iter_mut() {
+        let position = |field: &str| {
+            let token = format!("{}_{}", ptype, field);
+            ast.tokens.iter().position(|t| *t == token)
+        };
+        let priority_index = position("priority");
+        if priority_index.is_none() && !by_subject {
+            continue;
+        }
+        let sub_index = position("sub").unwrap_or(0);
+        let dom_index = position("dom");
+
+        let mut rules: Vec<Vec<String>> = std::mem::take(&mut ast.policy).into_iter().collect();
+        if let Some(i) = priority_index {
+            rules.sort_by_key(|rule| {
+                rule.get(i)
+                    .and_then(|p| p.trim().parse::<i64>().ok())
+                    .unwrap_or(i64::MAX)
+            });
+        }
+        if by_subject {
+            rules.sort_by_key(|rule| {
+                let sub = rule.get(sub_index).cloned().unwrap_or_default();
+                let dom = dom_index
+                    .and_then(|i| rule.get(i))
+                    .cloned()
+                    .unwrap_or_default();
+                std::cmp::Reverse(depths.get(&(dom, sub)).copied().unwrap_or(0))
+            });
+        }
+        ast.policy = rules.into_iter().collect();
+    }
+}
+
+// sorts tells whether sort_policies reorders the rules of the policy ptype.
+pub fn sorts(e: &CachedEnforcer, ptype: &str) -> bool {
+    let model = e.get_model().get_model();
+    let token = format!("{}_priority", ptype);
+    model
+        .get("e")
+        .and_then(|x| x.get("e"))
+        .is_some_and(|ast| ast.value == SUBJECT_PRIORITY)
+        || model
+            .get("p")
+            .and_then(|x| x.get(ptype))
+            .is_some_and(|ast| ast.tokens.contains(&token))
+}
+
+// sort_on_change keeps policies sorted as rules are added, removed or reloaded.
+pub fn sort_on_change(e: &mut CachedEnforcer, _d: EventData) {
+    sort_policies(e);
+}
+
+// subject_depths gets how far below the top of the `g` hierarchy each subject sits, keyed by
+// domain and subject, so a user is deeper than the roles it is assigned.
+fn subject_depths(e: &CachedEnforcer) -> HashMap<(String, String), usize> {
+    let mut parents: HashMap<(&str, &str), Vec<&str>> = HashMap::new();
+    if let Some(ast) = e.get_model().get_model().get("g").and_then(|x| x.get("g")) {
+        for rule in ast.get_policy() {
+            if rule.len() < 2 {
+                continue;
+            }
+            let dom = rule.get(2).map_or("", String::as_str);
+            parents.entry((dom, &rule[0])).or_default().push(&rule[1]);
+        }
+    }
+
+    fn depth<'a>(
+        key: (&'a str, &'a str),
+        parents: &HashMap<(&'a str, &'a str), Vec<&'a str>>,
+        depths: &mut HashMap<(&'a str, &'a str), usize>,
+        visiting: &mut HashSet<(&'a str, &'a str)>,
+    ) -> usize {
+        if let Some(d) = depths.get(&key) {
+            return *d;
+        }
+        // A cycle in the hierarchy gives no ordering, so it stops the walk.
+        if !visiting.insert(key) {
+            return 0;
+        }
+        let d = parents.get(&key).map_or(0, |ps| {
+            ps.iter()
+                .map(|p| depth((key.0, p), parents, depths, visiting) + 1)
+                .max()
+                .unwrap_or(0)
+        });
+        visiting.remove(&key);
+        depths.insert(key, d);
+        d
+    }
+
+    let mut depths = HashMap::new();
+    let mut visiting = HashSet::new();
+    for key in parents.keys() {
+        depth(*key, &parents, &mut depths, &mut visiting);
+    }
+    depths
+        .into_iter()
+        .map(|((dom, sub), d)| ((dom.to_owned(), sub.to_owned()), d))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::casbin_proto::casbin_server::Casbin;
+    use crate::casbin_proto::{EnforceRequest, PolicyRequest};
+    use crate::server::enforcer;
+    use crate::CasbinGRPC;
+    use casbin::{DefaultModel, FileAdapter};
+    use tonic::Request;
+
+    async fn serve(model: &str, policy: &'static str) -> (CasbinGRPC, i32) {
+        let casbin = CasbinGRPC::new_server();
+        let model_text = std::fs::read_to_string(model).unwrap();
+        let m = DefaultModel::from_str(&model_text).await.unwrap();
+        let e = enforcer::build_enforcer(m, FileAdapter::new(policy))
+            .await
+            .unwrap();
+        let handle = casbin.add_enforcer(e, model_text).await;
+        (casbin, handle)
+    }
+
+    async fn enforce(casbin: &CasbinGRPC, handle: i32, params: [&str; 3]) -> bool {
+        casbin
+            .enforce(Request::new(EnforceRequest {
+                enforcer_handler: handle,
+                params: params.iter().map(|p| p.to_string()).collect(),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .res
+    }
+
+    #[tokio::test]
+    async fn test_priority() {
+        let (casbin, h) = serve(
+            "examples/priority_model.conf",
+            "examples/priority_policy.csv",
+        )
+        .await;
+        assert!(enforce(&casbin, h, ["alice", "data1", "read"]).await);
+        assert!(!enforce(&casbin, h, ["alice", "data1", "write"]).await);
+        assert!(!enforce(&casbin, h, ["alice", "data2", "read"]).await);
+        assert!(!enforce(&casbin, h, ["bob", "data1", "read"]).await);
+        assert!(enforce(&casbin, h, ["bob", "data2", "read"]).await);
+        assert!(!enforce(&casbin, h, ["bob", "data2", "write"]).await);
+    }
+
+    #[tokio::test]
+    async fn test_explicit_priority() {
+        let (casbin, h) = serve(
+            "examples/priority_explicit_model.conf",
+            "examples/priority_explicit_policy.csv",
+        )
+        .await;
+        assert!(enforce(&casbin, h, ["alice", "data1", "read"]).await);
+        assert!(enforce(&casbin, h, ["alice", "data1", "write"]).await);
+        assert!(!enforce(&casbin, h, ["bob", "data2", "read"]).await);
+        assert!(!enforce(&casbin, h, ["bob", "data2", "write"]).await);
+
+        // A rule added later still applies by its priority rather than its position.
+        let added = casbin
+            .add_policy(Request::new(PolicyRequest {
+                enforcer_handler: h,
+                p_type: "p".to_string(),
+                params: vec!["0", "alice", "data1", "read", "deny"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .res;
+        assert!(added);
+        assert!(!enforce(&casbin, h, ["alice", "data1", "read"]).await);
+    }
+
+    #[tokio::test]
+    async fn test_deny_override() {
+        let (casbin, h) = serve(
+            "examples/rbac_with_deny_model.conf",
+            "examples/rbac_with_deny_policy.csv",
+        )
+        .await;
+        assert!(enforce(&casbin, h, ["alice", "data1", "read"]).await);
+        assert!(enforce(&casbin, h, ["alice", "data2", "read"]).await);
+        assert!(!enforce(&casbin, h, ["alice", "data2", "write"]).await);
+        assert!(enforce(&casbin, h, ["bob", "data2", "write"]).await);
+        assert!(!enforce(&casbin, h, ["bob", "data1", "read"]).await);
+    }
+
+    #[tokio::test]
+    async fn test_subject_priority() {
+        let (casbin, h) = serve(
+            "examples/subject_priority_model.conf",
+            "examples/subject_priority_policy.csv",
+        )
+        .await;
+        assert!(enforce(&casbin, h, ["jane", "data1", "read"]).await);
+        assert!(!enforce(&casbin, h, ["bob", "data1", "read"]).await);
+        assert!(enforce(&casbin, h, ["alice", "data1", "read"]).await);
+        assert!(!enforce(&casbin, h, ["root", "data1", "read"]).await);
+        assert!(!enforce(&casbin, h, ["carol", "data1", "read"]).await);
+    }
+}
@@ -0,0 +1,452 @@
+use crate::server::adapter::Config;
+use crate::server::auth::{AuthKey, AuthSubject};
+use crate::server::tls::TlsConnectInfo;
+use prost::Message;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex, RwLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::transport::server::TcpConnectInfo;
+use tonic::transport::NamedService;
+use tonic::{Code, Status};
+
+// SWEEP_INTERVAL is how often the buckets of the clients that stopped calling are dropped.
+static SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+// RETRY_INFO_TYPE is the type URL of the google.rpc.RetryInfo detail of a rate limited call.
+static RETRY_INFO_TYPE: &str = "type.googleapis.com/google.rpc.RetryInfo";
+
+// RateLimit is how many calls per second a client may make, in bursts of up to burst calls.
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimit {
+    pub rate: f64,
+    pub burst: f64,
+}
+
+impl RateLimit {
+    // new makes a limit of rate calls per second, with bursts of burst calls, or of a second of
+    // calls when it is 0.
+    pub fn new(rate: f64, burst: f64) -> Self {
+        let burst = if burst > 0.0 { burst } else { rate.max(1.0) };
+        RateLimit { rate, burst }
+    }
+}
+
+// Bucket holds the calls a client has left, refilled at the rate of its limit.
+struct Bucket {
+    tokens: f64,
+    at: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.rate).min(limit.burst);
+        self.at = now;
+    }
+
+    // take spends a call, or gets how long until the client has one again.
+    fn take(&mut self, limit: &RateLimit, now: Instant) -> Result<(), Duration> {
+        self.refill(limit, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(Duration::from_secs_f64((1.0 - self.tokens) / limit.rate))
+    }
+}
+
+// Buckets are the buckets of the clients by client and method, along with when the buckets
+// were last swept.
+type Buckets = (HashMap<(String, String), Bucket>, Instant);
+
+// Rates are the limits of a RateLimiter, by default and per method.
+struct Rates {
+    default: Option<RateLimit>,
+    methods: HashMap<String, RateLimit>,
+}
+
+impl Rates {
+    fn of(&self, method: &str) -> Option<&RateLimit> {
+        self.methods.get(method).or(self.default.as_ref())
+    }
+}
+
+// RateLimiter keeps a token bucket per client, and per client and method for the methods with
+// limits of their own, in place of the default limit, if any.
+pub struct RateLimiter {
+    rates: RwLock<Rates>,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(default: Option<RateLimit>, methods: HashMap<String, RateLimit>) -> Self {
+        RateLimiter {
+            rates: RwLock::new(Rates { default, methods }),
+            buckets: Mutex::new((HashMap::new(), Instant::now())),
+        }
+    }
+
+    // from_config makes the limiter of the rate limits of the local config.
+    pub fn from_config(cfg: &Config) -> Self {
+        let (default, methods) = rates(cfg);
+        RateLimiter::new(default, methods)
+    }
+
+    // reconfigure replaces the limits with those of a reloaded local config. The clients keep
+    // the calls they have left, up to the bursts of the new limits.
+    pub fn reconfigure(&self, cfg: &Config) {
+        let (default, methods) = rates(cfg);
+        *self.rates.write().unwrap() = Rates { default, methods };
+    }
+
+    fn is_empty(&self) -> bool {
+        let rates = self.rates.read().unwrap();
+        rates.default.is_none() && rates.methods.is_empty()
+    }
+
+    // check spends a call of client to method, failing with how long until it may call again.
+    pub fn check(&self, client: String, method: &str) -> Result<(), Duration> {
+        let rates = self.rates.read().unwrap();
+        let (key, limit) = match (rates.methods.get(method), &rates.default) {
+            (Some(limit), _) => ((client, method.to_owned()), limit),
+            (None, Some(limit)) => ((client, String::new()), limit),
+            (None, None) => return Ok(()),
+        };
+        let now = Instant::now();
+        let mut guard = self.buckets.lock().unwrap();
+        let (buckets, swept) = &mut *guard;
+        if now.duration_since(*swept) >= SWEEP_INTERVAL {
+            // Buckets filled up again are the same as new ones.
+            buckets.retain(|(_, method), bucket| match rates.of(method) {
+                Some(limit) => {
+                    bucket.refill(limit, now);
+                    bucket.tokens < limit.burst
+                }
+                None => false,
+            });
+            *swept = now;
+        }
+        buckets
+            .entry(key)
+            .or_insert(Bucket {
+                tokens: limit.burst,
+                at: now,
+            })
+            .take(limit, now)
+    }
+}
+
+// rates gets the rate limits of the local config, by default and per method.
+fn rates(cfg: &Config) -> (Option<RateLimit>, HashMap<String, RateLimit>) {
+    let default = (cfg.rate_limit_per_sec > 0.0)
+        .then(|| RateLimit::new(cfg.rate_limit_per_sec, cfg.rate_limit_burst));
+    let methods = cfg
+        .method_rate_limits_per_sec
+        .iter()
+        .filter(|(_, rate)| **rate > 0.0)
+        .map(|(method, rate)| (method.clone(), RateLimit::new(*rate, 0.0)))
+        .collect();
+    (default, methods)
+}
+
+// client_of tells the clients of the calls apart: by the API key or JWT subject they
+// authenticate with, else by the name of their client certificate, else by their IP address.
+fn client_of<B>(request: &http::Request<B>) -> String {
+    let extensions = request.extensions();
+    if let Some(AuthKey(key)) = extensions.get() {
+        return format!("key:{}", key);
+    }
+    if let Some(AuthSubject(subject)) = extensions.get() {
+        return format!("sub:{}", subject);
+    }
+    if let Some(info) = extensions.get::<TlsConnectInfo>() {
+        if let Some(identity) = info.identity() {
+            return format!("cert:{}", identity.name());
+        }
+        if let Some(addr) = info.remote_addr() {
+            return format!("ip:{}", addr.ip());
+        }
+    }
+    if let Some(addr) = extensions
+        .get::<TcpConnectInfo>()
+        .and_then(TcpConnectInfo::remote_addr)
+    {
+        return format!("ip:{}", addr.ip());
+    }
+    // Clients on the unix socket share a bucket.
+    String::from("local")
+}
+
+// RpcStatus, Any, RetryInfo and ProtoDuration encode the google.rpc details of a rate limited
+// call, so clients know when to retry.
+#[derive(Clone, PartialEq, Message)]
+struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    code: i32,
+    #[prost(string, tag = "2")]
+    message: String,
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<Any>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Any {
+    #[prost(string, tag = "1")]
+    type_url: String,
+    #[prost(bytes = "vec", tag = "2")]
+    value: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct RetryInfo {
+    #[prost(message, optional, tag = "1")]
+    retry_delay: Option<ProtoDuration>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ProtoDuration {
+    #[prost(int64, tag = "1")]
+    seconds: i64,
+    #[prost(int32, tag = "2")]
+    nanos: i32,
+}
+
+// rate_limited makes the RESOURCE_EXHAUSTED status of a rate limited call, telling the client
+// to retry after delay through a google.rpc.RetryInfo detail and a retry-after header, in
+// whole seconds.
+pub fn rate_limited(method: &str, delay: Duration) -> Status {
+    let message = format!("too many {} calls, retry in {:?}", method, delay);
+    let retry = RetryInfo {
+        retry_delay: Some(ProtoDuration {
+            seconds: delay.as_secs() as i64,
+            nanos: delay.subsec_nanos() as i32,
+        }),
+    };
+    let details = RpcStatus {
+        code: Code::ResourceExhausted as i32,
+        message: message.clone(),
+        details: vec![Any {
+            type_url: RETRY_INFO_TYPE.to_owned(),
+            value: retry.encode_to_vec(),
+        }],
+    };
+    let mut status = Status::with_details(
+        Code::ResourceExhausted,
+        message,
+        details.encode_to_vec().into(),
+    );
+    let seconds = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+    status
+        .metadata_mut()
+        .insert("retry-after", seconds.to_string().parse().unwrap());
+    status
+}
+
+// RateLimitService rejects the calls of the clients over their limit with RESOURCE_EXHAUSTED,
+// before they reach the service it wraps. A streaming call counts once, when it starts.
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S> RateLimitService<S> {
+    pub fn new(inner: S, limiter: Arc<RateLimiter>) -> Self {
+        RateLimitService { inner, limiter }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for RateLimitService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        if self.limiter.is_empty() {
+            return Box::pin(self.inner.call(request));
+        }
+        let method = request.uri().path().rsplit('/').next().unwrap_or_default();
+        if let Err(delay) = self.limiter.check(client_of(&request), method) {
+            let response = rate_limited(method, delay).to_http();
+            return Box::pin(async move { Ok(response) });
+        }
+        Box::pin(self.inner.call(request))
+    }
+}
+
+impl<S: NamedService> NamedService for RateLimitService<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{client_of, rate_limited, Bucket, RateLimit, RateLimitService, RateLimiter};
+    use super::{RetryInfo, RpcStatus, RETRY_INFO_TYPE};
+    use crate::server::adapter::Config;
+    use crate::server::auth::{AuthKey, AuthSubject};
+    use prost::Message;
+    use std::collections::HashMap;
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use tokio::net::{TcpListener, TcpStream};
+    use tonic::body::BoxBody;
+    use tonic::codegen::{http, Service};
+    use tonic::transport::server::Connected;
+    use tonic::Code;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_rate_limit_burst() {
+        assert_eq!(RateLimit::new(5.0, 2.0).burst, 2.0);
+        assert_eq!(RateLimit::new(5.0, 0.0).burst, 5.0);
+        assert_eq!(RateLimit::new(0.5, 0.0).burst, 1.0);
+    }
+
+    #[test]
+    fn test_bucket() {
+        let limit = RateLimit::new(2.0, 2.0);
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: limit.burst,
+            at: start,
+        };
+        assert!(bucket.take(&limit, start).is_ok());
+        assert!(bucket.take(&limit, start).is_ok());
+        assert_eq!(bucket.take(&limit, start), Err(Duration::from_millis(500)));
+
+        // Half a second refills a call, and a long pause no more than the burst.
+        let later = start + Duration::from_millis(500);
+        assert!(bucket.take(&limit, later).is_ok());
+        assert!(bucket.take(&limit, later).is_err());
+        let much_later = later + Duration::from_secs(60);
+        bucket.refill(&limit, much_later);
+        assert_eq!(bucket.tokens, 2.0);
+    }
+
+    #[test]
+    fn test_check() {
+        let methods = HashMap::from([("Enforce".to_owned(), RateLimit::new(1.0, 1.0))]);
+        let limiter = RateLimiter::new(Some(RateLimit::new(1.0, 2.0)), methods);
+        assert!(!limiter.is_empty());
+
+        // Enforce has a bucket of its own, the other methods share the default one.
+        assert!(limiter.check("ip:a".to_owned(), "Enforce").is_ok());
+        assert!(limiter.check("ip:a".to_owned(), "Enforce").is_err());
+        assert!(limiter.check("ip:a".to_owned(), "AddPolicy").is_ok());
+        assert!(limiter.check("ip:a".to_owned(), "GetPolicy").is_ok());
+        assert!(limiter.check("ip:a".to_owned(), "RemovePolicy").is_err());
+
+        // Each client has buckets of its own.
+        assert!(limiter.check("ip:b".to_owned(), "Enforce").is_ok());
+        assert!(limiter.check("ip:b".to_owned(), "AddPolicy").is_ok());
+    }
+
+    #[test]
+    fn test_from_config() {
+        assert!(RateLimiter::from_config(&Config::default()).is_empty());
+
+        let cfg = Config {
+            method_rate_limits_per_sec: HashMap::from([
+                ("Enforce".to_owned(), 1.0),
+                ("AddPolicy".to_owned(), 0.0),
+            ]),
+            ..Config::default()
+        };
+        let limiter = RateLimiter::from_config(&cfg);
+        assert!(limiter.check("local".to_owned(), "AddPolicy").is_ok());
+        assert!(limiter.check("local".to_owned(), "AddPolicy").is_ok());
+        assert!(limiter.check("local".to_owned(), "Enforce").is_ok());
+        assert!(limiter.check("local".to_owned(), "Enforce").is_err());
+
+        limiter.reconfigure(&Config::default());
+        assert!(limiter.is_empty());
+        assert!(limiter.check("local".to_owned(), "Enforce").is_ok());
+
+        let cfg = Config {
+            rate_limit_per_sec: 1.0,
+            rate_limit_burst: 3.0,
+            ..Config::default()
+        };
+        limiter.reconfigure(&cfg);
+        for _ in 0..3 {
+            assert!(limiter.check("local".to_owned(), "Enforce").is_ok());
+        }
+        assert!(limiter.check("local".to_owned(), "Enforce").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_client_of() {
+        let mut request = http::Request::new(());
+        assert_eq!(client_of(&request), "local");
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        request.extensions_mut().insert(stream.connect_info());
+        assert_eq!(client_of(&request), "ip:127.0.0.1");
+
+        request
+            .extensions_mut()
+            .insert(AuthSubject("alice".to_owned()));
+        assert_eq!(client_of(&request), "sub:alice");
+        request.extensions_mut().insert(AuthKey("ops".to_owned()));
+        assert_eq!(client_of(&request), "key:ops");
+    }
+
+    #[test]
+    fn test_rate_limited() {
+        let status = rate_limited("Enforce", Duration::from_millis(1500));
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert_eq!(status.metadata().get("retry-after").unwrap(), "2");
+
+        let details = RpcStatus::decode(status.details()).unwrap();
+        assert_eq!(details.code, Code::ResourceExhausted as i32);
+        assert_eq!(details.message, status.message());
+        assert_eq!(details.details[0].type_url, RETRY_INFO_TYPE);
+        let retry = RetryInfo::decode(details.details[0].value.as_slice()).unwrap();
+        let delay = retry.retry_delay.unwrap();
+        assert_eq!((delay.seconds, delay.nanos), (1, 500_000_000));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_service() {
+        let inner = tower::service_fn(|_: http::Request<()>| async {
+            Ok::<_, Infallible>(http::Response::new(BoxBody::default()))
+        });
+        let limiter = RateLimiter::new(Some(RateLimit::new(1.0, 1.0)), HashMap::new());
+        let mut service = RateLimitService::new(inner, Arc::new(limiter));
+        let call = || {
+            http::Request::builder()
+                .uri("/proto.Casbin/Enforce")
+                .body(())
+                .unwrap()
+        };
+
+        let response = service.ready().await.unwrap().call(call()).await.unwrap();
+        assert_eq!(response.headers().get("grpc-status"), None);
+        let response = service.ready().await.unwrap().call(call()).await.unwrap();
+        assert_eq!(
+            response.headers().get("grpc-status").unwrap(),
+            &(Code::ResourceExhausted as i32).to_string()
+        );
+        assert_eq!(response.headers().get("retry-after").unwrap(), "1");
+    }
+}
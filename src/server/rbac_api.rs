@@ -0,0 +1,436 @@
+use crate::server::error::casbin_status;
+use crate::CasbinGRPC;
+
+use casbin::{CachedEnforcer, CoreApi, MgmtApi, RbacApi};
+use std::collections::HashSet;
+use tonic::Status;
+
+impl CasbinGRPC {
+    // rbac_types resolves the policy and grouping policy types a role or permission request
+    // works on, `p` and `g` unless the request names others.
+    #[allow(clippy::result_large_err)]
+    pub fn rbac_types<'a>(
+        &self,
+        e: &CachedEnforcer,
+        ptype: &'a str,
+        gtype: &'a str,
+    ) -> Result<(&'a str, &'a str), Status> {
+        let ptype = if ptype.is_empty() { "p" } else { ptype };
+        let gtype = if gtype.is_empty() { "g" } else { gtype };
+        self.require_ptype(e, "p", ptype)?;
+        self.require_ptype(e, "g", gtype)?;
+        Ok((ptype, gtype))
+    }
+
+    // remove_cascade removes the grouping rules of gtype matching groupings from g_index, then
+    // the rules of ptype matching permissions, as one change: if the rules cannot be removed,
+    // the grouping rules already removed are added back before the error is returned.
+    pub async fn remove_cascade(
+        &self,
+        e: &mut CachedEnforcer,
+        (gtype, g_index, groupings): (&str, usize, Vec<String>),
+        (ptype, permissions): (&str, Vec<String>),
+    ) -> Result<bool, Status> {
+        let removed = e.get_filtered_named_grouping_policy(gtype, g_index, groupings.clone());
+        let roles_removed = e
+            .remove_filtered_named_grouping_policy(gtype, g_index, groupings)
+            .await
+            .map_err(casbin_status)?;
+        match e.remove_filtered_named_policy(ptype, 0, permissions).await {
+            Ok(permissions_removed) => Ok(roles_removed || permissions_removed),
+            Err(err) => {
+                if roles_removed {
+                    e.add_named_grouping_policies(gtype, removed)
+                        .await
+                        .map_err(casbin_status)?;
+                }
+                Err(casbin_status(err))
+            }
+        }
+    }
+
+    // grouping_rules gets the rules of the named grouping policy within a domain, or those
+    // without a domain when none is given.
+    // casbin links the rules of every grouping policy into one role manager, so roles are
+    // looked up in the rules themselves to keep g2 resource roles apart from g user roles.
+    fn grouping_rules<'a>(
+        &self,
+        e: &'a CachedEnforcer,
+        gtype: &str,
+        domain: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a Vec<String>> + 'a {
+        e.get_model()
+            .get_model()
+            .get("g")
+            .and_then(|x| x.get(gtype))
+            .into_iter()
+            .flat_map(|ast| ast.get_policy().iter())
+            .filter(move |rule| rule.len() >= 2 && rule.get(2).map(String::as_str) == domain)
+    }
+
+    // roles_for_user gets the roles a user has directly in the named grouping policy.
+    pub fn roles_for_user(
+        &self,
+        e: &CachedEnforcer,
+        gtype: &str,
+        user: &str,
+        domain: Option<&str>,
+    ) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.grouping_rules(e, gtype, domain)
+            .filter(|rule| rule[0] == user && seen.insert(&rule[1]))
+            .map(|rule| rule[1].to_owned())
+            .collect()
+    }
+
+    // users_for_role gets the users that have a role directly in the named grouping policy.
+    pub fn users_for_role(
+        &self,
+        e: &CachedEnforcer,
+        gtype: &str,
+        role: &str,
+        domain: Option<&str>,
+    ) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.grouping_rules(e, gtype, domain)
+            .filter(|rule| rule[1] == role && seen.insert(&rule[0]))
+            .map(|rule| rule[0].to_owned())
+            .collect()
+    }
+
+    // implicit_roles_for_user gets all roles a user has in the named grouping policy,
+    // including those inherited through other roles.
+    pub fn implicit_roles_for_user(
+        &self,
+        e: &CachedEnforcer,
+        gtype: &str,
+        user: &str,
+        domain: Option<&str>,
+    ) -> Vec<String> {
+        let mut res: Vec<String> = vec![];
+        let mut seen = HashSet::new();
+        let mut queue = vec![user.to_owned()];
+        while let Some(name) = queue.pop() {
+            for role in self.roles_for_user(e, gtype, &name, domain) {
+                if seen.insert(role.clone()) {
+                    res.push(role.clone());
+                    queue.push(role);
+                }
+            }
+        }
+        res
+    }
+
+    // permissions_for_user gets the rules of the named policy granted directly to a user or
+    // role, within a domain held in the second field when one is given.
+    pub fn permissions_for_user(
+        &self,
+        e: &CachedEnforcer,
+        ptype: &str,
+        user: &str,
+        domain: Option<&str>,
+    ) -> Vec<Vec<String>> {
+        let mut field_values = vec![user.to_owned()];
+        field_values.extend(domain.map(String::from));
+        e.get_filtered_named_policy(ptype, 0, field_values)
+    }
+
+    // implicit_permissions_for_user gets the rules of the named policy granted to a user or
+    // role, including those granted to the roles it inherits through the grouping policy.
+    pub fn implicit_permissions_for_user(
+        &self,
+        e: &CachedEnforcer,
+        ptype: &str,
+        gtype: &str,
+        user: &str,
+        domain: Option<&str>,
+    ) -> Vec<Vec<String>> {
+        let mut subjects = self.implicit_roles_for_user(e, gtype, user, domain);
+        subjects.insert(0, user.to_owned());
+        subjects
+            .iter()
+            .flat_map(|subject| self.permissions_for_user(e, ptype, subject, domain))
+            .collect()
+    }
+
+    // implicit_users_for_permission gets all users granted a permission, directly or through
+    // the roles they inherit, leaving out the roles themselves. casbin only enforces against
+    // `p`, so for other policy types a user is granted the permission by a rule holding it
+    // verbatim.
+    pub async fn implicit_users_for_permission(
+        &self,
+        e: &CachedEnforcer,
+        ptype: &str,
+        gtype: &str,
+        permission: Vec<String>,
+    ) -> Vec<String> {
+        let mut users = if ptype == "p" && gtype == "g" {
+            e.get_implicit_users_for_permission(permission).await
+        } else {
+            let roles: HashSet<String> = self
+                .get_values_for_field(e, "g", gtype, None, 1)
+                .into_iter()
+                .collect();
+            let mut candidates = self.get_values_for_field(e, "p", ptype, Some("sub"), 0);
+            candidates.extend(self.get_values_for_field(e, "g", gtype, None, 0));
+            let mut seen = HashSet::new();
+            candidates
+                .into_iter()
+                .filter(|user| !roles.contains(user) && seen.insert(user.to_owned()))
+                .filter(|user| {
+                    self.implicit_permissions_for_user(e, ptype, gtype, user, None)
+                        .iter()
+                        .any(|rule| rule[1..] == permission[..])
+                })
+                .collect()
+        };
+        users.sort();
+        users
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::casbin_proto::casbin_server::Casbin;
+    use crate::casbin_proto::UserRoleRequest;
+    use crate::server::enforcer;
+    use crate::CasbinGRPC;
+    use casbin::error::AdapterError;
+    use casbin::{Adapter, CachedEnforcer, DefaultModel, Filter, MemoryAdapter, MgmtApi, Model};
+    use tonic::{Code, Request};
+
+    static RBAC_WITH_DOMAINS: &str = "[request_definition]\nr = sub, dom, obj, act\n\n\
+        [policy_definition]\np = sub, dom, obj, act\n\n[role_definition]\ng = _, _, _\n\n\
+        [policy_effect]\ne = some(where (p.eft == allow))\n\n[matchers]\n\
+        m = g(r.sub, p.sub, r.dom) && r.dom == p.dom && r.obj == p.obj && r.act == p.act";
+
+    // FailingAdapter stores rules like MemoryAdapter, but fails to remove any from the
+    // policy, so only grouping rules can be removed.
+    #[derive(Default)]
+    struct FailingAdapter(MemoryAdapter);
+
+    #[tonic::async_trait]
+    impl Adapter for FailingAdapter {
+        async fn load_policy(&self, m: &mut dyn Model) -> casbin::Result<()> {
+            self.0.load_policy(m).await
+        }
+
+        async fn load_filtered_policy<'a>(
+            &mut self,
+            m: &mut dyn Model,
+            f: Filter<'a>,
+        ) -> casbin::Result<()> {
+            self.0.load_filtered_policy(m, f).await
+        }
+
+        async fn save_policy(&mut self, m: &mut dyn Model) -> casbin::Result<()> {
+            self.0.save_policy(m).await
+        }
+
+        async fn clear_policy(&mut self) -> casbin::Result<()> {
+            self.0.clear_policy().await
+        }
+
+        fn is_filtered(&self) -> bool {
+            self.0.is_filtered()
+        }
+
+        async fn add_policy(
+            &mut self,
+            sec: &str,
+            ptype: &str,
+            rule: Vec<String>,
+        ) -> casbin::Result<bool> {
+            self.0.add_policy(sec, ptype, rule).await
+        }
+
+        async fn add_policies(
+            &mut self,
+            sec: &str,
+            ptype: &str,
+            rules: Vec<Vec<String>>,
+        ) -> casbin::Result<bool> {
+            self.0.add_policies(sec, ptype, rules).await
+        }
+
+        async fn remove_policy(
+            &mut self,
+            sec: &str,
+            ptype: &str,
+            rule: Vec<String>,
+        ) -> casbin::Result<bool> {
+            self.0.remove_policy(sec, ptype, rule).await
+        }
+
+        async fn remove_policies(
+            &mut self,
+            sec: &str,
+            ptype: &str,
+            rules: Vec<Vec<String>>,
+        ) -> casbin::Result<bool> {
+            self.0.remove_policies(sec, ptype, rules).await
+        }
+
+        async fn remove_filtered_policy(
+            &mut self,
+            sec: &str,
+            ptype: &str,
+            field_index: usize,
+            field_values: Vec<String>,
+        ) -> casbin::Result<bool> {
+            if sec == "p" {
+                return Err(AdapterError("store unavailable".into()).into());
+            }
+            self.0
+                .remove_filtered_policy(sec, ptype, field_index, field_values)
+                .await
+        }
+    }
+
+    async fn serve<A: Adapter + 'static>(a: A) -> (CasbinGRPC, i32) {
+        let casbin = CasbinGRPC::new_server();
+        let m = DefaultModel::from_str(RBAC_WITH_DOMAINS).await.unwrap();
+        let mut e = enforcer::build_enforcer(m, a).await.unwrap();
+        seed(&mut e).await;
+        let handle = casbin.add_enforcer(e, RBAC_WITH_DOMAINS.to_string()).await;
+        (casbin, handle)
+    }
+
+    async fn seed(e: &mut CachedEnforcer) {
+        let rules = [
+            ["admin", "domain1", "data1", "read"],
+            ["admin", "domain2", "data2", "read"],
+            ["alice", "domain1", "data3", "read"],
+            ["alice", "domain2", "data4", "read"],
+        ];
+        for rule in rules {
+            e.add_policy(rule.iter().map(|f| f.to_string()).collect())
+                .await
+                .unwrap();
+        }
+        let groupings = [
+            ["alice", "admin", "domain1"],
+            ["alice", "admin", "domain2"],
+            ["bob", "admin", "domain1"],
+        ];
+        for rule in groupings {
+            e.add_grouping_policy(rule.iter().map(|f| f.to_string()).collect())
+                .await
+                .unwrap();
+        }
+    }
+
+    fn request(handle: i32, user: &str, role: &str, domain: &str) -> Request<UserRoleRequest> {
+        Request::new(UserRoleRequest {
+            enforcer_handler: handle,
+            user: user.to_string(),
+            role: role.to_string(),
+            domain: domain.to_string(),
+            ..Default::default()
+        })
+    }
+
+    async fn rules(casbin: &CasbinGRPC, handle: i32) -> (Vec<Vec<String>>, Vec<Vec<String>>) {
+        let e = casbin.get_enforcer(handle).await.unwrap();
+        let e = e.read().await;
+        (e.get_policy(), e.get_grouping_policy())
+    }
+
+    fn has(rules: &[Vec<String>], rule: &[&str]) -> bool {
+        rules.iter().any(|r| r == rule)
+    }
+
+    #[tokio::test]
+    async fn test_delete_user() {
+        let (casbin, h) = serve(MemoryAdapter::default()).await;
+        let res = casbin.delete_user(request(h, "alice", "", "")).await;
+        assert!(res.unwrap().into_inner().res);
+        let (p, g) = rules(&casbin, h).await;
+        assert_eq!(p.len(), 2);
+        assert!(!p.iter().chain(&g).any(|r| r[0] == "alice"));
+        assert!(has(&g, &["bob", "admin", "domain1"]));
+
+        let res = casbin.delete_user(request(h, "alice", "", "")).await;
+        assert!(!res.unwrap().into_inner().res);
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_in_domain() {
+        let (casbin, h) = serve(MemoryAdapter::default()).await;
+        let res = casbin.delete_user(request(h, "alice", "", "domain1")).await;
+        assert!(res.unwrap().into_inner().res);
+        let (p, g) = rules(&casbin, h).await;
+        assert!(!has(&p, &["alice", "domain1", "data3", "read"]));
+        assert!(!has(&g, &["alice", "admin", "domain1"]));
+        assert!(has(&p, &["alice", "domain2", "data4", "read"]));
+        assert!(has(&g, &["alice", "admin", "domain2"]));
+    }
+
+    #[tokio::test]
+    async fn test_delete_role() {
+        let (casbin, h) = serve(MemoryAdapter::default()).await;
+        casbin
+            .delete_role(request(h, "", "admin", ""))
+            .await
+            .unwrap();
+        let (p, g) = rules(&casbin, h).await;
+        assert!(g.is_empty());
+        assert!(!p.iter().any(|r| r[0] == "admin"));
+        assert_eq!(p.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_delete_role_in_domain() {
+        let (casbin, h) = serve(MemoryAdapter::default()).await;
+        casbin
+            .delete_role(request(h, "", "admin", "domain1"))
+            .await
+            .unwrap();
+        let (p, g) = rules(&casbin, h).await;
+        assert!(!has(&p, &["admin", "domain1", "data1", "read"]));
+        assert!(has(&p, &["admin", "domain2", "data2", "read"]));
+        assert_eq!(g, vec![vec!["alice", "admin", "domain2"]]);
+    }
+
+    #[tokio::test]
+    async fn test_delete_rolls_back() {
+        let (casbin, h) = serve(FailingAdapter::default()).await;
+        let before = rules(&casbin, h).await;
+
+        let res = casbin.delete_user(request(h, "alice", "", "")).await;
+        assert_eq!(res.unwrap_err().code(), Code::Unavailable);
+        let res = casbin.delete_role(request(h, "", "admin", "")).await;
+        assert_eq!(res.unwrap_err().code(), Code::Unavailable);
+
+        let (mut p, mut g) = rules(&casbin, h).await;
+        p.sort();
+        g.sort();
+        let (mut want_p, mut want_g) = before;
+        want_p.sort();
+        want_g.sort();
+        assert_eq!((p, g), (want_p, want_g));
+    }
+
+    #[tokio::test]
+    async fn test_empty_names() {
+        let (casbin, h) = serve(MemoryAdapter::default()).await;
+        for (user, role) in [("", "admin"), ("alice", "")] {
+            let res = casbin.has_role_for_user(request(h, user, role, "")).await;
+            assert_eq!(
+                res.unwrap_err().code(),
+                Code::InvalidArgument,
+                "{:?}",
+                (user, role)
+            );
+        }
+        let res = casbin.get_roles_for_user(request(h, "", "", "")).await;
+        assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+        let res = casbin.get_users_for_role(request(h, "", "", "")).await;
+        assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+
+        let res = casbin
+            .get_users_for_role(request(h, "admin", "", "domain1"))
+            .await;
+        assert_eq!(res.unwrap().into_inner().array.len(), 2);
+    }
+}
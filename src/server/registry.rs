@@ -0,0 +1,671 @@
+use crate::datastructure::lru::LruCache;
+use crate::server::abac::AbacArgs;
+use crate::server::compiled::Matchers;
+use crate::server::decisions::DecisionFeed;
+use crate::server::explain;
+use crate::server::functions::{self, CustomFunction, Functions};
+use crate::server::logger;
+use crate::server::metrics;
+use crate::server::roles::RoleCache;
+use crate::server::trace;
+use crate::watcher::feed::{FeedWatcher, PolicyFeed};
+use casbin::{CachedEnforcer, CoreApi, EnforceArgs};
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{OwnedRwLockReadGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tonic::Status;
+
+// now_millis is the current time in milliseconds since the Unix epoch.
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+// model_hash fingerprints a model text so clients can tell which model an enforcer runs.
+pub fn model_hash(model_text: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    model_text.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+// DecisionCacheOptions bound the decisions cached for an enforcer: no more than max_entries of
+// them, each for no longer than ttl unless it is zero. None are cached with max_entries 0, as
+// by default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DecisionCacheOptions {
+    pub max_entries: usize,
+    pub ttl: Duration,
+}
+
+impl DecisionCacheOptions {
+    // new gets the options of a cache, disabled or bounded by max_entries, and ttl.
+    pub fn new(disabled: bool, max_entries: usize, ttl: Duration) -> Self {
+        DecisionCacheOptions {
+            max_entries: if disabled { 0 } else { max_entries },
+            ttl,
+        }
+    }
+}
+
+// DecisionKey is the request a decision was made for: the matcher it was decided with, empty
+// for the matcher of the model, and the request values as they were given. A cached decision
+// is only used for a request equal to it, never for another one hashing alike.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct DecisionKey {
+    matcher: String,
+    params: Vec<String>,
+}
+
+impl DecisionKey {
+    fn new(matcher: &str, rvals: &AbacArgs) -> Self {
+        DecisionKey {
+            matcher: matcher.to_owned(),
+            params: rvals.params().to_vec(),
+        }
+    }
+}
+
+// Decision is a decision cached for an enforcer, with the rules that matched.
+struct Decision {
+    allowed: bool,
+    rules: Arc<[Vec<String>]>,
+}
+
+// DECISION_SHARDS is how many shards the decisions of an enforcer are split into, at most.
+static DECISION_SHARDS: usize = 16;
+
+// DecisionCache holds the decisions made by an enforcer since it last changed, by request, in
+// LRU caches sharded by the hash of the request, so the calls deciding side by side hardly
+// wait for each other. Each shard holds its part of max_entries, evicting its least recently
+// used decision to make room for another, and expires its decisions after ttl.
+struct DecisionCache {
+    options: DecisionCacheOptions,
+    shards: Vec<Mutex<LruCache<DecisionKey, Decision>>>,
+    hasher: RandomState,
+}
+
+impl DecisionCache {
+    fn new(options: DecisionCacheOptions) -> Self {
+        let max_entries = options.max_entries;
+        let count = DECISION_SHARDS.min(max_entries);
+        let shards = (0..count)
+            .map(|i| {
+                let capacity = max_entries / count + usize::from(i < max_entries % count);
+                let cache = LruCache::new(capacity)
+                    .with_ttl(options.ttl)
+                    .on_evict(|_, _, why| metrics::cache_evicted(why));
+                Mutex::new(cache)
+            })
+            .collect();
+        DecisionCache {
+            options,
+            shards,
+            hasher: RandomState::new(),
+        }
+    }
+
+    // shard gets the shard of the request key, none when no decision is cached.
+    fn shard(&self, key: &DecisionKey) -> Option<&Mutex<LruCache<DecisionKey, Decision>>> {
+        if self.shards.is_empty() {
+            return None;
+        }
+        let hash = self.hasher.hash_one(key) as usize;
+        Some(&self.shards[hash % self.shards.len()])
+    }
+
+    // get gets the decision cached for the request key, unless it expired.
+    fn get(&self, key: &DecisionKey) -> Option<(bool, Arc<[Vec<String>]>)> {
+        let mut shard = self.shard(key)?.lock().unwrap();
+        let decision = shard.get(key)?;
+        Some((decision.allowed, decision.rules.clone()))
+    }
+
+    fn insert(&self, key: DecisionKey, allowed: bool, rules: Arc<[Vec<String>]>) {
+        if let Some(shard) = self.shard(&key) {
+            let decision = Decision { allowed, rules };
+            shard.lock().unwrap().insert(key, decision);
+        }
+    }
+
+    // len counts the decisions of every shard, expired ones included until they are evicted.
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.shards.iter().map(|s| s.lock().unwrap().len()).sum()
+    }
+
+    fn clear(&self) {
+        for shard in &self.shards {
+            shard.lock().unwrap().clear();
+        }
+    }
+}
+
+// SharedEnforcer lets the calls only reading an enforcer, Enforce first of all, run side by
+// side, while a call changing it takes it for itself. The decisions made through
+// enforce_cached are cached apart from the enforcer, which only caches them with exclusive
+// access, and dropped whenever it is taken to be changed, so a change to its policy or roles
+// is never decided around.
+// The enforcer is held in a RwLock rather than swapped whole through an ArcSwap: a change
+// would have to be made to a copy of it, and a CachedEnforcer cannot be copied, as it owns
+// its adapter. Reading it through either costs the same next to deciding, as the enforce
+// benchmark shows.
+pub struct SharedEnforcer {
+    enforcer: Arc<RwLock<CachedEnforcer>>,
+    decisions: DecisionCache,
+    matchers: Matchers,
+}
+
+impl SharedEnforcer {
+    pub fn new(e: CachedEnforcer) -> Self {
+        SharedEnforcer::with_cache(
+            e,
+            DecisionCacheOptions::default(),
+            Default::default(),
+            Default::default(),
+        )
+    }
+
+    // with_cache shares e, caching its decisions within options and its role links in roles,
+    // and letting its matchers call functions.
+    pub fn with_cache(
+        e: CachedEnforcer,
+        options: DecisionCacheOptions,
+        roles: Arc<RoleCache>,
+        functions: Arc<Functions>,
+    ) -> Self {
+        SharedEnforcer {
+            enforcer: Arc::new(RwLock::new(e)),
+            decisions: DecisionCache::new(options),
+            matchers: Matchers::new(roles, functions),
+        }
+    }
+
+    // read shares the enforcer with the other calls reading it.
+    pub async fn read(&self) -> EnforcerRead<'_> {
+        EnforcerRead {
+            enforcer: ReadGuard::Locked(self.enforcer.read().await),
+            decisions: &self.decisions,
+            matchers: &self.matchers,
+        }
+    }
+
+    // snapshot shares the enforcer like read, for as long as any clone of the snapshot is held,
+    // so that tasks of their own can decide against it.
+    pub async fn snapshot(self: &Arc<Self>) -> EnforcerSnapshot {
+        EnforcerSnapshot {
+            enforcer: Arc::new(self.enforcer.clone().read_owned().await),
+            shared: self.clone(),
+        }
+    }
+
+    // write takes the enforcer for a call changing it, once the calls reading it are done.
+    pub async fn write(&self) -> RwLockWriteGuard<'_, CachedEnforcer> {
+        let enforcer = self.enforcer.write().await;
+        self.decisions.clear();
+        enforcer
+    }
+
+    // add_function lets the matchers of the enforcer call f. It is passed the enforcer taken
+    // with write, so that no decision is made while the functions change.
+    pub fn add_function(&self, _: &mut CachedEnforcer, f: CustomFunction) {
+        self.matchers.add_function(f);
+    }
+
+    // functions are the functions defined for the matchers of the enforcer.
+    pub fn functions(&self) -> Vec<CustomFunction> {
+        self.matchers.functions()
+    }
+}
+
+// EnforcerSnapshot is an enforcer shared by the calls reading it, which tasks other than the
+// call may hold on to. The enforcer cannot change until every clone of it is dropped.
+#[derive(Clone)]
+pub struct EnforcerSnapshot {
+    enforcer: Arc<OwnedRwLockReadGuard<CachedEnforcer>>,
+    shared: Arc<SharedEnforcer>,
+}
+
+impl EnforcerSnapshot {
+    pub fn read(&self) -> EnforcerRead<'_> {
+        EnforcerRead {
+            enforcer: ReadGuard::Held(&self.enforcer),
+            decisions: &self.shared.decisions,
+            matchers: &self.shared.matchers,
+        }
+    }
+}
+
+// ReadGuard keeps an enforcer from changing while it is read: locked for the reader alone, or
+// held by a snapshot.
+enum ReadGuard<'a> {
+    Locked(RwLockReadGuard<'a, CachedEnforcer>),
+    Held(&'a CachedEnforcer),
+}
+
+impl Deref for ReadGuard<'_> {
+    type Target = CachedEnforcer;
+
+    fn deref(&self) -> &CachedEnforcer {
+        match self {
+            ReadGuard::Locked(e) => e,
+            ReadGuard::Held(e) => e,
+        }
+    }
+}
+
+// EnforcerRead is an enforcer shared by the calls reading it.
+pub struct EnforcerRead<'a> {
+    enforcer: ReadGuard<'a>,
+    decisions: &'a DecisionCache,
+    matchers: &'a Matchers,
+}
+
+impl EnforcerRead<'_> {
+    // enforce_with decides like enforce, with the matchers compiled once for the enforcer, and
+    // with matcher in place of the matcher of the model unless it is empty.
+    pub fn enforce_with<ARGS: EnforceArgs>(
+        &self,
+        matcher: &str,
+        rvals: ARGS,
+    ) -> casbin::Result<bool> {
+        self.matchers.enforce(&self.enforcer, matcher, rvals)
+    }
+
+    // enforce_cached decides like enforce_mut, from the decisions cached since the enforcer
+    // last changed when it can.
+    pub fn enforce_cached(&self, rvals: AbacArgs) -> casbin::Result<bool> {
+        self.enforce_explained(rvals).map(|(res, _)| res)
+    }
+
+    // enforce_explained decides like enforce_cached, and returns the rules that matched along
+    // with the decision.
+    pub fn enforce_explained(&self, rvals: AbacArgs) -> casbin::Result<(bool, Arc<[Vec<String>]>)> {
+        self.decide(("Enforce", "casbin.enforce"), "", rvals)
+    }
+
+    // enforce_with_matcher decides like enforce_explained, but evaluates the request with
+    // matcher in place of the matcher of the model, or with the matcher of the model when it
+    // is empty. The matcher is compiled once and kept for the next requests giving it.
+    pub fn enforce_with_matcher(
+        &self,
+        matcher: &str,
+        rvals: AbacArgs,
+    ) -> casbin::Result<(bool, Arc<[Vec<String>]>)> {
+        self.decide(
+            ("EnforceWithMatcher", "casbin.enforce_with_matcher"),
+            matcher,
+            rvals,
+        )
+    }
+
+    // decide decides rvals with matcher for method, traced as a span named name, from the
+    // decisions cached since the enforcer last changed when it can. A decision for which a
+    // function that is not cacheable was called is not cached.
+    fn decide(
+        &self,
+        (method, name): (&'static str, &'static str),
+        matcher: &str,
+        rvals: AbacArgs,
+    ) -> casbin::Result<(bool, Arc<[Vec<String>]>)> {
+        let (start, key) = (Instant::now(), DecisionKey::new(matcher, &rvals));
+        let mut span = trace::Span::start(name);
+        if let Some((allowed, rules)) = self.decisions.get(&key) {
+            metrics::cache_lookup(true);
+            metrics::decided(method, allowed, start.elapsed());
+            span.set_bool("casbin.cached", true);
+            span.set_bool("casbin.allowed", allowed);
+            // casbin, which would log the decision, is not asked for it.
+            if self.enforcer.get_logger().is_enabled() {
+                logger::logger().log_enforce(&key.params, allowed, &rules);
+            }
+            return Ok((allowed, rules));
+        }
+        metrics::cache_lookup(false);
+        span.set_bool("casbin.cached", false);
+        explain::take_explained();
+        functions::take_uncacheable();
+        let res = self.enforce_with(matcher, rvals);
+        if let Err(err) = &res {
+            span.fail(err);
+        }
+        let res = res?;
+        metrics::decided(method, res, start.elapsed());
+        explain::log_if_slow(method, start.elapsed());
+        span.set_bool("casbin.allowed", res);
+        let rules: Arc<[Vec<String>]> = explain::take_explained().into();
+        if !functions::take_uncacheable() {
+            self.decisions.insert(key, res, rules.clone());
+        }
+        Ok((res, rules))
+    }
+}
+
+impl Deref for EnforcerRead<'_> {
+    type Target = CachedEnforcer;
+
+    fn deref(&self) -> &CachedEnforcer {
+        &self.enforcer
+    }
+}
+
+// EnforcerEntry is an enforcer registered under a handle, along with the text of the model it
+// runs, the feed of its policy changes and when it was last used.
+pub struct EnforcerEntry {
+    pub enforcer: Arc<SharedEnforcer>,
+    pub feed: Arc<PolicyFeed>,
+    pub decisions: Arc<DecisionFeed>,
+    model_text: std::sync::RwLock<String>,
+    pinned: AtomicBool,
+    last_used: AtomicI64,
+}
+
+impl EnforcerEntry {
+    pub fn model_text(&self) -> String {
+        self.model_text.read().unwrap().to_owned()
+    }
+
+    pub fn model_hash(&self) -> String {
+        model_hash(&self.model_text.read().unwrap())
+    }
+
+    // set_model_text records the text of a model swapped into the enforcer.
+    pub fn set_model_text(&self, model_text: String) {
+        *self.model_text.write().unwrap() = model_text;
+    }
+
+    pub fn last_used(&self) -> i64 {
+        self.last_used.load(Ordering::Relaxed)
+    }
+
+    // pinned entries are never evicted for being idle.
+    pub fn is_pinned(&self) -> bool {
+        self.pinned.load(Ordering::Relaxed)
+    }
+
+    fn touch(&self) {
+        self.last_used.store(now_millis(), Ordering::Relaxed);
+    }
+}
+
+// EnforcerRegistry holds the enforcers of the server by handle. Handles are never reused, so
+// a client holding the handle of a deleted enforcer gets an error instead of another
+// enforcer. The enforcers clients create are admitted within the limits of the registry, if
+// any: how many there may be, and how many decisions their caches may hold together. Their
+// matchers may call the functions of the registry.
+#[derive(Default)]
+pub struct EnforcerRegistry {
+    entries: RwLock<HashMap<i32, Arc<EnforcerEntry>>>,
+    next_handle: AtomicI32,
+    max_enforcers: usize,
+    max_cached_decisions: usize,
+    functions: Arc<Functions>,
+}
+
+impl EnforcerRegistry {
+    // with_limits makes a registry admitting up to max_enforcers enforcers, whose caches hold
+    // up to max_cached_decisions decisions together, either unlimited when 0, and whose
+    // matchers may call functions.
+    pub fn with_limits(
+        max_enforcers: usize,
+        max_cached_decisions: usize,
+        functions: Arc<Functions>,
+    ) -> Self {
+        EnforcerRegistry {
+            max_enforcers,
+            max_cached_decisions,
+            functions,
+            ..Default::default()
+        }
+    }
+
+    pub fn functions(&self) -> &Arc<Functions> {
+        &self.functions
+    }
+
+    fn entry(
+        &self,
+        mut e: CachedEnforcer,
+        model_text: String,
+        cache: DecisionCacheOptions,
+    ) -> EnforcerEntry {
+        let feed = Arc::new(PolicyFeed::default());
+        e.set_watcher(Box::new(FeedWatcher::new(feed.clone(), None)));
+        EnforcerEntry {
+            enforcer: Arc::new(SharedEnforcer::with_cache(
+                e,
+                cache,
+                feed.roles().clone(),
+                self.functions.clone(),
+            )),
+            feed,
+            decisions: Default::default(),
+            model_text: std::sync::RwLock::new(model_text),
+            pinned: AtomicBool::new(false),
+            last_used: AtomicI64::new(now_millis()),
+        }
+    }
+
+    // insert registers e, caching its decisions within cache, whatever the limits of the
+    // registry. Its role links are cached until its feed publishes a change to its roles.
+    pub async fn insert(
+        &self,
+        e: CachedEnforcer,
+        model_text: String,
+        cache: DecisionCacheOptions,
+    ) -> i32 {
+        let entry = self.entry(e, model_text, cache);
+        let mut entries = self.entries.write().await;
+        let handle = self.next_handle.fetch_add(1, Ordering::Relaxed);
+        entries.insert(handle, Arc::new(entry));
+        handle
+    }
+
+    // admit registers e like insert, unless that takes the registry past its limits, failing
+    // with RESOURCE_EXHAUSTED.
+    pub async fn admit(
+        &self,
+        e: CachedEnforcer,
+        model_text: String,
+        cache: DecisionCacheOptions,
+    ) -> Result<i32, Status> {
+        let entry = self.entry(e, model_text, cache);
+        let mut entries = self.entries.write().await;
+        if self.max_enforcers > 0 && entries.len() >= self.max_enforcers {
+            return Err(Status::resource_exhausted(format!(
+                "{} enforcers are registered, the most allowed",
+                entries.len()
+            )));
+        }
+        if self.max_cached_decisions > 0 {
+            let cached: usize = entries
+                .values()
+                .map(|entry| entry.enforcer.decisions.options.max_entries)
+                .sum();
+            if cached + cache.max_entries > self.max_cached_decisions {
+                return Err(Status::resource_exhausted(format!(
+                    "the enforcers cache up to {} decisions, {} more would pass the limit of {}, ask for fewer with decisionCache",
+                    cached, cache.max_entries, self.max_cached_decisions
+                )));
+            }
+        }
+        let handle = self.next_handle.fetch_add(1, Ordering::Relaxed);
+        entries.insert(handle, Arc::new(entry));
+        Ok(handle)
+    }
+
+    // get looks up an enforcer and marks it as used.
+    pub async fn get(&self, handle: i32) -> Option<Arc<EnforcerEntry>> {
+        let entry = self.entries.read().await.get(&handle).cloned()?;
+        entry.touch();
+        Some(entry)
+    }
+
+    pub async fn pin(&self, handle: i32) -> bool {
+        match self.entries.read().await.get(&handle) {
+            Some(entry) => {
+                entry.pinned.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // remove unregisters an enforcer. Calls already holding it run to completion.
+    pub async fn remove(&self, handle: i32) -> bool {
+        self.entries.write().await.remove(&handle).is_some()
+    }
+
+    // list gets the registered enforcers ordered by handle, without marking them as used.
+    pub async fn list(&self) -> Vec<(i32, Arc<EnforcerEntry>)> {
+        let mut entries: Vec<(i32, Arc<EnforcerEntry>)> = self
+            .entries
+            .read()
+            .await
+            .iter()
+            .map(|(handle, entry)| (*handle, entry.clone()))
+            .collect();
+        entries.sort_by_key(|(handle, _)| *handle);
+        entries
+    }
+
+    // evict_idle removes the enforcers that are not pinned and have not been used within ttl,
+    // returning their handles.
+    pub async fn evict_idle(&self, ttl: Duration) -> Vec<i32> {
+        let deadline = now_millis() - ttl.as_millis() as i64;
+        let mut entries = self.entries.write().await;
+        let idle: Vec<i32> = entries
+            .iter()
+            .filter(|(_, entry)| !entry.is_pinned() && entry.last_used() < deadline)
+            .map(|(handle, _)| *handle)
+            .collect();
+        for handle in idle.iter() {
+            entries.remove(handle);
+        }
+        idle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecisionCache, DecisionCacheOptions, DecisionKey, SharedEnforcer};
+    use crate::server::abac::resolve_abac;
+    use crate::server::enforcer;
+    use casbin::{DefaultModel, FileAdapter, MgmtApi};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    async fn shared(max_entries: usize) -> SharedEnforcer {
+        let m = DefaultModel::from_file("examples/rbac_model.conf")
+            .await
+            .unwrap();
+        let e = enforcer::build_enforcer(m, FileAdapter::new("examples/rbac_policy.csv"))
+            .await
+            .unwrap();
+        let cache = DecisionCacheOptions {
+            max_entries,
+            ..Default::default()
+        };
+        SharedEnforcer::with_cache(e, cache, Default::default(), Default::default())
+    }
+
+    fn request(params: &[&str]) -> crate::server::abac::AbacArgs {
+        resolve_abac(params.iter().map(|p| p.to_string()).collect()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_decision_cache_is_opt_in() {
+        assert_eq!(DecisionCacheOptions::default().max_entries, 0);
+        let e = shared(0).await;
+        let e = e.read().await;
+        assert!(e
+            .enforce_cached(request(&["alice", "data1", "read"]))
+            .unwrap());
+        assert!(e.decisions.len() == 0);
+    }
+
+    #[tokio::test]
+    async fn test_decision_cache_compares_requests() {
+        let e = shared(100).await;
+        let read = e.read().await;
+        let (allowed, rules) = read
+            .enforce_explained(request(&["alice", "data1", "read"]))
+            .unwrap();
+        assert!(allowed);
+        assert_eq!(rules.to_vec(), vec![vec!["alice", "data1", "read"]]);
+        assert_eq!(read.decisions.len(), 1);
+
+        // Cached or not, each request gets its own decision.
+        for _ in 0..2 {
+            assert!(read
+                .enforce_cached(request(&["alice", "data1", "read"]))
+                .unwrap());
+            assert!(!read
+                .enforce_cached(request(&["alice", "data1", "write"]))
+                .unwrap());
+            assert!(!read
+                .enforce_cached(request(&["alice", "data1read", ""]))
+                .unwrap());
+        }
+        assert_eq!(read.decisions.len(), 3);
+
+        // A matcher given to EnforceWithMatcher is part of the request.
+        let (allowed, _) = read
+            .enforce_with_matcher("r.sub == \"bob\"", request(&["alice", "data1", "read"]))
+            .unwrap();
+        assert!(!allowed);
+        assert!(read
+            .enforce_cached(request(&["alice", "data1", "read"]))
+            .unwrap());
+        assert_eq!(read.decisions.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_decision_cache_cleared_on_change() {
+        let e = shared(100).await;
+        let rvals = || request(&["alice", "data1", "read"]);
+        assert!(e.read().await.enforce_cached(rvals()).unwrap());
+        e.write()
+            .await
+            .remove_policy(vec!["alice".into(), "data1".into(), "read".into()])
+            .await
+            .unwrap();
+        let read = e.read().await;
+        assert_eq!(read.decisions.len(), 0);
+        assert!(!read.enforce_cached(rvals()).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_decision_cache_bounds() {
+        let e = shared(20).await;
+        let read = e.read().await;
+        for i in 0..100 {
+            let user = format!("user{}", i);
+            assert!(!read
+                .enforce_cached(request(&[&user, "data1", "read"]))
+                .unwrap());
+        }
+        assert!(read.decisions.len() <= 20);
+        assert_eq!(read.decisions.shards.len(), 16);
+
+        let cache = DecisionCache::new(DecisionCacheOptions {
+            max_entries: 3,
+            ttl: Duration::from_millis(20),
+        });
+        assert_eq!(cache.shards.len(), 3);
+        let key = DecisionKey {
+            matcher: String::new(),
+            params: vec!["alice".to_owned()],
+        };
+        cache.insert(key.clone(), true, Arc::from(vec![]));
+        assert_eq!(cache.get(&key).map(|(allowed, _)| allowed), Some(true));
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(cache.get(&key).is_none());
+        assert_eq!(cache.len(), 0);
+    }
+}
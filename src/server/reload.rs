@@ -0,0 +1,180 @@
+use crate::server::adapter::{load_local_configuration, Config};
+use crate::server::builder::{check_config, watch_policy};
+use crate::server::explain;
+use crate::server::limits::Limits;
+use crate::server::logger;
+use crate::server::ratelimit::RateLimiter;
+use crate::server::registry::EnforcerEntry;
+use crate::server::tls;
+use crate::watcher::feed::FeedWatcher;
+use casbin::CoreApi;
+use serde_json::{Map, Value};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+// RELOADABLE are the settings of the local config a reload applies to the running server.
+// The certificate is only swapped for another when the server is served over the TLS of the
+// local config, and the watcher when it has an enforcer of its own.
+pub static RELOADABLE: &[&str] = &[
+    "log_level",
+    "rate_limit_per_sec",
+    "rate_limit_burst",
+    "method_rate_limits_per_sec",
+    "max_recv_message_bytes",
+    "max_send_message_bytes",
+    "deadline_ms",
+    "method_deadline_ms",
+    "slow_decision_ms",
+    "tls_cert",
+    "tls_key",
+    "watcher",
+    "watcher_channel",
+];
+
+// Reloaded tells which settings differ from those the server started with: the ones a reload
+// applied, and the ones it cannot, which take effect once the server restarts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Reloaded {
+    pub reloaded: Vec<String>,
+    pub restart_required: Vec<String>,
+}
+
+// Reloader reloads the local config into the running server, on SIGHUP or a ReloadConfig
+// call, keeping its enforcers and their policy, decisions and connections as they are.
+// Reloads run one at a time.
+pub struct Reloader {
+    // running is what the server runs with, the settings it started with but for those
+    // reloaded since, as JSON.
+    running: Mutex<Map<String, Value>>,
+    rate_limiter: Arc<RateLimiter>,
+    limits: Arc<RwLock<Limits>>,
+    // local is the enforcer of the local config, and its handle.
+    local: Option<(Arc<EnforcerEntry>, i32)>,
+    // tls tells whether the server is served over the TLS of the local config.
+    tls: bool,
+}
+
+fn settings(cfg: &Config) -> Map<String, Value> {
+    match serde_json::to_value(cfg) {
+        Ok(Value::Object(settings)) => settings,
+        _ => Map::new(),
+    }
+}
+
+impl Reloader {
+    // new reloads into a server started with cfg, whose calls are limited by rate_limiter and
+    // limits.
+    pub fn new(
+        cfg: &Config,
+        rate_limiter: Arc<RateLimiter>,
+        limits: Arc<RwLock<Limits>>,
+        local: Option<(Arc<EnforcerEntry>, i32)>,
+        tls: bool,
+    ) -> Self {
+        Reloader {
+            running: Mutex::new(settings(cfg)),
+            rate_limiter,
+            limits,
+            local,
+            tls,
+        }
+    }
+
+    fn is_reloadable(&self, key: &str, cfg: &Config) -> bool {
+        if key.starts_with("tls_") && (!self.tls || cfg.tls_cert.is_empty()) {
+            return false;
+        }
+        RELOADABLE.contains(&key)
+    }
+
+    // reload loads the local config again and applies the reloadable settings that changed.
+    // A config that fails to load or check is rejected as a whole, and a certificate or a
+    // watcher that fails to open leaves the rest as they were.
+    pub async fn reload(&self) -> Result<Reloaded, String> {
+        let cfg = load_local_configuration()
+            .await
+            .map_err(|err| err.to_string())?;
+        check_config(&cfg).map_err(|err| err.to_string())?;
+        let mut running = self.running.lock().await;
+        let changed: Vec<(String, Value)> = settings(&cfg)
+            .into_iter()
+            .filter(|(key, value)| running.get(key) != Some(value))
+            .collect();
+        let reloads = |prefix: &str| {
+            changed
+                .iter()
+                .any(|(key, _)| key.starts_with(prefix) && self.is_reloadable(key, &cfg))
+        };
+        if reloads("tls_") {
+            tls::reload(&cfg.tls_cert, &cfg.tls_key)
+                .await
+                .map_err(|err| format!("tls_cert and tls_key: {}", err))?;
+        }
+        if reloads("watcher") {
+            self.reload_watcher(&cfg).await?;
+        }
+        logger::set_log_level(&cfg.log_level)?;
+        self.rate_limiter.reconfigure(&cfg);
+        *self.limits.write().unwrap() = Limits::from_config(&cfg);
+        explain::set_slow_decision(Duration::from_millis(cfg.slow_decision_ms));
+
+        let mut reloaded = Reloaded::default();
+        for (key, value) in changed {
+            if self.is_reloadable(&key, &cfg) {
+                running.insert(key.clone(), value);
+                reloaded.reloaded.push(key);
+            } else {
+                reloaded.restart_required.push(key);
+            }
+        }
+        Ok(reloaded)
+    }
+
+    // reload_watcher makes the enforcer of the local config notify its changes through the
+    // watcher of cfg, dropping the one it had.
+    async fn reload_watcher(&self, cfg: &Config) -> Result<(), String> {
+        let (entry, handle) = match &self.local {
+            Some(local) => local,
+            None => return Ok(()),
+        };
+        if cfg.watcher.is_empty() {
+            let watcher = FeedWatcher::new(entry.feed.clone(), None);
+            entry.enforcer.write().await.set_watcher(Box::new(watcher));
+            return Ok(());
+        }
+        watch_policy(entry.clone(), *handle, cfg)
+            .await
+            .map_err(|err| format!("watcher: {}", err))
+    }
+}
+
+// spawn_on_hangup reloads the local config whenever the process gets SIGHUP, logging what it
+// reloaded.
+pub fn spawn_on_hangup(reloader: Arc<Reloader>) {
+    #[cfg(unix)]
+    {
+        let mut hangups =
+            match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+                Ok(hangups) => hangups,
+                Err(err) => {
+                    tracing::error!(error = %err, "listening for SIGHUP failed");
+                    return;
+                }
+            };
+        tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                match reloader.reload().await {
+                    Ok(reloaded) => tracing::info!(
+                        reloaded = %reloaded.reloaded.join(", "),
+                        restart_required = %reloaded.restart_required.join(", "),
+                        "reloaded the config"
+                    ),
+                    Err(err) => tracing::error!(error = %err, "reloading the config failed"),
+                }
+            }
+        });
+    }
+    #[cfg(not(unix))]
+    let _ = reloader;
+}
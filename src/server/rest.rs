@@ -0,0 +1,373 @@
+use crate::casbin_proto::*;
+use crate::server::tls::{self, TlsConfig, TlsConnectInfo};
+use axum::extract::{FromRequestParts, Query, State};
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use bytes::{BufMut, BytesMut};
+use futures::Stream;
+use prost::Message;
+use serde::Serialize;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, Body, Service};
+use tonic::transport::server::{Connected, TcpConnectInfo, TcpIncoming};
+use tonic::{Code, Status};
+use tower::util::{BoxCloneService, MapRequest, ServiceExt};
+
+// FORWARDED_HEADERS are the headers of a REST call passed on to the gRPC call it is transcoded
+// to: the credentials of the client, the tenant it calls for and its trace context.
+static FORWARDED_HEADERS: &[&str] = &[
+    "authorization",
+    "x-api-key",
+    "x-tenant-id",
+    "traceparent",
+    "tracestate",
+];
+
+// GrpcService is the Casbin service, with the authentication, limits and replication of the
+// server in front of it, the REST calls are transcoded to.
+type GrpcService = BoxCloneService<http::Request<hyper::Body>, http::Response<BoxBody>, Infallible>;
+
+// Gateway transcodes REST calls, JSON messages shaped like those of the Casbin service, to
+// gRPC calls of the Casbin service, so they go through the same checks as any other call.
+#[derive(Clone)]
+struct Gateway {
+    grpc: Arc<Mutex<GrpcService>>,
+}
+
+// Caller is what a REST call passes on to the gRPC call it is transcoded to: the credentials
+// and the connection of its client.
+struct Caller {
+    headers: http::HeaderMap,
+    tcp: Option<TcpConnectInfo>,
+    tls: Option<TlsConnectInfo>,
+}
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Caller {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let mut headers = http::HeaderMap::new();
+        for name in FORWARDED_HEADERS {
+            if let Some(value) = parts.headers.get(*name) {
+                headers.insert(*name, value.clone());
+            }
+        }
+        Ok(Caller {
+            headers,
+            tcp: parts.extensions.get().cloned(),
+            tls: parts.extensions.get().cloned(),
+        })
+    }
+}
+
+impl Gateway {
+    // call makes the gRPC call to method with message on behalf of caller.
+    async fn call<Req, Reply>(
+        &self,
+        method: &str,
+        caller: Caller,
+        message: Req,
+    ) -> Result<Reply, Status>
+    where
+        Req: Message,
+        Reply: Message + Default,
+    {
+        let mut body = BytesMut::with_capacity(5 + message.encoded_len());
+        body.put_u8(0);
+        body.put_u32(message.encoded_len() as u32);
+        message
+            .encode(&mut body)
+            .map_err(|err| Status::internal(err.to_string()))?;
+        let mut request = http::Request::new(hyper::Body::from(body.freeze()));
+        *request.method_mut() = http::Method::POST;
+        *request.uri_mut() = format!("/proto.Casbin/{}", method)
+            .parse()
+            .map_err(|err: http::uri::InvalidUri| Status::internal(err.to_string()))?;
+        *request.headers_mut() = caller.headers;
+        let headers = request.headers_mut();
+        headers.insert(
+            "content-type",
+            http::HeaderValue::from_static("application/grpc"),
+        );
+        headers.insert("te", http::HeaderValue::from_static("trailers"));
+        let extensions = request.extensions_mut();
+        if let Some(info) = caller.tcp {
+            extensions.insert(info);
+        }
+        if let Some(info) = caller.tls {
+            extensions.insert(info);
+        }
+
+        let grpc = self.grpc.lock().unwrap().clone();
+        let response = grpc
+            .oneshot(request)
+            .await
+            .unwrap_or_else(|err| match err {});
+        // Calls failing before replying carry their status in the headers.
+        if let Some(status) = Status::from_header_map(response.headers()) {
+            if status.code() != Code::Ok {
+                return Err(status);
+            }
+        }
+        let mut body = response.into_body();
+        let mut data = BytesMut::new();
+        while let Some(chunk) = body.data().await {
+            data.extend_from_slice(&chunk?);
+        }
+        if let Some(status) = body
+            .trailers()
+            .await?
+            .as_ref()
+            .and_then(Status::from_header_map)
+        {
+            if status.code() != Code::Ok {
+                return Err(status);
+            }
+        }
+        if data.len() < 5 {
+            return Err(Status::internal("the reply is missing"));
+        }
+        Reply::decode(&data[5..]).map_err(|err| Status::internal(err.to_string()))
+    }
+}
+
+// RestError is the reply to a failed REST call: its gRPC status, with the HTTP status code
+// closest to it.
+struct RestError(Status);
+
+#[derive(Serialize)]
+struct ErrorBody {
+    code: i32,
+    message: String,
+}
+
+impl IntoResponse for RestError {
+    fn into_response(self) -> Response {
+        let status = match self.0.code() {
+            Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+                http::StatusCode::BAD_REQUEST
+            }
+            Code::Unauthenticated => http::StatusCode::UNAUTHORIZED,
+            Code::PermissionDenied => http::StatusCode::FORBIDDEN,
+            Code::NotFound => http::StatusCode::NOT_FOUND,
+            Code::AlreadyExists | Code::Aborted => http::StatusCode::CONFLICT,
+            Code::ResourceExhausted => http::StatusCode::TOO_MANY_REQUESTS,
+            Code::Cancelled => http::StatusCode::REQUEST_TIMEOUT,
+            Code::Unimplemented => http::StatusCode::NOT_IMPLEMENTED,
+            Code::Unavailable => http::StatusCode::SERVICE_UNAVAILABLE,
+            Code::DeadlineExceeded => http::StatusCode::GATEWAY_TIMEOUT,
+            _ => http::StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = ErrorBody {
+            code: self.0.code() as i32,
+            message: self.0.message().to_owned(),
+        };
+        let mut response = (status, Json(body)).into_response();
+        if let Some(retry_after) = self.0.metadata().get("retry-after") {
+            if let Ok(value) = http::HeaderValue::from_bytes(retry_after.as_bytes()) {
+                response.headers_mut().insert("retry-after", value);
+            }
+        }
+        response
+    }
+}
+
+// json_call and query_call make the handler of a REST call transcoded to method, taking its
+// message from the JSON body of the call, or from the query string of a GET.
+macro_rules! json_call {
+    ($method:literal, $req:ty => $reply:ty) => {
+        |State(gateway): State<Gateway>, caller: Caller, Json(message): Json<$req>| async move {
+            gateway
+                .call::<_, $reply>($method, caller, message)
+                .await
+                .map(Json)
+                .map_err(RestError)
+        }
+    };
+}
+
+macro_rules! query_call {
+    ($method:literal, $req:ty => $reply:ty) => {
+        |State(gateway): State<Gateway>, caller: Caller, Query(message): Query<$req>| async move {
+            gateway
+                .call::<_, $reply>($method, caller, message)
+                .await
+                .map(Json)
+                .map_err(RestError)
+        }
+    };
+}
+
+// routes maps the REST calls onto the RPCs of the Casbin service. The messages are the JSON
+// forms of those of the RPCs, with their fields in camelCase, e.g. `POST /v1/enforce` with
+// `{"enforcerHandler": 0, "params": ["alice", "data1", "read"]}` replies `{"res": true}`.
+fn routes(gateway: Gateway) -> Router {
+    Router::new()
+        .route(
+            "/v1/enforce",
+            post(json_call!("Enforce", EnforceRequest => BoolReply)),
+        )
+        .route(
+            "/v1/enforce/batch",
+            post(json_call!("BatchEnforce", BatchEnforceRequest => BoolArrayReply)),
+        )
+        .route(
+            "/v1/enforce/explain",
+            post(json_call!("EnforceEx", EnforceRequest => EnforceExReply)),
+        )
+        .route(
+            "/v1/enforcers",
+            get(query_call!("ListEnforcers", ListEnforcersRequest => ListEnforcersReply)),
+        )
+        .route(
+            "/v1/model",
+            get(query_call!("GetModel", EmptyRequest => ModelReply)),
+        )
+        .route(
+            "/v1/functions",
+            post(json_call!("AddCustomFunction", CustomFunctionRequest => EmptyReply)),
+        )
+        .route(
+            "/v1/config/reload",
+            post(json_call!("ReloadConfig", ReloadConfigRequest => ReloadConfigReply)),
+        )
+        .route(
+            "/v1/policies",
+            get(query_call!("GetPolicy", EmptyRequest => Array2DReply))
+                .post(json_call!("AddPolicy", PolicyRequest => BoolReply))
+                .put(json_call!("UpdatePolicy", UpdatePolicyRequest => BoolReply))
+                .delete(json_call!("RemovePolicy", PolicyRequest => BoolReply)),
+        )
+        .route(
+            "/v1/policies/filter",
+            post(json_call!("GetFilteredPolicy", FilteredPolicyRequest => Array2DReply)),
+        )
+        .route(
+            "/v1/policies/has",
+            post(json_call!("HasPolicy", PolicyRequest => BoolReply)),
+        )
+        .route(
+            "/v1/policies/load",
+            post(json_call!("LoadPolicy", EmptyRequest => EmptyReply)),
+        )
+        .route(
+            "/v1/policies/save",
+            post(json_call!("SavePolicy", EmptyRequest => EmptyReply)),
+        )
+        .route(
+            "/v1/grouping-policies",
+            get(query_call!("GetGroupingPolicy", EmptyRequest => Array2DReply))
+                .post(json_call!("AddGroupingPolicy", PolicyRequest => BoolReply))
+                .delete(json_call!("RemoveGroupingPolicy", PolicyRequest => BoolReply)),
+        )
+        .route(
+            "/v1/grouping-policies/filter",
+            post(json_call!("GetFilteredGroupingPolicy", FilteredPolicyRequest => Array2DReply)),
+        )
+        .route(
+            "/v1/subjects",
+            get(query_call!("GetAllSubjects", EmptyRequest => ArrayReply)),
+        )
+        .route(
+            "/v1/objects",
+            get(query_call!("GetAllObjects", EmptyRequest => ArrayReply)),
+        )
+        .route(
+            "/v1/actions",
+            get(query_call!("GetAllActions", EmptyRequest => ArrayReply)),
+        )
+        .route(
+            "/v1/roles",
+            get(query_call!("GetAllRoles", EmptyRequest => ArrayReply)),
+        )
+        .route(
+            "/v1/users/roles",
+            get(query_call!("GetImplicitRolesForUser", UserRoleRequest => ArrayReply))
+                .post(json_call!("AddRoleForUser", UserRoleRequest => BoolReply))
+                .delete(json_call!("DeleteRoleForUser", UserRoleRequest => BoolReply)),
+        )
+        .route(
+            "/v1/roles/users",
+            get(query_call!("GetUsersForRole", UserRoleRequest => ArrayReply)),
+        )
+        .route(
+            "/v1/users/permissions",
+            get(query_call!("GetImplicitPermissionsForUser", PermissionRequest => Array2DReply)),
+        )
+        .with_state(gateway)
+}
+
+// serve serves the REST gateway on addr, over TLS with tls if any, until shutdown completes,
+// transcoding its calls to calls of grpc.
+pub async fn serve<S, F>(
+    addr: SocketAddr,
+    tls: Option<TlsConfig>,
+    grpc: S,
+    shutdown: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    S: Service<http::Request<hyper::Body>, Response = http::Response<BoxBody>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    F: Future<Output = ()>,
+{
+    let app = routes(Gateway {
+        grpc: Arc::new(Mutex::new(BoxCloneService::new(grpc))),
+    });
+    match tls {
+        Some(tls) => {
+            let incoming = tls::incoming(addr, tls).await?;
+            tracing::info!(%addr, "REST gateway listening over TLS");
+            serve_incoming(incoming, app, shutdown).await
+        }
+        None => {
+            let incoming = TcpIncoming::new(addr, true, None)
+                .map_err(|err| err as Box<dyn std::error::Error>)?;
+            tracing::info!(%addr, "REST gateway listening");
+            serve_incoming(incoming, app, shutdown).await
+        }
+    }
+}
+
+// serve_incoming serves app on the connections of incoming, with the connect info of each in
+// the extensions of its requests, like tonic does.
+async fn serve_incoming<I, IO, E, F>(
+    incoming: I,
+    app: Router,
+    shutdown: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    I: Stream<Item = Result<IO, E>>,
+    IO: Connected + AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    F: Future<Output = ()>,
+{
+    let make = hyper::service::make_service_fn(move |conn: &IO| {
+        let info = conn.connect_info();
+        let app = app.clone();
+        async move {
+            Ok::<_, Infallible>(MapRequest::new(
+                app,
+                move |mut request: http::Request<hyper::Body>| {
+                    request.extensions_mut().insert(info.clone());
+                    request
+                },
+            ))
+        }
+    });
+    hyper::Server::builder(hyper::server::accept::from_stream(incoming))
+        .serve(make)
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    Ok(())
+}
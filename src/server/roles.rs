@@ -0,0 +1,159 @@
+use crate::datastructure::sharded::ShardedHashTable;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// ROLE_LINKS_CAPACITY bounds the role links cached for an enforcer. Once full, the links of
+// generations gone by are dropped, and then all of them.
+static ROLE_LINKS_CAPACITY: usize = 100_000;
+
+// RoleCacheStats tell how the role cache of an enforcer has done since it was made.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RoleCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub generation: u64,
+}
+
+// RoleCache caches whether a user reaches a role, directly or through other roles, as the
+// role definitions of the matchers ask the role manager. Each link is cached with the
+// generation of the roles it was resolved in; a change to the grouping policy starts another
+// generation, so the links resolved before it are resolved again when next asked for.
+#[derive(Default)]
+pub struct RoleCache {
+    generation: AtomicU64,
+    links: ShardedHashTable<LinkKey, (bool, u64)>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+// LinkKey is a link asked for: whether name1 reaches name2, in domain if given, through the
+// role definition role. A cached link is only used for a link equal to it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct LinkKey {
+    role: String,
+    name1: String,
+    name2: String,
+    domain: Option<String>,
+}
+
+impl LinkKey {
+    fn new(role: &str, name1: &str, name2: &str, domain: Option<&str>) -> Self {
+        LinkKey {
+            role: role.to_owned(),
+            name1: name1.to_owned(),
+            name2: name2.to_owned(),
+            domain: domain.map(str::to_owned),
+        }
+    }
+}
+
+impl RoleCache {
+    // has_link tells whether name1 reaches name2, in domain if given, through the role
+    // definition role, asking resolve unless the link is cached for the current generation.
+    pub fn has_link(
+        &self,
+        role: &str,
+        name1: &str,
+        name2: &str,
+        domain: Option<&str>,
+        resolve: impl FnOnce() -> bool,
+    ) -> bool {
+        let key = LinkKey::new(role, name1, name2, domain);
+        let generation = self.generation.load(Ordering::Acquire);
+        let cached = self
+            .links
+            .get_with(&key, |(linked, g)| (*g == generation).then_some(*linked))
+            .flatten();
+        if let Some(linked) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return linked;
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let linked = resolve();
+        let links = &self.links;
+        if links.len() >= ROLE_LINKS_CAPACITY && !links.contains_key(&key) {
+            links.retain(|_, (_, g)| *g == generation);
+            if links.len() >= ROLE_LINKS_CAPACITY {
+                links.clear();
+            }
+        }
+        links.insert(key, (linked, generation));
+        linked
+    }
+
+    // invalidate starts another generation of the roles, once they changed.
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    // apply starts another generation of the roles for a change to the policy ptype, as
+    // published to the feed of the enforcer, unless it only changed rules of a policy.
+    pub fn apply(&self, op: &str, ptype: &str) {
+        let rules_changed = matches!(
+            op,
+            "AddPolicy"
+                | "AddPolicies"
+                | "RemovePolicy"
+                | "RemovePolicies"
+                | "RemoveFilteredPolicy"
+        );
+        if !rules_changed || ptype.starts_with('g') {
+            self.invalidate();
+        }
+    }
+
+    pub fn stats(&self) -> RoleCacheStats {
+        let generation = self.generation.load(Ordering::Acquire);
+        RoleCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: {
+                let mut entries = 0;
+                self.links.for_each(|_, (_, g)| {
+                    if *g == generation {
+                        entries += 1;
+                    }
+                });
+                entries
+            },
+            generation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RoleCache;
+
+    #[test]
+    fn test_role_cache_keys_links_apart() {
+        let cache = RoleCache::default();
+        assert!(cache.has_link("g", "alice", "admin", None, || true));
+        assert!(!cache.has_link("g", "alice", "admin", Some("domain1"), || false));
+        assert!(!cache.has_link("g2", "alice", "admin", None, || false));
+        assert!(!cache.has_link("g", "alic", "eadmin", None, || false));
+
+        // Cached, each link keeps what it was resolved to.
+        let resolve = || panic!("the link is cached");
+        assert!(cache.has_link("g", "alice", "admin", None, resolve));
+        assert!(!cache.has_link("g", "alice", "admin", Some("domain1"), resolve));
+        assert!(!cache.has_link("g2", "alice", "admin", None, resolve));
+        assert!(!cache.has_link("g", "alic", "eadmin", None, resolve));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (4, 4, 4));
+    }
+
+    #[test]
+    fn test_role_cache_invalidate() {
+        let cache = RoleCache::default();
+        assert!(cache.has_link("g", "alice", "admin", None, || true));
+        cache.apply("AddPolicy", "p");
+        assert!(cache.has_link("g", "alice", "admin", None, || panic!("the link is cached")));
+        cache.apply("RemovePolicy", "g");
+        assert!(!cache.has_link("g", "alice", "admin", None, || false));
+        cache.apply("LoadPolicy", "");
+        assert_eq!(cache.stats().entries, 0);
+        assert_eq!(cache.stats().generation, 2);
+    }
+}
@@ -1,27 +1,68 @@
-use std::sync::Arc;
-
 use crate::casbin_proto;
 use crate::casbin_proto::casbin_server::Casbin;
 use crate::casbin_proto::{
-    Array2DReply, ArrayReply, BoolReply, EmptyReply, EmptyRequest, FilteredPolicyRequest,
-    PolicyRequest, SimpleGetRequest,
+    Array2DReply, ArrayReply, BoolArrayReply, BoolReply, ClearPolicyRequest, EmptyReply,
+    EmptyRequest, FilteredPolicyRequest, PoliciesRequest, PolicyRequest, SimpleGetRequest,
+    UpdatePoliciesRequest, UpdatePolicyRequest,
 };
 use futures::lock::Mutex;
-use tonic::{Request, Response, Status};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
 
+use crate::server::abac;
 use crate::server::adapter;
+use crate::server::audit::{Caller, Entry};
+use crate::server::batch;
+use crate::server::decisions::{self, DECISIONS_BUFFER};
+use crate::server::enforcer;
+use crate::server::error::casbin_status;
+use crate::server::explain;
+use crate::server::functions::CustomFunction;
+use crate::server::index;
+use crate::server::logger;
+use crate::server::model_api;
+use crate::server::model_source;
+use crate::server::priority;
+use crate::server::registry::DecisionCacheOptions;
 use crate::CasbinGRPC;
+use casbin::DefaultModel;
 use casbin::MgmtApi;
-use casbin::{Adapter, CoreApi, RbacApi};
-use casbin::{CachedEnforcer, DefaultModel};
+use casbin::{CachedApi, CoreApi, Filter};
 
 impl CasbinGRPC {
-    pub fn convert_permission(&self, user: String, permissions: Vec<String>) -> Vec<String> {
-        let mut params = vec![user];
-        for perm in permissions.into_iter() {
-            params.push(perm);
+    // require_permission rejects an empty permission, which would otherwise match, or be
+    // stored as, a rule without an object or action.
+    #[allow(clippy::result_large_err)]
+    pub fn require_permission(&self, permissions: &[String]) -> Result<(), Status> {
+        if permissions.is_empty() {
+            return Err(Status::invalid_argument("permission must not be empty"));
+        }
+        Ok(())
+    }
+
+    // require_name rejects an empty user or role, which casbin's filtered removals would
+    // otherwise treat as a wildcard matching every rule.
+    #[allow(clippy::result_large_err)]
+    pub fn require_name(&self, kind: &str, name: &str) -> Result<(), Status> {
+        if name.is_empty() {
+            return Err(Status::invalid_argument(format!(
+                "{} must not be empty",
+                kind
+            )));
+        }
+        Ok(())
+    }
+
+    // domain_of treats an empty domain in a request as no domain.
+    pub fn domain_of<'a>(&self, domain: &'a str) -> Option<&'a str> {
+        if domain.is_empty() {
+            None
+        } else {
+            Some(domain)
         }
-        params
     }
 }
 
@@ -35,39 +76,44 @@ impl Casbin for CasbinGRPC {
         request: Request<casbin_proto::UserRoleRequest>,
     ) -> Result<Response<casbin_proto::ArrayReply>, Status> {
         let get_inner = request.into_inner();
-
+        self.require_name("user", &get_inner.user)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
-        let mut roles = vec![];
-        if let Some(outer_model) = e.get_model().get_model().to_owned().get_mut("g") {
-            if let Some(inner_model) = outer_model.get_mut("g") {
-                // &mut Assertion
-                // mut Assertion
-                roles = inner_model.rm.write().get_roles(&get_inner.user, None);
-            }
-        }
-        // let roles_for_user = e.get_model().
-        let response = casbin_proto::ArrayReply { array: roles };
-        Ok(Response::new(response))
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        let (_, gtype) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let roles = self.roles_for_user(
+            &e,
+            gtype,
+            &get_inner.user,
+            self.domain_of(&get_inner.domain),
+        );
+        Ok(Response::new(casbin_proto::ArrayReply { array: roles }))
     }
 
-    // get_implicit_roles_for_user gets all permissions(including children) for a user or role.
+    // get_implicit_roles_for_user gets all roles a user has, including those inherited through
+    // other roles, in the request's domain if one is given.
     async fn get_implicit_roles_for_user(
         &self,
         request: Request<casbin_proto::UserRoleRequest>,
     ) -> Result<Response<casbin_proto::ArrayReply>, Status> {
         let get_inner = request.into_inner();
+        self.require_name("user", &get_inner.user)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
-        // let implicit_roles_for_user = e.expect("permission not found.");
-        let response = casbin_proto::ArrayReply { array: [].to_vec() };
-        Ok(Response::new(response))
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        let (_, gtype) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let mut roles = self.implicit_roles_for_user(
+            &e,
+            gtype,
+            &get_inner.user,
+            self.domain_of(&get_inner.domain),
+        );
+        roles.sort();
+        Ok(Response::new(casbin_proto::ArrayReply { array: roles }))
     }
 
     // get_users_for_role gets the users that have a role.
@@ -77,42 +123,49 @@ impl Casbin for CasbinGRPC {
     ) -> Result<Response<casbin_proto::ArrayReply>, Status> {
         let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
-        let mut res = vec![];
-        if let Some(t1) = e.get_model().get_model().get("g") {
-            if let Some(t2) = t1.get("g") {
-                res = t2.rm.read().get_users(&get_inner.user, None);
-            }
-        }
-        let response = casbin_proto::ArrayReply { array: res };
-        Ok(Response::new(response))
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        let (_, gtype) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        // Clients ported from casbin-server pass the role in the user field.
+        let role = if get_inner.role.is_empty() {
+            &get_inner.user
+        } else {
+            &get_inner.role
+        };
+        self.require_name("role", role)?;
+        let users = self.users_for_role(&e, gtype, role, self.domain_of(&get_inner.domain));
+        Ok(Response::new(casbin_proto::ArrayReply { array: users }))
     }
 
-    //  has_role_for_user determines whether a user has a role.
+    // has_role_for_user determines whether a user has a role directly, in the request's domain
+    // if one is given.
     async fn has_role_for_user(
         &self,
         request: Request<casbin_proto::UserRoleRequest>,
     ) -> Result<Response<casbin_proto::BoolReply>, Status> {
         let get_inner = request.into_inner();
+        self.require_name("user", &get_inner.user)?;
+        self.require_name("role", &get_inner.role)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
-
-        let roles = e.get_roles_for_user(get_inner.user.as_str(), None);
-        for role in roles.into_iter() {
-            if role == get_inner.role {
-                return Ok(Response::new(casbin_proto::BoolReply { res: true }));
-            }
-        }
-        return Ok(Response::new(casbin_proto::BoolReply { res: false }));
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        let (_, gtype) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let roles = self.roles_for_user(
+            &e,
+            gtype,
+            &get_inner.user,
+            self.domain_of(&get_inner.domain),
+        );
+        Ok(Response::new(casbin_proto::BoolReply {
+            res: roles.contains(&get_inner.role),
+        }))
     }
 
-    // add_role_for_user adds a role for a user.
+    // add_role_for_user adds a role for a user, in the request's domain if one is given.
     // Returns false if the user already has the role (aka not affected).
     async fn add_role_for_user(
         &self,
@@ -120,118 +173,138 @@ impl Casbin for CasbinGRPC {
     ) -> Result<Response<casbin_proto::BoolReply>, Status> {
         let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
-        let mut user_vec = Vec::new();
-        user_vec.push(get_inner.user);
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        let (_, gtype) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let mut rule = vec![get_inner.user, get_inner.role];
+        rule.extend(self.domain_of(&get_inner.domain).map(String::from));
+        self.check_room(&e, 1)?;
         let rule_added = e
-            .add_grouping_policy(user_vec)
+            .add_named_grouping_policy(gtype, rule)
             .await
-            .expect("permission not found.");
+            .map_err(casbin_status)?;
         Ok(Response::new(casbin_proto::BoolReply { res: rule_added }))
     }
 
-    // delete_role_for_user deletes a role for a user.
+    // delete_role_for_user deletes a role for a user, in the request's domain if one is given.
     // Returns false if the user does not have the role (aka not affected).
-
-    // Use middleware
     async fn delete_role_for_user(
         &self,
         request: Request<casbin_proto::UserRoleRequest>,
     ) -> Result<Response<casbin_proto::BoolReply>, Status> {
         let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
-        let mut user_vec = Vec::new();
-        user_vec.push(get_inner.user);
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        let (_, gtype) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let mut rule = vec![get_inner.user, get_inner.role];
+        rule.extend(self.domain_of(&get_inner.domain).map(String::from));
         let rule_removed = e
-            .remove_grouping_policy(user_vec)
+            .remove_named_grouping_policy(gtype, rule)
             .await
-            .expect("permission not found.");
+            .map_err(casbin_status)?;
         Ok(Response::new(casbin_proto::BoolReply { res: rule_removed }))
     }
 
-    // delete_roles_for_user deletes all roles for a user.
+    // delete_roles_for_user deletes all roles for a user, in the request's domain if one is given.
     // returns false if the user does not have any roles (aka not affected).
     async fn delete_roles_for_user(
         &self,
         request: Request<casbin_proto::UserRoleRequest>,
     ) -> Result<Response<casbin_proto::BoolReply>, Status> {
         let get_inner = request.into_inner();
+        self.require_name("user", &get_inner.user)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
-        let mut user_vec = Vec::new();
-        user_vec.push(get_inner.user);
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        let (_, gtype) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let mut field_values = vec![get_inner.user];
+        if let Some(domain) = self.domain_of(&get_inner.domain) {
+            field_values.extend([String::new(), domain.to_owned()]);
+        }
         let rule_removed = e
-            .remove_filtered_grouping_policy(0, user_vec)
+            .remove_filtered_named_grouping_policy(gtype, 0, field_values)
             .await
-            .expect("permission not found.");
+            .map_err(casbin_status)?;
         Ok(Response::new(casbin_proto::BoolReply { res: rule_removed }))
     }
 
-    // delete_user deletes a user.
+    // delete_user deletes a user, removing both the roles it has and the permissions
+    // granted to it directly, in the request's domain if one is given.
     // Returns false if the user does not exist (aka not affected).
     async fn delete_user(
         &self,
         request: Request<casbin_proto::UserRoleRequest>,
     ) -> Result<Response<casbin_proto::BoolReply>, Status> {
         let get_inner = request.into_inner();
+        self.require_name("user", &get_inner.user)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
-        let mut user_vec = Vec::new();
-        user_vec.push(get_inner.user);
-        let rule_removed = e
-            .remove_filtered_grouping_policy(0, user_vec)
-            .await
-            .expect("permission not found.");
-        Ok(Response::new(casbin_proto::BoolReply { res: rule_removed }))
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        let (ptype, gtype) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let mut groupings = vec![get_inner.user.clone()];
+        let mut permissions = vec![get_inner.user];
+        if let Some(domain) = self.domain_of(&get_inner.domain) {
+            groupings.extend([String::new(), domain.to_owned()]);
+            permissions.push(domain.to_owned());
+        }
+        let removed = self
+            .remove_cascade(&mut e, (gtype, 0, groupings), (ptype, permissions))
+            .await?;
+        Ok(Response::new(casbin_proto::BoolReply { res: removed }))
     }
 
-    // delete_role deletes a role
+    // delete_role deletes a role, removing it from every user that has it along with the
+    // permissions granted to it, in the request's domain if one is given.
     async fn delete_role(
         &self,
         request: Request<casbin_proto::UserRoleRequest>,
     ) -> Result<Response<casbin_proto::EmptyReply>, Status> {
         let get_inner = request.into_inner();
+        self.require_name("role", &get_inner.role)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
-            .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
-        let _ = e
-            .delete_role(&get_inner.role)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .expect("role not found");
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        let (ptype, gtype) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let mut groupings = vec![get_inner.role.clone()];
+        let mut permissions = vec![get_inner.role];
+        if let Some(domain) = self.domain_of(&get_inner.domain) {
+            groupings.push(domain.to_owned());
+            permissions.push(domain.to_owned());
+        }
+        self.remove_cascade(&mut e, (gtype, 1, groupings), (ptype, permissions))
+            .await?;
         Ok(Response::new(casbin_proto::EmptyReply {}))
     }
 
-    // delete_permission deletes a permission.
+    // delete_permission deletes a permission from every user or role that has it.
     // Returns false if the permission does not exist (aka not affected).
     async fn delete_permission(
         &self,
         request: Request<casbin_proto::PermissionRequest>,
     ) -> Result<Response<casbin_proto::BoolReply>, Status> {
         let get_inner = request.into_inner();
+        self.require_permission(&get_inner.permissions)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        let (ptype, _) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
         let rule_removed = e
-            .remove_filtered_policy(1, get_inner.permissions)
+            .remove_filtered_named_policy(ptype, 1, get_inner.permissions)
             .await
-            .expect("permissions not found.");
+            .map_err(casbin_status)?;
         Ok(Response::new(casbin_proto::BoolReply { res: rule_removed }))
     }
 
@@ -242,15 +315,21 @@ impl Casbin for CasbinGRPC {
         request: Request<casbin_proto::PermissionRequest>,
     ) -> Result<Response<casbin_proto::BoolReply>, Status> {
         let get_inner = request.into_inner();
+        self.require_name("user", &get_inner.user)?;
+        self.require_permission(&get_inner.permissions)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        let (ptype, _) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let mut rule = get_inner.permissions;
+        rule.insert(0, get_inner.user);
+        self.check_room(&e, 1)?;
         let rule_added = e
-            .add_policy(get_inner.permissions)
+            .add_named_policy(ptype, rule)
             .await
-            .expect("permissions not found.");
+            .map_err(casbin_status)?;
         Ok(Response::new(casbin_proto::BoolReply { res: rule_added }))
     }
 
@@ -261,15 +340,20 @@ impl Casbin for CasbinGRPC {
         request: Request<casbin_proto::PermissionRequest>,
     ) -> Result<Response<casbin_proto::BoolReply>, Status> {
         let get_inner = request.into_inner();
+        self.require_name("user", &get_inner.user)?;
+        self.require_permission(&get_inner.permissions)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        let (ptype, _) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let mut rule = get_inner.permissions;
+        rule.insert(0, get_inner.user);
         let rule_removed = e
-            .remove_policy(get_inner.permissions)
+            .remove_named_policy(ptype, rule)
             .await
-            .expect("permissions not found.");
+            .map_err(casbin_status)?;
         Ok(Response::new(casbin_proto::BoolReply { res: rule_removed }))
     }
 
@@ -280,125 +364,465 @@ impl Casbin for CasbinGRPC {
         request: Request<casbin_proto::PermissionRequest>,
     ) -> Result<Response<casbin_proto::BoolReply>, Status> {
         let get_inner = request.into_inner();
+        self.require_name("user", &get_inner.user)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
-
-        let mut user_vec = Vec::new();
-        user_vec.push(get_inner.user);
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        let (ptype, _) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
         let rule_removed = e
-            .remove_filtered_policy(0, user_vec)
+            .remove_filtered_named_policy(ptype, 0, vec![get_inner.user])
             .await
-            .expect("permissions not found.");
+            .map_err(casbin_status)?;
         Ok(Response::new(casbin_proto::BoolReply { res: rule_removed }))
     }
 
-    // get_permissions_for_user gets permissions for a user or role.
+    // get_permissions_for_user gets permissions for a user or role, in the request's domain if
+    // one is given.
     async fn get_permissions_for_user(
         &self,
         request: Request<casbin_proto::PermissionRequest>,
     ) -> Result<Response<casbin_proto::Array2DReply>, Status> {
         let get_inner = request.into_inner();
+        self.require_name("user", &get_inner.user)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        let (ptype, _) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
         Ok(Response::new(self.wrap_plain_policy(
-            e.get_filtered_policy(0, vec![get_inner.user]),
+            self.permissions_for_user(
+                &e,
+                ptype,
+                &get_inner.user,
+                self.domain_of(&get_inner.domain),
+            ),
         )))
     }
 
-    // get_implicit_permissions_for_user gets all permissions(including children) for a user or role.
+    // get_implicit_permissions_for_user gets all permissions of a user or role, including those
+    // granted to the roles it inherits, in the request's domain if one is given.
     async fn get_implicit_permissions_for_user(
         &self,
         request: Request<casbin_proto::PermissionRequest>,
     ) -> Result<Response<casbin_proto::Array2DReply>, Status> {
         let get_inner = request.into_inner();
+        self.require_name("user", &get_inner.user)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
-        let resp = e.get_implicit_permissions_for_user(get_inner.user.as_str(), None);
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        let (ptype, gtype) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let resp = self.implicit_permissions_for_user(
+            &e,
+            ptype,
+            gtype,
+            &get_inner.user,
+            self.domain_of(&get_inner.domain),
+        );
         Ok(Response::new(self.wrap_plain_policy(resp)))
     }
 
-    // has_permission_for_user gets determines whether a user has a permission.
+    // get_implicit_users_for_permission gets all users that are granted a permission, either
+    // directly or through the roles they inherit. Roles themselves are left out.
+    async fn get_implicit_users_for_permission(
+        &self,
+        request: Request<casbin_proto::PermissionRequest>,
+    ) -> Result<Response<casbin_proto::ArrayReply>, Status> {
+        let get_inner = request.into_inner();
+        self.require_permission(&get_inner.permissions)?;
+        let wrap_enforcer = self
+            .get_enforcer(get_inner.enforcer_handler)
+            .await
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        let (ptype, gtype) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let users = self
+            .implicit_users_for_permission(&e, ptype, gtype, get_inner.permissions)
+            .await;
+        Ok(Response::new(casbin_proto::ArrayReply { array: users }))
+    }
+
+    // has_permission_for_user determines whether a user or role has a permission directly.
     async fn has_permission_for_user(
         &self,
         request: Request<casbin_proto::PermissionRequest>,
     ) -> Result<Response<casbin_proto::BoolReply>, Status> {
         let get_inner = request.into_inner();
+        self.require_name("user", &get_inner.user)?;
+        self.require_permission(&get_inner.permissions)?;
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        let (ptype, _) = self.rbac_types(&e, &get_inner.p_type, &get_inner.g_type)?;
+        let mut rule = get_inner.permissions;
+        rule.insert(0, get_inner.user);
         Ok(Response::new(casbin_proto::BoolReply {
-            res: e.has_policy(self.convert_permission(get_inner.user, get_inner.permissions)),
+            res: e.has_named_policy(ptype, rule),
+        }))
+    }
+    // get_roles_for_user_in_domain gets the roles that a user has inside a domain.
+    async fn get_roles_for_user_in_domain(
+        &self,
+        request: Request<casbin_proto::UserRoleRequest>,
+    ) -> Result<Response<casbin_proto::ArrayReply>, Status> {
+        self.require_name("domain", &request.get_ref().domain)?;
+        self.get_roles_for_user(request).await
+    }
+
+    // get_users_for_role_in_domain gets the users that have a role inside a domain.
+    async fn get_users_for_role_in_domain(
+        &self,
+        request: Request<casbin_proto::UserRoleRequest>,
+    ) -> Result<Response<casbin_proto::ArrayReply>, Status> {
+        self.require_name("domain", &request.get_ref().domain)?;
+        self.get_users_for_role(request).await
+    }
+
+    // get_permissions_for_user_in_domain gets permissions for a user or role inside a domain.
+    async fn get_permissions_for_user_in_domain(
+        &self,
+        request: Request<casbin_proto::PermissionRequest>,
+    ) -> Result<Response<casbin_proto::Array2DReply>, Status> {
+        self.require_name("domain", &request.get_ref().domain)?;
+        self.get_permissions_for_user(request).await
+    }
+
+    // add_role_for_user_in_domain adds a role for a user inside a domain.
+    // Returns false if the user already has the role (aka not affected).
+    async fn add_role_for_user_in_domain(
+        &self,
+        request: Request<casbin_proto::UserRoleRequest>,
+    ) -> Result<Response<casbin_proto::BoolReply>, Status> {
+        self.require_name("domain", &request.get_ref().domain)?;
+        self.add_role_for_user(request).await
+    }
+
+    // delete_role_for_user_in_domain deletes a role for a user inside a domain.
+    // Returns false if the user does not have the role (aka not affected).
+    async fn delete_role_for_user_in_domain(
+        &self,
+        request: Request<casbin_proto::UserRoleRequest>,
+    ) -> Result<Response<casbin_proto::BoolReply>, Status> {
+        self.require_name("domain", &request.get_ref().domain)?;
+        self.delete_role_for_user(request).await
+    }
+
+    // get_all_domains gets the list of domains referenced by the policy.
+    async fn get_all_domains(
+        &self,
+        request: Request<EmptyRequest>,
+    ) -> Result<Response<ArrayReply>, Status> {
+        let get_inner = request.into_inner();
+        let wrap_enforcer = self
+            .get_enforcer(get_inner.handler)
+            .await
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        Ok(Response::new(ArrayReply {
+            array: self.list_domains(&e),
         }))
     }
+
+    // get_domains_for_user gets the domains in which a user has a role or a permission.
+    async fn get_domains_for_user(
+        &self,
+        request: Request<casbin_proto::UserRoleRequest>,
+    ) -> Result<Response<ArrayReply>, Status> {
+        let get_inner = request.into_inner();
+        self.require_name("user", &get_inner.user)?;
+        let wrap_enforcer = self
+            .get_enforcer(get_inner.enforcer_handler)
+            .await
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        Ok(Response::new(ArrayReply {
+            array: self.domains_for_user(&e, &get_inner.user),
+        }))
+    }
+
+    // delete_domains deletes all p and g rules belonging to the given domains, either all of
+    // them or none.
+    // Returns false if no rule belongs to the domains (aka not affected).
+    async fn delete_domains(
+        &self,
+        request: Request<casbin_proto::DomainsRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.into_inner();
+        if get_inner.domains.is_empty() {
+            return Err(Status::invalid_argument("domains must not be empty"));
+        }
+        for domain in get_inner.domains.iter() {
+            self.require_name("domain", domain)?;
+        }
+        let wrap_enforcer = self
+            .get_enforcer(get_inner.enforcer_handler)
+            .await
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        let res = self
+            .remove_domains(&mut e, &get_inner.domains)
+            .await
+            .map_err(casbin_status)?;
+        Ok(Response::new(BoolReply { res }))
+    }
+
     // Enforcer functions here
+
+    // new_enforcer creates an enforcer from a model and an optional adapter handle, and
+    // returns the handle under which the enforcer is registered.
     async fn new_enforcer(
         &self,
-        i: Request<casbin_proto::NewEnforcerRequest>,
+        request: Request<casbin_proto::NewEnforcerRequest>,
     ) -> Result<Response<casbin_proto::NewEnforcerReply>, Status> {
-        // let mut a: Option<Arc<Mutex<Box<dyn Adapter>>>> = None;
-        // let e: CachedEnforcer;
-
-        // let get_inner = i.into_inner();
-
-        // if get_inner.adapter_handle != -1 {
-        //     let a = match self.get_adapter(get_inner.adapter_handle).await {
-        //         Ok(v) => Some(v),
-        //         Err(_) => return Ok(Response::new(casbin_proto::NewEnforcerReply { handler: 0 })),
-        //     };
-        // }
-
-        // if get_inner.model_text == String::from("") {
-        //     let cfg = adapter::load_configuration("config/connection_config.json").await?;
-        //     let data = match std::fs::read_to_string(cfg.enforcer.as_str()) {
-        //         Ok(v) => v,
-        //         Err(_) => return Ok(Response::new(casbin_proto::NewEnforcerReply { handler: 0 })),
-        //     };
-        // }
-
-        // if a.is_none() {
-        //     let m = match DefaultModel::from_str(get_inner.model_text.as_str()).await {
-        //         Ok(v) => v,
-        //         Err(_) => return Ok(Response::new(casbin_proto::NewEnforcerReply { handler: 0 })),
-        //     };
-        //     e = match casbin::CachedEnforcer::new(m, ()).await {
-        //         Ok(v) => v,
-        //         Err(_) => return Ok(Response::new(casbin_proto::NewEnforcerReply { handler: 0 })),
-        //     };
-        // } else {
-        //     let m = match DefaultModel::from_str(get_inner.model_text.as_str()).await {
-        //         Ok(v) => v,
-        //         Err(_) => return Ok(Response::new(casbin_proto::NewEnforcerReply { handler: 0 })),
-        //     };
-        //     let adapter = a.unwrap().lock().await;
-
-        //     e = match casbin::CachedEnforcer::new(m, a).await {
-        //         Ok(v) => v,
-        //         Err(er) => return Ok(Response::new(casbin_proto::NewEnforcerReply { handler: 0 })),
-        //     };
-        // }
-
-        // let epass = Arc::new(Mutex::new(e));
-        // let h = self.add_enforcer(epass);
-        // Ok(Response::new(casbin_proto::NewEnforcerReply { handler: h }))
-        todo!()
+        let get_inner = request.into_inner();
+
+        let model_text = if !get_inner.model_text.is_empty() {
+            get_inner.model_text
+        } else if let Some(model_text) = &self.tenant_model {
+            // The files and URLs the server can read are not the tenant's to read.
+            if !get_inner.model_path.is_empty() {
+                return Err(Status::permission_denied(
+                    "the enforcers of a tenant take modelText, not modelPath",
+                ));
+            }
+            model_text.clone()
+        } else {
+            let cfg = adapter::load_local_configuration().await;
+            let cache_dir = cfg
+                .as_ref()
+                .map(|cfg| cfg.model_cache_dir.clone())
+                .unwrap_or_default();
+            let read = if !get_inner.model_path.is_empty() {
+                // Without a local config, clients may name no model source.
+                let sources = cfg
+                    .as_ref()
+                    .map(model_source::ModelSources::from_config)
+                    .unwrap_or_default();
+                model_source::read_client_model(
+                    &get_inner.model_path,
+                    &get_inner.model_headers,
+                    &cache_dir,
+                    &sources,
+                )
+                .await
+            } else {
+                let cfg = cfg
+                    .as_ref()
+                    .map_err(|err| Status::failed_precondition(err.to_string()))?;
+                model_source::read_model(&cfg.enforcer, &cfg.model_headers, &cache_dir).await
+            };
+            read.map_err(|err| casbin_status(err.into()))?
+        };
+        let m = model_api::parse_model(&model_text).await?;
+
+        let e = if get_inner.adapter_handle == -1 {
+            enforcer::build_enforcer(m, ()).await
+        } else {
+            let a = self
+                .get_adapter(get_inner.adapter_handle)
+                .await
+                .map_err(Status::not_found)?;
+            enforcer::build_enforcer(m, adapter::SharedAdapter::new(a).await).await
+        }
+        .map_err(casbin_status)?;
+
+        let handler = match get_inner.decision_cache {
+            None => {
+                self.enforcers
+                    .admit(e, model_text, self.decision_cache)
+                    .await?
+            }
+            Some(cache) => {
+                if cache.max_entries < 0 || cache.ttl_ms < 0 {
+                    return Err(Status::invalid_argument(
+                        "maxEntries and ttlMs cannot be negative",
+                    ));
+                }
+                let cache = DecisionCacheOptions::new(
+                    cache.disabled,
+                    cache.max_entries as usize,
+                    Duration::from_millis(cache.ttl_ms as u64),
+                );
+                self.enforcers.admit(e, model_text, cache).await?
+            }
+        };
+        Ok(Response::new(casbin_proto::NewEnforcerReply { handler }))
+    }
+
+    // delete_enforcer unregisters an enforcer. Calls already running on it are not affected.
+    async fn delete_enforcer(
+        &self,
+        request: Request<EmptyRequest>,
+    ) -> Result<Response<EmptyReply>, Status> {
+        let get_inner = request.into_inner();
+        if !self.enforcers.remove(get_inner.handler).await {
+            return Err(Status::not_found("No enforcer found"));
+        }
+        Ok(Response::new(casbin_proto::EmptyReply {}))
+    }
+
+    // list_enforcers describes every registered enforcer.
+    async fn list_enforcers(
+        &self,
+        _request: Request<casbin_proto::ListEnforcersRequest>,
+    ) -> Result<Response<casbin_proto::ListEnforcersReply>, Status> {
+        let mut enforcers = vec![];
+        for (handler, entry) in self.enforcers.list().await {
+            let e = entry.enforcer.read().await;
+            let roles = entry.feed.roles().stats();
+            enforcers.push(casbin_proto::EnforcerInfo {
+                handler,
+                model_hash: entry.model_hash(),
+                rule_count: self.count_rules(&e) as i64,
+                last_used: entry.last_used(),
+                pinned: entry.is_pinned(),
+                auto_save: e.has_auto_save_enabled(),
+                role_cache: Some(casbin_proto::RoleCacheStats {
+                    hits: roles.hits as i64,
+                    misses: roles.misses as i64,
+                    entries: roles.entries as i64,
+                    generation: roles.generation as i64,
+                }),
+            });
+        }
+        Ok(Response::new(casbin_proto::ListEnforcersReply {
+            enforcers,
+        }))
+    }
+
+    // get_model gets the text of the model an enforcer runs.
+    async fn get_model(
+        &self,
+        request: Request<EmptyRequest>,
+    ) -> Result<Response<casbin_proto::ModelReply>, Status> {
+        let get_inner = request.into_inner();
+        let entry = self
+            .enforcers
+            .get(get_inner.handler)
+            .await
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        Ok(Response::new(casbin_proto::ModelReply {
+            model_text: entry.model_text(),
+            model_hash: entry.model_hash(),
+        }))
+    }
+
+    // set_model replaces the model of an enforcer, keeping the rules it holds. The new model
+    // is parsed before the enforcer is locked, so calls on the enforcer only wait for the
+    // swap itself and run either entirely against the old model or against the new one.
+    async fn set_model(
+        &self,
+        request: Request<casbin_proto::SetModelRequest>,
+    ) -> Result<Response<EmptyReply>, Status> {
+        let get_inner = request.into_inner();
+        let entry = self
+            .enforcers
+            .get(get_inner.enforcer_handler)
+            .await
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        let m = model_api::parse_model(&get_inner.model_text).await?;
+
+        let mut e = entry.enforcer.write().await;
+        self.swap_model(&mut e, m)?;
+        // The role links are built again for the new model, without a change to the policy.
+        entry.feed.roles().invalidate();
+        logger::log_loaded(&e);
+        entry.set_model_text(get_inner.model_text);
+
+        Ok(Response::new(casbin_proto::EmptyReply {}))
+    }
+
+    // add_custom_function defines a function for the matchers of an enforcer in their own
+    // expression language, which the enforcer keeps whatever model it is given later.
+    async fn add_custom_function(
+        &self,
+        request: Request<casbin_proto::CustomFunctionRequest>,
+    ) -> Result<Response<EmptyReply>, Status> {
+        let get_inner = request.into_inner();
+        let entry = self
+            .enforcers
+            .get(get_inner.enforcer_handler)
+            .await
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        let f = CustomFunction::new(
+            get_inner.name,
+            get_inner.params,
+            get_inner.body,
+            self.enforcers.functions(),
+        )
+        .map_err(Status::invalid_argument)?;
+
+        let mut e = entry.enforcer.write().await;
+        let roles = e.get_model().get_model().get("g");
+        if roles.is_some_and(|roles| roles.contains_key(&f.name)) {
+            return Err(Status::invalid_argument(format!(
+                "{} is a role definition of the model",
+                f.name
+            )));
+        }
+        entry.enforcer.add_function(&mut e, f);
+        e.get_mut_cache().clear();
+
+        Ok(Response::new(EmptyReply {}))
+    }
+
+    // validate_model checks a model text without creating an enforcer, reporting every problem
+    // found instead of only the first.
+    async fn validate_model(
+        &self,
+        request: Request<casbin_proto::ValidateModelRequest>,
+    ) -> Result<Response<casbin_proto::ValidateModelReply>, Status> {
+        let get_inner = request.into_inner();
+        let mut diagnostics = model_api::validate_model_text(&get_inner.model_text);
+        if diagnostics.is_empty() {
+            if let Err(err) = DefaultModel::from_str(&get_inner.model_text).await {
+                diagnostics.push(casbin_proto::ModelDiagnostic {
+                    section: String::new(),
+                    line: 0,
+                    message: err.to_string(),
+                });
+            }
+        }
+        Ok(Response::new(casbin_proto::ValidateModelReply {
+            valid: diagnostics.is_empty(),
+            diagnostics,
+        }))
     }
 
+    // reload_config reloads the local config into the running server, as SIGHUP does, telling
+    // which changed settings it applied and which need a restart.
+    async fn reload_config(
+        &self,
+        _request: Request<casbin_proto::ReloadConfigRequest>,
+    ) -> Result<Response<casbin_proto::ReloadConfigReply>, Status> {
+        let reloader = self.reloader.as_ref().ok_or_else(|| {
+            Status::failed_precondition("the server was not started from a local config")
+        })?;
+        let reloaded = reloader
+            .reload()
+            .await
+            .map_err(Status::failed_precondition)?;
+        Ok(Response::new(casbin_proto::ReloadConfigReply {
+            reloaded: reloaded.reloaded,
+            restart_required: reloaded.restart_required,
+        }))
+    }
+
+    // new_adapter opens an adapter on the server from a driver name and connection string,
+    // and returns the handle under which NewEnforcer can use it.
     async fn new_adapter(
         &self,
-        mut i: Request<casbin_proto::NewAdapterRequest>,
+        request: Request<casbin_proto::NewAdapterRequest>,
     ) -> Result<Response<casbin_proto::NewAdapterReply>, Status> {
-        todo!()
+        let a = adapter::new_adapter(&self.drivers, request.into_inner()).await?;
+        let handler = self.add_adapter(Arc::new(Mutex::new(a))).await;
+        Ok(Response::new(casbin_proto::NewAdapterReply { handler }))
     }
 
     // Management API functions here
@@ -414,153 +838,755 @@ impl Casbin for CasbinGRPC {
     //    self.get_all_named_subjects()
     //}
 
+    // enforce decides whether a subject can access an object with the given action.
     async fn enforce(
         &self,
         request: Request<casbin_proto::EnforceRequest>,
     ) -> Result<Response<BoolReply>, Status> {
-        todo!()
+        let entry = self
+            .enforcers
+            .get(request.get_ref().enforcer_handler)
+            .await
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        let caller = self.caller_of(&request, &entry);
+        let get_inner = request.into_inner();
+        let rvals =
+            abac::resolve_abac(get_inner.params.clone()).map_err(Status::invalid_argument)?;
+        let e = entry.enforcer.read().await;
+        let (res, rules) = e.enforce_explained(rvals).map_err(casbin_status)?;
+        drop(e);
+        if let Some(caller) = &caller {
+            self.record(
+                &entry,
+                Entry {
+                    caller,
+                    method: "Enforce",
+                    enforcer: get_inner.enforcer_handler,
+                    request: &get_inner.params,
+                    matcher: "",
+                    allowed: res,
+                    rules: &rules,
+                },
+            )
+            .await;
+        }
+        Ok(Response::new(casbin_proto::BoolReply { res }))
+    }
+
+    // enforce_with_matcher decides like enforce, but evaluates the request with a one-off matcher.
+    async fn enforce_with_matcher(
+        &self,
+        request: Request<casbin_proto::EnforceWithMatcherRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let entry = self
+            .enforcers
+            .get(request.get_ref().enforcer_handler)
+            .await
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        let caller = self.caller_of(&request, &entry);
+        let get_inner = request.into_inner();
+        let rvals =
+            abac::resolve_abac(get_inner.params.clone()).map_err(Status::invalid_argument)?;
+        let e = entry.enforcer.read().await;
+        let (res, rules) = e
+            .enforce_with_matcher(&get_inner.matcher, rvals)
+            .map_err(casbin_status)?;
+        drop(e);
+        if let Some(caller) = &caller {
+            self.record(
+                &entry,
+                Entry {
+                    caller,
+                    method: "EnforceWithMatcher",
+                    enforcer: get_inner.enforcer_handler,
+                    request: &get_inner.params,
+                    matcher: &get_inner.matcher,
+                    allowed: res,
+                    rules: &rules,
+                },
+            )
+            .await;
+        }
+        Ok(Response::new(casbin_proto::BoolReply { res }))
+    }
 
-        // res, err := e.EnforceWithMatcher(m, params...)
+    type StreamEnforceStream = ReceiverStream<Result<casbin_proto::StreamEnforceReply, Status>>;
+
+    // stream_enforce enforces each request pushed by the client in order, replying with
+    // the decision tagged by the request id. Per-request errors are reported in the reply
+    // instead of ending the stream.
+    async fn stream_enforce(
+        &self,
+        request: Request<Streaming<casbin_proto::StreamEnforceRequest>>,
+    ) -> Result<Response<Self::StreamEnforceStream>, Status> {
+        let (audit, caller) = (self.audit.clone(), Caller::of(&request));
+        let mut stream = request.into_inner();
+        let enforcers = self.enforcers.clone();
+        let (tx, rx) = mpsc::channel(128);
+
+        tokio::spawn(async move {
+            loop {
+                let get_inner = match stream.message().await {
+                    Ok(Some(v)) => v,
+                    Ok(None) => break,
+                    Err(status) => {
+                        let _ = tx.send(Err(status)).await;
+                        break;
+                    }
+                };
+                let mut reply = casbin_proto::StreamEnforceReply {
+                    id: get_inner.id,
+                    res: false,
+                    error: String::new(),
+                };
+                match enforcers.get(get_inner.enforcer_handler).await {
+                    Some(entry) => match abac::resolve_abac(get_inner.params.clone()) {
+                        Ok(rvals) => {
+                            let e = entry.enforcer.read().await;
+                            let decision = e.enforce_explained(rvals);
+                            drop(e);
+                            match decision {
+                                Ok((res, rules)) => {
+                                    reply.res = res;
+                                    let decision = Entry {
+                                        caller: &caller,
+                                        method: "StreamEnforce",
+                                        enforcer: get_inner.enforcer_handler,
+                                        request: &get_inner.params,
+                                        matcher: "",
+                                        allowed: res,
+                                        rules: &rules,
+                                    };
+                                    decisions::record(audit.as_deref(), &entry, decision).await;
+                                }
+                                Err(err) => reply.error = err.to_string(),
+                            }
+                        }
+                        Err(err) => reply.error = err,
+                    },
+                    None => reply.error = String::from("No enforcer found"),
+                }
+                if tx.send(Ok(reply)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
     }
 
+    // batch_enforce enforces each request in turn, returning the decisions in request order.
+    async fn batch_enforce(
+        &self,
+        request: Request<casbin_proto::BatchEnforceRequest>,
+    ) -> Result<Response<BoolArrayReply>, Status> {
+        let entry = self
+            .enforcers
+            .get(request.get_ref().enforcer_handler)
+            .await
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        let caller = self.caller_of(&request, &entry);
+        let get_inner = request.into_inner();
+        let rvals = get_inner
+            .requests
+            .iter()
+            .map(|request| abac::resolve_abac(request.params.clone()))
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(Status::invalid_argument)?;
+        let snapshot = entry.enforcer.snapshot().await;
+        let decided =
+            batch::enforce_batch(snapshot, rvals, batch::parallelism(self.batch_parallelism))
+                .await?;
+        let mut res = Vec::with_capacity(get_inner.requests.len());
+        // The decisions made before one that failed are still recorded, as when deciding them
+        // in turn.
+        for (request, decision) in get_inner.requests.iter().zip(decided) {
+            let (allowed, rules) = decision.map_err(casbin_status)?;
+            if let Some(caller) = &caller {
+                self.record(
+                    &entry,
+                    Entry {
+                        caller,
+                        method: "BatchEnforce",
+                        enforcer: get_inner.enforcer_handler,
+                        request: &request.params,
+                        matcher: "",
+                        allowed,
+                        rules: &rules,
+                    },
+                )
+                .await;
+            }
+            res.push(allowed);
+        }
+        Ok(Response::new(casbin_proto::BoolArrayReply { res }))
+    }
+
+    // enforce_ex decides like enforce, and also returns the policy rules that explain the decision.
+    async fn enforce_ex(
+        &self,
+        request: Request<casbin_proto::EnforceRequest>,
+    ) -> Result<Response<casbin_proto::EnforceExReply>, Status> {
+        let entry = self
+            .enforcers
+            .get(request.get_ref().enforcer_handler)
+            .await
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        let caller = self.caller_of(&request, &entry);
+        let get_inner = request.into_inner();
+        let rvals =
+            abac::resolve_abac(get_inner.params.clone()).map_err(Status::invalid_argument)?;
+        let e = entry.enforcer.read().await;
+        let (res, explain) = explain::enforce_ex(&e, rvals).map_err(casbin_status)?;
+        drop(e);
+        if let Some(caller) = &caller {
+            self.record(
+                &entry,
+                Entry {
+                    caller,
+                    method: "EnforceEx",
+                    enforcer: get_inner.enforcer_handler,
+                    request: &get_inner.params,
+                    matcher: "",
+                    allowed: res,
+                    rules: &explain,
+                },
+            )
+            .await;
+        }
+        Ok(Response::new(casbin_proto::EnforceExReply {
+            res,
+            explain: explain
+                .into_iter()
+                .map(|d1| casbin_proto::enforce_ex_reply::D { d1 })
+                .collect(),
+        }))
+    }
+
+    // load_policy reloads the policy from the enforcer's adapter, replacing the rules in memory.
     async fn load_policy(
         &self,
         request: Request<EmptyRequest>,
     ) -> Result<Response<EmptyReply>, Status> {
         let get_inner = request.into_inner();
-        let wrap_enforcer = self.get_enforcer(get_inner.handler as i32).await.unwrap();
-        let e = wrap_enforcer.lock().await;
+        let entry = self
+            .enforcers
+            .get(get_inner.handler)
+            .await
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        let mut e = entry.enforcer.write().await;
+        enforcer::reload_policy(&mut e)
+            .await
+            .map_err(casbin_status)?;
+        entry.feed.publish("LoadPolicy", "", vec![]);
+        Ok(Response::new(casbin_proto::EmptyReply {}))
+    }
+
+    // load_filtered_policy reloads only the rules matching a filter from the enforcer's
+    // adapter, replacing the rules in memory.
+    async fn load_filtered_policy(
+        &self,
+        request: Request<casbin_proto::LoadFilteredPolicyRequest>,
+    ) -> Result<Response<EmptyReply>, Status> {
+        let get_inner = request.into_inner();
+        let entry = self
+            .enforcers
+            .get(get_inner.handler)
+            .await
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        let filter = Filter {
+            p: get_inner.p.iter().map(String::as_str).collect(),
+            g: get_inner.g.iter().map(String::as_str).collect(),
+        };
+        let mut e = entry.enforcer.write().await;
+        e.load_filtered_policy(filter)
+            .await
+            .map_err(casbin_status)?;
+        priority::sort_policies(&mut e);
+        // Cached decisions were made against the old rules.
+        e.get_mut_cache().clear();
+        logger::log_loaded(&e);
+        entry.feed.publish("LoadFilteredPolicy", "", vec![]);
         Ok(Response::new(casbin_proto::EmptyReply {}))
     }
 
+    // save_policy writes the rules in memory out to the enforcer's adapter, unless they were
+    // loaded through a filter and saving them would drop the rest of the stored rules.
     async fn save_policy(
         &self,
         request: Request<EmptyRequest>,
     ) -> Result<Response<EmptyReply>, Status> {
         let get_inner = request.into_inner();
-        let wrap_enforcer = self.get_enforcer(get_inner.handler as i32).await.unwrap();
-        let e = wrap_enforcer.lock().await;
+        let wrap_enforcer = self
+            .get_enforcer(get_inner.handler)
+            .await
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        if e.is_filtered() {
+            return Err(Status::failed_precondition(
+                "cannot save a policy loaded with LoadFilteredPolicy",
+            ));
+        }
+        e.save_policy().await.map_err(casbin_status)?;
+
+        Ok(Response::new(casbin_proto::EmptyReply {}))
+    }
+
+    // clear_policy removes all p and g rules from the enforcer, and from its adapter when
+    // auto-save is on. The caller has to set confirm, and can pass the rule count it
+    // expects the enforcer to hold so that clearing the wrong handle is refused.
+    async fn clear_policy(
+        &self,
+        request: Request<ClearPolicyRequest>,
+    ) -> Result<Response<EmptyReply>, Status> {
+        let get_inner = request.into_inner();
+        if !get_inner.confirm {
+            return Err(Status::invalid_argument(
+                "clearing the policy requires confirm to be set",
+            ));
+        }
+        let wrap_enforcer = self
+            .get_enforcer(get_inner.enforcer_handler)
+            .await
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        if let Some(expected) = get_inner.expected_rule_count {
+            let count = self.count_rules(&e);
+            if expected != count as i64 {
+                return Err(Status::failed_precondition(format!(
+                    "enforcer holds {} rules, expected {}",
+                    count, expected
+                )));
+            }
+        }
+        e.clear_policy().await.map_err(casbin_status)?;
+        e.get_mut_cache().clear();
+
+        Ok(Response::new(casbin_proto::EmptyReply {}))
+    }
+
+    // enable_auto_save turns writing rule changes through the enforcer's adapter on or off,
+    // e.g. off for a bulk import saved once with SavePolicy at the end.
+    async fn enable_auto_save(
+        &self,
+        request: Request<casbin_proto::EnableAutoSaveRequest>,
+    ) -> Result<Response<EmptyReply>, Status> {
+        let get_inner = request.into_inner();
+        let wrap_enforcer = self
+            .get_enforcer(get_inner.enforcer_handler)
+            .await
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        e.enable_auto_save(get_inner.enable);
+        Ok(Response::new(casbin_proto::EmptyReply {}))
+    }
 
+    // enable_log turns the logs of an enforcer on or off, logging the model and policy it has
+    // loaded as they are turned on.
+    async fn enable_log(
+        &self,
+        request: Request<casbin_proto::EnableLogRequest>,
+    ) -> Result<Response<EmptyReply>, Status> {
+        let get_inner = request.into_inner();
+        let wrap_enforcer = self
+            .get_enforcer(get_inner.enforcer_handler)
+            .await
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        e.enable_log(get_inner.enable);
+        logger::log_loaded(&e);
         Ok(Response::new(casbin_proto::EmptyReply {}))
     }
 
+    type WatchPolicyUpdatesStream = ReceiverStream<Result<casbin_proto::PolicyUpdate, Status>>;
+
+    // watch_policy_updates streams the policy changes of an enforcer as they are made, so other
+    // servers or sidecars can mirror them. A subscriber falling too far behind has its stream
+    // ended with DATA_LOSS, after which it should fetch the policy again. Streams end with
+    // UNAVAILABLE when the server stops, so it does not wait on them.
+    async fn watch_policy_updates(
+        &self,
+        request: Request<casbin_proto::WatchPolicyUpdatesRequest>,
+    ) -> Result<Response<Self::WatchPolicyUpdatesStream>, Status> {
+        let get_inner = request.into_inner();
+        let feed = self
+            .enforcers
+            .get(get_inner.enforcer_handler)
+            .await
+            .ok_or_else(|| Status::not_found("No enforcer found"))?
+            .feed
+            .clone();
+        let (mut updates, mut closed) = (feed.subscribe(), feed.closed());
+        let (tx, rx) = mpsc::channel(128);
+
+        tokio::spawn(async move {
+            loop {
+                if *closed.borrow() {
+                    let _ = tx
+                        .send(Err(Status::unavailable("the server is shutting down")))
+                        .await;
+                    break;
+                }
+                let update = tokio::select! {
+                    update = updates.recv() => update,
+                    _ = tx.closed() => break,
+                    // The flag is dropped along with the feed.
+                    res = closed.changed() => match res {
+                        Ok(()) => continue,
+                        Err(_) => break,
+                    },
+                };
+                // The feed closes once the enforcer is deleted.
+                let update = match update {
+                    Ok(update) => Ok(update),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => Err(Status::data_loss(
+                        format!("missed {} policy updates", missed),
+                    )),
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let lagged = update.is_err();
+                if tx.send(update).await.is_err() || lagged {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    type WatchDecisionsStream = ReceiverStream<Result<casbin_proto::DecisionEvent, Status>>;
+
+    // watch_decisions streams the decisions of an enforcer matching a filter as they are made,
+    // for consumers such as SIEM forwarders. Each subscriber has a buffer of its own, from which
+    // the oldest decisions are dropped while it falls behind, rather than slowing the others or
+    // the decisions down. Streams end with UNAVAILABLE when the server stops.
+    async fn watch_decisions(
+        &self,
+        request: Request<casbin_proto::WatchDecisionsRequest>,
+    ) -> Result<Response<Self::WatchDecisionsStream>, Status> {
+        let get_inner = request.into_inner();
+        let filter = get_inner.filter.unwrap_or_default();
+        if !["", "allow", "deny"].contains(&filter.decision.as_str()) {
+            return Err(Status::invalid_argument(
+                "decision must be allow, deny or empty",
+            ));
+        }
+        let buffer = match get_inner.buffer {
+            0 => DECISIONS_BUFFER,
+            n if n > 0 => n as usize,
+            _ => return Err(Status::invalid_argument("buffer must not be negative")),
+        };
+        let feed = self
+            .enforcers
+            .get(get_inner.enforcer_handler)
+            .await
+            .ok_or_else(|| Status::not_found("No enforcer found"))?
+            .decisions
+            .clone();
+        let (subscription, mut closed) = (feed.subscribe(filter, buffer), feed.closed());
+        // The decisions wait in the buffer of the subscription, not in the channel.
+        let (tx, rx) = mpsc::channel(1);
+
+        tokio::spawn(async move {
+            loop {
+                if *closed.borrow() {
+                    let _ = tx
+                        .send(Err(Status::unavailable("the server is shutting down")))
+                        .await;
+                    break;
+                }
+                let event = tokio::select! {
+                    event = subscription.next() => event,
+                    _ = tx.closed() => break,
+                    // The flag is dropped along with the feed, once the enforcer is deleted.
+                    res = closed.changed() => match res {
+                        Ok(()) => continue,
+                        Err(_) => break,
+                    },
+                };
+                if tx.send(Ok(event)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
     async fn add_policy(
         &self,
         mut request: Request<PolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
-        let mut get_inner = request.get_mut();
+        let get_inner = request.get_mut();
         get_inner.p_type = String::from("p");
 
-        Ok(self.add_named_policy(request).await.unwrap())
+        self.add_named_policy(request).await
     }
 
+    // add_named_policy adds a rule to the named policy.
+    // Returns false if the rule already exists (aka not affected).
     async fn add_named_policy(
         &self,
         request: Request<PolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
         let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        self.require_ptype(&e, "p", &get_inner.p_type)?;
+        self.check_room(&e, 1)?;
         let rule_added = e
             .add_named_policy(&get_inner.p_type, get_inner.params)
             .await
-            .unwrap();
+            .map_err(casbin_status)?;
 
         Ok(Response::new(casbin_proto::BoolReply { res: rule_added }))
     }
 
+    async fn add_policies(
+        &self,
+        mut request: Request<PoliciesRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.get_mut();
+        get_inner.p_type = String::from("p");
+
+        self.add_named_policies(request).await
+    }
+
+    // add_named_policies adds rules to the named policy atomically.
+    // Returns false, adding nothing, if any of the rules already exists.
+    async fn add_named_policies(
+        &self,
+        request: Request<PoliciesRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.into_inner();
+        let wrap_enforcer = self
+            .get_enforcer(get_inner.enforcer_handler)
+            .await
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        self.require_ptype(&e, "p", &get_inner.p_type)?;
+        self.check_room(&e, get_inner.rules.len())?;
+        let rules = get_inner.rules.into_iter().map(|d| d.params).collect();
+        let rules_added = e
+            .add_named_policies(&get_inner.p_type, rules)
+            .await
+            .map_err(casbin_status)?;
+
+        Ok(Response::new(casbin_proto::BoolReply { res: rules_added }))
+    }
+
     async fn remove_policy(
         &self,
         mut request: Request<PolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
-        let mut get_inner = request.get_mut();
+        let get_inner = request.get_mut();
         get_inner.p_type = String::from("p");
 
-        Ok(self.remove_named_policy(request).await.unwrap())
+        self.remove_named_policy(request).await
     }
 
+    // remove_named_policy removes a rule from the named policy.
+    // Returns false if the rule does not exist (aka not affected).
     async fn remove_named_policy(
         &self,
         request: Request<PolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
         let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        self.require_ptype(&e, "p", &get_inner.p_type)?;
         let rule_removed = e
             .remove_named_policy(&get_inner.p_type, get_inner.params)
             .await
-            .unwrap();
+            .map_err(casbin_status)?;
         Ok(Response::new(casbin_proto::BoolReply { res: rule_removed }))
     }
 
+    async fn remove_policies(
+        &self,
+        mut request: Request<PoliciesRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.get_mut();
+        get_inner.p_type = String::from("p");
+
+        self.remove_named_policies(request).await
+    }
+
+    // remove_named_policies removes rules from the named policy atomically.
+    // Returns false, removing nothing, if any of the rules does not exist.
+    async fn remove_named_policies(
+        &self,
+        request: Request<PoliciesRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.into_inner();
+        let wrap_enforcer = self
+            .get_enforcer(get_inner.enforcer_handler)
+            .await
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        self.require_ptype(&e, "p", &get_inner.p_type)?;
+        let rules = get_inner.rules.into_iter().map(|d| d.params).collect();
+        let rules_removed = e
+            .remove_named_policies(&get_inner.p_type, rules)
+            .await
+            .map_err(casbin_status)?;
+        Ok(Response::new(casbin_proto::BoolReply {
+            res: rules_removed,
+        }))
+    }
+
+    async fn update_policy(
+        &self,
+        mut request: Request<UpdatePolicyRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.get_mut();
+        get_inner.p_type = String::from("p");
+
+        self.update_named_policy(request).await
+    }
+
+    // update_named_policy replaces a rule of the named policy with a new one.
+    // Returns false if the old rule does not exist or the new one already does.
+    async fn update_named_policy(
+        &self,
+        request: Request<UpdatePolicyRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.into_inner();
+        self.update_named_policies(Request::new(casbin_proto::UpdatePoliciesRequest {
+            enforcer_handler: get_inner.enforcer_handler,
+            p_type: get_inner.p_type,
+            old_rules: vec![casbin_proto::update_policies_request::D {
+                params: get_inner.old_rule,
+            }],
+            new_rules: vec![casbin_proto::update_policies_request::D {
+                params: get_inner.new_rule,
+            }],
+        }))
+        .await
+    }
+
+    async fn update_policies(
+        &self,
+        mut request: Request<UpdatePoliciesRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.get_mut();
+        get_inner.p_type = String::from("p");
+
+        self.update_named_policies(request).await
+    }
+
+    // update_named_policies replaces rules of the named policy, pairing old_rules with
+    // new_rules by position. Either all rules are replaced or none are.
+    async fn update_named_policies(
+        &self,
+        request: Request<UpdatePoliciesRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.into_inner();
+        if get_inner.old_rules.len() != get_inner.new_rules.len() {
+            return Err(Status::invalid_argument(
+                "old and new rules must have the same length",
+            ));
+        }
+        let wrap_enforcer = self
+            .get_enforcer(get_inner.enforcer_handler)
+            .await
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        self.require_ptype(&e, "p", &get_inner.p_type)?;
+        let old_rules = get_inner.old_rules.into_iter().map(|d| d.params).collect();
+        let new_rules = get_inner.new_rules.into_iter().map(|d| d.params).collect();
+        let rules_updated = self
+            .replace_named_policies(&mut e, &get_inner.p_type, old_rules, new_rules)
+            .await
+            .map_err(casbin_status)?;
+        Ok(Response::new(casbin_proto::BoolReply {
+            res: rules_updated,
+        }))
+    }
+
     async fn remove_filtered_policy(
         &self,
         mut request: Request<FilteredPolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
-        let mut get_inner = request.get_mut();
+        let get_inner = request.get_mut();
         get_inner.p_type = String::from("p");
 
-        Ok(self.remove_filtered_named_policy(request).await.unwrap())
+        self.remove_filtered_named_policy(request).await
     }
 
+    // remove_filtered_named_policy removes the rules of the named policy whose fields,
+    // starting at field_index, match field_values. An empty field value matches anything.
     async fn remove_filtered_named_policy(
         &self,
         request: Request<FilteredPolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
         let get_inner = request.into_inner();
-        let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
-            .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
-
-        let rule_removed_filtered = e
-            .remove_filtered_named_policy(
-                &get_inner.p_type,
-                get_inner.field_index as usize,
-                get_inner.field_values,
-            )
+        let entry = self
+            .enforcers
+            .get(get_inner.enforcer_handler)
             .await
-            .unwrap();
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        let mut e = entry.enforcer.write().await;
+        self.require_ptype(&e, "p", &get_inner.p_type)?;
+        let field_index = self.check_field_filter(
+            &e,
+            entry.feed.index(),
+            "p",
+            &get_inner.p_type,
+            get_inner.field_index,
+            &get_inner.field_values,
+        )?;
+
+        let rule_removed_filtered = index::remove_filtered(
+            &mut e,
+            entry.feed.index(),
+            "p",
+            &get_inner.p_type,
+            field_index,
+            get_inner.field_values,
+        )
+        .await
+        .map_err(casbin_status)?;
 
         Ok(Response::new(casbin_proto::BoolReply {
             res: rule_removed_filtered,
         }))
     }
 
+    // get_policy gets all the authorization rules in the policy.
     async fn get_policy(
         &self,
         request: Request<EmptyRequest>,
     ) -> Result<Response<Array2DReply>, Status> {
-        Ok(self
-            .get_named_policy(Request::new(casbin_proto::PolicyRequest {
-                enforcer_handler: request.into_inner().handler,
-                p_type: String::from("p"),
-                params: vec![String::from("")],
-            }))
-            .await
-            .unwrap())
+        self.get_named_policy(Request::new(casbin_proto::PolicyRequest {
+            enforcer_handler: request.into_inner().handler,
+            p_type: String::from("p"),
+            params: vec![],
+        }))
+        .await
     }
 
+    // get_named_policy gets all the authorization rules in the named policy.
     async fn get_named_policy(
         &self,
         request: Request<PolicyRequest>,
     ) -> Result<Response<Array2DReply>, Status> {
         let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        self.require_ptype(&e, "p", &get_inner.p_type)?;
 
         Ok(Response::new(self.wrap_plain_policy(
-            e.get_model().get_policy("p", &*get_inner.p_type),
+            e.get_model().get_policy("p", &get_inner.p_type),
         )))
     }
 
@@ -568,28 +1594,41 @@ impl Casbin for CasbinGRPC {
         &self,
         mut request: Request<FilteredPolicyRequest>,
     ) -> Result<Response<Array2DReply>, Status> {
-        let mut get_inner = request.get_mut();
+        let get_inner = request.get_mut();
         get_inner.p_type = String::from("p");
 
-        Ok(self.get_filtered_named_policy(request).await.unwrap())
+        self.get_filtered_named_policy(request).await
     }
 
+    // get_filtered_named_policy gets the rules of the named policy whose fields,
+    // starting at field_index, match field_values. An empty field value matches anything.
     async fn get_filtered_named_policy(
         &self,
         request: Request<FilteredPolicyRequest>,
     ) -> Result<Response<Array2DReply>, Status> {
         let get_inner = request.into_inner();
-        let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+        let entry = self
+            .enforcers
+            .get(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        let e = entry.enforcer.read().await;
+        self.require_ptype(&e, "p", &get_inner.p_type)?;
+        let field_index = self.check_field_filter(
+            &e,
+            entry.feed.index(),
+            "p",
+            &get_inner.p_type,
+            get_inner.field_index,
+            &get_inner.field_values,
+        )?;
 
         Ok(Response::new(self.wrap_plain_policy(
-            e.get_model().get_filtered_policy(
+            entry.feed.index().filtered(
+                &e,
                 "p",
-                &*get_inner.p_type,
-                get_inner.field_index as usize,
+                &get_inner.p_type,
+                field_index,
                 get_inner.field_values,
             ),
         )))
@@ -599,26 +1638,31 @@ impl Casbin for CasbinGRPC {
         &self,
         mut request: Request<PolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
-        let mut get_inner = request.get_mut();
+        let get_inner = request.get_mut();
         get_inner.p_type = String::from("g");
 
-        Ok(self.add_named_grouping_policy(request).await.unwrap())
+        self.add_named_grouping_policy(request).await
     }
+
+    // add_named_grouping_policy adds a role inheritance rule to the named grouping policy.
+    // Returns false if the rule already exists (aka not affected).
     async fn add_named_grouping_policy(
         &self,
         request: Request<PolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
         let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        self.require_ptype(&e, "g", &get_inner.p_type)?;
+        self.check_room(&e, 1)?;
 
         let rule_added = e
             .add_named_grouping_policy(&get_inner.p_type, get_inner.params)
             .await
-            .unwrap();
+            .map_err(casbin_status)?;
         Ok(Response::new(casbin_proto::BoolReply { res: rule_added }))
     }
 
@@ -626,95 +1670,177 @@ impl Casbin for CasbinGRPC {
         &self,
         mut request: Request<PolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
-        let mut get_inner = request.get_mut();
+        let get_inner = request.get_mut();
         get_inner.p_type = String::from("g");
 
-        Ok(self.remove_named_grouping_policy(request).await.unwrap())
+        self.remove_named_grouping_policy(request).await
     }
 
+    // remove_named_grouping_policy removes a role inheritance rule from the named grouping policy.
+    // Returns false if the rule does not exist (aka not affected).
     async fn remove_named_grouping_policy(
         &self,
         request: Request<PolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
         let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        self.require_ptype(&e, "g", &get_inner.p_type)?;
 
         let rule_removed = e
             .remove_named_grouping_policy(&get_inner.p_type, get_inner.params)
             .await
-            .unwrap();
+            .map_err(casbin_status)?;
         Ok(Response::new(casbin_proto::BoolReply { res: rule_removed }))
     }
 
-    async fn remove_filtered_grouping_policy(
+    async fn add_grouping_policies(
         &self,
-        mut request: Request<FilteredPolicyRequest>,
+        mut request: Request<PoliciesRequest>,
     ) -> Result<Response<BoolReply>, Status> {
-        let mut get_inner = request.get_mut();
+        let get_inner = request.get_mut();
         get_inner.p_type = String::from("g");
 
-        Ok(self
-            .remove_filtered_named_grouping_policy(request)
+        self.add_named_grouping_policies(request).await
+    }
+
+    // add_named_grouping_policies adds role inheritance rules to the named grouping policy
+    // atomically. Returns false, adding nothing, if any of the rules already exists.
+    async fn add_named_grouping_policies(
+        &self,
+        request: Request<PoliciesRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.into_inner();
+        let wrap_enforcer = self
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap())
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        self.require_ptype(&e, "g", &get_inner.p_type)?;
+        self.check_room(&e, get_inner.rules.len())?;
+        let rules = get_inner.rules.into_iter().map(|d| d.params).collect();
+
+        let rules_added = e
+            .add_named_grouping_policies(&get_inner.p_type, rules)
+            .await
+            .map_err(casbin_status)?;
+        Ok(Response::new(casbin_proto::BoolReply { res: rules_added }))
     }
 
-    async fn remove_filtered_named_grouping_policy(
+    async fn remove_grouping_policies(
         &self,
-        request: Request<FilteredPolicyRequest>,
+        mut request: Request<PoliciesRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.get_mut();
+        get_inner.p_type = String::from("g");
+
+        self.remove_named_grouping_policies(request).await
+    }
+
+    // remove_named_grouping_policies removes role inheritance rules from the named grouping
+    // policy atomically. Returns false, removing nothing, if any of the rules does not exist.
+    async fn remove_named_grouping_policies(
+        &self,
+        request: Request<PoliciesRequest>,
     ) -> Result<Response<BoolReply>, Status> {
         let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let mut e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let mut e = wrap_enforcer.write().await;
+        self.require_ptype(&e, "g", &get_inner.p_type)?;
+        let rules = get_inner.rules.into_iter().map(|d| d.params).collect();
 
-        let rule_filtered_removed = e
-            .remove_filtered_named_grouping_policy(
-                &get_inner.p_type,
-                get_inner.field_index as usize,
-                get_inner.field_values,
-            )
+        let rules_removed = e
+            .remove_named_grouping_policies(&get_inner.p_type, rules)
             .await
-            .unwrap();
+            .map_err(casbin_status)?;
+        Ok(Response::new(casbin_proto::BoolReply {
+            res: rules_removed,
+        }))
+    }
+
+    async fn remove_filtered_grouping_policy(
+        &self,
+        mut request: Request<FilteredPolicyRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.get_mut();
+        get_inner.p_type = String::from("g");
+
+        self.remove_filtered_named_grouping_policy(request).await
+    }
+
+    // remove_filtered_named_grouping_policy removes the role inheritance rules of the named
+    // grouping policy whose fields, starting at field_index, match field_values.
+    async fn remove_filtered_named_grouping_policy(
+        &self,
+        request: Request<FilteredPolicyRequest>,
+    ) -> Result<Response<BoolReply>, Status> {
+        let get_inner = request.into_inner();
+        let entry = self
+            .enforcers
+            .get(get_inner.enforcer_handler)
+            .await
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        let mut e = entry.enforcer.write().await;
+        self.require_ptype(&e, "g", &get_inner.p_type)?;
+        let field_index = self.check_field_filter(
+            &e,
+            entry.feed.index(),
+            "g",
+            &get_inner.p_type,
+            get_inner.field_index,
+            &get_inner.field_values,
+        )?;
+
+        let rule_filtered_removed = index::remove_filtered(
+            &mut e,
+            entry.feed.index(),
+            "g",
+            &get_inner.p_type,
+            field_index,
+            get_inner.field_values,
+        )
+        .await
+        .map_err(casbin_status)?;
         Ok(Response::new(casbin_proto::BoolReply {
             res: rule_filtered_removed,
         }))
     }
 
+    // get_grouping_policy gets all the role inheritance rules in the policy.
     async fn get_grouping_policy(
         &self,
         request: Request<EmptyRequest>,
     ) -> Result<Response<Array2DReply>, Status> {
         let get_inner = request.into_inner();
-        Ok(self
-            .get_named_grouping_policy(Request::new(casbin_proto::PolicyRequest {
-                enforcer_handler: get_inner.handler,
-                p_type: String::from("g"),
-                params: vec![String::from("")],
-            }))
-            .await
-            .unwrap())
+        self.get_named_grouping_policy(Request::new(casbin_proto::PolicyRequest {
+            enforcer_handler: get_inner.handler,
+            p_type: String::from("g"),
+            params: vec![],
+        }))
+        .await
     }
 
+    // get_named_grouping_policy gets all the role inheritance rules in the named grouping policy.
     async fn get_named_grouping_policy(
         &self,
         request: Request<PolicyRequest>,
     ) -> Result<Response<Array2DReply>, Status> {
         let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        self.require_ptype(&e, "g", &get_inner.p_type)?;
 
         Ok(Response::new(self.wrap_plain_policy(
-            e.get_model().get_policy("p", &get_inner.p_type),
+            e.get_model().get_policy("g", &get_inner.p_type),
         )))
     }
 
@@ -722,181 +1848,189 @@ impl Casbin for CasbinGRPC {
         &self,
         mut request: Request<FilteredPolicyRequest>,
     ) -> Result<Response<Array2DReply>, Status> {
-        let mut get_inner = request.get_mut();
+        let get_inner = request.get_mut();
         get_inner.p_type = String::from("g");
 
-        Ok(self
-            .get_filtered_named_grouping_policy(request)
-            .await
-            .unwrap())
+        self.get_filtered_named_grouping_policy(request).await
     }
 
+    // get_filtered_named_grouping_policy gets the role inheritance rules of the named grouping
+    // policy whose fields, starting at field_index, match field_values.
     async fn get_filtered_named_grouping_policy(
         &self,
         request: Request<FilteredPolicyRequest>,
     ) -> Result<Response<Array2DReply>, Status> {
         let get_inner = request.into_inner();
-        let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+        let entry = self
+            .enforcers
+            .get(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
+            .ok_or_else(|| Status::not_found("No enforcer found"))?;
+        let e = entry.enforcer.read().await;
+        self.require_ptype(&e, "g", &get_inner.p_type)?;
+        let field_index = self.check_field_filter(
+            &e,
+            entry.feed.index(),
+            "g",
+            &get_inner.p_type,
+            get_inner.field_index,
+            &get_inner.field_values,
+        )?;
 
         Ok(Response::new(self.wrap_plain_policy(
-            e.get_model().get_filtered_policy(
+            entry.feed.index().filtered(
+                &e,
                 "g",
                 &get_inner.p_type,
-                get_inner.field_index as usize,
+                field_index,
                 get_inner.field_values,
             ),
         )))
     }
 
+    // get_all_subjects gets the list of subjects that show up in the current policy.
     async fn get_all_subjects(
         &self,
         request: Request<EmptyRequest>,
     ) -> Result<Response<ArrayReply>, Status> {
-        Ok(self
-            .get_all_named_subjects(Request::new(casbin_proto::SimpleGetRequest {
-                enforcer_handler: request.into_inner().handler,
-                p_type: String::from("p"),
-            }))
-            .await
-            .unwrap())
+        self.get_all_named_subjects(Request::new(SimpleGetRequest {
+            enforcer_handler: request.into_inner().handler,
+            p_type: String::from("p"),
+        }))
+        .await
     }
 
+    // get_all_named_subjects gets the list of subjects that show up in the current named policy.
     async fn get_all_named_subjects(
         &self,
-        mut request: Request<SimpleGetRequest>,
+        request: Request<SimpleGetRequest>,
     ) -> Result<Response<ArrayReply>, Status> {
+        let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(request.get_mut().enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        self.require_ptype(&e, "p", &get_inner.p_type)?;
 
-        Ok(Response::new(casbin_proto::ArrayReply {
-            array: e.get_model().get_values_for_field_in_policy(
-                "p",
-                &request.into_inner().p_type,
-                0,
-            ),
+        Ok(Response::new(ArrayReply {
+            array: self.get_values_for_field(&e, "p", &get_inner.p_type, Some("sub"), 0),
         }))
     }
 
+    // get_all_objects gets the list of objects that show up in the current policy.
     async fn get_all_objects(
         &self,
         request: Request<EmptyRequest>,
     ) -> Result<Response<ArrayReply>, Status> {
-        Ok(self
-            .get_all_named_objects(Request::new(casbin_proto::SimpleGetRequest {
-                enforcer_handler: request.into_inner().handler,
-                p_type: String::from("p"),
-            }))
-            .await
-            .unwrap())
+        self.get_all_named_objects(Request::new(SimpleGetRequest {
+            enforcer_handler: request.into_inner().handler,
+            p_type: String::from("p"),
+        }))
+        .await
     }
 
+    // get_all_named_objects gets the list of objects that show up in the current named policy.
     async fn get_all_named_objects(
         &self,
-        mut request: Request<SimpleGetRequest>,
+        request: Request<SimpleGetRequest>,
     ) -> Result<Response<ArrayReply>, Status> {
+        let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(request.get_mut().enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        self.require_ptype(&e, "p", &get_inner.p_type)?;
 
-        Ok(Response::new(casbin_proto::ArrayReply {
-            array: e.get_model().get_values_for_field_in_policy(
-                "p",
-                &request.into_inner().p_type,
-                1,
-            ),
+        Ok(Response::new(ArrayReply {
+            array: self.get_values_for_field(&e, "p", &get_inner.p_type, Some("obj"), 1),
         }))
     }
 
+    // get_all_actions gets the list of actions that show up in the current policy.
     async fn get_all_actions(
         &self,
         request: Request<EmptyRequest>,
     ) -> Result<Response<ArrayReply>, Status> {
-        Ok(self
-            .get_all_named_objects(Request::new(casbin_proto::SimpleGetRequest {
-                enforcer_handler: request.into_inner().handler,
-                p_type: String::from("p"),
-            }))
-            .await
-            .unwrap())
+        self.get_all_named_actions(Request::new(SimpleGetRequest {
+            enforcer_handler: request.into_inner().handler,
+            p_type: String::from("p"),
+        }))
+        .await
     }
 
+    // get_all_named_actions gets the list of actions that show up in the current named policy.
     async fn get_all_named_actions(
         &self,
-        mut request: Request<SimpleGetRequest>,
+        request: Request<SimpleGetRequest>,
     ) -> Result<Response<ArrayReply>, Status> {
+        let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(request.get_mut().enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        self.require_ptype(&e, "p", &get_inner.p_type)?;
 
-        Ok(Response::new(casbin_proto::ArrayReply {
-            array: e.get_model().get_values_for_field_in_policy(
-                "p",
-                &request.into_inner().p_type,
-                2,
-            ),
+        Ok(Response::new(ArrayReply {
+            array: self.get_values_for_field(&e, "p", &get_inner.p_type, Some("act"), 2),
         }))
     }
 
+    // get_all_roles gets the list of roles that show up in the current policy.
     async fn get_all_roles(
         &self,
         request: Request<EmptyRequest>,
     ) -> Result<Response<ArrayReply>, Status> {
-        Ok(self
-            .get_all_named_objects(Request::new(casbin_proto::SimpleGetRequest {
-                enforcer_handler: request.into_inner().handler,
-                p_type: String::from("g"),
-            }))
-            .await
-            .unwrap())
+        self.get_all_named_roles(Request::new(SimpleGetRequest {
+            enforcer_handler: request.into_inner().handler,
+            p_type: String::from("g"),
+        }))
+        .await
     }
 
+    // get_all_named_roles gets the list of roles that show up in the current named policy.
     async fn get_all_named_roles(
         &self,
-        mut request: Request<SimpleGetRequest>,
+        request: Request<SimpleGetRequest>,
     ) -> Result<Response<ArrayReply>, Status> {
+        let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(request.get_mut().enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        self.require_ptype(&e, "g", &get_inner.p_type)?;
 
-        Ok(Response::new(casbin_proto::ArrayReply {
-            array: e.get_model().get_values_for_field_in_policy(
-                "g",
-                &request.into_inner().p_type,
-                1,
-            ),
+        Ok(Response::new(ArrayReply {
+            array: self.get_values_for_field(&e, "g", &get_inner.p_type, None, 1),
         }))
     }
 
+    // has_policy determines whether an authorization rule exists.
     async fn has_policy(
         &self,
-        request: Request<PolicyRequest>,
+        mut request: Request<PolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
-        Ok(self.has_named_policy(request).await.unwrap())
+        let get_inner = request.get_mut();
+        get_inner.p_type = String::from("p");
+
+        self.has_named_policy(request).await
     }
 
+    // has_named_policy determines whether a named authorization rule exists.
     async fn has_named_policy(
         &self,
         request: Request<PolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
         let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        self.require_ptype(&e, "p", &get_inner.p_type)?;
 
         Ok(Response::new(casbin_proto::BoolReply {
             res: e
@@ -905,26 +2039,29 @@ impl Casbin for CasbinGRPC {
         }))
     }
 
+    // has_grouping_policy determines whether a role inheritance rule exists.
     async fn has_grouping_policy(
         &self,
-        request: Request<PolicyRequest>,
+        mut request: Request<PolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
-        let mut get_inner = request.into_inner();
+        let get_inner = request.get_mut();
         get_inner.p_type = String::from("g");
-        let req_new = Request::new(get_inner);
-        Ok(self.has_named_grouping_policy(req_new).await.unwrap())
+
+        self.has_named_grouping_policy(request).await
     }
 
+    // has_named_grouping_policy determines whether a named role inheritance rule exists.
     async fn has_named_grouping_policy(
         &self,
         request: Request<PolicyRequest>,
     ) -> Result<Response<BoolReply>, Status> {
         let get_inner = request.into_inner();
         let wrap_enforcer = self
-            .get_enforcer(get_inner.enforcer_handler as i32)
+            .get_enforcer(get_inner.enforcer_handler)
             .await
-            .unwrap();
-        let e = wrap_enforcer.lock().await;
+            .map_err(Status::not_found)?;
+        let e = wrap_enforcer.read().await;
+        self.require_ptype(&e, "g", &get_inner.p_type)?;
 
         Ok(Response::new(casbin_proto::BoolReply {
             res: e
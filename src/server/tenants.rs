@@ -0,0 +1,890 @@
+use crate::adapter::Drivers;
+use crate::casbin_proto::casbin_server::CasbinServer;
+use crate::datastructure::lru::LruCache;
+use crate::server::adapter::{Config, SharedAdapter};
+use crate::server::audit::AuditLog;
+use crate::server::auth::{AuthScope, AuthTenant, Scope};
+use crate::server::enforcer;
+use crate::server::error::casbin_status;
+use crate::server::functions::Functions;
+use crate::server::model_source;
+use crate::server::ratelimit::{self, RateLimit, RateLimiter};
+use crate::server::registry::{DecisionCacheOptions, EnforcerRegistry};
+use crate::{AdapterMap, CasbinGRPC};
+use casbin::{Adapter, CoreApi, DefaultModel, Filter, Model};
+use futures::lock::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::OnceCell;
+use tonic::body::BoxBody;
+use tonic::codec::CompressionEncoding;
+use tonic::codegen::{http, Body, BoxFuture, Service};
+use tonic::transport::NamedService;
+use tonic::Status;
+use tower::ServiceExt;
+
+// TENANT_HEADER is the metadata key naming the tenant a call is for.
+pub static TENANT_HEADER: &str = "x-tenant-id";
+
+// SERVER_METHODS are the RPCs reaching past the enforcers of a tenant, to the stores of the
+// server or to its config, which the calls for a tenant cannot make.
+static SERVER_METHODS: &[&str] = &["NewAdapter", "ReloadConfig"];
+
+static MAX_TENANTS: usize = 1000;
+
+// Tenant is the tenant a call is for, in the extensions of the request.
+#[derive(Clone, Debug)]
+pub struct Tenant(pub String);
+
+// denied is the error of a change the tenant of an enforcer may not make, failing its call with
+// PERMISSION_DENIED.
+fn denied(message: String) -> casbin::Error {
+    std::io::Error::new(std::io::ErrorKind::PermissionDenied, message).into()
+}
+
+// TenantAdapter is the adapter of the enforcer of a tenant, over the store of the local config:
+// it loads the rules whose tenant field is the tenant, and nothing else, and refuses to add,
+// update or remove the rules of any other tenant.
+pub struct TenantAdapter {
+    inner: Arc<Mutex<Box<dyn Adapter>>>,
+    tenant: String,
+    policy_field: usize,
+    grouping_field: usize,
+}
+
+impl TenantAdapter {
+    pub fn new(
+        inner: Arc<Mutex<Box<dyn Adapter>>>,
+        tenant: &str,
+        policy_field: usize,
+        grouping_field: usize,
+    ) -> Self {
+        TenantAdapter {
+            inner,
+            tenant: tenant.to_owned(),
+            policy_field,
+            grouping_field,
+        }
+    }
+
+    fn field(&self, sec: &str) -> usize {
+        if sec == "g" {
+            self.grouping_field
+        } else {
+            self.policy_field
+        }
+    }
+
+    // check refuses a rule of sec whose tenant field is not the tenant.
+    fn check(&self, sec: &str, rule: &[String]) -> casbin::Result<()> {
+        match rule.get(self.field(sec)) {
+            Some(tenant) if *tenant == self.tenant => Ok(()),
+            _ => Err(denied(format!(
+                "the rule {:?} is not one of tenant `{}`",
+                rule, self.tenant
+            ))),
+        }
+    }
+
+    // pin gets the values of a filter of sec starting at field index, with the tenant field
+    // set to the tenant, and the index they now start at. A filter naming another tenant
+    // fails.
+    fn pin(
+        &self,
+        sec: &str,
+        index: usize,
+        values: Vec<String>,
+    ) -> casbin::Result<(usize, Vec<String>)> {
+        let field = self.field(sec);
+        let start = index.min(field);
+        let mut pinned = vec![String::new(); index - start];
+        pinned.extend(values);
+        let at = field - start;
+        if pinned.len() <= at {
+            pinned.resize(at + 1, String::new());
+        }
+        if !pinned[at].is_empty() && pinned[at] != self.tenant {
+            return Err(denied(format!(
+                "the filter names tenant `{}`, not `{}`",
+                pinned[at], self.tenant
+            )));
+        }
+        pinned[at] = self.tenant.clone();
+        Ok((start, pinned))
+    }
+
+    async fn load(&self, m: &mut dyn Model, p: &[&str], g: &[&str]) -> casbin::Result<()> {
+        let owned = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+        let (_, p) = self.pin("p", 0, owned(p))?;
+        let (_, g) = self.pin("g", 0, owned(g))?;
+        let filter = Filter {
+            p: p.iter().map(String::as_str).collect(),
+            g: g.iter().map(String::as_str).collect(),
+        };
+        self.inner
+            .lock()
+            .await
+            .load_filtered_policy(m, filter)
+            .await
+    }
+}
+
+#[tonic::async_trait]
+impl Adapter for TenantAdapter {
+    async fn load_policy(&self, m: &mut dyn Model) -> casbin::Result<()> {
+        self.load(m, &[], &[]).await
+    }
+
+    async fn load_filtered_policy<'a>(
+        &mut self,
+        m: &mut dyn Model,
+        f: Filter<'a>,
+    ) -> casbin::Result<()> {
+        self.load(m, &f.p, &f.g).await
+    }
+
+    async fn save_policy(&mut self, _: &mut dyn Model) -> casbin::Result<()> {
+        Err(denied(
+            "the policy of a tenant cannot be saved as a whole".to_owned(),
+        ))
+    }
+
+    async fn clear_policy(&mut self) -> casbin::Result<()> {
+        Err(denied(
+            "clearing the store would clear every tenant, remove the rules of the tenant with RemoveFilteredPolicy instead".to_owned(),
+        ))
+    }
+
+    // is_filtered is always true, as the store holds the rules of the other tenants too.
+    fn is_filtered(&self) -> bool {
+        true
+    }
+
+    async fn add_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        self.check(sec, &rule)?;
+        self.inner.lock().await.add_policy(sec, ptype, rule).await
+    }
+
+    async fn add_policies(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        for rule in &rules {
+            self.check(sec, rule)?;
+        }
+        self.inner
+            .lock()
+            .await
+            .add_policies(sec, ptype, rules)
+            .await
+    }
+
+    async fn remove_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rule: Vec<String>,
+    ) -> casbin::Result<bool> {
+        self.check(sec, &rule)?;
+        self.inner
+            .lock()
+            .await
+            .remove_policy(sec, ptype, rule)
+            .await
+    }
+
+    async fn remove_policies(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        rules: Vec<Vec<String>>,
+    ) -> casbin::Result<bool> {
+        for rule in &rules {
+            self.check(sec, rule)?;
+        }
+        self.inner
+            .lock()
+            .await
+            .remove_policies(sec, ptype, rules)
+            .await
+    }
+
+    async fn remove_filtered_policy(
+        &mut self,
+        sec: &str,
+        ptype: &str,
+        field_index: usize,
+        field_values: Vec<String>,
+    ) -> casbin::Result<bool> {
+        let (field_index, field_values) = self.pin(sec, field_index, field_values)?;
+        self.inner
+            .lock()
+            .await
+            .remove_filtered_policy(sec, ptype, field_index, field_values)
+            .await
+    }
+}
+
+// Tenants are the enforcers of the tenants of the server, each tenant having a Casbin service
+// of its own, so a call for a tenant reaches no enforcer of another. The service of a tenant
+// is created on the first call for it, serving as handle 0 an enforcer of the tenant model
+// over the rules of the tenant in the store of the local config, which is its adapter 0 for
+// the enforcers it creates with NewEnforcer. The tenants share one connection to the store,
+// opened for the first of them, and the decision cache settings, batch workers, matcher
+// functions and audit log of the server, within the limits of a tenant. Only the tenants
+// known in advance and those of authenticated calls are created.
+pub struct Tenants {
+    model_text: String,
+    driver: String,
+    connection: String,
+    policy_field: usize,
+    grouping_field: usize,
+    drivers: Drivers,
+    store: OnceCell<Arc<Mutex<Box<dyn Adapter>>>>,
+    decision_cache: DecisionCacheOptions,
+    batch_parallelism: usize,
+    audit: Option<Arc<AuditLog>>,
+    functions: Arc<Functions>,
+    compression: Option<CompressionEncoding>,
+    max_rules: usize,
+    max_enforcers: usize,
+    max_cached_decisions: usize,
+    rate_limiter: Option<RateLimiter>,
+    // known are the tenants known in advance, which are never dropped.
+    known: HashSet<String>,
+    max_tenants: usize,
+    services: std::sync::Mutex<LruCache<String, Arc<OnceCell<TenantServer>>>>,
+}
+
+// TenantServer is the Casbin service of a tenant, along with the enforcers and adapters it
+// serves.
+#[derive(Clone)]
+struct TenantServer {
+    service: CasbinServer<CasbinGRPC>,
+    enforcers: Arc<EnforcerRegistry>,
+    adapters: AdapterMap,
+}
+
+impl TenantServer {
+    // holds_state tells whether the tenant holds more than its enforcer would once loaded
+    // again: enforcers or adapters it created, functions it defined, a model it set, or rule
+    // changes kept from the store.
+    async fn holds_state(&self, model_text: &str) -> bool {
+        if self.adapters.read().await.len() > 1 {
+            return true;
+        }
+        match self.enforcers.list().await.as_slice() {
+            [(0, entry)] => {
+                !entry.enforcer.functions().is_empty()
+                    || entry.model_text() != model_text
+                    || !entry.enforcer.read().await.has_auto_save_enabled()
+            }
+            _ => true,
+        }
+    }
+}
+
+impl Tenants {
+    // from_config sets up the tenants of the local config, none when it has no tenant model.
+    pub async fn from_config(
+        cfg: &Config,
+        server: &CasbinGRPC,
+        compression: Option<CompressionEncoding>,
+    ) -> casbin::Result<Option<Self>> {
+        if cfg.tenant_model.is_empty() {
+            return Ok(None);
+        }
+        let model_text =
+            model_source::read_model(&cfg.tenant_model, &cfg.model_headers, &cfg.model_cache_dir)
+                .await?;
+        // The model is checked once here rather than on the first call of every tenant.
+        DefaultModel::from_str(&model_text).await?;
+        let or = |value: usize, default: usize| if value == 0 { default } else { value };
+        let known = cfg
+            .tenants
+            .iter()
+            .chain(cfg.api_key_tenants.values())
+            .chain(server.api_key_tenants.values())
+            .cloned()
+            .collect();
+        Ok(Some(Tenants {
+            model_text,
+            driver: cfg.driver.clone(),
+            connection: cfg.connection.clone(),
+            policy_field: or(cfg.tenant_policy_field, 1),
+            grouping_field: or(cfg.tenant_grouping_field, 2),
+            drivers: server.drivers.clone(),
+            store: OnceCell::new(),
+            decision_cache: server.decision_cache,
+            batch_parallelism: server.batch_parallelism,
+            audit: server.audit.clone(),
+            functions: server.enforcers.functions().clone(),
+            compression,
+            max_rules: cfg.tenant_max_rules,
+            max_enforcers: cfg.tenant_max_enforcers,
+            max_cached_decisions: cfg.tenant_max_cached_decisions,
+            rate_limiter: (cfg.tenant_rate_limit_per_sec > 0.0).then(|| {
+                let limit =
+                    RateLimit::new(cfg.tenant_rate_limit_per_sec, cfg.tenant_rate_limit_burst);
+                RateLimiter::new(Some(limit), HashMap::new())
+            }),
+            known,
+            max_tenants: or(cfg.max_tenants, MAX_TENANTS),
+            // The tenants are dropped by service alone, the cache never evicts them itself.
+            services: std::sync::Mutex::new(LruCache::new(usize::MAX)),
+        }))
+    }
+
+    // service gets the Casbin service of a tenant, creating it on its first call if the tenant
+    // is known or authenticated, the credentials of the call naming it. Calls for a tenant
+    // being created wait for it, and try again if creating it failed.
+    async fn service(
+        &self,
+        tenant: &str,
+        authenticated: bool,
+    ) -> Result<CasbinServer<CasbinGRPC>, Status> {
+        let cell = loop {
+            let loaded = {
+                let mut services = self.services.lock().unwrap();
+                if let Some(cell) = services.get(&tenant.to_owned()) {
+                    break cell.clone();
+                }
+                if !authenticated && !self.known.contains(tenant) {
+                    return Err(Status::not_found(format!(
+                        "tenant `{}` is neither known nor loaded",
+                        tenant
+                    )));
+                }
+                if services.len() < self.max_tenants {
+                    let cell = Arc::new(OnceCell::new());
+                    services.insert(tenant.to_owned(), cell.clone());
+                    break cell;
+                }
+                let mut loaded: Vec<_> = services
+                    .iter()
+                    .map(|(tenant, cell)| (tenant.clone(), cell.clone()))
+                    .collect();
+                loaded.reverse();
+                loaded
+            };
+            self.drop_one(loaded).await?;
+        };
+        cell.get_or_try_init(|| self.open(tenant))
+            .await
+            .map(|server| server.service.clone())
+            .map_err(casbin_status)
+    }
+
+    // drop_one drops the least recently called of the tenants loaded, least recent first, that
+    // is not known in advance, is not being created and holds no state, failing with
+    // RESOURCE_EXHAUSTED when there is none.
+    async fn drop_one(
+        &self,
+        loaded: Vec<(String, Arc<OnceCell<TenantServer>>)>,
+    ) -> Result<(), Status> {
+        for (tenant, cell) in loaded {
+            if self.known.contains(&tenant) {
+                continue;
+            }
+            match cell.get() {
+                Some(server) if !server.holds_state(&self.model_text).await => {}
+                _ => continue,
+            }
+            let mut services = self.services.lock().unwrap();
+            if services
+                .peek(&tenant)
+                .is_some_and(|other| Arc::ptr_eq(other, &cell))
+            {
+                services.remove(&tenant);
+                tracing::info!(tenant = %tenant, "dropped the enforcer of the tenant");
+            }
+            return Ok(());
+        }
+        Err(Status::resource_exhausted(format!(
+            "{} tenants are loaded, the most allowed, and none can be dropped",
+            self.max_tenants
+        )))
+    }
+
+    // store gets the connection to the store the tenants share, opening it on the first call.
+    async fn store(&self) -> casbin::Result<Arc<Mutex<Box<dyn Adapter>>>> {
+        self.store
+            .get_or_try_init(|| async {
+                let a = self.drivers.open(&self.driver, &self.connection).await?;
+                Ok::<_, casbin::Error>(Arc::new(Mutex::new(a)))
+            })
+            .await
+            .cloned()
+    }
+
+    // server makes the server of a tenant, within its limits, its enforcer 0 loaded.
+    async fn server(&self, tenant: &str) -> casbin::Result<CasbinGRPC> {
+        let a: Box<dyn Adapter> = Box::new(TenantAdapter::new(
+            self.store().await?,
+            tenant,
+            self.policy_field,
+            self.grouping_field,
+        ));
+        let a = Arc::new(Mutex::new(a));
+        let m = DefaultModel::from_str(&self.model_text).await?;
+        let e = enforcer::build_enforcer(m, SharedAdapter::new(a.clone()).await).await?;
+        let mut server = CasbinGRPC::new_server();
+        server.enforcers = Arc::new(EnforcerRegistry::with_limits(
+            self.max_enforcers,
+            self.max_cached_decisions,
+            self.functions.clone(),
+        ));
+        server.drivers = self.drivers.clone();
+        server.decision_cache = self.decision_cache;
+        if self.max_cached_decisions > 0 {
+            server.decision_cache.max_entries = server
+                .decision_cache
+                .max_entries
+                .min(self.max_cached_decisions);
+        }
+        server.batch_parallelism = self.batch_parallelism;
+        server.audit = self.audit.clone();
+        server.max_rules = self.max_rules;
+        server.tenant_model = Some(self.model_text.clone());
+        server.add_adapter(a).await;
+        let handle = server.add_enforcer(e, self.model_text.clone()).await;
+        server.enforcers.pin(handle).await;
+        tracing::info!(tenant = %tenant, "loaded the enforcer of the tenant");
+        Ok(server)
+    }
+
+    async fn open(&self, tenant: &str) -> casbin::Result<TenantServer> {
+        let server = self.server(tenant).await?;
+        let (enforcers, adapters) = (server.enforcers.clone(), server.adapter_map.clone());
+        let mut service = CasbinServer::new(server);
+        if let Some(encoding) = self.compression {
+            service = service
+                .send_compressed(encoding)
+                .accept_compressed(encoding);
+        }
+        Ok(TenantServer {
+            service,
+            enforcers,
+            adapters,
+        })
+    }
+}
+
+// tenant_of gets the tenant a call is for, and whether its credentials name it: the tenant of
+// the JWT or API key it is authenticated with, which its x-tenant-id may only repeat, or for a
+// call with an admin API key of no tenant, the tenant its x-tenant-id names, if any. A call
+// whose credentials name no tenant is refused unless its API key is such an admin key, which
+// may call for no tenant.
+#[allow(clippy::result_large_err)]
+fn tenant_of<B>(request: &http::Request<B>) -> Result<Option<(String, bool)>, Status> {
+    let named = match request.headers().get(TENANT_HEADER) {
+        Some(value) => Some(
+            value
+                .to_str()
+                .map_err(|_| Status::invalid_argument("x-tenant-id must be ASCII text"))?
+                .trim()
+                .to_owned(),
+        ),
+        None => None,
+    };
+    let extensions = request.extensions();
+    let admin = matches!(extensions.get(), Some(AuthScope(Scope::Admin)));
+    let tenant = match (extensions.get::<AuthTenant>(), named) {
+        (Some(AuthTenant(tenant)), Some(named)) if named != *tenant => {
+            return Err(Status::permission_denied(format!(
+                "the credentials are for tenant `{}`, not `{}`",
+                tenant, named
+            )))
+        }
+        (Some(AuthTenant(tenant)), _) => (tenant.clone(), true),
+        (None, Some(named)) if admin => (named, false),
+        (None, None) if admin => return Ok(None),
+        (None, Some(_)) => {
+            return Err(Status::permission_denied(
+                "only admin API keys of no tenant may name a tenant with x-tenant-id",
+            ))
+        }
+        (None, None) => {
+            return Err(Status::permission_denied(
+                "the calls are for a tenant, and the credentials name none",
+            ))
+        }
+    };
+    // An empty tenant field matches the rules of every tenant.
+    if tenant.0.is_empty() {
+        return Err(Status::invalid_argument("the tenant is empty"));
+    }
+    Ok(Some(tenant))
+}
+
+// TenantService passes the calls for a tenant on to the Casbin service of the tenant, and the
+// admin calls for none to the one it wraps, which gets every call without tenants. Calls for a tenant to the
+// RPCs of SERVER_METHODS are refused as PERMISSION_DENIED, and those past the rate of a tenant
+// as RESOURCE_EXHAUSTED.
+#[derive(Clone)]
+pub struct TenantService<S> {
+    inner: S,
+    tenants: Option<Arc<Tenants>>,
+}
+
+impl<S> TenantService<S> {
+    pub fn new(inner: S, tenants: Option<Arc<Tenants>>) -> Self {
+        TenantService { inner, tenants }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for TenantService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    B: Body + Send + 'static,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // The service polled ready is the one to call, so it is taken and a clone left behind.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let tenants = match &self.tenants {
+            Some(tenants) => tenants.clone(),
+            None => return Box::pin(inner.call(request)),
+        };
+        Box::pin(async move {
+            let (tenant, authenticated) = match tenant_of(&request) {
+                Ok(Some(tenant)) => tenant,
+                Ok(None) => return inner.call(request).await,
+                Err(status) => return Ok(status.to_http()),
+            };
+            let method = request.uri().path().rsplit('/').next().unwrap_or_default();
+            if SERVER_METHODS.contains(&method) {
+                return Ok(Status::permission_denied(format!(
+                    "{} cannot be called for a tenant",
+                    method
+                ))
+                .to_http());
+            }
+            if let Some(limiter) = &tenants.rate_limiter {
+                if let Err(delay) = limiter.check(tenant.clone(), method) {
+                    return Ok(ratelimit::rate_limited(method, delay).to_http());
+                }
+            }
+            let service = match tenants.service(&tenant, authenticated).await {
+                Ok(service) => service,
+                Err(status) => return Ok(status.to_http()),
+            };
+            request.extensions_mut().insert(Tenant(tenant));
+            service.oneshot(request).await
+        })
+    }
+}
+
+impl<S: NamedService> NamedService for TenantService<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tenant_of, TenantService, Tenants, TENANT_HEADER};
+    use crate::adapter::csv::CsvAdapter;
+    use crate::casbin_proto::casbin_server::Casbin;
+    use crate::casbin_proto::{
+        policies_request, DecisionCacheOptions, NewEnforcerRequest, PoliciesRequest,
+    };
+    use crate::server::adapter::Config;
+    use crate::server::auth::{AuthScope, AuthTenant, Scope};
+    use crate::server::enforcer;
+    use crate::CasbinGRPC;
+    use casbin::{DefaultModel, FileAdapter, MgmtApi};
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tonic::body::BoxBody;
+    use tonic::codegen::http;
+    use tonic::{Code, Request};
+    use tower::ServiceExt;
+
+    fn request(
+        tenant: Option<&str>,
+        scope: Option<Scope>,
+        auth: Option<&str>,
+    ) -> http::Request<()> {
+        let mut request = http::Request::builder();
+        if let Some(tenant) = tenant {
+            request = request.header(TENANT_HEADER, tenant);
+        }
+        let mut request = request.body(()).unwrap();
+        if let Some(scope) = scope {
+            request.extensions_mut().insert(AuthScope(scope));
+        }
+        if let Some(tenant) = auth {
+            request
+                .extensions_mut()
+                .insert(AuthTenant(tenant.to_owned()));
+        }
+        request
+    }
+
+    fn code<T>(res: Result<T, tonic::Status>) -> Code {
+        res.err().map_or(Code::Ok, |status| status.code())
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn test_tenant_of_credentials() {
+        let of = |tenant, scope, auth| tenant_of(&request(tenant, scope, auth));
+        // The tenant of the credentials, which x-tenant-id may only repeat.
+        let read = Some(Scope::Read);
+        assert_eq!(
+            of(None, read, Some("t1")).unwrap(),
+            Some(("t1".into(), true))
+        );
+        assert_eq!(
+            of(Some("t1"), read, Some("t1")).unwrap(),
+            Some(("t1".into(), true))
+        );
+        assert_eq!(
+            code(of(Some("t2"), read, Some("t1"))),
+            Code::PermissionDenied
+        );
+        let admin = Some(Scope::Admin);
+        assert_eq!(
+            code(of(Some("t2"), admin, Some("t1"))),
+            Code::PermissionDenied
+        );
+
+        // Only admins of no tenant name one, and call for none.
+        assert_eq!(
+            of(Some("t2"), admin, None).unwrap(),
+            Some(("t2".into(), false))
+        );
+        assert_eq!(of(None, admin, None).unwrap(), None);
+        assert_eq!(code(of(Some("t2"), read, None)), Code::PermissionDenied);
+        assert_eq!(code(of(Some("t2"), None, None)), Code::PermissionDenied);
+        assert_eq!(code(of(None, read, None)), Code::PermissionDenied);
+        assert_eq!(code(of(None, None, None)), Code::PermissionDenied);
+        assert_eq!(code(of(None, None, Some(""))), Code::InvalidArgument);
+    }
+
+    // config serves the tenants of the rbac_with_domains example.
+    fn config(max_tenants: usize, known: &[&str]) -> Config {
+        Config {
+            tenant_model: "examples/rbac_with_domains_model.conf".to_owned(),
+            driver: "counted".to_owned(),
+            connection: "examples/rbac_with_domains_policy.csv".to_owned(),
+            max_tenants,
+            tenants: known.iter().map(|tenant| tenant.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    // tenants serves the tenants of cfg, counting the connections opened to their store.
+    async fn tenants(cfg: Config) -> (Tenants, Arc<AtomicUsize>) {
+        let opened = Arc::new(AtomicUsize::new(0));
+        let counted = opened.clone();
+        let server = CasbinGRPC::builder()
+            .adapter_driver("counted", move |connection| {
+                counted.fetch_add(1, Ordering::Relaxed);
+                async move { Ok(CsvAdapter::new(connection)) }
+            })
+            .build();
+        let tenants = Tenants::from_config(&cfg, &server, None).await.unwrap();
+        (tenants.unwrap(), opened)
+    }
+
+    fn loaded(tenants: &Tenants) -> Vec<String> {
+        let services = tenants.services.lock().unwrap();
+        let mut loaded: Vec<String> = services.iter().map(|(t, _)| t.clone()).collect();
+        loaded.sort();
+        loaded
+    }
+
+    #[tokio::test]
+    async fn test_tenants_share_the_store() {
+        let (tenants, opened) = tenants(config(10, &["domain2"])).await;
+        tenants.service("domain1", true).await.unwrap();
+        tenants.service("domain2", false).await.unwrap();
+        tenants.service("domain1", true).await.unwrap();
+        assert_eq!(opened.load(Ordering::Relaxed), 1);
+        assert_eq!(loaded(&tenants), vec!["domain1", "domain2"]);
+
+        // A tenant named by an admin is only created when known.
+        assert_eq!(
+            code(tenants.service("domain3", false).await),
+            Code::NotFound
+        );
+        tenants.service("domain3", true).await.unwrap();
+        tenants.service("domain3", false).await.unwrap();
+
+        let cell = tenants
+            .services
+            .lock()
+            .unwrap()
+            .peek(&"domain1".to_owned())
+            .cloned();
+        let server = cell.unwrap().get().cloned().unwrap();
+        let (_, entry) = server.enforcers.list().await.pop().unwrap();
+        let e = entry.enforcer.read().await;
+        // Each tenant holds its own rules alone.
+        let rules = e.get_policy();
+        assert_eq!(rules.len(), 2);
+        assert!(rules.iter().all(|rule| rule[1] == "domain1"));
+        assert_eq!(
+            e.get_grouping_policy(),
+            vec![vec!["alice", "admin", "domain1"]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tenants_holding_state_are_kept() {
+        let (tenants, opened) = tenants(config(2, &["domain1"])).await;
+        tenants.service("domain1", false).await.unwrap();
+        tenants.service("domain2", true).await.unwrap();
+        // domain2 is the only tenant that can be dropped, known tenants never are.
+        tenants.service("domain3", true).await.unwrap();
+        assert_eq!(loaded(&tenants), vec!["domain1", "domain3"]);
+        tenants.service("domain1", false).await.unwrap();
+        tenants.service("domain2", true).await.unwrap();
+        assert_eq!(loaded(&tenants), vec!["domain1", "domain2"]);
+        assert_eq!(opened.load(Ordering::Relaxed), 1);
+
+        // An enforcer created by domain2 keeps it loaded.
+        let cell = tenants
+            .services
+            .lock()
+            .unwrap()
+            .peek(&"domain2".to_owned())
+            .cloned();
+        let server = cell.unwrap().get().cloned().unwrap();
+        let text = std::fs::read_to_string("examples/rbac_with_domains_model.conf").unwrap();
+        let m = DefaultModel::from_str(&text).await.unwrap();
+        let e =
+            enforcer::build_enforcer(m, FileAdapter::new("examples/rbac_with_domains_policy.csv"))
+                .await
+                .unwrap();
+        server.enforcers.insert(e, text, Default::default()).await;
+        assert_eq!(
+            code(tenants.service("domain3", true).await),
+            Code::ResourceExhausted
+        );
+        assert_eq!(loaded(&tenants), vec!["domain1", "domain2"]);
+    }
+
+    fn new_enforcer(max_entries: i64) -> Request<NewEnforcerRequest> {
+        Request::new(NewEnforcerRequest {
+            adapter_handle: 0,
+            decision_cache: Some(DecisionCacheOptions {
+                max_entries,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_tenant_limits() {
+        // The rules added are saved to a copy of the store.
+        let store = std::env::temp_dir().join(format!("tenant-limits-{}.csv", std::process::id()));
+        std::fs::copy("examples/rbac_with_domains_policy.csv", &store).unwrap();
+        let cfg = Config {
+            connection: store.to_string_lossy().into_owned(),
+            tenant_max_rules: 5,
+            tenant_max_enforcers: 2,
+            tenant_max_cached_decisions: 100,
+            ..config(10, &[])
+        };
+        let (tenants, _) = tenants(cfg).await;
+        let server = tenants.server("domain1").await.unwrap();
+
+        // domain1 holds 3 rules.
+        let add = |rules: &[&str]| {
+            Request::new(PoliciesRequest {
+                enforcer_handler: 0,
+                p_type: "p".to_owned(),
+                rules: rules
+                    .iter()
+                    .map(|obj| policies_request::D {
+                        params: vec![
+                            "admin".into(),
+                            "domain1".into(),
+                            obj.to_string(),
+                            "read".into(),
+                        ],
+                    })
+                    .collect(),
+            })
+        };
+        let exhausted = server.add_policies(add(&["a", "b", "c"])).await;
+        assert_eq!(code(exhausted), Code::ResourceExhausted);
+        server.add_policies(add(&["a", "b"])).await.unwrap();
+        assert_eq!(
+            code(server.add_policies(add(&["c"])).await),
+            Code::ResourceExhausted
+        );
+
+        // Enforcer 0 counts as one of the 2, and caches up to 100 decisions of the 100.
+        let exhausted = server.new_enforcer(new_enforcer(101)).await;
+        assert_eq!(code(exhausted), Code::ResourceExhausted);
+        server.new_enforcer(new_enforcer(100)).await.unwrap();
+        assert_eq!(
+            code(server.new_enforcer(new_enforcer(0)).await),
+            Code::ResourceExhausted
+        );
+        std::fs::remove_file(store).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tenant_rate_limit() {
+        let cfg = Config {
+            tenant_rate_limit_per_sec: 1.0,
+            tenant_rate_limit_burst: 1.0,
+            ..config(10, &["domain1"])
+        };
+        let (tenants, _) = tenants(cfg).await;
+        let inner = tower::service_fn(|_: http::Request<BoxBody>| async {
+            Ok::<_, Infallible>(http::Response::new(tonic::body::empty_body()))
+        });
+        let service = TenantService::new(inner, Some(Arc::new(tenants)));
+        let call = |tenant: &str| {
+            let mut request = http::Request::builder()
+                .uri("/proto.Casbin/Enforce")
+                .header(TENANT_HEADER, tenant)
+                .body(tonic::body::empty_body())
+                .unwrap();
+            request.extensions_mut().insert(AuthScope(Scope::Admin));
+            service.clone().oneshot(request)
+        };
+        let status = |response: http::Response<BoxBody>| {
+            response
+                .headers()
+                .get("grpc-status")
+                .map(|code| Code::from_bytes(code.as_bytes()))
+        };
+        assert_ne!(
+            status(call("domain1").await.unwrap()),
+            Some(Code::ResourceExhausted)
+        );
+        assert_eq!(
+            status(call("domain1").await.unwrap()),
+            Some(Code::ResourceExhausted)
+        );
+    }
+}
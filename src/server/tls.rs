@@ -0,0 +1,531 @@
+use futures::Stream;
+use rustls::server::{AllowAnyAuthenticatedClient, ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+use std::io::{Error, ErrorKind};
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, RwLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::TlsAcceptor;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::server::Connected;
+use x509_parser::extensions::GeneralName;
+
+// RELOAD_INTERVAL is how often the certificate files are checked for changes.
+static RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+// HANDSHAKE_TIMEOUT bounds the TLS handshake of a connection, so clients that never finish it
+// do not pile up.
+static HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// TlsConfig is the certificate chain and private key the server is served with, each either
+// the path of a PEM file or PEM text. Certificates read from files are reloaded when the files
+// change, so a renewed certificate is served to new connections without a restart.
+//
+// With client_ca, clients must present a certificate signed by one of its CAs, read once at
+// start, and their identity is handed to the RPCs in the TlsConnectInfo of their requests.
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    pub cert: String,
+    pub key: String,
+    pub client_ca: String,
+}
+
+impl TlsConfig {
+    pub fn new(cert: impl Into<String>, key: impl Into<String>) -> Self {
+        TlsConfig {
+            cert: cert.into(),
+            key: key.into(),
+            client_ca: String::new(),
+        }
+    }
+
+    // with_client_ca requires clients to present a certificate signed by the CAs of a PEM
+    // file or text.
+    pub fn with_client_ca(mut self, client_ca: impl Into<String>) -> Self {
+        self.client_ca = client_ca.into();
+        self
+    }
+
+    fn is_pem(source: &str) -> bool {
+        source.trim_start().starts_with("-----BEGIN")
+    }
+
+    async fn read(source: &str) -> Result<Vec<u8>, Error> {
+        if Self::is_pem(source) {
+            return Ok(source.as_bytes().to_vec());
+        }
+        tokio::fs::read(source).await
+    }
+
+    // modified gets when the files of the config last changed, none for PEM text.
+    async fn modified(&self) -> Vec<Option<SystemTime>> {
+        let mut times = vec![];
+        for source in [&self.cert, &self.key] {
+            if Self::is_pem(source) {
+                continue;
+            }
+            let time = tokio::fs::metadata(source)
+                .await
+                .and_then(|m| m.modified())
+                .ok();
+            times.push(time);
+        }
+        times
+    }
+
+    async fn read_certs(&self, source: &str) -> Result<Vec<Certificate>, Error> {
+        let pem = Self::read(source).await?;
+        let certs: Vec<Certificate> = rustls_pemfile::certs(&mut pem.as_slice())?
+            .into_iter()
+            .map(Certificate)
+            .collect();
+        if certs.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("no certificate found in {}", self.source_name(source)),
+            ));
+        }
+        Ok(certs)
+    }
+
+    // load parses the certificate chain and the private key, PKCS#8, PKCS#1 or SEC1.
+    async fn load(&self) -> Result<Arc<CertifiedKey>, Error> {
+        let chain = self.read_certs(&self.cert).await?;
+        let key = Self::read(&self.key).await?;
+        let key = rustls_pemfile::read_all(&mut key.as_slice())?
+            .into_iter()
+            .find_map(|item| match item {
+                rustls_pemfile::Item::PKCS8Key(der)
+                | rustls_pemfile::Item::RSAKey(der)
+                | rustls_pemfile::Item::ECKey(der) => Some(PrivateKey(der)),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("no private key found in {}", self.source_name(&self.key)),
+                )
+            })?;
+        let key = rustls::sign::any_supported_type(&key)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        Ok(Arc::new(CertifiedKey::new(chain, key)))
+    }
+
+    fn source_name<'a>(&self, source: &'a str) -> &'a str {
+        if Self::is_pem(source) {
+            "the PEM text"
+        } else {
+            source
+        }
+    }
+}
+
+// CertResolver serves the last certificate loaded, swapped when its files change or the
+// local config names others.
+struct CertResolver {
+    key: RwLock<Arc<CertifiedKey>>,
+    // source is the config the certificate was loaded from, and when its files last changed.
+    source: tokio::sync::Mutex<(TlsConfig, Vec<Option<SystemTime>>)>,
+}
+
+impl CertResolver {
+    async fn new(config: TlsConfig) -> Result<Arc<Self>, Error> {
+        let modified = config.modified().await;
+        let key = config.load().await?;
+        Ok(Arc::new(CertResolver {
+            key: RwLock::new(key),
+            source: tokio::sync::Mutex::new((config, modified)),
+        }))
+    }
+
+    // reload loads the certificate again if its files changed, or from other files, or PEM
+    // text, when given cert and key. A certificate that fails to load leaves the previous one
+    // served. It tells whether another certificate is served.
+    async fn reload(&self, files: Option<(&str, &str)>) -> Result<bool, Error> {
+        let mut source = self.source.lock().await;
+        let mut config = source.0.clone();
+        if let Some((cert, key)) = files {
+            config.cert = cert.to_owned();
+            config.key = key.to_owned();
+        }
+        let modified = config.modified().await;
+        if (&config.cert, &config.key, &modified) == (&source.0.cert, &source.0.key, &source.1) {
+            return Ok(false);
+        }
+        *self.key.write().unwrap() = config.load().await?;
+        *source = (config, modified);
+        Ok(true)
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.key.read().unwrap().clone())
+    }
+}
+
+// RESOLVERS are the resolvers of every listener over TLS, which reload swaps the certificates
+// of.
+static RESOLVERS: Mutex<Vec<Arc<CertResolver>>> = Mutex::new(Vec::new());
+
+// reload serves the certificate chain and private key cert and key, each the path of a PEM
+// file or PEM text, on every listener over TLS from now on, in place of the ones they serve.
+pub async fn reload(cert: &str, key: &str) -> Result<(), Error> {
+    let resolvers = RESOLVERS.lock().unwrap().clone();
+    for resolver in resolvers {
+        resolver.reload(Some((cert, key))).await?;
+    }
+    Ok(())
+}
+
+// spawn_reload reloads the certificate when its files change, so a renewal written in several
+// steps is picked up once complete.
+fn spawn_reload(resolver: Arc<CertResolver>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(RELOAD_INTERVAL);
+        loop {
+            ticker.tick().await;
+            match resolver.reload(None).await {
+                Ok(true) => {
+                    let cert = resolver.source.lock().await.0.cert.clone();
+                    tracing::info!(%cert, "reloaded the TLS certificate");
+                }
+                Ok(false) => {}
+                Err(err) => tracing::error!(error = %err, "reloading the TLS certificate failed"),
+            }
+        }
+    });
+}
+
+// incoming listens on addr and yields the connections once their TLS handshake is done.
+// Handshakes run apart from the listener, and those failing are reported and dropped.
+pub async fn incoming(
+    addr: SocketAddr,
+    config: TlsConfig,
+) -> Result<impl Stream<Item = Result<TlsConnection, Error>>, Error> {
+    let resolver = CertResolver::new(config.clone()).await?;
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let mut server_config = if config.client_ca.is_empty() {
+        builder
+            .with_no_client_auth()
+            .with_cert_resolver(resolver.clone())
+    } else {
+        let mut roots = RootCertStore::empty();
+        for ca in config.read_certs(&config.client_ca).await? {
+            roots
+                .add(&ca)
+                .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        }
+        builder
+            .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+            .with_cert_resolver(resolver.clone())
+    };
+    // HTTP/1.1 is for the REST gateway and gRPC-Web, gRPC clients pick h2.
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    let acceptor = TlsAcceptor::from(Arc::new(server_config));
+    RESOLVERS.lock().unwrap().push(resolver.clone());
+    spawn_reload(resolver);
+
+    let listener = TcpListener::bind(addr).await?;
+    let (tx, rx) = mpsc::channel(128);
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    tracing::warn!(error = %err, "accepting a connection failed");
+                    continue;
+                }
+            };
+            let (acceptor, tx) = (acceptor.clone(), tx.clone());
+            tokio::spawn(async move {
+                let accepted = tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream));
+                match accepted.await {
+                    Ok(Ok(stream)) => {
+                        let info = TlsConnectInfo {
+                            remote_addr: Some(peer),
+                            identity: stream
+                                .get_ref()
+                                .1
+                                .peer_certificates()
+                                .and_then(|certs| certs.first())
+                                .and_then(ClientIdentity::parse)
+                                .map(Arc::new),
+                        };
+                        let _ = tx.send(Ok(TlsConnection { stream, info })).await;
+                    }
+                    Ok(Err(err)) => tracing::warn!(%peer, error = %err, "TLS handshake failed"),
+                    Err(_) => tracing::warn!(%peer, "TLS handshake timed out"),
+                }
+            });
+        }
+    });
+    Ok(ReceiverStream::new(rx))
+}
+
+// ClientIdentity is who the verified certificate of a client names: the common name of its
+// subject and its subject alternative names, DNS names, URIs such as SPIFFE IDs, emails and IP
+// addresses.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClientIdentity {
+    pub common_name: String,
+    pub sans: Vec<String>,
+}
+
+impl ClientIdentity {
+    fn parse(cert: &Certificate) -> Option<Self> {
+        let (_, cert) = x509_parser::parse_x509_certificate(&cert.0).ok()?;
+        let common_name = cert
+            .subject()
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .unwrap_or_default()
+            .to_owned();
+        let mut sans = vec![];
+        if let Ok(Some(ext)) = cert.subject_alternative_name() {
+            for name in ext.value.general_names.iter() {
+                match name {
+                    GeneralName::DNSName(name)
+                    | GeneralName::URI(name)
+                    | GeneralName::RFC822Name(name) => sans.push(name.to_string()),
+                    GeneralName::IPAddress(ip) => {
+                        if let Ok(ip) = <[u8; 4]>::try_from(*ip) {
+                            sans.push(IpAddr::from(ip).to_string());
+                        } else if let Ok(ip) = <[u8; 16]>::try_from(*ip) {
+                            sans.push(IpAddr::from(ip).to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Some(ClientIdentity { common_name, sans })
+    }
+
+    // name is what authorization goes by: the first subject alternative name, which is what
+    // certificates are issued for nowadays, or else the common name.
+    pub fn name(&self) -> &str {
+        self.sans.first().unwrap_or(&self.common_name)
+    }
+}
+
+// TlsConnectInfo describes the connection of a request served over TLS. It is in the
+// extensions of every request, where client_identity finds it.
+#[derive(Clone, Debug)]
+pub struct TlsConnectInfo {
+    remote_addr: Option<SocketAddr>,
+    identity: Option<Arc<ClientIdentity>>,
+}
+
+impl TlsConnectInfo {
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+
+    // identity is the client named by its certificate, none without mutual TLS.
+    pub fn identity(&self) -> Option<&ClientIdentity> {
+        self.identity.as_deref()
+    }
+}
+
+// client_identity gets the identity of the client of a request, verified through mutual TLS.
+pub fn client_identity<T>(request: &tonic::Request<T>) -> Option<&ClientIdentity> {
+    request
+        .extensions()
+        .get::<TlsConnectInfo>()
+        .and_then(TlsConnectInfo::identity)
+}
+
+// TlsConnection is a connection served over TLS.
+pub struct TlsConnection {
+    stream: tokio_rustls::server::TlsStream<TcpStream>,
+    info: TlsConnectInfo,
+}
+
+impl Connected for TlsConnection {
+    type ConnectInfo = TlsConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.info.clone()
+    }
+}
+
+impl AsyncRead for TlsConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TlsConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{incoming, CertResolver, ClientIdentity, TlsConfig};
+    use futures::StreamExt;
+    use rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore, ServerName};
+    use std::sync::Arc;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_rustls::TlsConnector;
+    use tonic::transport::server::Connected;
+
+    static CA: &str = "examples/tls/ca.pem";
+    static SERVER_CERT: &str = "examples/tls/server.pem";
+    static SERVER_KEY: &str = "examples/tls/server.key";
+    static CLIENT_CERT: &str = "examples/tls/client.pem";
+    static CLIENT_KEY: &str = "examples/tls/client.key";
+
+    fn served(resolver: &CertResolver) -> Vec<Certificate> {
+        resolver.key.read().unwrap().cert.clone()
+    }
+
+    #[tokio::test]
+    async fn test_load() {
+        let from_files = TlsConfig::new(SERVER_CERT, SERVER_KEY)
+            .load()
+            .await
+            .unwrap();
+        let pem = |file| std::fs::read_to_string(file).unwrap();
+        let from_text = TlsConfig::new(pem(SERVER_CERT), pem(SERVER_KEY))
+            .load()
+            .await
+            .unwrap();
+        assert_eq!(from_files.cert, from_text.cert);
+
+        let err = TlsConfig::new(SERVER_KEY, SERVER_KEY)
+            .load()
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains("no certificate found in"),
+            "{}",
+            err
+        );
+        let err = TlsConfig::new(SERVER_CERT, pem(SERVER_CERT))
+            .load()
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "no private key found in the PEM text");
+        assert!(TlsConfig::new("examples/tls/missing.pem", SERVER_KEY)
+            .load()
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reload() {
+        let resolver = CertResolver::new(TlsConfig::new(SERVER_CERT, SERVER_KEY))
+            .await
+            .unwrap();
+        let server = served(&resolver);
+        assert!(!resolver.reload(None).await.unwrap());
+        assert!(!resolver
+            .reload(Some((SERVER_CERT, SERVER_KEY)))
+            .await
+            .unwrap());
+
+        assert!(resolver
+            .reload(Some((CLIENT_CERT, CLIENT_KEY)))
+            .await
+            .unwrap());
+        let client = served(&resolver);
+        assert_ne!(client, server);
+
+        // A certificate failing to load leaves the previous one served.
+        assert!(resolver
+            .reload(Some((CLIENT_CERT, SERVER_CERT)))
+            .await
+            .is_err());
+        assert_eq!(served(&resolver), client);
+    }
+
+    async fn read_pem(file: &str) -> Vec<Vec<u8>> {
+        let pem = tokio::fs::read(file).await.unwrap();
+        rustls_pemfile::read_all(&mut pem.as_slice())
+            .unwrap()
+            .into_iter()
+            .map(|item| match item {
+                rustls_pemfile::Item::X509Certificate(der)
+                | rustls_pemfile::Item::PKCS8Key(der) => der,
+                _ => panic!("unexpected PEM item in {}", file),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_mutual_tls_identity() {
+        // The port of a listener just closed is free for the server.
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let config = TlsConfig::new(SERVER_CERT, SERVER_KEY).with_client_ca(CA);
+        let mut connections = Box::pin(incoming(addr, config).await.unwrap());
+
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(&Certificate(read_pem(CA).await.remove(0)))
+            .unwrap();
+        let chain = read_pem(CLIENT_CERT).await.into_iter().map(Certificate);
+        let key = PrivateKey(read_pem(CLIENT_KEY).await.remove(0));
+        let client = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_client_auth_cert(chain.collect(), key)
+            .unwrap();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let _stream = TlsConnector::from(Arc::new(client))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+            .unwrap();
+
+        let connection = connections.next().await.unwrap().unwrap();
+        let info = connection.connect_info();
+        assert_eq!(info.remote_addr().unwrap().ip(), addr.ip());
+        let identity = info.identity().unwrap();
+        assert_eq!(identity.common_name, "alice");
+        assert_eq!(
+            identity.sans,
+            vec!["spiffe://example.org/alice", "alice@example.org"]
+        );
+        assert_eq!(identity.name(), "spiffe://example.org/alice");
+    }
+
+    #[test]
+    fn test_identity_name() {
+        let identity = ClientIdentity {
+            common_name: "alice".to_owned(),
+            sans: vec![],
+        };
+        assert_eq!(identity.name(), "alice");
+    }
+}
@@ -0,0 +1,221 @@
+#[cfg(feature = "otel")]
+use opentelemetry::global::{self, BoxedSpan};
+#[cfg(feature = "otel")]
+use opentelemetry::propagation::Extractor;
+#[cfg(feature = "otel")]
+use opentelemetry::sdk::propagation::TraceContextPropagator;
+#[cfg(feature = "otel")]
+use opentelemetry::trace::{FutureExt, Span as _, SpanKind, Status, TraceContextExt, Tracer};
+#[cfg(feature = "otel")]
+use opentelemetry::{Context as TraceContext, KeyValue};
+#[cfg(feature = "otel")]
+use opentelemetry_otlp::WithExportConfig;
+use std::convert::Infallible;
+#[cfg(feature = "otel")]
+use std::fmt::Display;
+use std::future::Future;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::transport::NamedService;
+
+// TRACER is the name of the tracer of the spans of the server.
+#[cfg(feature = "otel")]
+static TRACER: &str = "casbin-grpc";
+
+// init exports the spans of the server over OTLP to the collector at endpoint, such as
+// `http://collector:4317`, as the service named by OTEL_SERVICE_NAME, else casbin-grpc. The
+// trace context of the calls is taken from their W3C traceparent and tracestate headers.
+#[cfg(feature = "otel")]
+pub fn init(endpoint: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let service_name =
+        std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| String::from("casbin-grpc"));
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(opentelemetry::sdk::trace::config().with_resource(
+            opentelemetry::sdk::Resource::new(vec![KeyValue::new("service.name", service_name)]),
+        ))
+        .install_batch(opentelemetry::runtime::Tokio)?;
+    tracing::info!(%endpoint, "exporting traces");
+    Ok(())
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn init(_endpoint: &str) -> Result<(), Box<dyn std::error::Error>> {
+    Err("otlp_endpoint is set, but this server is built without OpenTelemetry".into())
+}
+
+// shutdown exports the spans not yet exported, as the server stops.
+#[cfg(feature = "otel")]
+pub async fn shutdown() {
+    // Shutting down blocks until the exporter is done.
+    let _ = tokio::task::spawn_blocking(global::shutdown_tracer_provider).await;
+}
+
+#[cfg(not(feature = "otel"))]
+pub async fn shutdown() {}
+
+// Span is a span of the work of a call, a child of the span of the call, ended once dropped.
+// Spans are not recorded until init is called.
+pub struct Span {
+    #[cfg(feature = "otel")]
+    inner: BoxedSpan,
+}
+
+#[cfg(feature = "otel")]
+impl Span {
+    // start starts the span name as a child of the span of the current call.
+    pub fn start(name: &'static str) -> Self {
+        Span {
+            inner: global::tracer(TRACER).start(name),
+        }
+    }
+
+    pub fn set_bool(&mut self, key: &'static str, value: bool) {
+        self.inner.set_attribute(KeyValue::new(key, value));
+    }
+
+    // fail marks the span failed with err.
+    pub fn fail(&mut self, err: &dyn Display) {
+        self.inner.set_status(Status::error(err.to_string()));
+    }
+}
+
+#[cfg(not(feature = "otel"))]
+impl Span {
+    pub fn start(_name: &'static str) -> Self {
+        Span {}
+    }
+
+    pub fn set_bool(&mut self, _key: &'static str, _value: bool) {}
+
+    pub fn fail(&mut self, _err: &dyn std::fmt::Display) {}
+}
+
+// adapter_call runs a round trip to a policy store in the span name, making it a child of the
+// span of the current call.
+#[cfg(feature = "otel")]
+pub async fn adapter_call<T, F>(name: &'static str, call: F) -> casbin::Result<T>
+where
+    F: Future<Output = casbin::Result<T>>,
+{
+    let span = global::tracer(TRACER).start(name);
+    let cx = TraceContext::current_with_span(span);
+    let res = call.with_context(cx.clone()).await;
+    if let Err(err) = &res {
+        cx.span().set_status(Status::error(err.to_string()));
+    }
+    cx.span().end();
+    res
+}
+
+#[cfg(not(feature = "otel"))]
+pub async fn adapter_call<T, F>(_name: &'static str, call: F) -> casbin::Result<T>
+where
+    F: Future<Output = casbin::Result<T>>,
+{
+    call.await
+}
+
+// HeaderExtractor reads the trace context of a call from its headers.
+#[cfg(feature = "otel")]
+struct HeaderExtractor<'a>(&'a http::HeaderMap);
+
+#[cfg(feature = "otel")]
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+// TraceService runs each call to the service it wraps in a span of its own, continuing the
+// trace of the client when the call carries its context. The span of a streaming call ends
+// once the reply starts.
+#[derive(Clone)]
+pub struct TraceService<S> {
+    inner: S,
+}
+
+impl<S> TraceService<S> {
+    pub fn new(inner: S) -> Self {
+        TraceService { inner }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for TraceService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    #[cfg(feature = "otel")]
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let parent = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        let path = request.uri().path().trim_start_matches('/');
+        let (service, method) = path.split_once('/').unwrap_or((path, ""));
+        let tracer = global::tracer(TRACER);
+        let span = tracer
+            .span_builder(path.to_owned())
+            .with_kind(SpanKind::Server)
+            .with_attributes(vec![
+                KeyValue::new("rpc.system", "grpc"),
+                KeyValue::new("rpc.service", service.to_owned()),
+                KeyValue::new("rpc.method", method.to_owned()),
+            ])
+            .start_with_context(&tracer, &parent);
+        let cx = parent.with_span(span);
+        let fut = self.inner.call(request).with_context(cx.clone());
+        Box::pin(async move {
+            let response = fut.await?;
+            // Failed calls reply with their status in the headers, the others in the trailers.
+            let code = response
+                .headers()
+                .get("grpc-status")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<i64>().ok())
+                .unwrap_or(0);
+            let span = cx.span();
+            span.set_attribute(KeyValue::new("rpc.grpc.status_code", code));
+            if code != 0 {
+                span.set_status(Status::error(format!(
+                    "{:?}",
+                    tonic::Code::from(code as i32)
+                )));
+            }
+            span.end();
+            Ok(response)
+        })
+    }
+
+    #[cfg(not(feature = "otel"))]
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        Box::pin(self.inner.call(request))
+    }
+}
+
+impl<S: NamedService> NamedService for TraceService<S> {
+    const NAME: &'static str = S::NAME;
+}
@@ -0,0 +1,42 @@
+use std::io::{Error, ErrorKind};
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+
+// incoming binds a unix socket at path and yields its connections. A socket left behind by a
+// server that did not stop cleanly is replaced, any other file at path is an error.
+#[cfg(unix)]
+pub fn incoming(path: &str) -> Result<tokio_stream::wrappers::UnixListenerStream, Error> {
+    match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path),
+            ))
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    Ok(tokio_stream::wrappers::UnixListenerStream::new(listener))
+}
+
+#[cfg(not(unix))]
+pub fn incoming(
+    path: &str,
+) -> Result<futures::stream::Empty<Result<tokio::net::TcpStream, Error>>, Error> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        format!(
+            "cannot serve on {}, unix sockets are not supported here",
+            path
+        ),
+    ))
+}
+
+// remove removes the socket at path once the server stopped serving on it.
+pub fn remove(path: &str) {
+    if let Err(err) = std::fs::remove_file(path) {
+        tracing::warn!(%path, error = %err, "removing the unix socket failed");
+    }
+}
@@ -0,0 +1,88 @@
+#[cfg(feature = "web")]
+use crate::adapter::adapter_error;
+#[cfg(feature = "web")]
+use std::time::Duration;
+#[cfg(feature = "web")]
+use tonic::codegen::http::{HeaderName, HeaderValue, Method};
+#[cfg(feature = "web")]
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+// MAX_AGE is how long browsers may reuse the answer to a preflight request.
+#[cfg(feature = "web")]
+static MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+// ALLOW_HEADERS are the request headers browsers may send cross-origin: those of gRPC-Web, and
+// the credentials of the calls.
+#[cfg(feature = "web")]
+static ALLOW_HEADERS: &[&str] = &[
+    "content-type",
+    "x-grpc-web",
+    "x-user-agent",
+    "grpc-timeout",
+    "authorization",
+    "x-api-key",
+];
+
+// EXPOSE_HEADERS are the response headers scripts of other origins may read: the status of
+// the calls, and when to retry a rate limited one.
+#[cfg(feature = "web")]
+static EXPOSE_HEADERS: &[&str] = &[
+    "grpc-status",
+    "grpc-message",
+    "grpc-status-details-bin",
+    "retry-after",
+];
+
+// WebLayer makes the services of a server answer the CORS preflight requests of browsers, then
+// serve their gRPC-Web calls like gRPC ones.
+#[cfg(feature = "web")]
+pub type WebLayer = tower::layer::util::Stack<tonic_web::GrpcWebLayer, CorsLayer>;
+
+#[cfg(not(feature = "web"))]
+pub type WebLayer = tower::layer::util::Identity;
+
+// layer makes the layer serving gRPC-Web to the scripts of origins, such as
+// `https://admin.example.com`, or of any origin when there are none.
+#[cfg(feature = "web")]
+pub fn layer(origins: &[String]) -> casbin::Result<WebLayer> {
+    let allow_origin = if origins.is_empty() {
+        AllowOrigin::mirror_request()
+    } else {
+        let origins = origins
+            .iter()
+            .map(|origin| {
+                HeaderValue::from_str(origin)
+                    .map_err(|_| adapter_error(format!("invalid gRPC-Web origin `{}`", origin)))
+            })
+            .collect::<casbin::Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+    let cors = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_credentials(true)
+        .allow_methods([Method::POST])
+        .max_age(MAX_AGE)
+        .allow_headers(
+            ALLOW_HEADERS
+                .iter()
+                .map(|h| HeaderName::from_static(h))
+                .collect::<Vec<_>>(),
+        )
+        .expose_headers(
+            EXPOSE_HEADERS
+                .iter()
+                .map(|h| HeaderName::from_static(h))
+                .collect::<Vec<_>>(),
+        );
+    Ok(tower::layer::util::Stack::new(
+        tonic_web::GrpcWebLayer::new(),
+        cors,
+    ))
+}
+
+#[cfg(not(feature = "web"))]
+pub fn layer(_origins: &[String]) -> casbin::Result<WebLayer> {
+    Err(crate::adapter::adapter_error(
+        "grpc_web is set, but this server is built without gRPC-Web",
+    ))
+}
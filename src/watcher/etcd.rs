@@ -0,0 +1,170 @@
+use crate::adapter::{adapter_error, etcd};
+use crate::watcher::{
+    new_id, run_callback, spawn_publish, Callback, Message, Tasks, UpdateHandler, WatcherEx,
+};
+use casbin::{EventData, Watcher};
+use etcd_client::{Client, EventType, PutOptions, WatchOptions};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// LEASE_TTL is how long, in seconds, the notifications of a replica outlive it.
+static LEASE_TTL: i64 = 10;
+
+static RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+// EtcdWatcher keeps the replicas of a server in sync through an etcd key: a replica puts a
+// message in the key when the policy of its enforcer changes, and the others hand it to their
+// update handler when they see it change, which applies the change.
+//
+// The messages are put under a lease the replica keeps alive, so they are deleted once it is
+// gone. After a reconnect the key is watched again from the last revision seen, so
+// notifications put in the meantime are still acted on, and those seen already are ignored;
+// when the missed revisions were compacted away, the policy is reloaded once.
+pub struct EtcdWatcher {
+    id: String,
+    key: String,
+    client: Client,
+    lease: Arc<AtomicI64>,
+    callback: Callback,
+    tasks: Tasks,
+}
+
+impl EtcdWatcher {
+    // new connects to the endpoints of a connection string such as `etcd://host:2379` and
+    // watches key, starting from its current revision.
+    pub async fn new(connection: &str, key: &str) -> casbin::Result<Self> {
+        let (mut client, path) = etcd::connect(connection).await?;
+        if !path.is_empty() {
+            return Err(adapter_error(
+                "the watcher key is set by watcher_channel, not by the connection string",
+            ));
+        }
+        let revision = client
+            .get(key, None)
+            .await
+            .map_err(adapter_error)?
+            .header()
+            .map_or(0, |header| header.revision());
+        let mut watcher = EtcdWatcher {
+            id: new_id(),
+            key: key.to_owned(),
+            client,
+            lease: Default::default(),
+            callback: Default::default(),
+            tasks: Default::default(),
+        };
+
+        let (mut client, lease) = (watcher.client.clone(), watcher.lease.clone());
+        watcher.tasks.spawn(async move {
+            loop {
+                if let Err(err) = keep_lease(&mut client, &lease).await {
+                    tracing::warn!(error = %err, "watcher lease failed");
+                }
+                lease.store(0, Ordering::Relaxed);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+
+        let (mut client, id, key, callback) = (
+            watcher.client.clone(),
+            watcher.id.clone(),
+            watcher.key.clone(),
+            watcher.callback.clone(),
+        );
+        watcher.tasks.spawn(async move {
+            let mut revision = revision;
+            loop {
+                if let Err(err) = watch(&mut client, &id, &key, &callback, &mut revision).await {
+                    tracing::warn!(%key, error = %err, "watcher failed");
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+        Ok(watcher)
+    }
+}
+
+// keep_lease grants the lease the notifications are put under and keeps it alive until the
+// connection drops or etcd lets it expire.
+async fn keep_lease(client: &mut Client, lease: &AtomicI64) -> Result<(), etcd_client::Error> {
+    let id = client.lease_grant(LEASE_TTL, None).await?.id();
+    lease.store(id, Ordering::Relaxed);
+    let (mut keeper, mut responses) = client.lease_keep_alive(id).await?;
+    let mut ticker = tokio::time::interval(Duration::from_secs(LEASE_TTL as u64 / 3));
+    loop {
+        ticker.tick().await;
+        keeper.keep_alive().await?;
+        match responses.message().await? {
+            Some(res) if res.ttl() > 0 => {}
+            _ => return Ok(()),
+        }
+    }
+}
+
+// watch runs the update handler for the notifications other replicas put in key after
+// revision, which it advances, until the connection drops.
+async fn watch(
+    client: &mut Client,
+    id: &str,
+    key: &str,
+    callback: &Callback,
+    revision: &mut i64,
+) -> Result<(), etcd_client::Error> {
+    let options = WatchOptions::new().with_start_revision(*revision + 1);
+    let (_watcher, mut stream) = client.watch(key, Some(options)).await?;
+    while let Some(res) = stream.message().await? {
+        if res.compact_revision() > 0 {
+            // The notifications since revision are gone, any of them may have been missed, so
+            // the whole policy is reloaded.
+            *revision = res.compact_revision() - 1;
+            run_callback(callback, Message::default());
+            return Ok(());
+        }
+        for event in res.events() {
+            let kv = match event.kv() {
+                Some(kv) if kv.mod_revision() > *revision => kv,
+                _ => continue,
+            };
+            *revision = kv.mod_revision();
+            // Deletes are the leases of gone replicas expiring, not policy changes.
+            if event.event_type() != EventType::Put {
+                continue;
+            }
+            if let Some(update) = Message::received(kv.value(), id) {
+                run_callback(callback, update);
+            }
+        }
+    }
+    Ok(())
+}
+
+impl WatcherEx for EtcdWatcher {
+    fn set_update_handler(&mut self, handler: UpdateHandler) {
+        *self.callback.lock().unwrap() = Some(handler);
+    }
+}
+
+impl Watcher for EtcdWatcher {
+    fn set_update_callback(&mut self, mut cb: Box<dyn FnMut() + Send + Sync>) {
+        self.set_update_handler(Box::new(move |_| cb()));
+    }
+
+    // update puts the notification in the background, as the enforcer notifies its watcher
+    // synchronously while it holds the policy.
+    fn update(&mut self, d: EventData) {
+        let payload = serde_json::to_string(&Message::new(&self.id, &d)).unwrap_or_default();
+        let lease = self.lease.load(Ordering::Relaxed);
+        let options = if lease == 0 {
+            None
+        } else {
+            Some(PutOptions::new().with_lease(lease))
+        };
+        let (mut client, key) = (self.client.clone(), self.key.clone());
+        spawn_publish(async move {
+            if let Err(err) = client.put(key.as_str(), payload, options).await {
+                tracing::error!(%key, error = %err, "putting the policy change failed");
+            }
+        });
+    }
+}
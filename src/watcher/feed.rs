@@ -0,0 +1,151 @@
+use crate::casbin_proto::{policy_update, PolicyUpdate};
+use crate::server::index::PolicyIndex;
+use crate::server::metrics;
+use crate::server::roles::RoleCache;
+use crate::watcher::{UpdateHandler, WatcherEx};
+use casbin::{EventData, Watcher};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{broadcast, watch};
+
+// FEED_CAPACITY is how many updates a WatchPolicyUpdates subscriber may fall behind by before
+// its stream is ended.
+static FEED_CAPACITY: usize = 1024;
+
+// PolicyFeed numbers the policy changes of an enforcer and broadcasts them to the clients
+// subscribed through WatchPolicyUpdates. Changes are published while the enforcer is locked,
+// so subscribers see them in revision order. As every change goes through the feed, it keeps
+// the index of the policy up to date too, and starts another generation of the role cache
+// when the roles change.
+pub struct PolicyFeed {
+    sender: broadcast::Sender<PolicyUpdate>,
+    revision: AtomicI64,
+    closed: watch::Sender<bool>,
+    index: PolicyIndex,
+    roles: Arc<RoleCache>,
+}
+
+impl Default for PolicyFeed {
+    fn default() -> Self {
+        PolicyFeed {
+            sender: broadcast::channel(FEED_CAPACITY).0,
+            revision: AtomicI64::new(0),
+            closed: watch::channel(false).0,
+            index: PolicyIndex::default(),
+            roles: Default::default(),
+        }
+    }
+}
+
+impl PolicyFeed {
+    pub fn index(&self) -> &PolicyIndex {
+        &self.index
+    }
+
+    pub fn roles(&self) -> &Arc<RoleCache> {
+        &self.roles
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PolicyUpdate> {
+        self.sender.subscribe()
+    }
+
+    // closed gets a flag set once the feed is closed, when its subscribers should end their
+    // streams.
+    pub fn closed(&self) -> watch::Receiver<bool> {
+        self.closed.subscribe()
+    }
+
+    // close ends the streams of the subscribers, as the server is stopping.
+    pub fn close(&self) {
+        self.closed.send_replace(true);
+    }
+
+    // publish sends a change to the subscribers under the next revision.
+    pub fn publish(&self, op: &str, p_type: &str, rules: Vec<Vec<String>>) {
+        metrics::policy_changed(op);
+        self.index.apply(op, p_type, &rules);
+        self.roles.apply(op, p_type);
+        let revision = self.revision.fetch_add(1, Ordering::Relaxed) + 1;
+        // Sending only fails when nobody is subscribed.
+        let _ = self.sender.send(PolicyUpdate {
+            op: op.to_owned(),
+            p_type: p_type.to_owned(),
+            rules: rules
+                .into_iter()
+                .map(|params| policy_update::D { params })
+                .collect(),
+            revision,
+        });
+    }
+}
+
+// FeedWatcher is the watcher of every enforcer: it publishes the policy changes to the feed of
+// the enforcer, then passes them on to the watcher notifying the other replicas, if the
+// enforcer has one, unless muted while applying a change notified by another replica.
+pub struct FeedWatcher {
+    feed: Arc<PolicyFeed>,
+    inner: Option<Box<dyn WatcherEx>>,
+    muted: Arc<AtomicBool>,
+}
+
+impl FeedWatcher {
+    pub fn new(feed: Arc<PolicyFeed>, inner: Option<Box<dyn WatcherEx>>) -> Self {
+        FeedWatcher {
+            feed,
+            inner,
+            muted: Default::default(),
+        }
+    }
+
+    // muted gets the flag that keeps the changes from the other replicas while set.
+    pub fn muted(&self) -> Arc<AtomicBool> {
+        self.muted.clone()
+    }
+}
+
+impl WatcherEx for FeedWatcher {
+    fn set_update_handler(&mut self, handler: UpdateHandler) {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.set_update_handler(handler);
+        }
+    }
+}
+
+impl Watcher for FeedWatcher {
+    fn set_update_callback(&mut self, cb: Box<dyn FnMut() + Send + Sync>) {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.set_update_callback(cb);
+        }
+    }
+
+    fn update(&mut self, d: EventData) {
+        match &d {
+            EventData::AddPolicy(_, ptype, rule) => {
+                self.feed.publish("AddPolicy", ptype, vec![rule.clone()])
+            }
+            EventData::AddPolicies(_, ptype, rules) => {
+                self.feed.publish("AddPolicies", ptype, rules.clone())
+            }
+            EventData::RemovePolicy(_, ptype, rule) => {
+                self.feed.publish("RemovePolicy", ptype, vec![rule.clone()])
+            }
+            EventData::RemovePolicies(_, ptype, rules) => {
+                self.feed.publish("RemovePolicies", ptype, rules.clone())
+            }
+            EventData::RemoveFilteredPolicy(_, ptype, rules) => {
+                self.feed
+                    .publish("RemoveFilteredPolicy", ptype, rules.clone())
+            }
+            EventData::SavePolicy(_) => self.feed.publish("SavePolicy", "", vec![]),
+            EventData::ClearPolicy => self.feed.publish("ClearPolicy", "", vec![]),
+            EventData::ClearCache => {}
+        }
+        if self.muted.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Some(inner) = self.inner.as_mut() {
+            inner.update(d);
+        }
+    }
+}
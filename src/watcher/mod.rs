@@ -0,0 +1,230 @@
+use crate::adapter::adapter_error;
+use casbin::{EventData, Watcher};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "etcd")]
+pub mod etcd;
+pub mod feed;
+#[cfg(feature = "nats")]
+pub mod nats;
+#[cfg(feature = "redis")]
+pub mod redis;
+
+// CHANNEL is the Redis channel, or etcd key, the Go watchers notify policy changes on by
+// default.
+pub static CHANNEL: &str = "/casbin";
+
+// open_watcher connects the watcher for the scheme of a connection string, `redis://`,
+// `etcd://` or `nats://`, notifying the policy changes of the enforcer behind handle on
+// channel, or under it as a subject prefix for NATS.
+#[cfg_attr(not(feature = "nats"), allow(unused_variables))]
+pub async fn open_watcher(
+    connection: &str,
+    channel: &str,
+    handle: i32,
+) -> casbin::Result<Box<dyn WatcherEx>> {
+    let or_default = |default| if channel.is_empty() { default } else { channel };
+    match connection.split(':').next().unwrap_or_default() {
+        #[cfg(feature = "redis")]
+        "redis" | "rediss" => {
+            return Ok(Box::new(
+                self::redis::RedisWatcher::new(connection, or_default(CHANNEL)).await?,
+            ))
+        }
+        #[cfg(feature = "etcd")]
+        "etcd" => {
+            return Ok(Box::new(
+                etcd::EtcdWatcher::new(connection, or_default(CHANNEL)).await?,
+            ))
+        }
+        #[cfg(feature = "nats")]
+        "nats" | "tls" => {
+            return Ok(Box::new(
+                nats::NatsWatcher::new(connection, or_default(nats::SUBJECT), handle).await?,
+            ))
+        }
+        _ => {}
+    }
+    Err(adapter_error(format!(
+        "unsupported watcher `{}`, this server supports: {}",
+        connection,
+        WATCHERS.join(", ")
+    )))
+}
+
+// WATCHERS lists the connection string schemes of the watchers this build supports.
+static WATCHERS: &[&str] = &[
+    #[cfg(feature = "redis")]
+    "redis://",
+    #[cfg(feature = "etcd")]
+    "etcd://",
+    #[cfg(feature = "nats")]
+    "nats://",
+];
+
+// WatcherEx is a watcher notifying the changes along with their rules, like the WatcherEx of
+// the Go watchers, which hands the notifications of other replicas to an update handler so
+// they can be applied as deltas instead of reloading the whole policy.
+pub trait WatcherEx: Watcher {
+    fn set_update_handler(&mut self, handler: UpdateHandler);
+}
+
+pub type UpdateHandler = Box<dyn FnMut(Message) + Send + Sync>;
+
+// Callback is the update handler set on a watcher, shared with the task receiving the
+// notifications.
+pub type Callback = Arc<Mutex<Option<UpdateHandler>>>;
+
+pub fn run_callback(callback: &Callback, msg: Message) {
+    if let Some(handler) = callback.lock().unwrap().as_mut() {
+        handler(msg);
+    }
+}
+
+// Tasks are the tasks of a watcher receiving the notifications of the other replicas, stopped
+// once the watcher is dropped, such as when a reloaded config names another watcher.
+#[derive(Default)]
+pub struct Tasks(Vec<tokio::task::JoinHandle<()>>);
+
+impl Tasks {
+    pub fn spawn<F>(&mut self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.0.push(tokio::spawn(task));
+    }
+}
+
+impl Drop for Tasks {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+// Message notifies a policy change, with the fields of the Go watchers so servers of both kinds
+// can share a channel. Go sends the slices it leaves unset as null.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Message {
+    #[serde(rename = "Method", default)]
+    pub method: String,
+    #[serde(rename = "ID", default)]
+    pub id: String,
+    #[serde(rename = "Sec", default)]
+    pub sec: String,
+    #[serde(rename = "Ptype", default)]
+    pub ptype: String,
+    #[serde(rename = "OldRule", default, deserialize_with = "nullable")]
+    pub old_rule: Vec<String>,
+    #[serde(rename = "OldRules", default, deserialize_with = "nullable")]
+    pub old_rules: Vec<Vec<String>>,
+    #[serde(rename = "NewRule", default, deserialize_with = "nullable")]
+    pub new_rule: Vec<String>,
+    #[serde(rename = "NewRules", default, deserialize_with = "nullable")]
+    pub new_rules: Vec<Vec<String>>,
+    #[serde(rename = "FieldIndex", default)]
+    pub field_index: usize,
+    #[serde(rename = "FieldValues", default, deserialize_with = "nullable")]
+    pub field_values: Vec<String>,
+}
+
+fn nullable<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Option::unwrap_or_default)
+}
+
+impl Message {
+    // new describes a policy change like the Go watchers do: the rules removed by a filter are
+    // sent as removed rules, as the filter itself is not known, and changes of the whole
+    // policy without their rules, making the other replicas reload it.
+    pub fn new(id: &str, d: &EventData) -> Self {
+        let mut msg = Message {
+            id: id.to_owned(),
+            ..Default::default()
+        };
+        let (method, sec, ptype) = match d {
+            EventData::AddPolicy(sec, ptype, rule) => {
+                msg.new_rule = rule.clone();
+                ("UpdateForAddPolicy", sec, ptype)
+            }
+            EventData::AddPolicies(sec, ptype, rules) => {
+                msg.new_rules = rules.clone();
+                ("UpdateForAddPolicies", sec, ptype)
+            }
+            EventData::RemovePolicy(sec, ptype, rule) => {
+                msg.new_rule = rule.clone();
+                ("UpdateForRemovePolicy", sec, ptype)
+            }
+            EventData::RemovePolicies(sec, ptype, rules)
+            | EventData::RemoveFilteredPolicy(sec, ptype, rules) => {
+                msg.new_rules = rules.clone();
+                ("UpdateForRemovePolicies", sec, ptype)
+            }
+            EventData::SavePolicy(..) => {
+                msg.method = String::from("UpdateForSavePolicy");
+                return msg;
+            }
+            EventData::ClearPolicy | EventData::ClearCache => {
+                msg.method = String::from("Update");
+                return msg;
+            }
+        };
+        msg.method = method.to_owned();
+        msg.sec = sec.to_owned();
+        msg.ptype = ptype.to_owned();
+        msg
+    }
+
+    // received parses a notification, unless the watcher with id sent it itself. Notifications
+    // that are not JSON come from publishers not sending an ID, so are never our own, and
+    // make the policy reload.
+    pub fn received(payload: &[u8], id: &str) -> Option<Self> {
+        let msg = serde_json::from_slice::<Message>(payload).unwrap_or_default();
+        if msg.id == id {
+            return None;
+        }
+        Some(msg)
+    }
+}
+// PUBLISHING counts the notifications the watchers are publishing in the background, which
+// flush waits for.
+static PUBLISHING: AtomicUsize = AtomicUsize::new(0);
+
+// spawn_publish runs the publishing of a notification in the background, counted until done.
+pub fn spawn_publish<F>(publish: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    PUBLISHING.fetch_add(1, Ordering::SeqCst);
+    tokio::spawn(async move {
+        publish.await;
+        PUBLISHING.fetch_sub(1, Ordering::SeqCst);
+    });
+}
+
+// flush waits, at most for timeout, until the notifications being published are sent, so a
+// server stopping right after a change still notifies the other replicas. It reports whether
+// they all were.
+pub async fn flush(timeout: Duration) -> bool {
+    let start = Instant::now();
+    while PUBLISHING.load(Ordering::SeqCst) > 0 {
+        if start.elapsed() >= timeout {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    true
+}
+
+// new_id identifies a watcher, so it can skip the notifications it sent itself.
+pub fn new_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
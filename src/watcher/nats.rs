@@ -0,0 +1,173 @@
+use crate::adapter::adapter_error;
+use crate::watcher::{
+    new_id, run_callback, spawn_publish, Callback, Message, Tasks, UpdateHandler, WatcherEx,
+};
+use async_nats::jetstream::{self, consumer, stream};
+use async_nats::ServerAddr;
+use casbin::{EventData, Watcher};
+use futures::StreamExt;
+use std::time::Duration;
+
+// SUBJECT is the subject prefix notifications are published under, followed by the handle of
+// the enforcer that changed.
+pub static SUBJECT: &str = "casbin.policy";
+
+static RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+// NatsWatcher keeps the replicas of a server in sync through NATS: it publishes a message on
+// the subject of its enforcer's handle when the policy changes, and hands the messages other
+// replicas publish to the update handler, which applies their changes.
+//
+// With a JetStream stream the notifications are kept in it, the last one of every subject, and
+// read through a consumer. A durable consumer remembers what it acknowledged, so a replica
+// restarted under the same durable name replays the notifications it missed meanwhile.
+pub struct NatsWatcher {
+    id: String,
+    subject: String,
+    client: async_nats::Client,
+    jetstream: Option<jetstream::Context>,
+    callback: Callback,
+    tasks: Tasks,
+}
+
+impl NatsWatcher {
+    // new connects to the servers of a connection string such as
+    // `nats://host1:4222,nats://host2:4222` and subscribes to the subject of handle under
+    // prefix. `?stream=` names the JetStream stream to keep the notifications in, created when
+    // missing, and `&durable=` the durable consumer of this replica, which must differ between
+    // replicas.
+    pub async fn new(connection: &str, prefix: &str, handle: i32) -> casbin::Result<Self> {
+        let (servers, query) = connection.split_once('?').unwrap_or((connection, ""));
+        let servers = servers
+            .split(',')
+            .map(|server| server.parse::<ServerAddr>())
+            .collect::<Result<Vec<ServerAddr>, _>>()
+            .map_err(adapter_error)?;
+        let (mut stream_name, mut durable) = (String::new(), None);
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            match pair.split_once('=').unwrap_or((pair, "")) {
+                ("stream", value) => stream_name = value.to_owned(),
+                ("durable", value) => durable = Some(value.to_owned()),
+                (name, _) => return Err(adapter_error(format!("unknown parameter `{}`", name))),
+            }
+        }
+        if durable.is_some() && stream_name.is_empty() {
+            return Err(adapter_error("a durable consumer needs a stream"));
+        }
+
+        let client = async_nats::connect(servers).await.map_err(adapter_error)?;
+        let mut watcher = NatsWatcher {
+            id: new_id(),
+            subject: format!("{}.{}", prefix, handle),
+            client: client.clone(),
+            jetstream: None,
+            callback: Default::default(),
+            tasks: Default::default(),
+        };
+        let (id, callback) = (watcher.id.clone(), watcher.callback.clone());
+
+        if stream_name.is_empty() {
+            let mut subscriber = client
+                .subscribe(watcher.subject.clone())
+                .await
+                .map_err(adapter_error)?;
+            // The client reconnects and subscribes again on its own.
+            watcher.tasks.spawn(async move {
+                while let Some(msg) = subscriber.next().await {
+                    if let Some(update) = Message::received(&msg.payload, &id) {
+                        run_callback(&callback, update);
+                    }
+                }
+            });
+            return Ok(watcher);
+        }
+
+        let context = jetstream::new(client);
+        let stream = context
+            .get_or_create_stream(stream::Config {
+                name: stream_name,
+                subjects: vec![format!("{}.>", prefix)],
+                max_messages_per_subject: 1,
+                ..Default::default()
+            })
+            .await
+            .map_err(adapter_error)?;
+        let config = consumer::pull::Config {
+            durable_name: durable.clone(),
+            filter_subject: watcher.subject.clone(),
+            deliver_policy: consumer::DeliverPolicy::New,
+            ack_policy: consumer::AckPolicy::Explicit,
+            ..Default::default()
+        };
+        let consumer = match &durable {
+            Some(name) => stream.get_or_create_consumer(name, config).await,
+            None => stream.create_consumer(config).await,
+        }
+        .map_err(adapter_error)?;
+        watcher.tasks.spawn(async move {
+            loop {
+                if let Err(err) = consume(&consumer, &id, &callback).await {
+                    tracing::warn!(error = %err, "watcher consumer failed");
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+        watcher.jetstream = Some(context);
+        Ok(watcher)
+    }
+}
+
+// consume runs the update handler for the notifications of other replicas read by a
+// JetStream consumer, acknowledging each once handled.
+async fn consume(
+    consumer: &consumer::Consumer<consumer::pull::Config>,
+    id: &str,
+    callback: &Callback,
+) -> Result<(), async_nats::Error> {
+    let mut messages = consumer.messages().await?;
+    while let Some(msg) = messages.next().await {
+        let msg = msg?;
+        if let Some(update) = Message::received(&msg.payload, id) {
+            run_callback(callback, update);
+        }
+        msg.ack().await?;
+    }
+    Ok(())
+}
+
+impl WatcherEx for NatsWatcher {
+    fn set_update_handler(&mut self, handler: UpdateHandler) {
+        *self.callback.lock().unwrap() = Some(handler);
+    }
+}
+
+impl Watcher for NatsWatcher {
+    fn set_update_callback(&mut self, mut cb: Box<dyn FnMut() + Send + Sync>) {
+        self.set_update_handler(Box::new(move |_| cb()));
+    }
+
+    // update publishes in the background, as the enforcer notifies its watcher synchronously
+    // while it holds the policy. Through JetStream it waits for the stream to store the
+    // notification.
+    fn update(&mut self, d: EventData) {
+        let payload = serde_json::to_string(&Message::new(&self.id, &d)).unwrap_or_default();
+        let subject = self.subject.clone();
+        let (client, jetstream) = (self.client.clone(), self.jetstream.clone());
+        spawn_publish(async move {
+            let res: Result<(), async_nats::Error> = match jetstream {
+                Some(context) => match context.publish(subject.clone(), payload.into()).await {
+                    Ok(ack) => ack.await.map(|_| ()),
+                    Err(err) => Err(err),
+                },
+                // Published messages are buffered until flushed.
+                None => match client.publish(subject.clone(), payload.into()).await {
+                    Ok(()) => client.flush().await.map_err(Into::into),
+                    Err(err) => Err(err.into()),
+                },
+            };
+            if let Err(err) = res {
+                tracing::error!(%subject, error = %err, "publishing the policy change failed");
+            }
+        });
+    }
+}
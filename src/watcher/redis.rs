@@ -0,0 +1,99 @@
+use crate::adapter::adapter_error;
+use crate::watcher::{
+    new_id, run_callback, spawn_publish, Callback, Message, Tasks, UpdateHandler, WatcherEx,
+};
+use casbin::{EventData, Watcher};
+use futures::StreamExt;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use std::time::Duration;
+
+static RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
+// RedisWatcher keeps the replicas of a server in sync: it publishes a message on a Redis
+// channel when the policy of its enforcer changes, and hands the messages other replicas
+// publish to the update handler, which applies their changes.
+pub struct RedisWatcher {
+    id: String,
+    channel: String,
+    conn: ConnectionManager,
+    callback: Callback,
+    tasks: Tasks,
+}
+
+impl RedisWatcher {
+    // new connects to the server of a connection string such as `redis://host:6379` and
+    // subscribes to channel, subscribing again whenever the connection drops.
+    pub async fn new(url: &str, channel: &str) -> casbin::Result<Self> {
+        let client = redis::Client::open(url).map_err(adapter_error)?;
+        let conn = ConnectionManager::new(client.clone())
+            .await
+            .map_err(adapter_error)?;
+        let mut watcher = RedisWatcher {
+            id: new_id(),
+            channel: channel.to_owned(),
+            conn,
+            callback: Default::default(),
+            tasks: Default::default(),
+        };
+
+        let (id, channel, callback) = (
+            watcher.id.clone(),
+            watcher.channel.clone(),
+            watcher.callback.clone(),
+        );
+        watcher.tasks.spawn(async move {
+            loop {
+                if let Err(err) = subscribe(&client, &id, &channel, &callback).await {
+                    tracing::warn!(%channel, error = %err, "watcher subscription failed");
+                }
+                tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+            }
+        });
+        Ok(watcher)
+    }
+}
+
+// subscribe runs the update handler for the messages published on channel by other replicas
+// until the connection drops.
+async fn subscribe(
+    client: &redis::Client,
+    id: &str,
+    channel: &str,
+    callback: &Callback,
+) -> redis::RedisResult<()> {
+    let mut pubsub = client.get_async_connection().await?.into_pubsub();
+    pubsub.subscribe(channel).await?;
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        if let Some(update) = Message::received(msg.get_payload_bytes(), id) {
+            run_callback(callback, update);
+        }
+    }
+    Ok(())
+}
+
+impl WatcherEx for RedisWatcher {
+    fn set_update_handler(&mut self, handler: UpdateHandler) {
+        *self.callback.lock().unwrap() = Some(handler);
+    }
+}
+
+impl Watcher for RedisWatcher {
+    fn set_update_callback(&mut self, mut cb: Box<dyn FnMut() + Send + Sync>) {
+        self.set_update_handler(Box::new(move |_| cb()));
+    }
+
+    // update publishes in the background, as the enforcer notifies its watcher synchronously
+    // while it holds the policy.
+    fn update(&mut self, d: EventData) {
+        let payload = serde_json::to_string(&Message::new(&self.id, &d)).unwrap_or_default();
+        let (mut conn, channel) = (self.conn.clone(), self.channel.clone());
+        spawn_publish(async move {
+            let res: redis::RedisResult<()> = conn.publish(&channel, payload).await;
+            if let Err(err) = res {
+                tracing::error!(%channel, error = %err, "publishing the policy change failed");
+            }
+        });
+    }
+}